@@ -0,0 +1,25 @@
+/* C API of rust-grapher, built with `cargo build --release --features ffi`
+ * (target/release/librust_grapher.so, .dylib or rust_grapher.dll). */
+
+#ifndef RUST_GRAPHER_H
+#define RUST_GRAPHER_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Run a CLI command (argv without the program name, e.g. {"fn-graph", "--format", "json"}).
+ * Returns the output, to be released with rust_grapher_free, or NULL on error or a failed check. */
+char *rust_grapher_run(int argc, const char *const *argv);
+
+/* Error message of the last failed rust_grapher_run on this thread, valid until the next call. */
+const char *rust_grapher_last_error(void);
+
+/* Release a string returned by rust_grapher_run. NULL is ignored. */
+void rust_grapher_free(char *output);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
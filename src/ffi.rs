@@ -0,0 +1,95 @@
+// ============================================================================
+// C API (feature "ffi")
+// ============================================================================
+//
+// Embeds the grapher in non-Rust build systems and editors without spawning a
+// process. Arguments are those of the CLI, without the program name:
+//
+//   const char *argv[] = {"fn-graph", "--source-dir", "src", "--format", "json"};
+//   char *json = rust_grapher_run(5, argv);
+//   if (json == NULL) fprintf(stderr, "%s\n", rust_grapher_last_error());
+//   rust_grapher_free(json);
+//
+// Returned strings belong to the caller and are released with
+// `rust_grapher_free`. With `--output` the file is written too. Warnings still
+// go to stderr. A failed check such as `combine --exit-code` returns NULL, its
+// output file written. Declarations are in include/rust_grapher.h.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_int, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+use crate::{types::CheckFailed, utils::{cli::{parse_cli, run_cli}, helper::write_atomic}};
+
+thread_local! {
+    /// Message of the last failed call on this thread
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+/// Run a command and return its output, or NULL with `rust_grapher_last_error` set
+///
+/// # Safety
+/// `argv` must point to `argc` NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn rust_grapher_run(argc: c_int, argv: *const *const c_char) -> *mut c_char {
+    let args: Vec<String> = (0..argc.max(0) as usize)
+        .map(|i| CStr::from_ptr(*argv.add(i)).to_string_lossy().into_owned())
+        .collect();
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| run(args)))
+        .unwrap_or_else(|_| Err("internal error (panic)".to_string()));
+
+    match result.and_then(|output| CString::new(output).map_err(|e| e.to_string())) {
+        Ok(output) => output.into_raw(),
+        Err(message) => {
+            set_last_error(message);
+            ptr::null_mut()
+        }
+    }
+}
+
+fn run(args: Vec<String>) -> Result<String, String> {
+    let argv = std::iter::once("rust-grapher".to_string()).chain(args);
+    let cli = parse_cli(argv).map_err(|e| e.to_string())?;
+    let (output, output_path) = match run_cli(&cli) {
+        Ok(result) => result,
+        Err(e) => match e.downcast::<CheckFailed>() {
+            Ok(failed) => {
+                if let Some(ref path) = failed.output_path {
+                    write_atomic(path, &failed.output).map_err(|e| format!("Error writing to file: {}", e))?;
+                }
+                return Err(format!("Check failed: {}", failed));
+            }
+            Err(e) => return Err(e.to_string()),
+        },
+    };
+    if let Some(ref path) = output_path {
+        write_atomic(path, &output).map_err(|e| format!("Error writing to file: {}", e))?;
+    }
+    Ok(output)
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|cell| *cell.borrow_mut() = message);
+}
+
+/// Error of the last failed `rust_grapher_run` on this thread ("" if none); valid until the next call
+#[no_mangle]
+pub extern "C" fn rust_grapher_last_error() -> *const c_char {
+    LAST_ERROR.with(|cell| cell.borrow().as_ptr())
+}
+
+/// Release a string returned by `rust_grapher_run`; NULL is ignored
+///
+/// # Safety
+/// `output` must come from `rust_grapher_run` and not be freed twice.
+#[no_mangle]
+pub unsafe extern "C" fn rust_grapher_free(output: *mut c_char) {
+    if !output.is_null() {
+        drop(CString::from_raw(output));
+    }
+}
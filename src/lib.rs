@@ -0,0 +1,86 @@
+//! Dependency and function call graphs for Rust projects.
+//!
+//! The `rust-grapher` binary is a thin CLI over these modules. Graphs are
+//! `types::Graph` values; `extract_focus` cuts out the neighborhood of a node.
+//! The petgraph `DiGraph` is the public `graph` field, with lookups by key beside it
+//! (`node`, `neighbors`, `edges`, ...), and `DiGraphMap::<u64, _>::from(&graph)` keys
+//! the same graph by `stable_id` for algorithms that want copyable node IDs.
+//! `petgraph` is re-exported so callers build against the same version.
+//! `generate_to` keeps committed diagrams current from a build script.
+
+pub mod types;
+pub mod utils {
+    #[cfg(feature = "native")]
+    pub mod advisories;
+    pub mod analyzer;
+    #[cfg(feature = "native")]
+    pub mod assertions;
+    pub mod badges;
+    #[cfg(feature = "native")]
+    pub mod batch;
+    #[cfg(feature = "native")]
+    pub mod cache;
+    pub mod capabilities;
+    pub mod categories;
+    pub mod churn;
+    #[cfg(feature = "native")]
+    pub mod cli;
+    pub mod combine;
+    #[cfg(feature = "native")]
+    pub mod crates_io;
+    #[cfg(feature = "native")]
+    pub mod daemon;
+    pub mod demangle;
+    #[cfg(feature = "native")]
+    pub mod features;
+    pub mod filter_expr;
+    pub mod findings;
+    #[cfg(feature = "native")]
+    pub mod freshness;
+    pub mod generator;
+    pub mod graph;
+    pub mod grapher;
+    pub mod groups;
+    pub mod helper;
+    #[cfg(feature = "native")]
+    pub mod history;
+    #[cfg(feature = "native")]
+    pub mod inject;
+    #[cfg(feature = "native")]
+    pub mod layers;
+    pub mod logs;
+    pub mod metrics;
+    #[cfg(feature = "native")]
+    pub mod owners;
+    pub mod pages;
+    pub mod preset;
+    pub mod profile;
+    pub mod reexports;
+    #[cfg(feature = "native")]
+    pub mod regenerate;
+    #[cfg(feature = "native")]
+    pub mod registries;
+    #[cfg(feature = "native")]
+    pub mod selftest;
+    #[cfg(feature = "native")]
+    pub mod render;
+    pub mod size;
+    pub mod stats;
+    pub mod toml;
+    pub mod warnings;
+    #[cfg(feature = "native")]
+    pub mod why;
+}
+
+#[cfg(target_arch = "wasm32")]
+pub mod wasm;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+pub use petgraph;
+pub use types::{FnGraphData, Graph, GraphData};
+pub use utils::graph::{extract_focus, stable_id, GraphNode};
+pub use utils::grapher::fn_graph_from_sources;
+pub use utils::metrics::{attach_metrics, MetricNode, MetricSource};
+#[cfg(feature = "native")]
+pub use utils::regenerate::{generate_to, GenerateOptions};
@@ -1,8 +1,7 @@
 use std::path::PathBuf;
-use cargo_metadata::PackageId;
 use clap::{Args, Parser, Subcommand, ValueEnum};
 use petgraph::graph::{DiGraph, NodeIndex};
-use std::collections::{HashMap};
+use std::collections::{BTreeMap, HashMap};
 
 // ============================================================================
 // CLI Arguments
@@ -26,10 +25,21 @@ EXAMPLES:
     rust-grapher fn-graph
     rust-grapher fn-graph --focus main --depth 3
     rust-grapher fn-graph -f dot | dot -Tpng -o call-graph.png
+
+    # Combine saved JSON graphs
+    rust-grapher combine api.json core.json --op union
 "#)]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// Fail (exit status 2, output still written) when a warning of this kind occurs; repeatable
+    #[arg(long, global = true, value_enum, value_name = "CODE")]
+    pub deny: Vec<WarningCode>,
+
+    /// Silence warnings of this kind; repeatable (--deny wins for a code given to both)
+    #[arg(long, global = true, value_enum, value_name = "CODE")]
+    pub allow: Vec<WarningCode>,
 }
 
 #[derive(Subcommand)]
@@ -38,18 +48,76 @@ pub enum Commands {
     Deps(DepsArgs),
     /// Analyze function call graph (function mode)
     FnGraph(FnGraphArgs),
+    /// Combine previously generated JSON graphs (union/intersection/difference)
+    Combine(CombineArgs),
+    /// Draw a JSON graph in the `-f json` schema, from rust-grapher or another tool, in any format
+    Render(RenderArgs),
+    /// Answer graph queries over stdin/stdout JSON-RPC, keeping the graphs in memory (editor integrations)
+    Daemon(DaemonArgs),
+    /// Chart the dependency graph over git history: node/edge counts and added crates per point
+    History(HistoryArgs),
+    /// Show which dependents (and which of their features) enabled each feature of a crate
+    ExplainFeatures(ExplainFeaturesArgs),
+    /// Report how far behind the latest crates.io release each dependency is, failing past thresholds
+    Freshness(FreshnessArgs),
+    /// Report the crates.io owners of each dependency and how many have a single maintainer
+    Owners(OwnersArgs),
+    /// Show the dependency paths from the workspace to a crate
+    Why(WhyArgs),
+    /// Check expectations about a graph ("edge A -> B", "no path X -> Y", "nodes < 500"), failing on any miss
+    Assert(AssertArgs),
+    /// Run one command over every project of a list, writing an output per project and a summary.json
+    Batch(BatchArgs),
+    /// Run both analyses on rust-grapher's own sources to check the installation (cargo, Graphviz)
+    Selftest,
 }
 
-#[derive(Args)]
+#[derive(Args, Default)]
 pub struct DepsArgs {
     // === Input Options ===
     /// Path to Cargo.toml
     #[arg(long, short = 'm', default_value = "Cargo.toml")]
     pub manifest_path: PathBuf,
 
-    /// Focus on specific package (workspace member)
-    #[arg(long, short = 'p')]
-    pub package: Option<String>,
+    /// Graph only these packages (workspace members) and their dependencies; repeat to see how
+    /// several trees overlap
+    #[arg(long, short = 'p', value_name = "PACKAGE")]
+    pub package: Vec<String>,
+
+    /// Resolve with these features of the workspace members activated (`feat` or `member/feat`, comma-separated)
+    #[arg(long, short = 'F', value_delimiter = ',')]
+    pub features: Vec<String>,
+
+    /// Resolve with all features of the workspace members activated
+    #[arg(long)]
+    pub all_features: bool,
+
+    /// Resolve without the `default` features of the workspace members
+    #[arg(long)]
+    pub no_default_features: bool,
+
+    /// Keep only the dependencies that build for this target triple, e.g. `wasm32-unknown-unknown`
+    #[arg(long, value_name = "TRIPLE", conflicts_with = "workspace_only")]
+    pub target: Option<String>,
+
+    /// Never touch the network: run `cargo metadata --offline`, and with --audit use the cached
+    /// advisory database as it is
+    #[arg(long, conflicts_with_all = ["show_owners", "check_outdated"])]
+    pub offline: bool,
+
+    /// Cache `cargo metadata` output here, reused until Cargo.lock or a Cargo.toml changes (crates.io responses and
+    /// the RustSec advisory database for a day)
+    #[arg(long)]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Apply a named bundle of options; explicitly passed options, including the --no-* and
+    /// --dev/--build flags that undo them, take precedence
+    #[arg(long, value_enum)]
+    pub preset: Option<Preset>,
+
+    /// Command that computes custom per-node metrics (JSON nodes on stdin, metrics on stdout)
+    #[arg(long)]
+    pub metric_script: Option<String>,
 
     // === Output Options ===
     /// Output file path (stdout if not specified)
@@ -60,6 +128,31 @@ pub struct DepsArgs {
     #[arg(long, short = 'f', value_enum, default_value = "mermaid")]
     pub format: OutputFormat,
 
+    /// Print a summary instead of the graph: counts, max depth, longest chain, duplicate
+    /// versions and the crates most depended on (`-f json` for an object, text otherwise)
+    #[arg(long, conflicts_with_all = ["out_dir", "index", "badges"])]
+    pub stats: bool,
+
+    /// Also write a Markdown index of every node (version, license, description, links)
+    #[arg(long)]
+    pub index: Option<PathBuf>,
+
+    /// Also write shields.io endpoint JSON here (dependencies.json, direct-dependencies.json)
+    #[arg(long, value_name = "DIR")]
+    pub badges: Option<PathBuf>,
+
+    /// Write the diagram into this directory (as graph.md, graph.dot, ...) instead of --output
+    #[arg(long, value_name = "DIR", conflicts_with = "output")]
+    pub out_dir: Option<PathBuf>,
+
+    /// With --out-dir, also write a page per node (metadata, direct neighbors, metrics) that the diagram links to
+    #[arg(long, value_enum, requires = "out_dir")]
+    pub detail_pages: Option<PageFormat>,
+
+    /// Regenerate the diagram blocks in the Markdown files a TOML map lists, each with its own arguments
+    #[arg(long, value_name = "FILE")]
+    pub inject_map: Option<PathBuf>,
+
     /// Omit code fence markers (```mermaid)
     #[arg(long)]
     pub(crate) no_fence: bool,
@@ -69,67 +162,323 @@ pub struct DepsArgs {
     pub direction: String,
 
     // === Filtering Options ===
-    /// Maximum dependency depth (0 = unlimited)
+    /// Maximum dependency depth, counted along the shortest path from the roots (0 = unlimited)
     #[arg(long, default_value = "0")]
     pub depth: usize,
 
     /// Exclude dev-dependencies
-    #[arg(long)]
+    #[arg(long, overrides_with = "dev")]
     pub no_dev: bool,
 
+    /// Keep dev-dependencies, e.g. with --preset minimal
+    #[arg(long, overrides_with = "no_dev")]
+    pub dev: bool,
+
     /// Exclude build-dependencies
-    #[arg(long)]
+    #[arg(long, overrides_with = "build")]
     pub no_build: bool,
 
-    /// Exclude crates matching pattern (supports * wildcard, can be used multiple times)
+    /// Keep build-dependencies, e.g. with --preset minimal
+    #[arg(long, overrides_with = "no_build")]
+    pub build: bool,
+
+    /// Exclude crates matching pattern (supports * wildcard and `@` versions: `serde@1.*`,
+    /// `tokio@>=1.30`; can be used multiple times)
     #[arg(long, short = 'e')]
     pub exclude: Vec<String>,
 
-    /// Include only crates matching pattern (supports * wildcard, can be used multiple times)
+    /// Include only crates matching pattern (supports * wildcard and `@` versions, can be used multiple times)
     #[arg(long, short = 'i')]
     pub include: Vec<String>,
 
+    /// File of --exclude patterns, one per line (`#` starts a comment); shared with fn-graph
+    #[arg(long, value_name = "FILE")]
+    pub exclude_from: Vec<PathBuf>,
+
+    /// File of --include patterns, one per line (`#` starts a comment)
+    #[arg(long, value_name = "FILE")]
+    pub include_from: Vec<PathBuf>,
+
+    /// Exclude crates from this registry, by its name in .cargo/config.toml (`crates-io` for
+    /// crates.io, the index host for unnamed ones; supports * wildcard, can be used multiple times)
+    #[arg(long, value_name = "NAME")]
+    pub exclude_registry: Vec<String>,
+
+    /// Keep only workspace members and crates from this registry (can be used multiple times)
+    #[arg(long, value_name = "NAME")]
+    pub only_registry: Vec<String>,
+
+    /// Patterns of the --exclude-from and --include-from files, added to --exclude and --include
+    #[arg(skip)]
+    pub file_patterns: Vec<String>,
+
     /// Show only crates connected to this crate
     #[arg(long)]
     pub focus: Option<String>,
 
-    /// Show only workspace members
-    #[arg(long)]
+    /// Fade crates the more hops they are from the --focus crate (JSON `metrics.hops` either way)
+    #[arg(long, requires = "focus")]
+    pub fade_by_hops: bool,
+
+    /// Keep only crates on some dependency path from this crate to the --to crate, e.g.
+    /// `--from my-app --to openssl-sys`
+    #[arg(long, value_name = "CRATE", requires = "to")]
+    pub from: Option<String>,
+
+    /// End of the --from paths
+    #[arg(long, value_name = "CRATE", requires = "from")]
+    pub to: Option<String>,
+
+    /// Show only workspace members (reads declared dependencies via `cargo metadata --no-deps`)
+    #[arg(long, overrides_with = "no_workspace_only")]
     pub workspace_only: bool,
 
+    /// Show every dependency, e.g. with --preset docs
+    #[arg(long, overrides_with = "workspace_only")]
+    pub no_workspace_only: bool,
+
+    /// Keep only nodes matching a boolean expression, e.g. 'is_workspace && depth <= 2 || name ~ "serde*"'
+    /// (attributes: name, version, is_workspace, is_optional, depth, license, category, source (crates-io, registry, git, path, workspace), registry, fan_in, fan_out, metric.<name>)
+    #[arg(long)]
+    pub filter: Option<String>,
+
+    /// Preview with about N crates: the best-connected ones plus random neighbors (same picks every run)
+    #[arg(long, value_name = "N")]
+    pub sample: Option<usize>,
+
+    /// Draw at most N outgoing edges per node (the ones leading to the most crates); JSON output keeps every edge
+    #[arg(long, value_name = "N")]
+    pub max_edges_per_node: Option<usize>,
+
+    /// Remove crates without dependencies, N times over as new ones appear (1 without N)
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "1")]
+    pub prune_leaves: Option<usize>,
+
+    /// Repeatedly remove crates with fewer than K incoming edges, keeping the roots
+    #[arg(long, value_name = "K")]
+    pub min_fanin: Option<usize>,
+
+    /// Cut the graph into pages of M crates, neighbors together and the same cut every run, and draw
+    /// page --page (for printing graphs too large for one diagram)
+    #[arg(long, value_name = "M", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    pub page_size: Option<usize>,
+
+    /// Page of --page-size to draw, from 1
+    #[arg(long, value_name = "N", requires = "page_size", default_value_t = 1)]
+    pub page: usize,
+
     /// Show only direct dependencies (no transitive)
     #[arg(long)]
     pub no_transitive: bool,
 
+    /// Keep only crates linked into workspace binaries (normal dependencies reachable from bin targets)
+    #[arg(long)]
+    pub reachable_from_bins: bool,
+
+    /// Point edges from each crate to its dependents; with a crate, keep only it and what depends on it
+    #[arg(long, value_name = "CRATE", num_args = 0..=1, conflicts_with = "relative_depth")]
+    pub invert: Option<Option<String>>,
+
     // === Display Options ===
     /// Show version numbers with crate names
-    #[arg(long, short = 'v')]
+    #[arg(long, short = 'v', overrides_with = "no_show_versions")]
     pub show_versions: bool,
 
+    /// Hide version numbers, e.g. with --preset audit
+    #[arg(long, overrides_with = "show_versions")]
+    pub no_show_versions: bool,
+
+    /// Show the Cargo.lock checksum of registry crates (git crates always show their commit)
+    #[arg(long, overrides_with = "no_show_checksums")]
+    pub show_checksums: bool,
+
+    /// Hide checksums and commits, e.g. with --preset audit
+    #[arg(long, overrides_with = "show_checksums")]
+    pub no_show_checksums: bool,
+
+    /// Show each crate's license (SPDX expression from its manifest) in labels and JSON
+    #[arg(long, overrides_with = "no_show_licenses")]
+    pub show_licenses: bool,
+
+    /// Hide licenses, e.g. with --preset audit
+    #[arg(long, overrides_with = "show_licenses")]
+    pub no_show_licenses: bool,
+
+    /// With --show-licenses, mark crates that can only be used under a copyleft license (GPL, MPL, ...)
+    #[arg(long, requires = "show_licenses")]
+    pub highlight_copyleft: bool,
+
+    /// Fetch the crates.io owners (users and teams) of registry crates
+    #[arg(long)]
+    pub show_owners: bool,
+
+    /// Look up the latest stable release of crates.io packages and mark the ones behind it
+    #[arg(long)]
+    pub check_outdated: bool,
+
+    /// Check crates.io packages against the RustSec advisory database, marking vulnerable ones
+    /// (advisory IDs in JSON)
+    #[arg(long, overrides_with = "no_audit")]
+    pub audit: bool,
+
+    /// Skip the advisory check, e.g. with --preset audit
+    #[arg(long, overrides_with = "audit")]
+    pub no_audit: bool,
+
+    /// With --audit, use this checkout of the advisory database instead of a cached clone updated daily
+    #[arg(long, value_name = "DIR", requires = "audit")]
+    pub advisory_db: Option<PathBuf>,
+
+    /// Fail (exit status 2) when workspace members depend on each other in a cycle, through
+    /// any kind of dependency, printing each cycle
+    #[arg(long)]
+    pub fail_on_cycles: bool,
+
+    /// Fail (exit status 2) on every dependency between layers that the `[layers]` section of
+    /// this config file does not allow, printing each one
+    #[arg(long, value_name = "FILE", num_args = 0..=1, default_missing_value = ".rust-grapher.toml")]
+    pub check_layers: Option<PathBuf>,
+
+    /// Mark crates present in several versions (red border) and list them with their versions
+    #[arg(long, overrides_with = "no_show_duplicates")]
+    pub show_duplicates: bool,
+
+    /// Leave duplicate versions unmarked, e.g. with --preset audit
+    #[arg(long, overrides_with = "show_duplicates")]
+    pub no_show_duplicates: bool,
+
+    /// Label edges of optional dependencies with the features of the dependent that enable them
+    #[arg(long)]
+    pub show_feature_gates: bool,
+
     /// Group dependencies by kind (dev/build/normal) using subgraphs
     #[arg(long)]
     pub group_by_kind: bool,
 
-    /// Deduplicate: show each crate only once
+    /// Show each build.rs as its own node owning the package's build-dependencies
     #[arg(long)]
+    pub split_build_scripts: bool,
+
+    /// Deduplicate: show each crate only once
+    #[arg(long, overrides_with = "no_dedup")]
     pub dedup: bool,
 
+    /// Do not deduplicate, e.g. with --preset minimal or docs
+    #[arg(long, overrides_with = "dedup")]
+    pub no_dedup: bool,
+
+    /// Report crates that would share a diagram ID (e.g. two versions) and the IDs they get instead
+    #[arg(long)]
+    pub warn_collisions: bool,
+
+    /// TOML file mapping group names to crate patterns, each drawn as a colored cluster
+    #[arg(long, conflicts_with = "group_by_kind")]
+    pub groups: Option<PathBuf>,
+
+    /// Draw each disconnected part of the graph as its own cluster, named after its workspace members
+    #[arg(long, conflicts_with_all = ["groups", "group_by_kind"])]
+    pub separate_components: bool,
+
+    /// Draw each workspace member as a cluster holding its build.rs and the path dependencies
+    /// only it uses, e.g. to show team ownership in a monorepo
+    #[arg(long, conflicts_with_all = ["groups", "group_by_kind", "separate_components"], overrides_with = "no_cluster_workspace")]
+    pub cluster_workspace: bool,
+
+    /// Draw no workspace member clusters, e.g. with --preset docs
+    #[arg(long, overrides_with = "cluster_workspace")]
+    pub no_cluster_workspace: bool,
+
+    /// Color nodes by an attribute, with a legend
+    #[arg(long, value_enum)]
+    pub color_by: Option<ColorBy>,
+
+    /// Same as `--color-by depth`: a gradient from the root packages along their BFS depth
+    #[arg(long, conflicts_with = "color_by")]
+    pub color_by_depth: bool,
+
+    /// Metric used by --color-by metric
+    #[arg(long, required_if_eq("color_by", "metric"))]
+    pub color_metric: Option<String>,
+
+    /// Measure depth as the fewest hops from any workspace member (JSON `distance`); implies --color-by depth
+    #[arg(long)]
+    pub relative_depth: bool,
+
+    /// Size nodes by how heavy each crate is: width and border in DOT, `size` in JSON
+    #[arg(long, value_enum, value_name = "MEASURE")]
+    pub size_by: Option<SizeBy>,
+
+    /// Constrain workspace members to a shared rank (DOT output)
+    #[arg(long, value_enum)]
+    pub rank_workspace: Option<RankWorkspace>,
+
     // === Style Options ===
     /// Color theme
     #[arg(long, value_enum, default_value = "default")]
     pub theme: Theme,
 
+    /// Mermaid release to write for: 8 (no subgraph directions), 10 (`%%{init}%%` directive
+    /// first) or 11 (frontmatter config); by default the syntax current GitHub renders
+    #[arg(long, value_enum, value_name = "VERSION")]
+    pub mermaid_version: Option<MermaidVersion>,
+
     /// Highlight specific crates (can be used multiple times)
     #[arg(long, short = 'H')]
     pub highlight: Vec<String>,
 }
 
-#[derive(Args)]
+#[derive(Args, Default)]
 pub struct FnGraphArgs {
     /// Source directory to analyze
     #[arg(long, short = 's', default_value = "src")]
     pub source_dir: PathBuf,
 
+    /// Path to Cargo.toml (used with --workspace)
+    #[arg(long, short = 'm', default_value = "Cargo.toml")]
+    pub manifest_path: PathBuf,
+
+    /// Analyze all workspace members discovered via cargo metadata (instead of --source-dir)
+    #[arg(long)]
+    pub workspace: bool,
+
+    /// Only analyze these workspace members (supports * wildcard, can be used multiple times)
+    #[arg(long, requires = "workspace")]
+    pub member: Vec<String>,
+
+    /// Analyze a dependency of the workspace instead, from the sources cargo downloaded for it
+    #[arg(long = "crate", value_name = "NAME[@VERSION]", conflicts_with = "workspace")]
+    pub crate_name: Option<String>,
+
+    /// Start qualified names with this crate name (`mycrate::Parser::parse`), as --workspace does;
+    /// without a value, the package name from the Cargo.toml around --source-dir
+    #[arg(long = "crate-name", value_name = "NAME", num_args = 0..=1, default_missing_value = "", conflicts_with_all = ["workspace", "crate_name"])]
+    pub root_name: Option<String>,
+
+    /// How calls are resolved to functions (`ra` runs rust-analyzer, or $RUST_ANALYZER)
+    #[arg(long, value_enum, default_value = "heuristic")]
+    pub resolver: Resolver,
+
+    /// Command that computes custom per-function metrics (JSON nodes on stdin, metrics on stdout)
+    #[arg(long)]
+    pub metric_script: Option<String>,
+
+    /// CPU profile (collapsed stacks or `perf script` output) to overlay as sample counts
+    #[arg(long)]
+    pub profile_data: Option<PathBuf>,
+
+    /// Function sizes (`cargo bloat -n 0 --message-format json` or a linker map) to overlay in bytes
+    #[arg(long)]
+    pub size_data: Option<PathBuf>,
+
+    /// Blame the sources with git: `metric.churn` (commits among a function's lines), `metric.age`
+    /// (days since the newest) and `last_modified`; implied by --color-by churn|age
+    #[arg(long)]
+    pub churn: bool,
+
+    /// Count the log/tracing macro calls of each function as `metric.logs` (and list them in JSON)
+    #[arg(long)]
+    pub logs: bool,
+
     /// Output file path (stdout if not specified)
     #[arg(long, short = 'o')]
     pub output: Option<PathBuf>,
@@ -138,6 +487,30 @@ pub struct FnGraphArgs {
     #[arg(long, short = 'f', value_enum, default_value = "mermaid")]
     pub format: OutputFormat,
 
+    /// Also write a Markdown index of every function (file, visibility, signature)
+    #[arg(long)]
+    pub index: Option<PathBuf>,
+
+    /// Also write a Markdown table of the log/tracing macro calls of the graph's functions
+    #[arg(long, value_name = "FILE")]
+    pub log_table: Option<PathBuf>,
+
+    /// Also write shields.io endpoint JSON here (unsafe-fns.json, public-api.json)
+    #[arg(long, value_name = "DIR")]
+    pub badges: Option<PathBuf>,
+
+    /// Write the diagram into this directory (as graph.md, graph.dot, ...) instead of --output
+    #[arg(long, value_name = "DIR", conflicts_with = "output")]
+    pub out_dir: Option<PathBuf>,
+
+    /// With --out-dir, also write a page per node (metadata, direct neighbors, metrics) that the diagram links to
+    #[arg(long, value_enum, requires = "out_dir")]
+    pub detail_pages: Option<PageFormat>,
+
+    /// Regenerate the diagram blocks in the Markdown files a TOML map lists, each with its own arguments
+    #[arg(long, value_name = "FILE")]
+    pub inject_map: Option<PathBuf>,
+
     /// Omit code fence markers (```mermaid)
     #[arg(long)]
     pub no_fence: bool,
@@ -150,6 +523,18 @@ pub struct FnGraphArgs {
     #[arg(long)]
     pub focus: Option<String>,
 
+    /// Fade functions the more calls they are from the --focus function (JSON `metrics.hops` either way)
+    #[arg(long, requires = "focus")]
+    pub fade_by_hops: bool,
+
+    /// Keep only functions on some call path from this function to --slice-to (--focus syntax)
+    #[arg(long, value_name = "FN", requires = "slice_to")]
+    pub slice_from: Option<String>,
+
+    /// End of the --slice-from paths
+    #[arg(long, value_name = "FN", requires = "slice_from")]
+    pub slice_to: Option<String>,
+
     /// Maximum call depth (0 = unlimited)
     #[arg(long, default_value = "0")]
     pub depth: usize,
@@ -158,112 +543,1219 @@ pub struct FnGraphArgs {
     #[arg(long, short = 'e')]
     pub exclude: Vec<String>,
 
+    /// File of --exclude patterns, one per line (`#` starts a comment); shared with deps
+    #[arg(long, value_name = "FILE")]
+    pub exclude_from: Vec<PathBuf>,
+
+    /// Patterns of the --exclude-from files, added to --exclude
+    #[arg(skip)]
+    pub file_patterns: Vec<String>,
+
     /// Include only public functions
     #[arg(long)]
     pub public_only: bool,
 
+    /// Keep only functions defined in files matching these patterns (* wildcard, e.g. 'src/handlers/**'),
+    /// plus their direct callers and callees in other files, grayed
+    #[arg(long, value_name = "GLOB")]
+    pub only_files: Vec<String>,
+
+    /// Keep only functions matching a boolean expression, e.g. 'is_public && !is_async'
+    /// (attributes: name, qualified_name, file, line, is_public, is_async, is_unsafe, crate, uses_fs, uses_net, uses_process, depth, fan_in, fan_out, metric.<name>)
+    #[arg(long)]
+    pub filter: Option<String>,
+
+    /// Keep only calls of these kinds, e.g. `direct,method` (default: all)
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "KINDS")]
+    pub edge_kinds: Vec<CallKind>,
+
+    /// Extra entry points for unreachable-function findings (FFI exports, macro or reflection callees),
+    /// one name, qualified name or * pattern per line
+    #[arg(long, value_name = "FILE")]
+    pub roots: Option<PathBuf>,
+
+    /// Preview with about N functions: the best-connected ones plus random neighbors (same picks every run)
+    #[arg(long, value_name = "N")]
+    pub sample: Option<usize>,
+
+    /// Draw at most N outgoing edges per node (the most profiled calls, then the ones leading to the most functions); JSON output keeps every edge
+    #[arg(long, value_name = "N")]
+    pub max_edges_per_node: Option<usize>,
+
+    /// Remove functions without callees, N times over as new ones appear (1 without N)
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "1")]
+    pub prune_leaves: Option<usize>,
+
+    /// Repeatedly remove functions with fewer than K incoming edges, keeping the roots
+    #[arg(long, value_name = "K")]
+    pub min_fanin: Option<usize>,
+
+    /// Cut the graph into pages of M functions, neighbors together and the same cut every run, and draw
+    /// page --page (for printing graphs too large for one diagram)
+    #[arg(long, value_name = "M", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    pub page_size: Option<usize>,
+
+    /// Page of --page-size to draw, from 1
+    #[arg(long, value_name = "N", requires = "page_size", default_value_t = 1)]
+    pub page: usize,
+
+    /// Report functions that would share a diagram ID (e.g. `new` in several impls) and the IDs they get instead
+    #[arg(long)]
+    pub warn_collisions: bool,
+
     /// Show function signatures
     #[arg(long)]
     pub show_signatures: bool,
 
+    /// Draw which functions read which const/static items and environment variables, instead of the calls
+    #[arg(long)]
+    pub config_map: bool,
+
+    /// Draw which functions take or return which structs, enums, unions, traits and type aliases of
+    /// the sources, instead of the calls
+    #[arg(long, conflicts_with = "config_map")]
+    pub type_map: bool,
+
+    /// Follow `pub use` re-exports: label functions with their shortest public path and flag the ones
+    /// public under several paths (JSON: `public_paths`, `reexports`)
+    #[arg(long)]
+    pub reexports: bool,
+
+    /// Keep only call paths from entry points to functions calling filesystem, network or process
+    /// APIs, coloring those; optionally only some kinds, e.g. `--capability-view net,process`
+    #[arg(long, value_enum, value_delimiter = ',', num_args = 0.., value_name = "KINDS")]
+    pub capability_view: Option<Vec<Capability>>,
+
+    /// TOML file mapping group names to function patterns, each drawn as a colored cluster
+    #[arg(long)]
+    pub groups: Option<PathBuf>,
+
+    /// Draw a cluster per cargo feature gating functions through `#[cfg(feature = ...)]` (on the
+    /// function, its impl or a module), and an "always" cluster for unconditional code
+    #[arg(long, conflicts_with = "groups")]
+    pub cluster_features: bool,
+
+    /// Color nodes by an attribute, with a legend
+    #[arg(long, value_enum)]
+    pub color_by: Option<ColorBy>,
+
+    /// Metric used by --color-by metric
+    #[arg(long, required_if_eq("color_by", "metric"))]
+    pub color_metric: Option<String>,
+
     /// Color theme
     #[arg(long, value_enum, default_value = "default")]
     pub theme: Theme,
 
+    /// Mermaid release to write for: 8 (no subgraph directions), 10 (`%%{init}%%` directive
+    /// first) or 11 (frontmatter config); by default the syntax current GitHub renders
+    #[arg(long, value_enum, value_name = "VERSION")]
+    pub mermaid_version: Option<MermaidVersion>,
+
     /// Highlight specific functions (can be used multiple times)
     #[arg(long, short = 'H')]
     pub highlight: Vec<String>,
 }
 
-#[derive(Clone, ValueEnum)]
-pub enum OutputFormat {
-    Mermaid,
-    Dot,
-    Json,
-}
+#[derive(Args)]
+pub struct CombineArgs {
+    /// JSON graph files produced with `-f json` (two or more)
+    #[arg(required = true, num_args = 2..)]
+    pub inputs: Vec<PathBuf>,
 
-#[derive(Clone, ValueEnum)]
-pub enum Theme {
+    /// Set operation to apply (difference keeps what the first graph has and the others lack)
+    #[arg(long, value_enum, default_value = "union")]
+    pub op: CombineOp,
 
-    Default,
-    Light,
-    Dark,
-}
+    /// Output file path (stdout if not specified)
+    #[arg(long, short = 'o')]
+    pub output: Option<PathBuf>,
 
-// ============================================================================
-// Data Structures - Deps
-// ============================================================================
+    /// Output format
+    #[arg(long, short = 'f', value_enum, default_value = "mermaid")]
+    pub format: OutputFormat,
 
-#[derive(Clone)]
-pub struct NodeInfo {
-    pub name: String,
-    pub version: String,
-    #[allow(dead_code)]
-    pub kind: DepKind,
-    pub is_workspace_member: bool,
-}
+    /// Omit code fence markers (```mermaid)
+    #[arg(long)]
+    pub no_fence: bool,
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
-pub enum DepKind {
-    Normal,
-    Dev,
-    Build,
-}
+    /// Graph direction: LR (left-right) or TB (top-bottom)
+    #[arg(long, short = 'd', default_value = "LR")]
+    pub direction: String,
 
-pub struct GraphData {
-    pub graph: DiGraph<NodeInfo, DepKind>,
-    pub node_indices: HashMap<PackageId, NodeIndex>,
-}
+    /// Color theme
+    #[arg(long, value_enum, default_value = "default")]
+    pub theme: Theme,
 
-// ============================================================================
-// Data Structures - Function Graph
-// ============================================================================
+    /// Mermaid release to write for: 8 (no subgraph directions), 10 (`%%{init}%%` directive
+    /// first) or 11 (frontmatter config); by default the syntax current GitHub renders
+    #[arg(long, value_enum, value_name = "VERSION")]
+    pub mermaid_version: Option<MermaidVersion>,
 
-#[derive(Clone)]
-pub struct FnNodeInfo {
-    pub name: String,
-    pub qualified_name: String,
-    pub file_path: String,
-    pub line: usize,
-    pub is_public: bool,
-    pub signature: Option<String>,
-    pub is_async: bool,
-}
+    /// Highlight specific nodes (can be used multiple times)
+    #[arg(long, short = 'H')]
+    pub highlight: Vec<String>,
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
-pub enum CallKind {
-    Direct,
-    Method,
+    /// Exit with status 2 when the result is not empty, e.g. new dependencies with --op difference
+    #[arg(long)]
+    pub exit_code: bool,
 }
 
-pub struct FnGraphData {
-    pub graph: DiGraph<FnNodeInfo, CallKind>,
-    pub node_indices: HashMap<String, NodeIndex>,
-}
+#[derive(Args)]
+pub struct RenderArgs {
+    /// JSON graph file (`-` for stdin): nodes with an `id` (and a `qualified_name` for functions),
+    /// edges with `from` and `to`
+    pub input: PathBuf,
 
-#[derive(Clone)]
-pub struct FunctionDef {
-    pub name: String,
-    pub qualified_name: String,
-    pub is_public: bool,
-    pub line: usize,
-    pub signature: String,
-    pub is_async: bool,
-}
+    /// Output file path (stdout if not specified)
+    #[arg(long, short = 'o')]
+    pub output: Option<PathBuf>,
 
-pub struct CallInfo {
-    pub caller: String,
-    pub callee: String,
-    pub kind: CallKind,
+    /// Output format
+    #[arg(long, short = 'f', value_enum, default_value = "mermaid")]
+    pub format: OutputFormat,
+
+    /// Omit code fence markers (```mermaid)
+    #[arg(long)]
+    pub no_fence: bool,
+
+    /// Graph direction: LR (left-right) or TB (top-bottom)
+    #[arg(long, short = 'd', default_value = "LR")]
+    pub direction: String,
+
+    /// Show only nodes connected to this one
+    #[arg(long)]
+    pub focus: Option<String>,
+
+    /// Fade nodes the more hops they are from the --focus node (JSON `metrics.hops` either way)
+    #[arg(long, requires = "focus")]
+    pub fade_by_hops: bool,
+
+    /// With --focus on a function graph, maximum call depth (0 = unlimited)
+    #[arg(long, default_value = "0")]
+    pub depth: usize,
+
+    /// Keep only nodes matching a boolean expression (the attributes of `deps --filter` or `fn-graph --filter`)
+    #[arg(long)]
+    pub filter: Option<String>,
+
+    /// Preview with about N nodes: the best-connected ones plus random neighbors (same picks every run)
+    #[arg(long, value_name = "N")]
+    pub sample: Option<usize>,
+
+    /// Draw at most N outgoing edges per node (the ones leading to the most nodes); JSON output keeps every edge
+    #[arg(long, value_name = "N")]
+    pub max_edges_per_node: Option<usize>,
+
+    /// Remove nodes without outgoing edges, N times over as new ones appear (1 without N)
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "1")]
+    pub prune_leaves: Option<usize>,
+
+    /// Repeatedly remove nodes with fewer than K incoming edges, keeping the roots
+    #[arg(long, value_name = "K")]
+    pub min_fanin: Option<usize>,
+
+    /// Cut the graph into pages of M nodes, neighbors together and the same cut every run, and draw
+    /// page --page (for printing graphs too large for one diagram)
+    #[arg(long, value_name = "M", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    pub page_size: Option<usize>,
+
+    /// Page of --page-size to draw, from 1
+    #[arg(long, value_name = "N", requires = "page_size", default_value_t = 1)]
+    pub page: usize,
+
+    /// Show version numbers with crate names
+    #[arg(long)]
+    pub show_versions: bool,
+
+    /// Show each crate's license in labels
+    #[arg(long)]
+    pub show_licenses: bool,
+
+    /// With --show-licenses, mark crates that can only be used under a copyleft license (GPL, MPL, ...)
+    #[arg(long, requires = "show_licenses")]
+    pub highlight_copyleft: bool,
+
+    /// Show function signatures
+    #[arg(long)]
+    pub show_signatures: bool,
+
+    /// Group dependencies by kind (dev/build/normal) using subgraphs; in DOT, nodes with a
+    /// `group` in the graph stay in its cluster
+    #[arg(long, conflicts_with_all = ["groups", "separate_components"])]
+    pub group_by_kind: bool,
+
+    /// TOML file mapping group names to node patterns, each drawn as a colored cluster
+    #[arg(long)]
+    pub groups: Option<PathBuf>,
+
+    /// Draw each disconnected part of a dependency graph as its own cluster
+    #[arg(long)]
+    pub separate_components: bool,
+
+    /// Color nodes by an attribute, with a legend
+    #[arg(long, value_enum)]
+    pub color_by: Option<ColorBy>,
+
+    /// Metric used by --color-by metric
+    #[arg(long, required_if_eq("color_by", "metric"))]
+    pub color_metric: Option<String>,
+
+    /// Color theme
+    #[arg(long, value_enum, default_value = "default")]
+    pub theme: Theme,
+
+    /// Mermaid release to write for: 8 (no subgraph directions), 10 (`%%{init}%%` directive
+    /// first) or 11 (frontmatter config); by default the syntax current GitHub renders
+    #[arg(long, value_enum, value_name = "VERSION")]
+    pub mermaid_version: Option<MermaidVersion>,
+
+    /// Highlight specific nodes (can be used multiple times)
+    #[arg(long, short = 'H')]
+    pub highlight: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct DaemonArgs {
+    /// Source directory to analyze for function queries
+    #[arg(long, short = 's', default_value = "src")]
+    pub source_dir: PathBuf,
+
+    /// Path to Cargo.toml, for dependency queries and --workspace
+    #[arg(long, short = 'm', default_value = "Cargo.toml")]
+    pub manifest_path: PathBuf,
+
+    /// Analyze all workspace members discovered via cargo metadata (instead of --source-dir)
+    #[arg(long)]
+    pub workspace: bool,
+
+    /// How calls are resolved to functions (`ra` runs rust-analyzer, or $RUST_ANALYZER)
+    #[arg(long, value_enum, default_value = "heuristic")]
+    pub resolver: Resolver,
+}
+
+#[derive(Args)]
+pub struct HistoryArgs {
+    /// Path to Cargo.toml, inside a git repository
+    #[arg(long, short = 'm', default_value = "Cargo.toml")]
+    pub manifest_path: PathBuf,
+
+    /// Analyze every N-th first-parent commit up to HEAD (and HEAD itself), or every tag
+    #[arg(long, value_name = "N|tag", default_value = "tag")]
+    pub every: HistoryEvery,
+
+    /// Output file path (stdout if not specified)
+    #[arg(long, short = 'o')]
+    pub output: Option<PathBuf>,
+
+    /// Time series format
+    #[arg(long, short = 'f', value_enum, default_value = "json")]
+    pub format: HistoryFormat,
+
+    /// Also write the graph at each point as a numbered DOT frame, for an animation
+    #[arg(long, value_name = "DIR")]
+    pub frames: Option<PathBuf>,
+
+    /// Exclude dev-dependencies
+    #[arg(long)]
+    pub no_dev: bool,
+
+    /// Exclude build-dependencies
+    #[arg(long)]
+    pub no_build: bool,
+}
+
+#[derive(Args)]
+pub struct ExplainFeaturesArgs {
+    /// Crate whose enabled features to explain (every version in the graph)
+    #[arg(value_name = "CRATE")]
+    pub crate_name: String,
+
+    /// Path to Cargo.toml
+    #[arg(long, short = 'm', default_value = "Cargo.toml")]
+    pub manifest_path: PathBuf,
+
+    /// Output file path (stdout if not specified)
+    #[arg(long, short = 'o')]
+    pub output: Option<PathBuf>,
+
+    /// Output format (mermaid, dot or json)
+    #[arg(long, short = 'f', value_enum, default_value = "mermaid")]
+    pub format: OutputFormat,
+
+    /// Omit code fence markers (```mermaid)
+    #[arg(long)]
+    pub no_fence: bool,
+
+    /// Graph direction: LR (left-right) or TB (top-bottom)
+    #[arg(long, short = 'd', default_value = "LR")]
+    pub direction: String,
+
+    /// Color theme
+    #[arg(long, value_enum, default_value = "default")]
+    pub theme: Theme,
+
+    /// Mermaid release to write for: 8 (no subgraph directions), 10 (`%%{init}%%` directive
+    /// first) or 11 (frontmatter config); by default the syntax current GitHub renders
+    #[arg(long, value_enum, value_name = "VERSION")]
+    pub mermaid_version: Option<MermaidVersion>,
+}
+
+#[derive(Args)]
+pub struct FreshnessArgs {
+    /// Path to Cargo.toml
+    #[arg(long, short = 'm', default_value = "Cargo.toml")]
+    pub manifest_path: PathBuf,
+
+    /// Output file path (stdout if not specified)
+    #[arg(long, short = 'o')]
+    pub output: Option<PathBuf>,
+
+    /// Report format
+    #[arg(long, short = 'f', value_enum, default_value = "markdown")]
+    pub format: ReportFormat,
+
+    /// Only dependencies declared by workspace members
+    #[arg(long)]
+    pub direct_only: bool,
+
+    /// Exclude dev-dependencies
+    #[arg(long)]
+    pub no_dev: bool,
+
+    /// Exclude build-dependencies
+    #[arg(long)]
+    pub no_build: bool,
+
+    /// Fail (exit status 2) when a dependency is this far behind the latest release, or further
+    #[arg(long, value_enum)]
+    pub fail_on: Option<Behind>,
+
+    /// Fail (exit status 2) when an outdated dependency's release is older than this many days
+    #[arg(long, value_name = "DAYS")]
+    pub max_age: Option<u64>,
+
+    /// Where crates.io responses are cached for a day (default: the user cache directory)
+    #[arg(long)]
+    pub cache_dir: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct OwnersArgs {
+    /// Path to Cargo.toml
+    #[arg(long, short = 'm', default_value = "Cargo.toml")]
+    pub manifest_path: PathBuf,
+
+    /// Output file path (stdout if not specified)
+    #[arg(long, short = 'o')]
+    pub output: Option<PathBuf>,
+
+    /// Report format
+    #[arg(long, short = 'f', value_enum, default_value = "markdown")]
+    pub format: ReportFormat,
+
+    /// Only dependencies declared by workspace members
+    #[arg(long)]
+    pub direct_only: bool,
+
+    /// Exclude dev-dependencies
+    #[arg(long)]
+    pub no_dev: bool,
+
+    /// Exclude build-dependencies
+    #[arg(long)]
+    pub no_build: bool,
+
+    /// Where crates.io responses are cached for a day (default: the user cache directory)
+    #[arg(long)]
+    pub cache_dir: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct AssertArgs {
+    /// Assertions, one per line: `[no] node A`, `[no] edge A -> B`, `[no] path A -> B`,
+    /// `nodes < N` or `edges < N` (also <=, >, >=, ==, !=)
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+
+    /// Report file path (stdout if not specified)
+    #[arg(long, short = 'o')]
+    pub output: Option<PathBuf>,
+
+    /// Command building the graph, after `--` (default: `deps`), e.g. `-- fn-graph --source-dir src`
+    #[arg(last = true, value_name = "COMMAND")]
+    pub command: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct BatchArgs {
+    /// TOML file of `[[project]]` tables: `manifest_path` (relative to the file), optional `name`
+    /// and extra `args`
+    #[arg(long, value_name = "FILE")]
+    pub projects: PathBuf,
+
+    /// Directory for the per-project outputs (`<name>.<ext>`) and summary.json
+    #[arg(long, value_name = "DIR", default_value = "batch")]
+    pub out_dir: PathBuf,
+
+    /// Report file path (stdout if not specified)
+    #[arg(long, short = 'o')]
+    pub output: Option<PathBuf>,
+
+    /// Command run for each project with its --manifest-path, after `--` (default: `deps`),
+    /// e.g. `-- deps --stats -f json`
+    #[arg(last = true, value_name = "COMMAND")]
+    pub command: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct WhyArgs {
+    /// Crate to explain, as `name` or `name@version`
+    #[arg(value_name = "CRATE")]
+    pub crate_name: String,
+
+    /// Path to Cargo.toml
+    #[arg(long, short = 'm', default_value = "Cargo.toml")]
+    pub manifest_path: PathBuf,
+
+    /// Output file path (stdout if not specified)
+    #[arg(long, short = 'o')]
+    pub output: Option<PathBuf>,
+
+    /// Output format: one `app -> tokio -> mio` chain per line, or the paths as a diagram
+    #[arg(long, short = 'f', value_enum, default_value = "text")]
+    pub format: WhyFormat,
+
+    /// Only the N shortest paths (0 = all)
+    #[arg(long, short = 'n', value_name = "N", default_value = "0")]
+    pub limit: usize,
+
+    /// Exclude dev-dependencies
+    #[arg(long)]
+    pub no_dev: bool,
+
+    /// Exclude build-dependencies
+    #[arg(long)]
+    pub no_build: bool,
+
+    /// Omit code fence markers (```mermaid)
+    #[arg(long)]
+    pub no_fence: bool,
+
+    /// Graph direction: LR (left-right) or TB (top-bottom)
+    #[arg(long, short = 'd', default_value = "LR")]
+    pub direction: String,
+
+    /// Color theme
+    #[arg(long, value_enum, default_value = "default")]
+    pub theme: Theme,
+
+    /// Mermaid release to write for: 8 (no subgraph directions), 10 (`%%{init}%%` directive
+    /// first) or 11 (frontmatter config); by default the syntax current GitHub renders
+    #[arg(long, value_enum, value_name = "VERSION")]
+    pub mermaid_version: Option<MermaidVersion>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum WhyFormat {
+    Text,
+    Mermaid,
+    Dot,
+}
+
+/// Points in history that `history --every` analyzes
+#[derive(Clone, Copy)]
+pub enum HistoryEvery {
+    Commits(usize),
+    Tags,
+}
+
+impl std::str::FromStr for HistoryEvery {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "tag" | "tags" => Ok(HistoryEvery::Tags),
+            _ => match value.parse() {
+                Ok(0) | Err(_) => Err(format!("expected a positive number of commits or \"tag\", found '{}'", value)),
+                Ok(n) => Ok(HistoryEvery::Commits(n)),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum PageFormat {
+    Markdown,
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ReportFormat {
+    Markdown,
+    Json,
+}
+
+/// How far a used version is behind the latest stable release
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum Behind {
+    #[value(skip)]
+    UpToDate,
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Behind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Behind::UpToDate => "up to date",
+            Behind::Patch => "patch",
+            Behind::Minor => "minor",
+            Behind::Major => "major",
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum HistoryFormat {
+    Json,
+    Csv,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Preset {
+    /// Runtime dependencies only, each crate shown once
+    Minimal,
+    /// Workspace members only, deduplicated, clustered by member, dark theme
+    Docs,
+    /// Every dependency kind with resolved versions, git commits, checksums, licenses, duplicate
+    /// versions and RustSec advisories shown
+    Audit,
+}
+
+#[derive(Clone, ValueEnum)]
+pub enum CombineOp {
+    Union,
+    Intersection,
+    Difference,
+}
+
+#[derive(Clone, Default, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Mermaid,
+    Dot,
+    Json,
+    /// LSP callHierarchy items with incoming/outgoing calls of the --focus function (fn-graph only)
+    CallHierarchy,
+    /// SARIF 2.1.0 log of findings such as unreachable functions (fn-graph only)
+    Sarif,
+    /// Nodes (id/label/group/metadata) and edges for VS Code graph webviews, with absolute file URIs
+    VscodeGraph,
+    /// Markdown for `$GITHUB_STEP_SUMMARY`: totals, the Mermaid diagram and a node table, within GitHub's limits
+    GhSummary,
+}
+
+impl OutputFormat {
+    /// Name as written on the command line
+    pub fn name(&self) -> String {
+        self.to_possible_value().map(|value| value.get_name().to_string()).unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorBy {
+    /// Distance from the roots (deps) or from the focus/entry function (fn-graph)
+    Depth,
+    /// Value of the --color-metric metric, from low (yellow) to high (dark red)
+    Metric,
+    /// Compiled function size from --size-data (fn-graph; same as `--color-metric size`)
+    Size,
+    /// Kind of crate (async, serialization, crypto, web, ...) from its crates.io categories and keywords (deps)
+    Category,
+    /// Commits among a function's current lines, from git blame (fn-graph; same as `--churn --color-metric churn`)
+    Churn,
+    /// Days since a function's lines last changed, from git blame (fn-graph; same as `--churn --color-metric age`)
+    Age,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SizeBy {
+    /// Lines of Rust in the package directory (without target/ and nested packages)
+    Loc,
+    /// Rust files in the package directory
+    Files,
+    /// Crates the crate depends on, directly or transitively
+    Deps,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Resolver {
+    /// Match callee names against collected functions (syn only)
+    #[default]
+    Heuristic,
+    /// Ask rust-analyzer for each call's definition, falling back to the heuristic
+    Ra,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RankWorkspace {
+    /// All workspace members on one rank
+    Same,
+    /// Workspace members on the first rank (left with LR)
+    Source,
+}
+
+#[derive(Clone, Default, ValueEnum)]
+pub enum Theme {
+    #[default]
+    Default,
+    Light,
+    Dark,
+}
+
+/// Mermaid release the output must render in, for sites bundling older ones
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MermaidVersion {
+    #[value(name = "8")]
+    V8,
+    #[value(name = "10")]
+    V10,
+    #[value(name = "11")]
+    V11,
+}
+
+/// A run whose output is still written, but that must exit with status 2
+/// (e.g. `combine --exit-code` finding changes)
+#[derive(Debug)]
+pub struct CheckFailed {
+    pub message: String,
+    pub output: String,
+    pub output_path: Option<PathBuf>,
+}
+
+impl std::fmt::Display for CheckFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CheckFailed {}
+
+/// Kind of warning, for --deny and --allow and the `warnings` of JSON output
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum WarningCode {
+    /// Calls rust-analyzer could not resolve, resolved by name instead
+    UnresolvedCall,
+    /// Nodes that would share a diagram ID and were renamed
+    IdCollision,
+    /// Source or advisory files skipped because they do not parse
+    ParseError,
+    /// An --exclude pattern, root, slice, metric or data file that matched nothing
+    NoMatch,
+    /// crates.io, advisory database, Cargo.lock or git data that could not be read
+    MissingData,
+}
+
+impl WarningCode {
+    /// Name as written on the command line
+    pub fn name(self) -> String {
+        self.to_possible_value().map(|value| value.get_name().to_string()).unwrap_or_default()
+    }
+}
+
+/// A warning printed during a run
+#[derive(Clone)]
+pub struct Warning {
+    pub code: WarningCode,
+    pub message: String,
+}
+
+// ============================================================================
+// Data Structures - Graph Model
+// ============================================================================
+
+/// A directed graph plus a lookup from each node's key to its index
+/// (see `utils::graph` for the operations shared by every graph kind)
+pub struct Graph<K, N, E> {
+    pub graph: DiGraph<N, E>,
+    pub node_indices: HashMap<K, NodeIndex>,
+    /// Filters that shrank the graph, in the order applied (drawn as the filter legend)
+    pub filters: Vec<AppliedFilter>,
+}
+
+#[derive(Clone)]
+pub struct AppliedFilter {
+    /// The option as given, e.g. `--focus serde`
+    pub description: String,
+    /// Nodes and edges it removed; unknown for the ones applied while the graph is built
+    pub removed: Option<(usize, usize)>,
+}
+
+// ============================================================================
+// Data Structures - Deps
+// ============================================================================
+
+#[derive(Clone, Default)]
+pub struct NodeInfo {
+    pub name: String,
+    pub version: String,
+    #[allow(dead_code)]
+    pub kind: DepKind,
+    pub is_workspace_member: bool,
+    pub license: Option<String>,
+    pub description: Option<String>,
+    pub repository: Option<String>,
+    pub homepage: Option<String>,
+    pub documentation: Option<String>,
+    /// Cargo source ID for non-path packages, e.g. `git+https://...?rev=v1#<commit>`
+    pub source: Option<String>,
+    /// Registry of registry packages: `crates-io`, the name .cargo/config.toml gives it, or its index host
+    pub registry: Option<String>,
+    /// Cargo.lock checksum of registry packages (set with --show-checksums)
+    pub checksum: Option<String>,
+    /// crates.io owners: user logins, and teams as `github:org:team` (set with --show-owners)
+    pub owners: Option<Vec<String>>,
+    /// IDs of the RustSec advisories affecting this version, empty when none (set with --audit)
+    pub advisories: Option<Vec<String>>,
+    /// Latest stable release on crates.io and how far this version is behind it (set with --check-outdated)
+    pub latest_version: Option<String>,
+    pub behind: Option<Behind>,
+    /// Features enabling each optional dependency, by dependency name (set with --show-feature-gates)
+    pub feature_gates: BTreeMap<String, Vec<String>>,
+    /// Every version of the crate in the graph, oldest first, when there are several (set with --show-duplicates)
+    pub duplicate_versions: Option<Vec<String>>,
+    /// Pseudo-node standing for a package's build.rs (with --split-build-scripts)
+    pub is_build_script: bool,
+    /// Only pulled in through `optional = true` dependencies, i.e. by enabled features
+    pub is_optional: bool,
+    /// Distance from the root packages (set with --color-by depth)
+    pub depth: Option<usize>,
+    /// Fewest hops from any workspace member (set with --relative-depth)
+    pub distance: Option<usize>,
+    /// Cluster from --groups
+    pub group: Option<NodeGroup>,
+    /// Coarse category from the manifest's crates.io categories and keywords, for dependencies
+    pub category: Option<String>,
+    /// Lines of Rust, Rust files or transitive dependencies (set with --size-by)
+    pub size: Option<u64>,
+    /// Version in the older graph, for a crate `combine --op difference` found updated
+    pub previous_version: Option<String>,
+    /// Custom metrics from --metric-script, by name
+    pub metrics: BTreeMap<String, f64>,
+}
+
+/// Named cluster a node was assigned to by `--groups`
+#[derive(Clone)]
+pub struct NodeGroup {
+    pub name: String,
+    pub color: String,
+    /// Layout direction inside the Mermaid subgraph (TB, BT, LR or RL), overriding --direction
+    pub direction: Option<String>,
+}
+
+/// Where cargo got a package from, as told by its source ID
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SourceKind {
+    /// A workspace member
+    Workspace,
+    /// The crates.io index
+    CratesIo,
+    /// Another registry
+    Registry,
+    /// A git repository
+    Git,
+    /// A local directory outside the workspace (`path = ...` or a [patch] override)
+    Path,
+}
+
+impl SourceKind {
+    /// Name in JSON output and --filter expressions
+    pub fn name(self) -> &'static str {
+        match self {
+            SourceKind::Workspace => "workspace",
+            SourceKind::CratesIo => "crates-io",
+            SourceKind::Registry => "registry",
+            SourceKind::Git => "git",
+            SourceKind::Path => "path",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DepKind {
+    #[default]
+    Normal,
+    Dev,
+    Build,
+}
+
+impl DepKind {
+    pub const ALL: [DepKind; 3] = [DepKind::Normal, DepKind::Build, DepKind::Dev];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DepKind::Normal => "normal",
+            DepKind::Dev => "dev",
+            DepKind::Build => "build",
+        }
+    }
+
+    fn bit(self) -> u8 {
+        match self {
+            DepKind::Normal => 1,
+            DepKind::Build => 2,
+            DepKind::Dev => 4,
+        }
+    }
+}
+
+/// Every kind a dependency is declared with (a crate can be both a normal and a dev dependency)
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct DepKinds(u8);
+
+impl DepKinds {
+    pub fn insert(&mut self, kind: DepKind) {
+        self.0 |= kind.bit();
+    }
+
+    pub fn remove(&mut self, kind: DepKind) {
+        self.0 &= !kind.bit();
+    }
+
+    pub fn contains(self, kind: DepKind) -> bool {
+        self.0 & kind.bit() != 0
+    }
+
+    pub fn union(self, other: DepKinds) -> DepKinds {
+        DepKinds(self.0 | other.0)
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Kinds in display order: normal, build, dev
+    pub fn iter(self) -> impl Iterator<Item = DepKind> {
+        DepKind::ALL.into_iter().filter(move |kind| self.contains(*kind))
+    }
+
+    /// The kind that decides arrow style: normal over build over dev
+    pub fn primary(self) -> DepKind {
+        self.iter().next().unwrap_or(DepKind::Normal)
+    }
+}
+
+impl From<DepKind> for DepKinds {
+    fn from(kind: DepKind) -> Self {
+        DepKinds(kind.bit())
+    }
+}
+
+/// Dependency graph, keyed by package id
+pub type GraphData = Graph<PackageId, NodeInfo, DepKinds>;
+
+#[cfg(feature = "native")]
+pub use cargo_metadata::PackageId;
+
+/// Stand-in for `cargo_metadata::PackageId` without the `native` feature
+#[cfg(not(feature = "native"))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PackageId {
+    pub repr: String,
+}
+
+// ============================================================================
+// Data Structures - Function Graph
+// ============================================================================
+
+#[derive(Clone)]
+pub struct FnNodeInfo {
+    pub name: String,
+    pub qualified_name: String,
+    pub file_path: String,
+    /// 1-based line and 0-based column of the function name
+    pub line: usize,
+    pub column: usize,
+    /// Last line of the whole item
+    pub end_line: usize,
+    pub is_method: bool,
+    /// Reachable by definition: main, #[test]/#[bench], #[no_mangle] or a trait impl method
+    pub is_entry_point: bool,
+    pub is_public: bool,
+    pub signature: Option<String>,
+    pub is_async: bool,
+    pub is_unsafe: bool,
+    /// Marked `#[allow(grapher::unreachable)]`: a root of the unreachable-function analysis
+    pub allow_unreachable: bool,
+    pub crate_name: Option<String>,
+    /// `FunctionDef::body_hash` and `content_hash`, when known (graphs loaded from older JSON have none)
+    pub body_hash: Option<u64>,
+    pub content_hash: Option<u64>,
+    /// const/static items and environment variables the function reads, sorted
+    pub config: Vec<ConfigRead>,
+    /// log/tracing macro calls, in source order
+    pub logs: Vec<LogCall>,
+    /// Filesystem, network and process APIs the function calls directly, sorted
+    pub capabilities: Vec<Capability>,
+    /// Qualified names of the types of the sources in the signature, sorted
+    pub types: Vec<String>,
+    /// Cargo features gating the function through `#[cfg]` on it, its impl or its modules, sorted
+    pub features: Vec<String>,
+    /// Paths the function is public under, fewest segments first: its definition's, if public, and
+    /// those of `pub use` re-exports (set with --reexports)
+    pub public_paths: Vec<String>,
+    /// Every path `pub use` items re-export the function under, public or not (set with --reexports)
+    pub reexports: Vec<Reexport>,
+    /// Distance from the focus or entry function (set with --color-by depth)
+    pub depth: Option<usize>,
+    /// Date the newest of the function's lines was committed, e.g. `2024-03-18` (set with --churn)
+    pub last_modified: Option<String>,
+    /// Outside the --only-files files, kept as a direct caller or callee of a function inside
+    pub is_context: bool,
+    /// Cluster from --groups
+    pub group: Option<NodeGroup>,
+    /// Custom metrics from --metric-script, by name
+    pub metrics: BTreeMap<String, f64>,
+}
+
+/// A configuration knob read by a function
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ConfigRead {
+    pub kind: ConfigKind,
+    /// Qualified name of the item, or the variable name
+    pub name: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConfigKind {
+    Const,
+    Static,
+    /// `env!`, `option_env!`, `std::env::var` or `var_os` with a literal name
+    Env,
+}
+
+impl ConfigKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ConfigKind::Const => "const",
+            ConfigKind::Static => "static",
+            ConfigKind::Env => "env",
+        }
+    }
+}
+
+/// A kind of side effect, recognized by the paths of the APIs called
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum Capability {
+    /// `std::fs`, `File`, `OpenOptions`, `tokio::fs`, ...
+    Fs,
+    /// `std::net`, `TcpStream`, `reqwest`, `hyper`, ...
+    Net,
+    /// `std::process::Command`, `tokio::process::Command`, ...
+    Process,
+}
+
+impl Capability {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Capability::Fs => "fs",
+            Capability::Net => "net",
+            Capability::Process => "process",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ValueEnum)]
+pub enum CallKind {
+    /// `f()` or `path::f()`
+    Direct,
+    /// `value.f()`
+    Method,
+    /// A call in the arguments of a macro, e.g. `println!("{}", f())`
+    Macro,
+    /// A call whose result is awaited, `f().await`
+    Await,
+}
+
+impl CallKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CallKind::Direct => "direct",
+            CallKind::Method => "method",
+            CallKind::Macro => "macro",
+            CallKind::Await => "await",
+        }
+    }
+}
+
+/// A call between two functions
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CallEdge {
+    pub kind: CallKind,
+    /// Profile samples with the caller directly calling the callee (--profile-data)
+    pub samples: Option<u64>,
+}
+
+impl From<CallKind> for CallEdge {
+    fn from(kind: CallKind) -> Self {
+        CallEdge { kind, samples: None }
+    }
+}
+
+/// Function call graph, keyed by qualified name
+pub type FnGraphData = Graph<String, FnNodeInfo, CallEdge>;
+
+/// A package, or one of its features, in the activation chains of `explain-features`
+pub struct FeatureNode {
+    /// `name@version`
+    pub package: String,
+    pub feature: Option<String>,
+    pub is_workspace_member: bool,
+    /// Belongs to the crate being explained
+    pub is_target: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FeatureEdge {
+    /// A dependency declaration asks for the feature (`features = [...]`, or `default`)
+    Requests,
+    /// An enabled feature turns the feature on (`[features]` entry)
+    Enables,
+}
+
+pub type FeatureGraph = DiGraph<FeatureNode, FeatureEdge>;
+
+#[derive(Clone)]
+pub struct FunctionDef {
+    pub name: String,
+    pub qualified_name: String,
+    pub is_public: bool,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub is_method: bool,
+    pub is_entry_point: bool,
+    pub signature: String,
+    pub is_async: bool,
+    pub is_unsafe: bool,
+    pub allow_unreachable: bool,
+    pub crate_name: Option<String>,
+    /// Hash of the normalized signature (without the name) and body, unchanged by a rename
+    pub body_hash: u64,
+    /// Hash of the whole normalized item but its doc comments: changes with any edit that matters
+    pub content_hash: u64,
+    /// Names the signature's types and bounds end with (`Self` as the impl type), sorted
+    pub signature_types: Vec<String>,
+    /// Cargo features in the `#[cfg]` of the function and its impl, sorted
+    pub features: Vec<String>,
+}
+
+/// A directory of Rust sources, optionally belonging to a workspace member
+pub struct SourceRoot {
+    pub crate_name: Option<String>,
+    pub dir: PathBuf,
+    /// File paths are reported relative to this directory
+    pub base: PathBuf,
+}
+
+/// An `info!`, `tracing::warn!(target: "db", ...)`, `event!(Level::DEBUG, ...)`, ... call
+#[derive(Clone)]
+pub struct LogCall {
+    pub level: String,
+    pub target: Option<String>,
+    /// The format string, if the macro has one
+    pub message: Option<String>,
+    pub line: usize,
+}
+
+/// A name brought in by a `pub use` item: `pub use a::{b, c as d};` gives two
+pub struct UseItem {
+    /// Module of the `use`, from the crate root
+    pub module: Vec<String>,
+    /// Path as written, `crate`/`self`/`super` included
+    pub target: Vec<String>,
+    /// Name it is re-exported as, `*` for a glob
+    pub alias: String,
+}
+
+/// A path a function is re-exported under by `pub use`
+#[derive(Clone)]
+pub struct Reexport {
+    pub path: String,
+    /// The paths it re-exports in turn, down to the definition
+    pub via: Vec<String>,
+}
+
+/// A path that may name a const/static item, or an environment variable read, inside a function
+pub struct ConfigUse {
+    pub function: String,
+    /// As written (`config::TIMEOUT`), or the variable name
+    pub name: String,
+    pub is_env: bool,
+}
+
+pub struct CallInfo {
+    pub caller: String,
+    pub callee: String,
+    pub kind: CallKind,
+    /// Position of the callee name at the call site
+    pub file_path: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Call sites and file locations kept aside for `--format call-hierarchy`
+#[derive(Default)]
+pub struct SourceLocations {
+    /// (caller, callee) qualified names -> (line, column) of every call
+    pub call_sites: HashMap<(String, String), Vec<(usize, usize)>>,
+    /// File path as shown on nodes -> absolute path
+    pub files: HashMap<String, PathBuf>,
+}
+
+/// A problem reported by one of the fn-graph analyses
+pub struct Finding {
+    pub rule_id: &'static str,
+    pub message: String,
+    pub file_path: String,
+    pub line: usize,
+    pub column: usize,
 }
 
 pub struct FunctionCollector {
+    pub crate_name: Option<String>,
     pub module_path: Vec<String>,
     pub functions: Vec<FunctionDef>,
     pub current_impl_type: Option<String>,
+    /// Inside `impl Trait for Type`
+    pub in_trait_impl: bool,
+    /// Qualified names of the const and static items
+    pub config_items: Vec<(String, ConfigKind)>,
+    /// Qualified names of the struct, enum, union, trait and type alias items
+    pub type_items: Vec<String>,
+    /// `pub use` items, by module path like `type_items`
+    pub pub_uses: Vec<UseItem>,
+    /// Paths of the `mod` items and whether they are `pub`
+    pub modules: Vec<(Vec<String>, bool)>,
+    /// Cargo features in the `#[cfg]` of the impl being visited
+    pub impl_features: Vec<String>,
+    /// Paths of the `mod` items with cargo features in their `#[cfg]`, and those features
+    pub gated_modules: Vec<(Vec<String>, Vec<String>)>,
 }
 
 pub struct CallCollector {
     pub current_function: String,
     pub calls: Vec<CallInfo>,
+    /// The call being visited is the operand of `.await`
+    pub awaiting: bool,
+    /// Nesting depth of macro arguments being visited
+    pub in_macro: usize,
+    pub config_uses: Vec<ConfigUse>,
+    pub log_calls: Vec<(String, LogCall)>,
 }
\ No newline at end of file
@@ -0,0 +1,153 @@
+// ============================================================================
+// RustSec Advisories (deps --audit)
+// ============================================================================
+//
+// The RustSec advisory database is a git repository with one Markdown file
+// per advisory, whose TOML front matter names the crate and the versions it
+// does not apply to:
+//
+//   crates/time/RUSTSEC-2020-0071.md
+//
+//   [advisory]
+//   id = "RUSTSEC-2020-0071"
+//   package = "time"
+//   [versions]
+//   patched = [">= 0.2.23"]
+//   unaffected = ["= 0.2.0", "= 0.2.1", "= 0.2.2"]
+//
+// A shallow clone is kept under <cache-dir>/advisory-db and pulled at most
+// once a day (never with --offline); --advisory-db points at an existing
+// checkout instead.
+// Withdrawn and informational (unmaintained, unsound, ...) advisories are
+// ignored.
+
+use std::{collections::HashMap, error::Error, fs, path::{Path, PathBuf}, process::Command, time::{Duration, SystemTime}};
+
+use cargo_metadata::semver::{Version, VersionReq};
+
+use crate::{types::{GraphData, WarningCode}, utils::{crates_io::default_cache_dir, helper::is_crates_io, toml::{self, TomlValue}, warnings::warn}};
+
+const ADVISORY_DB: &str = "https://github.com/rustsec/advisory-db";
+const UPDATE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+struct Advisory {
+    id: String,
+    /// Versions the advisory does not apply to: `patched` and `unaffected`
+    safe: Vec<VersionReq>,
+}
+
+impl Advisory {
+    fn affects(&self, version: &Version) -> bool {
+        !self.safe.iter().any(|req| req.matches(version))
+    }
+}
+
+/// Checkout of the advisory database: `db` as given, or the clone under `cache_dir`
+/// (or the user cache directory), cloned or pulled when older than a day unless `offline`
+pub fn advisory_db(db: Option<&Path>, cache_dir: Option<&Path>, offline: bool) -> Result<PathBuf, Box<dyn Error>> {
+    if let Some(db) = db {
+        if !db.join("crates").is_dir() {
+            return Err(format!("{} is not a RustSec advisory database (no crates/ directory)", db.display()).into());
+        }
+        return Ok(db.to_path_buf());
+    }
+
+    let dir = cache_dir.map(Path::to_path_buf).unwrap_or_else(default_cache_dir).join("advisory-db");
+    if offline && !dir.join(".git").is_dir() {
+        return Err(format!("No advisory database under {} to use offline; pass --advisory-db DIR", dir.display()).into());
+    }
+    if !dir.join(".git").is_dir() {
+        let parent = dir.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        eprintln!("Cloning the RustSec advisory database into {}", dir.display());
+        git(parent, &["clone", "--quiet", "--depth", "1", ADVISORY_DB, &dir.to_string_lossy()])?;
+        return Ok(dir);
+    }
+
+    // FETCH_HEAD is touched by every pull; a fresh clone has none yet
+    let git_dir = dir.join(".git");
+    let age = fs::metadata(git_dir.join("FETCH_HEAD")).or_else(|_| fs::metadata(git_dir.join("HEAD")))
+        .and_then(|m| m.modified()).ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+    if !offline && age.is_none_or(|age| age >= UPDATE_INTERVAL) {
+        let updated = git(&dir, &["fetch", "--quiet", "--depth", "1", "origin", "HEAD"])
+            .and_then(|_| git(&dir, &["reset", "--quiet", "--hard", "FETCH_HEAD"]));
+        if let Err(e) = updated {
+            warn(WarningCode::MissingData, format!("{}; using the advisory database as of the last update", e));
+        }
+    }
+    Ok(dir)
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("git {} failed: {}", args[0], String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+    Ok(())
+}
+
+/// Set `advisories` of every crates.io package to the IDs of the advisories affecting its version,
+/// returning how many packages have at least one
+pub fn attach_advisories(graph_data: &mut GraphData, db: &Path) -> usize {
+    let mut by_name: HashMap<String, Vec<Advisory>> = HashMap::new();
+    let mut vulnerable = 0;
+
+    for info in graph_data.graph.node_weights_mut() {
+        if info.is_build_script || !info.source.as_deref().is_some_and(is_crates_io) {
+            continue;
+        }
+        let Ok(version) = Version::parse(&info.version) else { continue };
+        let advisories = by_name.entry(info.name.clone()).or_insert_with(|| crate_advisories(db, &info.name));
+        let ids: Vec<String> = advisories.iter().filter(|a| a.affects(&version)).map(|a| a.id.clone()).collect();
+        if !ids.is_empty() {
+            vulnerable += 1;
+        }
+        info.advisories = Some(ids);
+    }
+    vulnerable
+}
+
+/// Advisories of a crate, by ID, warning about the files that cannot be read
+fn crate_advisories(db: &Path, name: &str) -> Vec<Advisory> {
+    let Ok(entries) = fs::read_dir(db.join("crates").join(name)) else {
+        return Vec::new();
+    };
+
+    let mut advisories: Vec<Advisory> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|path| parse_advisory(&path).unwrap_or_else(|e| {
+            warn(WarningCode::ParseError, format!("skipping {}: {}", path.display(), e));
+            None
+        }))
+        .collect();
+    advisories.sort_by(|a, b| a.id.cmp(&b.id));
+    advisories
+}
+
+/// The advisory in the ```toml block of an advisory file, or None when it is withdrawn or informational
+fn parse_advisory(path: &Path) -> Result<Option<Advisory>, Box<dyn Error>> {
+    let content = fs::read_to_string(path)?;
+    let front_matter = content.split("```toml").nth(1)
+        .and_then(|rest| rest.split("```").next())
+        .ok_or("no ```toml front matter")?;
+    let document = toml::parse(front_matter)?;
+
+    let advisory = document.get("advisory").ok_or("no [advisory] table")?;
+    if advisory.get("withdrawn").is_some() || advisory.get("informational").is_some() {
+        return Ok(None);
+    }
+    let id = advisory.get("id").and_then(TomlValue::as_str).ok_or("no advisory id")?;
+
+    let mut safe = Vec::new();
+    for key in ["patched", "unaffected"] {
+        let reqs = document.get("versions").and_then(|versions| versions.get(key)).and_then(TomlValue::as_string_list);
+        for req in reqs.unwrap_or_default() {
+            safe.push(VersionReq::parse(&req).map_err(|e| format!("invalid version requirement '{}': {}", req, e))?);
+        }
+    }
+    Ok(Some(Advisory { id: id.to_string(), safe }))
+}
@@ -0,0 +1,258 @@
+// ============================================================================
+// rust-analyzer Resolution Backend (--resolver ra)
+// ============================================================================
+//
+// Talks LSP over stdio to a `rust-analyzer` process (or `$RUST_ANALYZER`) and
+// asks for the definition of every call site. Any failure is reported to the
+// caller, which falls back to the syn heuristics.
+
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde_json::{json, Value};
+
+use crate::utils::helper::{file_uri, path_from_file_uri};
+
+/// Loading a large workspace can take a while
+const READY_TIMEOUT: Duration = Duration::from_secs(300);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct RustAnalyzer {
+    child: Child,
+    stdin: ChildStdin,
+    messages: Receiver<Value>,
+    next_id: u64,
+    /// Whether the server agreed to count columns in chars (UTF-32) rather than UTF-16 code units
+    utf32: bool,
+    /// Lines of the files asked about, read once to convert columns to UTF-16
+    lines: HashMap<PathBuf, Vec<String>>,
+}
+
+impl RustAnalyzer {
+    /// Spawn rust-analyzer for `root` and wait until it has finished loading the workspace
+    pub fn start(root: &Path) -> Result<Self, String> {
+        let program = env::var("RUST_ANALYZER").unwrap_or_else(|_| "rust-analyzer".to_string());
+        let mut child = Command::new(&program)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("failed to start {}: {}", program, e))?;
+
+        let stdin = child.stdin.take().ok_or("rust-analyzer has no stdin")?;
+        let stdout = child.stdout.take().ok_or("rust-analyzer has no stdout")?;
+
+        // Read on a thread so requests can time out
+        let (sender, messages) = mpsc::channel();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(stdout);
+            while let Some(message) = read_message(&mut reader) {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut analyzer = RustAnalyzer { child, stdin, messages, next_id: 0, utf32: false, lines: HashMap::new() };
+        analyzer.initialize(root)?;
+        Ok(analyzer)
+    }
+
+    fn initialize(&mut self, root: &Path) -> Result<(), String> {
+        let root_uri = file_uri(root);
+        let result = self.request("initialize", json!({
+            "processId": std::process::id(),
+            "rootUri": root_uri,
+            "workspaceFolders": [{ "uri": root_uri, "name": "root" }],
+            "capabilities": {
+                "general": { "positionEncodings": ["utf-32", "utf-16"] },
+                "experimental": { "serverStatusNotification": true }
+            },
+            "initializationOptions": {
+                "cachePriming": { "enable": false },
+                "checkOnSave": false
+            }
+        }), REQUEST_TIMEOUT)?;
+        // Servers that don't answer use the LSP default, UTF-16
+        self.utf32 = result["capabilities"]["positionEncoding"] == "utf-32";
+        self.notify("initialized", json!({}))?;
+
+        // Definitions are only reliable once the server reports it is quiescent
+        let deadline = Instant::now() + READY_TIMEOUT;
+        loop {
+            let message = self.receive(deadline)?;
+            if message["method"] == "experimental/serverStatus" {
+                let params = &message["params"];
+                if params["health"] == "error" {
+                    let reason = params["message"].as_str().unwrap_or("unknown error");
+                    return Err(format!("rust-analyzer failed to load the workspace: {}", reason));
+                }
+                if params["quiescent"] == true {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// File and 1-based line of the definition for the symbol at `line` (1-based) and `column`
+    /// (0-based, in chars as proc_macro2 counts them)
+    pub fn definition(&mut self, file: &Path, line: usize, column: usize) -> Result<Option<(PathBuf, usize)>, String> {
+        let column = match self.utf32 {
+            true => column,
+            false => {
+                let lines = self.lines.entry(file.to_path_buf()).or_insert_with(|| read_lines(file));
+                utf16_column(lines, line, column)
+            }
+        };
+        let result = self.request("textDocument/definition", json!({
+            "textDocument": { "uri": file_uri(file) },
+            "position": { "line": line.saturating_sub(1), "character": column }
+        }), REQUEST_TIMEOUT)?;
+
+        // Location | Location[] | LocationLink[]
+        let location = match &result {
+            Value::Array(items) => items.first(),
+            Value::Null => None,
+            other => Some(other),
+        };
+        let Some(location) = location else {
+            return Ok(None);
+        };
+
+        let uri = location["targetUri"].as_str().or_else(|| location["uri"].as_str());
+        let range = if location["targetSelectionRange"].is_object() {
+            &location["targetSelectionRange"]
+        } else {
+            &location["range"]
+        };
+        let start_line = range["start"]["line"].as_u64();
+
+        Ok(match (uri.and_then(path_from_file_uri), start_line) {
+            (Some(path), Some(start_line)) => Some((path, start_line as usize + 1)),
+            _ => None,
+        })
+    }
+
+    fn request(&mut self, method: &str, params: Value, timeout: Duration) -> Result<Value, String> {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(&json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))?;
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let message = self.receive(deadline)?;
+            if message["id"] == id && message.get("method").is_none() {
+                if let Some(error) = message.get("error") {
+                    return Err(format!("rust-analyzer {} failed: {}", method, error["message"]));
+                }
+                return Ok(message["result"].clone());
+            }
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: Value) -> Result<(), String> {
+        self.send(&json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+    }
+
+    /// Next message from the server, answering its own requests along the way
+    fn receive(&mut self, deadline: Instant) -> Result<Value, String> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        let message = match self.messages.recv_timeout(timeout) {
+            Ok(message) => message,
+            Err(RecvTimeoutError::Timeout) => return Err("rust-analyzer did not respond in time".to_string()),
+            Err(RecvTimeoutError::Disconnected) => return Err("rust-analyzer exited unexpectedly".to_string()),
+        };
+
+        if let (Some(id), Some(method)) = (message.get("id"), message["method"].as_str()) {
+            // workspace/configuration expects one entry per requested item
+            let result = if method == "workspace/configuration" {
+                let items = message["params"]["items"].as_array().map_or(0, Vec::len);
+                Value::Array(vec![Value::Null; items])
+            } else {
+                Value::Null
+            };
+            self.send(&json!({ "jsonrpc": "2.0", "id": id, "result": result }))?;
+        }
+
+        Ok(message)
+    }
+
+    fn send(&mut self, message: &Value) -> Result<(), String> {
+        let body = message.to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body)
+            .and_then(|()| self.stdin.flush())
+            .map_err(|e| format!("failed to write to rust-analyzer: {}", e))
+    }
+}
+
+impl Drop for RustAnalyzer {
+    fn drop(&mut self) {
+        let deadline = Duration::from_secs(5);
+        if self.request("shutdown", Value::Null, deadline).is_ok() {
+            let _ = self.notify("exit", Value::Null);
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Lines of `file`, none when it cannot be read
+fn read_lines(file: &Path) -> Vec<String> {
+    fs::read_to_string(file)
+        .map(|content| content.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// `column` chars into `line` (1-based) of `lines`, in UTF-16 code units; unchanged when there
+/// is no such line
+fn utf16_column(lines: &[String], line: usize, column: usize) -> usize {
+    match lines.get(line.saturating_sub(1)) {
+        Some(text) => text.chars().take(column).map(char::len_utf16).sum::<usize>() + column.saturating_sub(text.chars().count()),
+        None => column,
+    }
+}
+
+/// One `Content-Length`-framed JSON-RPC message, or `None` at end of stream
+fn read_message(reader: &mut impl BufRead) -> Option<Value> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).ok()? == 0 {
+            return None;
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            content_length = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let mut body = vec![0; content_length?];
+    reader.read_exact(&mut body).ok()?;
+    serde_json::from_slice(&body).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utf16_column_counts_code_units() {
+        let lines = vec!["let a = 1;".to_string(), "let s = \"𝄞é\"; f(s);".to_string()];
+        assert_eq!(utf16_column(&lines, 1, 4), 4);
+        // 𝄞 is two UTF-16 code units, é one
+        assert_eq!(utf16_column(&lines, 2, 14), 15);
+        assert_eq!(utf16_column(&lines, 3, 7), 7);
+    }
+}
@@ -0,0 +1,282 @@
+// ============================================================================
+// Graph Assertions (`assert`)
+// ============================================================================
+//
+// Checks expectations about a freshly built graph, one per line:
+//
+//   edge api -> core          api depends on (or calls) core directly
+//   no edge core -> api
+//   path app -> serde*        through any number of crates
+//   no path domain -> sqlx*   the domain layer never reaches the database
+//   node tokio                some node matches
+//   no node openssl*
+//   nodes < 500               also <=, >, >=, ==, != and `edges`
+//
+// Names match a node's name, qualified name or ID, with * wildcards, and
+// `→` works as well as `->`. Lines starting with `#` are comments. The graph
+// comes from the command line after `--` (`deps` by default), built as JSON:
+//
+//   rust-grapher assert arch.txt -- fn-graph --source-dir src
+//
+// Every assertion gets an `ok` or `FAIL` line, with the offending path or
+// edge for a failed `no ...`; any failure exits with status 2.
+
+use std::{collections::{HashMap, HashSet, VecDeque}, error::Error, fs, path::{Path, PathBuf}};
+
+use crate::{types::{AssertArgs, CheckFailed, Commands, OutputFormat, WarningCode}, utils::{cli::{parse_cli, run_cli}, helper::matches_any_pattern, warnings::warn}};
+
+enum Expectation {
+    Node(String),
+    Edge(String, String),
+    Path(String, String),
+    Count { edges: bool, op: String, value: usize },
+}
+
+struct Assertion {
+    line: usize,
+    text: String,
+    negated: bool,
+    expectation: Expectation,
+}
+
+/// Nodes (display name, and the names patterns match) and edges of the JSON graph
+struct CheckedGraph {
+    nodes: Vec<(String, Vec<String>)>,
+    successors: Vec<Vec<usize>>,
+    edge_count: usize,
+}
+
+pub fn run_assert(args: &AssertArgs) -> Result<(String, Option<PathBuf>), Box<dyn Error>> {
+    let assertions = load_assertions(&args.file)?;
+    let graph = build_graph(&args.command)?;
+
+    let mut output = String::new();
+    let mut failed = 0;
+    for assertion in &assertions {
+        let (passed, detail) = graph.check(assertion);
+        if !passed {
+            failed += 1;
+        }
+        let status = if passed { "ok" } else { "FAIL" };
+        let detail = detail.map(|detail| format!(" ({})", detail)).unwrap_or_default();
+        output.push_str(&format!("{:<5} {}: {}{}\n", status, assertion.line, assertion.text, detail));
+    }
+
+    if failed > 0 {
+        let message = format!("{} of {} assertions in {} failed", failed, assertions.len(), args.file.display());
+        return Err(Box::new(CheckFailed { message, output, output_path: args.output.clone() }));
+    }
+    Ok((output, args.output.clone()))
+}
+
+fn load_assertions(path: &Path) -> Result<Vec<Assertion>, Box<dyn Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let mut assertions = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let text = line.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+        let (negated, rest) = match text.strip_prefix("no ") {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, text),
+        };
+        let expectation = parse_expectation(rest, negated)
+            .map_err(|e| format!("{}:{}: {}", path.display(), index + 1, e))?;
+        assertions.push(Assertion { line: index + 1, text: text.to_string(), negated, expectation });
+    }
+
+    if assertions.is_empty() {
+        return Err(format!("{} holds no assertions", path.display()).into());
+    }
+    Ok(assertions)
+}
+
+fn parse_expectation(text: &str, negated: bool) -> Result<Expectation, String> {
+    let (keyword, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let rest = rest.trim();
+    let endpoints = || -> Result<(String, String), String> {
+        let (from, to) = rest.split_once("->").or_else(|| rest.split_once('→'))
+            .ok_or_else(|| format!("expected `{} A -> B`", keyword))?;
+        match (from.trim(), to.trim()) {
+            ("", _) | (_, "") => Err(format!("expected `{} A -> B`", keyword)),
+            (from, to) => Ok((from.to_string(), to.to_string())),
+        }
+    };
+
+    match keyword {
+        "node" if !rest.is_empty() => Ok(Expectation::Node(rest.to_string())),
+        "edge" => endpoints().map(|(from, to)| Expectation::Edge(from, to)),
+        "path" => endpoints().map(|(from, to)| Expectation::Path(from, to)),
+        "nodes" | "edges" if !negated => {
+            let (op, value) = rest.split_once(char::is_whitespace)
+                .filter(|(op, _)| ["<", "<=", ">", ">=", "==", "!="].contains(op))
+                .ok_or_else(|| format!("expected `{} < N` (or <=, >, >=, ==, !=)", keyword))?;
+            let value = value.trim().parse().map_err(|_| format!("'{}' is not a count", value.trim()))?;
+            Ok(Expectation::Count { edges: keyword == "edges", op: op.to_string(), value })
+        }
+        _ => Err(format!("expected `node`, `edge`, `path` (each optionally after `no`), `nodes` or `edges`, found '{}'", text)),
+    }
+}
+
+/// Run the graph command with JSON output and read the graph back
+fn build_graph(command: &[String]) -> Result<CheckedGraph, Box<dyn Error>> {
+    let command: Vec<&str> = if command.is_empty() { vec!["deps"] } else { command.iter().map(String::as_str).collect() };
+    let mut cli = parse_cli(std::iter::once("rust-grapher").chain(command.iter().copied()))
+        .map_err(|e| e.render().to_string())?;
+
+    let output = match &mut cli.command {
+        Commands::Deps(args) if args.inject_map.is_none() => {
+            args.format = OutputFormat::Json;
+            args.output.is_some() || args.out_dir.is_some()
+        }
+        Commands::FnGraph(args) if args.inject_map.is_none() => {
+            args.format = OutputFormat::Json;
+            args.output.is_some() || args.out_dir.is_some()
+        }
+        Commands::Render(args) => {
+            args.format = OutputFormat::Json;
+            args.output.is_some()
+        }
+        _ => return Err(format!("assert checks the graph of deps, fn-graph or render, not `{}`", command.join(" ")).into()),
+    };
+    if output {
+        return Err("--output and --out-dir are not allowed in the graph command of assert".into());
+    }
+
+    let (json, _) = run_cli(&cli)?;
+    let json: serde_json::Value = serde_json::from_str(&json)?;
+    Ok(CheckedGraph::from_json(&json))
+}
+
+impl CheckedGraph {
+    fn from_json(json: &serde_json::Value) -> Self {
+        let empty = Vec::new();
+        let nodes = json["nodes"].as_array().unwrap_or(&empty);
+        let edges = json["edges"].as_array().unwrap_or(&empty);
+
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        let nodes: Vec<(String, Vec<String>)> = nodes.iter().enumerate()
+            .map(|(i, node)| {
+                let id = node["id"].as_str().unwrap_or_default();
+                index.insert(id, i);
+                let names: Vec<String> = ["name", "qualified_name", "id"].iter()
+                    .filter_map(|key| node[*key].as_str().map(str::to_string))
+                    .collect();
+                let display = node["qualified_name"].as_str().or(node["name"].as_str()).unwrap_or(id).to_string();
+                (display, names)
+            })
+            .collect();
+
+        let mut successors = vec![Vec::new(); nodes.len()];
+        for edge in edges {
+            let from = edge["from"].as_str().and_then(|id| index.get(id));
+            let to = edge["to"].as_str().and_then(|id| index.get(id));
+            if let (Some(&from), Some(&to)) = (from, to) {
+                successors[from].push(to);
+            }
+        }
+        CheckedGraph { nodes, successors, edge_count: edges.len() }
+    }
+
+    /// Indices of the nodes `pattern` matches
+    fn matching(&self, pattern: &str) -> Vec<usize> {
+        let patterns = [pattern.to_string()];
+        (0..self.nodes.len())
+            .filter(|&i| self.nodes[i].1.iter().any(|name| matches_any_pattern(name, &patterns)))
+            .collect()
+    }
+
+    /// Whether the assertion holds, and what to show beside it
+    fn check(&self, assertion: &Assertion) -> (bool, Option<String>) {
+        let endpoints = |from: &str, to: &str| -> Result<(Vec<usize>, Vec<usize>), String> {
+            let (sources, targets) = (self.matching(from), self.matching(to));
+            let unmatched = [(from, &sources), (to, &targets)].into_iter().find(|(_, nodes)| nodes.is_empty());
+            match unmatched {
+                Some((pattern, _)) => Err(format!("'{}' matches no node", pattern)),
+                None => Ok((sources, targets)),
+            }
+        };
+
+        let found = match &assertion.expectation {
+            Expectation::Count { edges, op, value } => {
+                let (count, what) = if *edges { (self.edge_count, "edges") } else { (self.nodes.len(), "nodes") };
+                let holds = match op.as_str() {
+                    "<" => count < *value,
+                    "<=" => count <= *value,
+                    ">" => count > *value,
+                    ">=" => count >= *value,
+                    "==" => count == *value,
+                    _ => count != *value,
+                };
+                return (holds, Some(format!("{} {}", count, what)));
+            }
+            Expectation::Node(pattern) => self.matching(pattern).first().map(|&i| vec![i]).ok_or(format!("'{}' matches no node", pattern)),
+            Expectation::Edge(from, to) => endpoints(from, to).and_then(|(sources, targets)| {
+                sources.iter()
+                    .find_map(|&from| self.successors[from].iter().find(|to| targets.contains(to)).map(|&to| vec![from, to]))
+                    .ok_or_else(|| "no such edge".to_string())
+            }),
+            Expectation::Path(from, to) => endpoints(from, to).and_then(|(sources, targets)| {
+                self.shortest_path(&sources, &targets).ok_or_else(|| "no such path".to_string())
+            }),
+        };
+
+        match (found, assertion.negated) {
+            (Ok(_), false) => (true, None),
+            (Err(detail), false) => (false, Some(detail)),
+            (Ok(nodes), true) => (false, Some(self.describe(&nodes))),
+            (Err(detail), true) => {
+                // A typo in `no path` would pass silently
+                if detail.ends_with("matches no node") && !matches!(assertion.expectation, Expectation::Node(_)) {
+                    warn(WarningCode::NoMatch, format!("assertion on line {}: {}", assertion.line, detail));
+                }
+                (true, None)
+            }
+        }
+    }
+
+    /// A shortest path of at least one edge from a source to a target, breadth first
+    fn shortest_path(&self, sources: &[usize], targets: &[usize]) -> Option<Vec<usize>> {
+        let mut parent: HashMap<usize, usize> = HashMap::new();
+        let mut seen: HashSet<usize> = HashSet::new();
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        for &source in sources {
+            for &next in &self.successors[source] {
+                if seen.insert(next) {
+                    parent.insert(next, source);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        while let Some(node) = queue.pop_front() {
+            if targets.contains(&node) {
+                let mut path = vec![node];
+                let mut current = node;
+                while let Some(&previous) = parent.get(&current) {
+                    path.push(previous);
+                    if sources.contains(&previous) {
+                        break;
+                    }
+                    current = previous;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for &next in &self.successors[node] {
+                if seen.insert(next) {
+                    parent.insert(next, node);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    fn describe(&self, nodes: &[usize]) -> String {
+        nodes.iter().map(|&i| self.nodes[i].0.as_str()).collect::<Vec<_>>().join(" -> ")
+    }
+}
@@ -0,0 +1,78 @@
+// ============================================================================
+// Badges - shields.io Endpoint JSON (--badges)
+// ============================================================================
+//
+// One file per badge in the --badges directory, in the shields.io endpoint
+// schema, for a README badge backed by a CI artifact:
+//
+//   {"schemaVersion": 1, "label": "dependencies", "message": "42", "color": "blue"}
+//   https://img.shields.io/endpoint?url=https://example.org/badges/dependencies.json
+
+use std::{collections::HashSet, error::Error, path::Path};
+
+use petgraph::graph::NodeIndex;
+
+use crate::{types::{FnGraphData, GraphData}, utils::helper::write_atomic};
+
+/// A badge: file stem, label, count and color
+pub struct Badge {
+    pub name: &'static str,
+    pub label: &'static str,
+    pub count: usize,
+    pub color: &'static str,
+}
+
+impl Badge {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&serde_json::json!({
+            "schemaVersion": 1,
+            "label": self.label,
+            "message": self.count.to_string(),
+            "color": self.color
+        }))
+        .unwrap_or_else(|_| "{}".to_string())
+    }
+}
+
+/// Third-party crates in the graph, and those the workspace members depend on directly
+pub fn deps_badges(graph_data: &GraphData) -> Vec<Badge> {
+    let graph = &graph_data.graph;
+    let is_dependency = |idx: NodeIndex| !graph[idx].is_workspace_member && !graph[idx].is_build_script;
+
+    let total: HashSet<NodeIndex> = graph.node_indices().filter(|&idx| is_dependency(idx)).collect();
+    let direct: HashSet<NodeIndex> = graph.edge_indices()
+        .filter_map(|edge| graph.edge_endpoints(edge))
+        .filter(|&(from, to)| graph[from].is_workspace_member && is_dependency(to))
+        .map(|(_, to)| to)
+        .collect();
+
+    vec![
+        Badge { name: "dependencies", label: "dependencies", count: total.len(), color: "blue" },
+        Badge { name: "direct-dependencies", label: "direct dependencies", count: direct.len(), color: "blue" },
+    ]
+}
+
+/// `unsafe fn` definitions and public functions in the graph
+pub fn fn_badges(graph_data: &FnGraphData) -> Vec<Badge> {
+    let unsafe_fns = graph_data.graph.node_weights().filter(|info| info.is_unsafe).count();
+    let public_fns = graph_data.graph.node_weights().filter(|info| info.is_public).count();
+
+    vec![
+        Badge {
+            name: "unsafe-fns",
+            label: "unsafe fns",
+            count: unsafe_fns,
+            color: if unsafe_fns == 0 { "brightgreen" } else { "orange" },
+        },
+        Badge { name: "public-api", label: "public API", count: public_fns, color: "blue" },
+    ]
+}
+
+/// Write `<dir>/<name>.json` for each badge
+pub fn write_badges(dir: &Path, badges: &[Badge]) -> Result<(), Box<dyn Error>> {
+    for badge in badges {
+        write_atomic(&dir.join(format!("{}.json", badge.name)), badge.to_json())?;
+    }
+    eprintln!("Badges written to: {}", dir.display());
+    Ok(())
+}
@@ -0,0 +1,196 @@
+// ============================================================================
+// Batch - One Command over Many Projects (`batch`)
+// ============================================================================
+//
+// projects.toml lists the projects, e.g. repositories checked out side by
+// side in CI:
+//
+//   [[project]]
+//   name = "billing"
+//   manifest_path = "../billing/Cargo.toml"
+//   args = ["--no-dev"]
+//
+// The command after `--` (`deps` by default) runs for each of them with its
+// --manifest-path and extra `args`, and fn-graph finds --source-dir beside the
+// manifest. Each output goes to <out-dir>/<name>.<ext>, and
+// <out-dir>/summary.json collects how every run went:
+//
+//   rust-grapher batch --projects projects.toml --out-dir audit -- deps --stats -f json
+//
+// `name` defaults to the directory of the manifest, and a manifest_path may
+// also be the directory itself; paths are relative to projects.toml. A failed
+// project does not stop the others, but fails the batch with status 2.
+
+use std::{collections::HashSet, error::Error, fs, path::{Path, PathBuf}};
+
+use crate::{types::{BatchArgs, CheckFailed, Commands, OutputFormat, WarningCode}, utils::{cli::{parse_cli, run_cli}, helper::write_atomic, pages::diagram_file_name, toml::{self, TomlValue}, warnings::{collect, warn}}};
+
+struct Project {
+    name: String,
+    manifest_path: PathBuf,
+    args: Vec<String>,
+}
+
+/// How the command went for one project
+struct Outcome {
+    status: &'static str,
+    file: Option<PathBuf>,
+    error: Option<String>,
+}
+
+pub fn run_batch(args: &BatchArgs) -> Result<(String, Option<PathBuf>), Box<dyn Error>> {
+    let projects = load_projects(&args.projects)?;
+    if projects.is_empty() {
+        warn(WarningCode::NoMatch, format!("{} lists no [[project]]", args.projects.display()));
+    }
+    let command: Vec<&str> = if args.command.is_empty() { vec!["deps"] } else { args.command.iter().map(String::as_str).collect() };
+
+    let mut output = String::new();
+    let mut entries = Vec::new();
+    let mut failed = 0;
+    for project in &projects {
+        let (outcome, warnings) = collect(&[], &[], || run_project(project, &command, &args.out_dir));
+        if outcome.status != "ok" {
+            failed += 1;
+        }
+
+        let file = outcome.file.as_ref().map(|file| file.display().to_string()).unwrap_or_else(|| "-".to_string());
+        let detail = outcome.error.as_ref()
+            .map(|error| format!(" ({})", error.lines().map(str::trim).filter(|line| !line.is_empty()).collect::<Vec<_>>().join(" ")))
+            .unwrap_or_default();
+        let status = if outcome.status == "ok" { "ok" } else { "FAIL" };
+        output.push_str(&format!("{:<5} {}: {}{}\n", status, project.name, file, detail));
+
+        let mut entry = serde_json::json!({
+            "name": project.name,
+            "manifest_path": project.manifest_path.display().to_string(),
+            "status": outcome.status,
+            "output": outcome.file.map(|file| file.display().to_string()),
+            "warnings": warnings.iter()
+                .map(|warning| serde_json::json!({ "code": warning.code.name(), "message": warning.message }))
+                .collect::<Vec<_>>(),
+        });
+        if let Some(error) = outcome.error {
+            entry["error"] = serde_json::json!(error);
+        }
+        entries.push(entry);
+    }
+
+    let summary = serde_json::json!({
+        "command": command,
+        "projects": entries,
+        "ok": projects.len() - failed,
+        "failed": failed,
+    });
+    let summary_path = args.out_dir.join("summary.json");
+    write_atomic(&summary_path, serde_json::to_string_pretty(&summary)?)
+        .map_err(|e| format!("Failed to write {}: {}", summary_path.display(), e))?;
+    eprintln!("Summary written to: {}", summary_path.display());
+
+    if failed > 0 {
+        let message = format!("{} of {} projects in {} failed", failed, projects.len(), args.projects.display());
+        return Err(Box::new(CheckFailed { message, output, output_path: args.output.clone() }));
+    }
+    Ok((output, args.output.clone()))
+}
+
+/// Run the command for one project and write its output, whatever went wrong
+fn run_project(project: &Project, command: &[&str], out_dir: &Path) -> Outcome {
+    let failed = |status, error: String| Outcome { status, file: None, error: Some(error) };
+
+    let manifest_path = project.manifest_path.display().to_string();
+    let argv = std::iter::once("rust-grapher")
+        .chain(command.iter().copied())
+        .chain(["--manifest-path", manifest_path.as_str()])
+        .chain(project.args.iter().map(String::as_str));
+    let mut cli = match parse_cli(argv) {
+        Ok(cli) => cli,
+        Err(e) => return failed("failed", e.render().to_string().trim().to_string()),
+    };
+    if matches!(cli.command, Commands::Batch(_)) {
+        return failed("failed", "batch cannot be nested".to_string());
+    }
+    if let Commands::FnGraph(args) = &mut cli.command {
+        if let Some(dir) = project.manifest_path.parent().filter(|_| args.source_dir.is_relative()) {
+            args.source_dir = dir.join(&args.source_dir);
+        }
+    }
+
+    let (output, status, error) = match run_cli(&cli) {
+        Ok((_, Some(_))) => return failed("failed", "--output and --out-dir are not allowed, the output goes to the batch directory".to_string()),
+        Ok((output, None)) => (output, "ok", None),
+        Err(e) => match e.downcast::<CheckFailed>() {
+            Ok(check) if check.output_path.is_none() => (check.output, "check-failed", Some(check.message)),
+            Ok(_) => return failed("failed", "--output and --out-dir are not allowed, the output goes to the batch directory".to_string()),
+            Err(e) => return failed("failed", e.to_string()),
+        },
+    };
+
+    let file = out_dir.join(format!("{}.{}", project.name, output_extension(&cli.command)));
+    match write_atomic(&file, output) {
+        Ok(()) => Outcome { status, file: Some(file), error },
+        Err(e) => failed("failed", format!("cannot write {}: {}", file.display(), e)),
+    }
+}
+
+/// File extension of a command's output
+fn output_extension(command: &Commands) -> &'static str {
+    let (format, no_fence) = match command {
+        Commands::Deps(args) if args.stats && !matches!(args.format, OutputFormat::Json) => return "txt",
+        Commands::Deps(args) => (&args.format, args.no_fence),
+        Commands::FnGraph(args) => (&args.format, args.no_fence),
+        Commands::Render(args) => (&args.format, args.no_fence),
+        _ => return "txt",
+    };
+    diagram_file_name(format, no_fence).rsplit('.').next().unwrap_or("txt")
+}
+
+fn load_projects(path: &Path) -> Result<Vec<Project>, Box<dyn Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let document = toml::parse(&content)
+        .map_err(|e| format!("Invalid projects file {}: {}", path.display(), e))?;
+    let base = path.parent().unwrap_or(Path::new(""));
+
+    let entries = match document.get("project") {
+        Some(TomlValue::Array(entries)) => entries.as_slice(),
+        Some(_) => return Err(format!("Invalid projects file {}: \"project\" must be [[project]] tables", path.display()).into()),
+        None => &[],
+    };
+
+    let mut projects = Vec::new();
+    let mut names = HashSet::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let invalid = |reason: &str| format!("Invalid project {} in {}: {}", i + 1, path.display(), reason);
+        let string = |key: &str| match entry.get(key) {
+            Some(value) => value.as_str().map(|s| Some(s.to_string())).ok_or_else(|| invalid(&format!("\"{}\" must be a string", key))),
+            None => Ok(None),
+        };
+
+        let mut manifest_path = base.join(string("manifest_path")?.ok_or_else(|| invalid("missing \"manifest_path\""))?);
+        if manifest_path.is_dir() {
+            manifest_path.push("Cargo.toml");
+        }
+        let name = match string("name")? {
+            Some(name) => name,
+            None => manifest_path.parent()
+                .and_then(|dir| fs::canonicalize(dir).ok())
+                .and_then(|dir| dir.file_name().map(|name| name.to_string_lossy().to_string()))
+                .ok_or_else(|| invalid("no \"name\", and none from the manifest's directory"))?,
+        };
+        if name.is_empty() || name.contains(['/', '\\']) {
+            return Err(invalid(&format!("'{}' cannot name an output file", name)).into());
+        }
+        if !names.insert(name.clone()) {
+            return Err(invalid(&format!("another project is already named '{}'", name)).into());
+        }
+        let args = match entry.get("args") {
+            Some(args) => args.as_string_list().ok_or_else(|| invalid("\"args\" must be a list of strings"))?,
+            None => Vec::new(),
+        };
+
+        projects.push(Project { name, manifest_path, args });
+    }
+
+    Ok(projects)
+}
@@ -0,0 +1,68 @@
+// ============================================================================
+// Metadata Cache
+// ============================================================================
+
+use std::{error::Error, fs, path::{Path, PathBuf}};
+
+use cargo_metadata::{Metadata, MetadataCommand};
+use walkdir::WalkDir;
+
+use crate::utils::helper::{fnv1a_64, write_atomic};
+
+/// Run `command`, reusing a previous result from `cache_dir` while Cargo.lock and the
+/// manifests are unchanged.
+///
+/// The key covers the Cargo.lock contents, every Cargo.toml of the workspace (features,
+/// licenses and descriptions change without touching the lockfile) and the full cargo
+/// invocation, so different manifests or flags never share an entry. Without a Cargo.lock
+/// nothing is cached.
+pub fn cached_metadata(command: &MetadataCommand, manifest_path: &Path, cache_dir: &Path) -> Result<Metadata, Box<dyn Error>> {
+    let Some((root, lock_contents)) = find_lockfile(manifest_path) else {
+        return Ok(command.exec()?);
+    };
+
+    let invocation = format!("{:?}", command.cargo_command());
+    let mut keyed = [lock_contents.as_bytes(), invocation.as_bytes()].concat();
+    for manifest in workspace_manifests(&root) {
+        keyed.extend(manifest.to_string_lossy().as_bytes());
+        keyed.extend(fs::read(&manifest).unwrap_or_default());
+    }
+    let key = fnv1a_64(&keyed);
+    let cache_file = cache_dir.join(format!("metadata-{:016x}.json", key));
+
+    if let Ok(content) = fs::read_to_string(&cache_file) {
+        if let Ok(metadata) = serde_json::from_str::<Metadata>(&content) {
+            return Ok(metadata);
+        }
+    }
+
+    let metadata = command.exec()?;
+    write_atomic(&cache_file, serde_json::to_string(&metadata)?)?;
+    Ok(metadata)
+}
+
+/// Directory and contents of the Cargo.lock next to the manifest or in one of its parent directories
+fn find_lockfile(manifest_path: &Path) -> Option<(PathBuf, String)> {
+    let manifest_path = fs::canonicalize(manifest_path).ok()?;
+    manifest_path
+        .ancestors()
+        .skip(1)
+        .find_map(|dir| Some((dir.to_path_buf(), fs::read_to_string(dir.join("Cargo.lock")).ok()?)))
+}
+
+/// Every Cargo.toml below the workspace root, in a stable order, leaving out target/ and
+/// hidden directories
+fn workspace_manifests(root: &Path) -> Vec<PathBuf> {
+    let mut manifests: Vec<PathBuf> = WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            entry.depth() == 0 || !entry.file_type().is_dir() || !(name.starts_with('.') || name == "target")
+        })
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file() && entry.file_name() == "Cargo.toml")
+        .map(|entry| entry.into_path())
+        .collect();
+    manifests.sort();
+    manifests
+}
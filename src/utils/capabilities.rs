@@ -0,0 +1,57 @@
+// ============================================================================
+// Capability Sinks (--capability-view)
+// ============================================================================
+//
+// Functions that touch the filesystem, the network or other processes, guessed
+// from the paths of the functions they call:
+//
+//   fs::read_to_string(..)  File::open(..)  tokio::fs::write(..)       -> fs
+//   TcpStream::connect(..)  reqwest::get(..)  hyper::Client::new()     -> net
+//   Command::new("git")  std::process::Command::new(..)                -> process
+//
+// Method calls are not looked at (`file.read_to_end(..)` needs a `File::open` first),
+// and neither are renamed imports. --capability-view keeps the call paths from the
+// entry points (main, tests, public functions) to these sinks.
+
+use petgraph::Direction;
+
+use crate::{types::{Capability, FnGraphData, WarningCode}, utils::{graph::reachable_from, warnings::warn}};
+
+/// Crates whose every function is a network call
+const NET_CRATES: [&str; 6] = ["reqwest", "hyper", "ureq", "surf", "isahc", "attohttpc"];
+
+/// The capability a call of `callee` (a path as written) exercises, if any
+pub fn capability_of(callee: &str) -> Option<Capability> {
+    let segments: Vec<&str> = callee.split("::").collect();
+    let (_, modules) = segments.split_last()?;
+
+    if modules.first().is_some_and(|first| NET_CRATES.contains(first)) {
+        return Some(Capability::Net);
+    }
+    // The innermost module or type decides: `process::exit` and `process::id` don't spawn anything
+    modules.iter().rev().find_map(|segment| match *segment {
+        "fs" | "File" | "OpenOptions" | "DirBuilder" => Some(Capability::Fs),
+        "net" | "TcpStream" | "TcpListener" | "UdpSocket" | "UnixStream" | "UnixListener" => Some(Capability::Net),
+        "Command" => Some(Capability::Process),
+        _ => None,
+    })
+}
+
+/// Whether a function is a sink of one of `kinds` (all kinds when empty)
+pub fn is_sink(capabilities: &[Capability], kinds: &[Capability]) -> bool {
+    capabilities.iter().any(|capability| kinds.is_empty() || kinds.contains(capability))
+}
+
+/// Keep only the functions on some call path from an entry point to a sink of `kinds`
+pub fn capability_view(graph_data: &mut FnGraphData, kinds: &[Capability]) {
+    let graph = &graph_data.graph;
+    let entries: Vec<_> = graph.node_indices().filter(|&idx| graph[idx].is_entry_point || graph[idx].is_public).collect();
+    let sinks: Vec<_> = graph.node_indices().filter(|&idx| is_sink(&graph[idx].capabilities, kinds)).collect();
+    if sinks.is_empty() {
+        warn(WarningCode::NoMatch, "no function calls filesystem, network or process APIs of the requested kinds");
+    }
+
+    let forward = reachable_from(graph, &entries, Direction::Outgoing);
+    let backward = reachable_from(graph, &sinks, Direction::Incoming);
+    graph_data.retain_nodes(|idx| forward.contains(&idx) && backward.contains(&idx));
+}
@@ -0,0 +1,107 @@
+// ============================================================================
+// Crate Categories (deps --color-by category)
+// ============================================================================
+//
+// crates.io category slugs and keywords from each manifest, as reported by
+// `cargo metadata`, mapped onto a handful of coarse categories. Categories
+// decide first (in the order of the table), then keywords:
+//
+//   categories = ["asynchronous", "network-programming"]   -> async
+//   keywords = ["serde", "json"]                           -> serialization
+//
+// Crates with categories or keywords matching none of them are "other";
+// crates with neither stay uncolored.
+
+/// A coarse category with its fill color, and the crates.io category slugs and keywords it covers
+pub struct Category {
+    pub name: &'static str,
+    pub color: &'static str,
+    /// Slugs, matching subcategories too (`web-programming` covers `web-programming::http-client`)
+    pub categories: &'static [&'static str],
+    pub keywords: &'static [&'static str],
+}
+
+pub const CATEGORIES: &[Category] = &[
+    Category {
+        name: "async",
+        color: "#d0ebff",
+        categories: &["asynchronous", "concurrency"],
+        keywords: &["async", "await", "futures", "tokio", "runtime"],
+    },
+    Category {
+        name: "serialization",
+        color: "#d3f9d8",
+        categories: &["encoding"],
+        keywords: &["serde", "serialization", "serialize", "deserialize", "json", "toml", "yaml"],
+    },
+    Category {
+        name: "crypto",
+        color: "#ffe3e3",
+        categories: &["cryptography", "authentication"],
+        keywords: &["crypto", "cryptography", "encryption", "hash", "tls", "ssl"],
+    },
+    Category {
+        name: "web",
+        color: "#fff3bf",
+        categories: &["web-programming", "network-programming"],
+        keywords: &["http", "web", "server", "client", "network", "websocket"],
+    },
+    Category {
+        name: "cli",
+        color: "#e5dbff",
+        categories: &["command-line-interface", "command-line-utilities"],
+        keywords: &["cli", "command-line", "terminal", "console", "argument"],
+    },
+    Category {
+        name: "parsing",
+        color: "#ffe8cc",
+        categories: &["parsing", "parser-implementations", "text-processing"],
+        keywords: &["parser", "parsing", "regex", "text"],
+    },
+    Category {
+        name: "macros",
+        color: "#f3d9fa",
+        categories: &["development-tools::procedural-macro-helpers"],
+        keywords: &["macro", "macros", "proc-macro", "derive"],
+    },
+    Category {
+        name: "logging",
+        color: "#c5f6fa",
+        categories: &["development-tools::debugging"],
+        keywords: &["log", "logging", "tracing"],
+    },
+    Category {
+        name: "os",
+        color: "#e9ecef",
+        categories: &["os", "filesystem", "hardware-support", "external-ffi-bindings"],
+        keywords: &["ffi", "sys", "windows", "unix", "posix", "filesystem"],
+    },
+    Category {
+        name: "data-structures",
+        color: "#e6fcf5",
+        categories: &["data-structures", "algorithms", "memory-management"],
+        keywords: &["collections", "data-structure", "hashmap", "vec"],
+    },
+];
+
+/// Category of crates whose categories and keywords match no other
+pub const OTHER: &str = "other";
+const OTHER_COLOR: &str = "#f8f9fa";
+
+/// Coarse category of a crate from its manifest `categories` and `keywords`
+pub fn crate_category(categories: &[String], keywords: &[String]) -> Option<&'static str> {
+    let covers = |slug: &str, category: &str| {
+        slug == category || slug.strip_prefix(category).is_some_and(|rest| rest.starts_with("::"))
+    };
+
+    CATEGORIES.iter()
+        .find(|c| categories.iter().any(|slug| c.categories.iter().any(|category| covers(slug, category))))
+        .or_else(|| CATEGORIES.iter().find(|c| keywords.iter().any(|keyword| c.keywords.contains(&keyword.to_lowercase().as_str()))))
+        .map(|c| c.name)
+        .or_else(|| (!categories.is_empty() || !keywords.is_empty()).then_some(OTHER))
+}
+
+/// Fill color of a category name, `other` for unknown ones
+pub fn category_color(name: &str) -> &'static str {
+    CATEGORIES.iter().find(|c| c.name == name).map_or(OTHER_COLOR, |c| c.color)
+}
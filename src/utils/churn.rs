@@ -0,0 +1,109 @@
+// ============================================================================
+// Change History Overlay (fn-graph --churn)
+// ============================================================================
+//
+// Blames every source file once (`git blame --line-porcelain`) and, for the
+// lines of each function, sets:
+//
+//   metric.churn    distinct commits among its current lines
+//   metric.age      days since the newest of them
+//   last_modified   date of the newest, e.g. 2024-03-18
+//
+// Lines not committed yet count as changed today but add no commit. Files git
+// cannot blame (outside a repository, untracked) are skipped with a warning.
+
+use std::{collections::{HashMap, HashSet}, path::Path, process::Command};
+
+use crate::{types::{FnGraphData, SourceLocations, WarningCode}, utils::warnings::warn};
+
+/// Metric names set on function nodes
+pub const CHURN: &str = "churn";
+pub const AGE: &str = "age";
+
+/// Commit that last changed a line, and its author time in seconds since the epoch
+struct BlameLine {
+    commit: String,
+    time: i64,
+}
+
+pub fn apply_churn(graph_data: &mut FnGraphData, locations: &SourceLocations) {
+    let mut by_file: HashMap<String, Vec<_>> = HashMap::new();
+    for idx in graph_data.graph.node_indices() {
+        by_file.entry(graph_data.graph[idx].file_path.clone()).or_default().push(idx);
+    }
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+
+    for (file, nodes) in by_file {
+        let Some(path) = locations.files.get(&file) else {
+            continue;
+        };
+        let lines = match blame(path) {
+            Ok(lines) => lines,
+            Err(e) => {
+                warn(WarningCode::MissingData, format!("no git history for {}: {}", file, e));
+                continue;
+            }
+        };
+
+        for idx in nodes {
+            let info = &mut graph_data.graph[idx];
+            let range = lines.get(info.line.saturating_sub(1)..info.end_line.max(info.line).min(lines.len()));
+            let Some(newest) = range.and_then(|range| range.iter().map(|line| line.time).max()) else {
+                continue;
+            };
+            let commits: HashSet<&str> = range.into_iter().flatten()
+                .map(|line| line.commit.as_str())
+                .filter(|commit| commit.bytes().any(|b| b != b'0'))
+                .collect();
+
+            info.metrics.insert(CHURN.to_string(), commits.len() as f64);
+            info.metrics.insert(AGE.to_string(), ((now - newest).max(0) / 86_400) as f64);
+            info.last_modified = Some(civil_date(newest.div_euclid(86_400)));
+        }
+    }
+}
+
+/// The commit and author time of every line of `path`, in line order
+fn blame(path: &Path) -> Result<Vec<BlameLine>, String> {
+    let dir = path.parent().ok_or("no parent directory")?;
+    let name = path.file_name().ok_or("not a file")?;
+    let output = Command::new("git")
+        .arg("-C").arg(dir)
+        .args(["blame", "--line-porcelain", "--"])
+        .arg(name)
+        .output()
+        .map_err(|e| format!("cannot run git: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    // Each line: `<commit> <orig> <final> [<count>]`, headers, then the content after a tab
+    let mut lines = Vec::new();
+    let (mut commit, mut time) = (String::new(), 0);
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if line.starts_with('\t') {
+            lines.push(BlameLine { commit: commit.clone(), time });
+        } else if let Some(value) = line.strip_prefix("author-time ") {
+            time = value.parse().unwrap_or(0);
+        } else if let Some(hash) = line.split(' ').next().filter(|hash| hash.len() == 40 && hash.bytes().all(|b| b.is_ascii_hexdigit())) {
+            commit = hash.to_string();
+        }
+    }
+    Ok(lines)
+}
+
+/// `YYYY-MM-DD` of a day count since 1970-01-01 (Howard Hinnant's civil_from_days)
+fn civil_date(days: i64) -> String {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
@@ -0,0 +1,177 @@
+// ============================================================================
+// Command Line
+// ============================================================================
+//
+// Argument parsing and dispatch shared by the `rust-grapher` binary and the
+// C API, so both accept exactly the same options.
+
+use std::{collections::BTreeSet, error::Error, ffi::OsString, path::PathBuf};
+
+use clap::{error::ErrorKind, CommandFactory, FromArgMatches};
+
+use crate::{types::{CheckFailed, Cli, ColorBy, Commands, DepsArgs, FnGraphArgs, OutputFormat, Warning}, utils};
+
+use utils::assertions::run_assert;
+use utils::batch::run_batch;
+use utils::churn::{AGE, CHURN};
+use utils::combine::run_combine;
+use utils::daemon::run_daemon;
+use utils::features::run_explain_features;
+use utils::freshness::run_freshness;
+use utils::grapher::{run_deps, run_fn_graph};
+use utils::helper::read_patterns;
+use utils::history::run_history;
+use utils::inject::run_inject_map;
+use utils::logs::LOGS;
+use utils::owners::run_owners;
+use utils::preset::deps_preset_args;
+use utils::profile::SAMPLES;
+use utils::render::run_render;
+use utils::selftest::run_selftest;
+use utils::size::SIZE;
+use utils::warnings::{collect, with_json_warnings};
+use utils::why::run_why;
+
+/// Parse `argv` (program name first) and fill in presets and implied options
+pub fn parse_cli<I, T>(argv: I) -> Result<Cli, clap::Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let argv: Vec<OsString> = argv.into_iter().map(Into::into).collect();
+    let mut matches = Cli::command().try_get_matches_from(&argv)?;
+
+    // Presets add the options they stand for that were not given, checked like typed ones
+    if let Some(("deps", sub_matches)) = matches.subcommand() {
+        let preset_args = deps_preset_args(sub_matches);
+        if !preset_args.is_empty() {
+            matches = Cli::command().try_get_matches_from(argv.into_iter().chain(preset_args.into_iter().map(OsString::from)))?;
+        }
+    }
+    let mut cli = Cli::from_arg_matches(&matches)?;
+
+    // A profile colors functions by heat, size data by bytes, --logs by logging calls, unless --color-by says otherwise
+    if let Commands::FnGraph(args) = &mut cli.command {
+        if args.profile_data.is_some() && args.color_by.is_none() {
+            args.color_by = Some(ColorBy::Metric);
+            args.color_metric = Some(SAMPLES.to_string());
+        }
+        if args.color_by == Some(ColorBy::Size) || (args.size_data.is_some() && args.color_by.is_none()) {
+            args.color_by = Some(ColorBy::Metric);
+            args.color_metric = Some(SIZE.to_string());
+        }
+        if args.logs && args.color_by.is_none() {
+            args.color_by = Some(ColorBy::Metric);
+            args.color_metric = Some(LOGS.to_string());
+        }
+        if let Some(metric) = churn_metric(args.color_by) {
+            args.churn = true;
+            args.color_by = Some(ColorBy::Metric);
+            args.color_metric = Some(metric.to_string());
+        }
+    }
+    if let Commands::Render(args) = &mut cli.command {
+        if args.color_by == Some(ColorBy::Size) {
+            args.color_by = Some(ColorBy::Metric);
+            args.color_metric = Some(SIZE.to_string());
+        }
+        if let Some(metric) = churn_metric(args.color_by) {
+            args.color_by = Some(ColorBy::Metric);
+            args.color_metric = Some(metric.to_string());
+        }
+    }
+    if let Commands::Deps(args) = &mut cli.command {
+        if args.color_by_depth {
+            args.color_by = Some(ColorBy::Depth);
+        }
+        if args.relative_depth && args.color_by.is_none() {
+            args.color_by = Some(ColorBy::Depth);
+        }
+    }
+
+    // Pattern files add to the patterns given on the command line. Shared lists often hold
+    // patterns a graph has nothing for, so those aren't reported as matching nothing
+    match &mut cli.command {
+        Commands::Deps(args) => {
+            add_pattern_files(&args.exclude_from, &mut args.exclude, &mut args.file_patterns)?;
+            add_pattern_files(&args.include_from, &mut args.include, &mut args.file_patterns)?;
+        }
+        Commands::FnGraph(args) => add_pattern_files(&args.exclude_from, &mut args.exclude, &mut args.file_patterns)?,
+        _ => {}
+    }
+
+    if let Commands::Deps(DepsArgs { color_by: Some(ColorBy::Size), .. }) = cli.command {
+        return Err(Cli::command().error(ErrorKind::InvalidValue, "--color-by size is only available for fn-graph (with --size-data)"));
+    }
+    if let Commands::Deps(DepsArgs { color_by: Some(ColorBy::Churn | ColorBy::Age), .. }) = cli.command {
+        return Err(Cli::command().error(ErrorKind::InvalidValue, "--color-by churn and age are only available for fn-graph"));
+    }
+    if let Commands::FnGraph(FnGraphArgs { color_by: Some(ColorBy::Category), .. }) = cli.command {
+        return Err(Cli::command().error(ErrorKind::InvalidValue, "--color-by category is only available for deps"));
+    }
+
+    Ok(cli)
+}
+
+/// Metric `--color-by churn` and `age` stand for
+fn churn_metric(color_by: Option<ColorBy>) -> Option<&'static str> {
+    match color_by? {
+        ColorBy::Churn => Some(CHURN),
+        ColorBy::Age => Some(AGE),
+        _ => None,
+    }
+}
+
+/// Add the patterns of `files` to `patterns`, remembering them in `file_patterns`
+fn add_pattern_files(files: &[PathBuf], patterns: &mut Vec<String>, file_patterns: &mut Vec<String>) -> Result<(), clap::Error> {
+    for file in files {
+        let read = read_patterns(file).map_err(|e| Cli::command().error(ErrorKind::Io, e))?;
+        file_patterns.extend(read.iter().cloned());
+        patterns.extend(read);
+    }
+    Ok(())
+}
+
+/// Run the command: its output, and the file it goes to (stdout when `None`). A warning of a
+/// `--deny` code fails the run as a check, its output still written.
+pub fn run_cli(cli: &Cli) -> Result<(String, Option<PathBuf>), Box<dyn Error>> {
+    let (result, warnings) = collect(&cli.allow, &cli.deny, || run_command(&cli.command));
+    let (output, output_path) = result?;
+
+    let json = match &cli.command {
+        Commands::Deps(args) => matches!(args.format, OutputFormat::Json),
+        Commands::FnGraph(args) => matches!(args.format, OutputFormat::Json),
+        Commands::Render(args) => matches!(args.format, OutputFormat::Json),
+        _ => false,
+    };
+    let output = if json { with_json_warnings(output, &warnings) } else { output };
+
+    let denied: Vec<&Warning> = warnings.iter().filter(|warning| cli.deny.contains(&warning.code)).collect();
+    if !denied.is_empty() {
+        let codes: BTreeSet<String> = denied.iter().map(|warning| warning.code.name()).collect();
+        let codes: Vec<String> = codes.into_iter().collect();
+        let message = format!("denied warnings: {} ({})", denied.len(), codes.join(", "));
+        return Err(Box::new(CheckFailed { message, output, output_path }));
+    }
+    Ok((output, output_path))
+}
+
+fn run_command(command: &Commands) -> Result<(String, Option<PathBuf>), Box<dyn Error>> {
+    match command {
+        Commands::Deps(DepsArgs { inject_map: Some(map), .. }) => run_inject_map(map, "deps"),
+        Commands::FnGraph(FnGraphArgs { inject_map: Some(map), .. }) => run_inject_map(map, "fn-graph"),
+        Commands::Deps(args) => run_deps(args),
+        Commands::FnGraph(args) => run_fn_graph(args),
+        Commands::Combine(args) => run_combine(args),
+        Commands::Render(args) => run_render(args),
+        Commands::Daemon(args) => run_daemon(args),
+        Commands::History(args) => run_history(args),
+        Commands::ExplainFeatures(args) => run_explain_features(args),
+        Commands::Freshness(args) => run_freshness(args),
+        Commands::Owners(args) => run_owners(args),
+        Commands::Why(args) => run_why(args),
+        Commands::Assert(args) => run_assert(args),
+        Commands::Batch(args) => run_batch(args),
+        Commands::Selftest => run_selftest(),
+    }
+}
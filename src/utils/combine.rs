@@ -0,0 +1,517 @@
+// ============================================================================
+// Combine - Set Operations on Saved JSON Graphs
+// ============================================================================
+
+use std::{collections::{BTreeMap, HashMap, HashSet}, fs, io, path::{Path, PathBuf}};
+
+use clap::ValueEnum;
+use serde_json::Value;
+
+use crate::{types, utils::{generator, helper::{fnv1a_64, group_color}}};
+
+use types::{Behind, CallKind, Capability, CheckFailed, CombineArgs, ConfigKind, ConfigRead, CombineOp, DepKind, DepKinds, DepsArgs, FnGraphArgs, FnGraphData, FnNodeInfo, GraphData, LogCall, NodeGroup, NodeInfo, OutputFormat, PackageId, Reexport, SourceLocations};
+use generator::{generate_deps_dot, generate_deps_gh_summary, generate_deps_json, generate_deps_mermaid, generate_deps_vscode_graph};
+use generator::{generate_fn_dot, generate_fn_gh_summary, generate_fn_json, generate_fn_mermaid, generate_fn_vscode_graph};
+
+/// A graph loaded from the JSON output format, keyed by node id
+pub struct JsonGraph {
+    nodes: Vec<JsonNode>,
+    edges: Vec<(EdgeKey, Vec<String>)>,
+}
+
+/// A node's id and its JSON object
+type JsonNode = (String, Value);
+
+/// Edges are identified by (from, to) and carry their set of kinds
+type EdgeKey = (String, String);
+
+pub fn run_combine(args: &CombineArgs) -> Result<(String, Option<PathBuf>), Box<dyn std::error::Error>> {
+    let graphs = args.inputs.iter()
+        .map(|path| load_json_graph(path))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Every input must be the same kind of graph (empty graphs fit either)
+    let fn_graphs = graphs.iter().filter(|g| !g.nodes.is_empty() && is_fn_graph(g)).count();
+    let deps_graphs = graphs.iter().filter(|g| !g.nodes.is_empty() && !is_fn_graph(g)).count();
+    if fn_graphs > 0 && deps_graphs > 0 {
+        return Err("Cannot combine dependency graphs with function graphs".into());
+    }
+
+    if matches!(args.format, OutputFormat::CallHierarchy | OutputFormat::Sarif) {
+        return Err(format!("--format {} is only available for fn-graph", args.format.name()).into());
+    }
+
+    let combined = match args.op {
+        CombineOp::Union => union(&graphs),
+        CombineOp::Intersection => intersection(&graphs),
+        CombineOp::Difference => difference(&graphs),
+    };
+
+    let output = if fn_graphs > 0 {
+        let fn_args = FnGraphArgs {
+            format: args.format.clone(),
+            no_fence: args.no_fence,
+            direction: args.direction.clone(),
+            theme: args.theme.clone(),
+            mermaid_version: args.mermaid_version,
+            highlight: args.highlight.clone(),
+            ..Default::default()
+        };
+        let graph_data = to_fn_graph_data(&combined);
+        match args.format {
+            OutputFormat::Mermaid => generate_fn_mermaid(&graph_data, &fn_args),
+            OutputFormat::Dot => generate_fn_dot(&graph_data, &fn_args),
+            OutputFormat::Json => generate_fn_json(&graph_data, &fn_args),
+            OutputFormat::VscodeGraph => generate_fn_vscode_graph(&graph_data, &fn_args, &SourceLocations::default()),
+            OutputFormat::GhSummary => generate_fn_gh_summary(&graph_data, &fn_args),
+            OutputFormat::CallHierarchy | OutputFormat::Sarif => unreachable!(),
+        }
+    } else {
+        let deps_args = DepsArgs {
+            format: args.format.clone(),
+            no_fence: args.no_fence,
+            direction: args.direction.clone(),
+            theme: args.theme.clone(),
+            mermaid_version: args.mermaid_version,
+            highlight: args.highlight.clone(),
+            ..Default::default()
+        };
+        let graph_data = to_deps_graph_data(&combined);
+        match args.format {
+            OutputFormat::Mermaid => generate_deps_mermaid(&graph_data, &deps_args),
+            OutputFormat::Dot => generate_deps_dot(&graph_data, &deps_args),
+            OutputFormat::Json => generate_deps_json(&graph_data, &deps_args),
+            OutputFormat::VscodeGraph => generate_deps_vscode_graph(&graph_data, &deps_args),
+            OutputFormat::GhSummary => generate_deps_gh_summary(&graph_data, &deps_args),
+            OutputFormat::CallHierarchy | OutputFormat::Sarif => unreachable!(),
+        }
+    };
+
+    if args.exit_code && !combined.nodes.is_empty() {
+        return Err(Box::new(CheckFailed {
+            message: format!("{} nodes and {} edges in the combined graph", combined.nodes.len(), combined.edges.len()),
+            output,
+            output_path: args.output.clone(),
+        }));
+    }
+
+    Ok((output, args.output.clone()))
+}
+
+pub fn load_json_graph(path: &Path) -> Result<JsonGraph, Box<dyn std::error::Error>> {
+    let content = if path == Path::new("-") {
+        io::read_to_string(io::stdin()).map_err(|e| format!("Failed to read stdin: {}", e))?
+    } else {
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+    };
+    let value: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid JSON in {}: {}", path.display(), e))?;
+
+    let nodes = value["nodes"].as_array()
+        .ok_or_else(|| format!("{} has no \"nodes\" array", path.display()))?;
+    let edges = value["edges"].as_array()
+        .ok_or_else(|| format!("{} has no \"edges\" array", path.display()))?;
+
+    let mut graph = JsonGraph { nodes: Vec::new(), edges: Vec::new() };
+
+    for node in nodes {
+        let id = node["id"].as_str()
+            .ok_or_else(|| format!("{} has a node without an \"id\"", path.display()))?;
+        graph.nodes.push((id.to_string(), node.clone()));
+    }
+
+    for edge in edges {
+        let (Some(from), Some(to)) = (edge["from"].as_str(), edge["to"].as_str()) else {
+            return Err(format!("{} has an edge without \"from\"/\"to\"", path.display()).into());
+        };
+        // Dependency edges list every kind, function edges only have one
+        let kinds: Vec<String> = match edge["kinds"].as_array() {
+            Some(kinds) => kinds.iter().filter_map(Value::as_str).map(str::to_string).collect(),
+            None => edge["kind"].as_str().map(str::to_string).into_iter().collect(),
+        };
+        graph.edges.push(((from.to_string(), to.to_string()), kinds));
+    }
+
+    Ok(graph)
+}
+
+pub fn is_fn_graph(graph: &JsonGraph) -> bool {
+    graph.nodes.iter().any(|(_, node)| node.get("qualified_name").is_some())
+}
+
+// ============================================================================
+// Set Operations
+// ============================================================================
+
+fn union(graphs: &[JsonGraph]) -> JsonGraph {
+    let mut result = JsonGraph { nodes: Vec::new(), edges: Vec::new() };
+    let mut seen_nodes: HashSet<String> = HashSet::new();
+    let mut edge_positions: HashMap<EdgeKey, usize> = HashMap::new();
+
+    // First occurrence wins when the same node appears in several graphs,
+    // while the kinds of a repeated edge are merged
+    for graph in graphs {
+        for (id, node) in &graph.nodes {
+            if seen_nodes.insert(id.clone()) {
+                result.nodes.push((id.clone(), node.clone()));
+            }
+        }
+        for (key, kinds) in &graph.edges {
+            match edge_positions.get(key) {
+                Some(&pos) => {
+                    let merged = &mut result.edges[pos].1;
+                    for kind in kinds {
+                        if !merged.contains(kind) {
+                            merged.push(kind.clone());
+                        }
+                    }
+                }
+                None => {
+                    edge_positions.insert(key.clone(), result.edges.len());
+                    result.edges.push((key.clone(), kinds.clone()));
+                }
+            }
+        }
+    }
+
+    result
+}
+
+fn intersection(graphs: &[JsonGraph]) -> JsonGraph {
+    let (first, rest) = graphs.split_first().expect("combine requires at least one graph");
+    let node_sets: Vec<HashSet<&String>> = rest.iter()
+        .map(|g| g.nodes.iter().map(|(id, _)| id).collect())
+        .collect();
+    let edge_maps: Vec<HashMap<&EdgeKey, &Vec<String>>> = rest.iter()
+        .map(|g| g.edges.iter().map(|(key, kinds)| (key, kinds)).collect())
+        .collect();
+
+    // An edge survives with the kinds every graph agrees on
+    let edges = first.edges.iter()
+        .filter_map(|(key, kinds)| {
+            let common: Vec<String> = kinds.iter()
+                .filter(|kind| edge_maps.iter().all(|map| map.get(key).is_some_and(|k| k.contains(kind))))
+                .cloned()
+                .collect();
+            (!common.is_empty()).then(|| (key.clone(), common))
+        })
+        .collect();
+
+    JsonGraph {
+        nodes: first.nodes.iter()
+            .filter(|(id, _)| node_sets.iter().all(|set| set.contains(id)))
+            .cloned()
+            .collect(),
+        edges,
+    }
+}
+
+fn difference(graphs: &[JsonGraph]) -> JsonGraph {
+    let (first, rest) = graphs.split_first().expect("combine requires at least one graph");
+    // A function whose content hash differs from every other copy changed, so it stays
+    let mut other_hashes: HashMap<&String, Vec<Option<&str>>> = HashMap::new();
+    for (id, node) in rest.iter().flat_map(|g| &g.nodes) {
+        other_hashes.entry(id).or_default().push(node["content_hash"].as_str());
+    }
+    let unchanged: HashSet<&String> = first.nodes.iter()
+        .filter(|(id, node)| other_hashes.get(id).is_some_and(|hashes| {
+            let hash = node["content_hash"].as_str();
+            hashes.iter().any(|other| hash.is_none() || other.is_none() || *other == hash)
+        }))
+        .map(|(id, _)| id)
+        .collect();
+
+    // A renamed function, or a crate in another version, is compared under its old id
+    let renames = renamed_functions(first, rest);
+    let updates = updated_crates(first, rest);
+    let old_id = |id: &String| renames.get(id).or(updates.get(id)).map_or(id.clone(), |old| (*old).clone());
+    let mut other_edges: HashMap<&EdgeKey, HashSet<&String>> = HashMap::new();
+    for graph in rest {
+        for (key, kinds) in &graph.edges {
+            other_edges.entry(key).or_default().extend(kinds);
+        }
+    }
+
+    // An edge survives with the kinds none of the other graphs have
+    let edges: Vec<(EdgeKey, Vec<String>)> = first.edges.iter()
+        .filter_map(|(key, kinds)| {
+            let old_key = (old_id(&key.0), old_id(&key.1));
+            let remaining: Vec<String> = kinds.iter()
+                .filter(|kind| !other_edges.get(&old_key).is_some_and(|other| other.contains(kind)))
+                .cloned()
+                .collect();
+            (!remaining.is_empty()).then(|| (key.clone(), remaining))
+        })
+        .collect();
+
+    // Keep endpoints of new edges so they still have something to connect
+    let endpoints: HashSet<&String> = edges.iter()
+        .flat_map(|((from, to), _)| [from, to])
+        .collect();
+
+    // Updated crates stay, labeled with both versions
+    let versions: HashMap<&String, &Value> = rest.iter().flat_map(|g| &g.nodes).map(|(id, node)| (id, &node["version"])).collect();
+    let nodes = first.nodes.iter()
+        .filter(|(id, _)| !(unchanged.contains(id) || renames.contains_key(id)) || endpoints.contains(id))
+        .map(|(id, node)| match updates.get(id).and_then(|old| versions.get(old)) {
+            Some(&previous) => {
+                let mut node = node.clone();
+                node["previous_version"] = previous.clone();
+                (id.clone(), node)
+            }
+            None => (id.clone(), node.clone()),
+        })
+        .collect();
+
+    JsonGraph { nodes, edges }
+}
+
+/// New id -> old id of crates that only `first` has in some version and only the other graphs
+/// have in another, one version on each side. Each update is reported on stderr.
+fn updated_crates<'a>(first: &'a JsonGraph, rest: &'a [JsonGraph]) -> HashMap<&'a String, &'a String> {
+    let first_ids: HashSet<&String> = first.nodes.iter().map(|(id, _)| id).collect();
+    let rest_ids: HashSet<&String> = rest.iter().flat_map(|g| g.nodes.iter().map(|(id, _)| id)).collect();
+    let crate_name = |node: &'a JsonNode| -> Option<&'a str> {
+        node.1.get("qualified_name").is_none().then(|| node.1["name"].as_str()).flatten()
+    };
+
+    // Crate name -> (added, removed) versions
+    let mut by_name: BTreeMap<&str, (Vec<&JsonNode>, Vec<&JsonNode>)> = BTreeMap::new();
+    for node in first.nodes.iter().filter(|(id, _)| !rest_ids.contains(id)) {
+        if let Some(name) = crate_name(node) {
+            by_name.entry(name).or_default().0.push(node);
+        }
+    }
+    for node in rest.iter().flat_map(|g| &g.nodes).filter(|(id, _)| !first_ids.contains(id)) {
+        if let Some(name) = crate_name(node) {
+            by_name.entry(name).or_default().1.push(node);
+        }
+    }
+
+    let mut updates = HashMap::new();
+    for (name, (added, removed)) in by_name {
+        let ([(new_id, new_node)], [(old_id, old_node)]) = (added.as_slice(), removed.as_slice()) else {
+            continue;
+        };
+        let version = |node: &Value| node["version"].as_str().unwrap_or("?").to_string();
+        eprintln!("Updated: {} {} -> {}", name, version(old_node), version(new_node));
+        updates.insert(new_id, old_id);
+    }
+    updates
+}
+
+/// New id -> old id of functions that only `first` has, whose body hash matches exactly
+/// one function only the other graphs have. Each rename is reported on stderr.
+fn renamed_functions<'a>(first: &'a JsonGraph, rest: &'a [JsonGraph]) -> HashMap<&'a String, &'a String> {
+    let first_ids: HashSet<&String> = first.nodes.iter().map(|(id, _)| id).collect();
+    let rest_ids: HashSet<&String> = rest.iter().flat_map(|g| g.nodes.iter().map(|(id, _)| id)).collect();
+
+    // Body hash -> (added, removed) functions
+    let mut by_hash: HashMap<&str, (Vec<&JsonNode>, Vec<&JsonNode>)> = HashMap::new();
+    for node in first.nodes.iter().filter(|(id, _)| !rest_ids.contains(id)) {
+        if let Some(hash) = node.1["body_hash"].as_str() {
+            by_hash.entry(hash).or_default().0.push(node);
+        }
+    }
+    for node in rest.iter().flat_map(|g| &g.nodes).filter(|(id, _)| !first_ids.contains(id)) {
+        if let Some(hash) = node.1["body_hash"].as_str() {
+            by_hash.entry(hash).or_default().1.push(node);
+        }
+    }
+
+    let mut renames = HashMap::new();
+    let mut pairs: Vec<_> = by_hash.into_values()
+        .filter_map(|(added, removed)| match (added.as_slice(), removed.as_slice()) {
+            ([new], [old]) => Some((*old, *new)),
+            _ => None,
+        })
+        .collect();
+    pairs.sort_by(|a, b| a.0.0.cmp(&b.0.0));
+
+    for ((old_id, old_node), (new_id, new_node)) in pairs {
+        let name = |id: &'a String, node: &'a Value| node["qualified_name"].as_str().unwrap_or(id);
+        eprintln!("Renamed: {} -> {}", name(old_id, old_node), name(new_id, new_node));
+        renames.insert(new_id, old_id);
+    }
+    renames
+}
+
+// ============================================================================
+// Conversion Back to Graph Data
+// ============================================================================
+
+/// `reexports` of a function node
+fn json_reexports(node: &Value) -> Vec<Reexport> {
+    node["reexports"].as_array().into_iter().flatten()
+        .filter_map(|reexport| Some(Reexport {
+            path: reexport["path"].as_str()?.to_string(),
+            via: reexport["via"].as_array().into_iter().flatten().filter_map(|p| p.as_str()).map(str::to_string).collect(),
+        }))
+        .collect()
+}
+
+/// `config` reads of a function node, in the order they were written
+fn json_config(node: &Value) -> Vec<ConfigRead> {
+    node["config"].as_array().into_iter().flatten()
+        .filter_map(|read| {
+            let kind = match read["kind"].as_str()? {
+                "const" => ConfigKind::Const,
+                "static" => ConfigKind::Static,
+                "env" => ConfigKind::Env,
+                _ => return None,
+            };
+            Some(ConfigRead { name: read["name"].as_str()?.to_string(), kind })
+        })
+        .collect()
+}
+
+fn json_capabilities(node: &Value) -> Vec<Capability> {
+    let mut capabilities: Vec<Capability> = node["capabilities"].as_array().into_iter().flatten()
+        .filter_map(|capability| Capability::from_str(capability.as_str()?, false).ok())
+        .collect();
+    capabilities.sort();
+    capabilities
+}
+
+fn json_logs(node: &Value) -> Vec<LogCall> {
+    node["logs"].as_array().into_iter().flatten()
+        .filter_map(|call| Some(LogCall {
+            level: call["level"].as_str()?.to_string(),
+            target: call["target"].as_str().map(str::to_string),
+            message: call["message"].as_str().map(str::to_string),
+            line: call["line"].as_u64().unwrap_or_default() as usize,
+        }))
+        .collect()
+}
+
+pub fn to_deps_graph_data(graph: &JsonGraph) -> GraphData {
+    let mut graph_data = GraphData::new();
+    let mut by_id = HashMap::new();
+
+    for (id, node) in &graph.nodes {
+        let info = NodeInfo {
+            name: node["name"].as_str().unwrap_or(id).to_string(),
+            version: node["version"].as_str().unwrap_or_default().to_string(),
+            kind: DepKind::Normal,
+            is_workspace_member: node["is_workspace_member"].as_bool().unwrap_or(false),
+            license: node["license"].as_str().map(str::to_string),
+            source: node["source"].as_str().map(str::to_string),
+            registry: node["registry"].as_str().map(str::to_string),
+            checksum: node["checksum"].as_str().map(str::to_string),
+            owners: node["owners"].as_array().map(|owners| owners.iter().filter_map(|o| o.as_str()).map(str::to_string).collect()),
+            latest_version: node["latest_version"].as_str().map(str::to_string),
+            behind: node["behind"].as_str().map(|behind| Behind::from_str(behind, true).unwrap_or(Behind::UpToDate)),
+            advisories: node["advisories"].as_array().map(|ids| ids.iter().filter_map(|id| id.as_str()).map(str::to_string).collect()),
+            duplicate_versions: node["duplicate_versions"].as_array().map(|versions| versions.iter().filter_map(|v| v.as_str()).map(str::to_string).collect()),
+            is_build_script: node["is_build_script"].as_bool().unwrap_or(false),
+            is_optional: node["optional"].as_bool().unwrap_or(false),
+            group: json_group(node),
+            category: node["category"].as_str().map(str::to_string),
+            size: node["size"].as_u64(),
+            previous_version: node["previous_version"].as_str().map(str::to_string),
+            metrics: json_metrics(node),
+            ..Default::default()
+        };
+        let idx = graph_data.graph.add_node(info);
+        graph_data.node_indices.insert(PackageId { repr: id.clone() }, idx);
+        by_id.insert(id.as_str(), idx);
+    }
+
+    for ((from, to), kinds) in &graph.edges {
+        if let (Some(&from_idx), Some(&to_idx)) = (by_id.get(from.as_str()), by_id.get(to.as_str())) {
+            // Edges written by other tools may carry no kind
+            let mut edge_kinds = DepKinds::default();
+            if kinds.is_empty() {
+                edge_kinds.insert(DepKind::Normal);
+            }
+            for kind in kinds {
+                edge_kinds.insert(match kind.as_str() {
+                    "dev" => DepKind::Dev,
+                    "build" => DepKind::Build,
+                    _ => DepKind::Normal,
+                });
+            }
+            graph_data.graph.add_edge(from_idx, to_idx, edge_kinds);
+        }
+    }
+
+    graph_data
+}
+
+pub fn to_fn_graph_data(graph: &JsonGraph) -> FnGraphData {
+    let mut graph_data = FnGraphData::new();
+    let mut by_id = HashMap::new();
+
+    for (id, node) in &graph.nodes {
+        let name = node["name"].as_str().unwrap_or(id).to_string();
+        let qualified_name = node["qualified_name"].as_str().unwrap_or(&name).to_string();
+        let info = FnNodeInfo {
+            name,
+            qualified_name: qualified_name.clone(),
+            file_path: node["file"].as_str().unwrap_or_default().to_string(),
+            line: node["line"].as_u64().unwrap_or(0) as usize,
+            column: 0,
+            end_line: 0,
+            is_method: false,
+            is_entry_point: false,
+            is_public: node["is_public"].as_bool().unwrap_or(false),
+            signature: node["signature"].as_str().map(str::to_string),
+            is_async: node["is_async"].as_bool().unwrap_or(false),
+            is_unsafe: node["is_unsafe"].as_bool().unwrap_or(false),
+            allow_unreachable: false,
+            crate_name: node["crate"].as_str().map(str::to_string),
+            body_hash: json_hash(node, "body_hash"),
+            content_hash: json_hash(node, "content_hash"),
+            config: json_config(node),
+            logs: json_logs(node),
+            capabilities: json_capabilities(node),
+            types: node["types"].as_array().into_iter().flatten().filter_map(|t| t.as_str()).map(str::to_string).collect(),
+            features: node["features"].as_array().into_iter().flatten().filter_map(|f| f.as_str()).map(str::to_string).collect(),
+            public_paths: node["public_paths"].as_array().into_iter().flatten().filter_map(|p| p.as_str()).map(str::to_string).collect(),
+            reexports: json_reexports(node),
+            depth: None,
+            last_modified: node["last_modified"].as_str().map(str::to_string),
+            is_context: node["context"].as_bool().unwrap_or(false),
+            group: json_group(node),
+            metrics: json_metrics(node),
+        };
+        let idx = graph_data.graph.add_node(info);
+        graph_data.node_indices.insert(qualified_name, idx);
+        by_id.insert(id.as_str(), idx);
+    }
+
+    for ((from, to), kinds) in &graph.edges {
+        if let (Some(&from_idx), Some(&to_idx)) = (by_id.get(from.as_str()), by_id.get(to.as_str())) {
+            // A merged pair counts as a direct call unless every input agrees on another kind
+            let kind = match kinds.split_first() {
+                Some((first, rest)) if rest.iter().all(|kind| kind == first) => {
+                    CallKind::from_str(first, false).unwrap_or(CallKind::Direct)
+                }
+                _ => CallKind::Direct,
+            };
+            graph_data.graph.add_edge(from_idx, to_idx, kind.into());
+        }
+    }
+
+    graph_data
+}
+
+/// The node's `group`; groups without a `group_color` get a palette color picked by name
+fn json_group(node: &Value) -> Option<NodeGroup> {
+    let name = node["group"].as_str()?;
+    Some(NodeGroup {
+        name: name.to_string(),
+        color: node["group_color"].as_str().unwrap_or_else(|| group_color(fnv1a_64(name.as_bytes()) as usize)).to_string(),
+        direction: node["group_direction"].as_str().map(str::to_string),
+    })
+}
+
+fn json_hash(node: &Value, key: &str) -> Option<u64> {
+    u64::from_str_radix(node[key].as_str()?, 16).ok()
+}
+
+fn json_metrics(node: &Value) -> BTreeMap<String, f64> {
+    node["metrics"].as_object()
+        .map(|metrics| metrics.iter().filter_map(|(name, value)| Some((name.clone(), value.as_f64()?))).collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,142 @@
+// ============================================================================
+// crates.io API
+// ============================================================================
+//
+// Responses of https://crates.io/api/v1/crates/<name>[/...], fetched with curl
+// and cached as JSON files for a day:
+//
+//   <cache-dir>/crates-io/serde.json
+//   <cache-dir>/crates-io/serde-owners.json
+//
+// crates.io asks crawlers for at most one request per second, so uncached
+// requests are spaced out. When a request fails, a stale cache entry is used
+// instead, with a warning.
+
+use std::{collections::BTreeSet, error::Error, fs, path::{Path, PathBuf}, process::Command, thread, time::{Duration, Instant, SystemTime}};
+
+use petgraph::Direction;
+use serde_json::Value;
+
+use crate::{types::{GraphData, WarningCode}, utils::{helper::{is_crates_io, write_atomic}, warnings::warn}};
+
+const API: &str = "https://crates.io/api/v1/crates";
+const USER_AGENT: &str = concat!("rust-grapher/", env!("CARGO_PKG_VERSION"), " (", env!("CARGO_PKG_REPOSITORY"), ")");
+const CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+const REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct CratesIo {
+    cache_dir: PathBuf,
+    last_request: Option<Instant>,
+}
+
+impl CratesIo {
+    /// Client caching under `cache_dir`, or the user cache directory
+    pub fn new(cache_dir: Option<&Path>) -> Self {
+        let cache_dir = cache_dir.map(Path::to_path_buf).unwrap_or_else(default_cache_dir).join("crates-io");
+        CratesIo { cache_dir, last_request: None }
+    }
+
+    /// `GET /api/v1/crates/<name>`: the crate (`max_stable_version`, ...) and all its `versions`
+    pub fn crate_info(&mut self, name: &str) -> Result<Value, Box<dyn Error>> {
+        self.get(name, &format!("{}/{}", API, name))
+    }
+
+    /// `GET /api/v1/crates/<name>/owners`: logins of the users and teams that can publish it
+    pub fn owners(&mut self, name: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        let response = self.get(&format!("{}-owners", name), &format!("{}/{}/owners", API, name))?;
+        let users = response["users"].as_array().ok_or_else(|| format!("No owners in the crates.io response for {}", name))?;
+        Ok(users.iter().filter_map(|user| user["login"].as_str()).map(str::to_string).collect())
+    }
+
+    fn get(&mut self, key: &str, url: &str) -> Result<Value, Box<dyn Error>> {
+        let cache_file = self.cache_dir.join(format!("{}.json", key));
+        let cached = fs::read_to_string(&cache_file).ok().and_then(|content| serde_json::from_str::<Value>(&content).ok());
+        let age = fs::metadata(&cache_file).and_then(|m| m.modified()).ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+        if let (Some(value), Some(age)) = (&cached, age) {
+            if age < CACHE_TTL {
+                return Ok(value.clone());
+            }
+        }
+
+        if let Some(elapsed) = self.last_request.map(|at| at.elapsed()) {
+            thread::sleep(REQUEST_INTERVAL.saturating_sub(elapsed));
+        }
+        self.last_request = Some(Instant::now());
+
+        match fetch(url) {
+            Ok(body) => {
+                let value: Value = serde_json::from_str(&body).map_err(|e| format!("Invalid response from {}: {}", url, e))?;
+                if let Err(e) = write_atomic(&cache_file, body) {
+                    warn(WarningCode::MissingData, format!("failed to cache {}: {}", cache_file.display(), e));
+                }
+                Ok(value)
+            }
+            Err(e) => match cached {
+                Some(value) => {
+                    warn(WarningCode::MissingData, format!("{}; using the cached response from {}", e, cache_file.display()));
+                    Ok(value)
+                }
+                None => Err(e),
+            },
+        }
+    }
+}
+
+fn fetch(url: &str) -> Result<String, Box<dyn Error>> {
+    let output = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location", "--max-time", "30", "--user-agent", USER_AGENT, url])
+        .output()
+        .map_err(|e| format!("Failed to run curl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("GET {} failed: {}", url, String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Whether an owner login is a team (`github:org:team`) rather than a user
+pub fn is_team(login: &str) -> bool {
+    login.contains(':')
+}
+
+/// `(name, version)` of the crates.io packages in the graph, only those declared by
+/// workspace members with `direct_only`
+pub fn crates_io_packages(graph_data: &GraphData, direct_only: bool) -> BTreeSet<(&str, &str)> {
+    let graph = &graph_data.graph;
+    graph.node_indices()
+        .filter(|&idx| graph[idx].source.as_deref().is_some_and(is_crates_io))
+        .filter(|&idx| !direct_only || graph.neighbors_directed(idx, Direction::Incoming).any(|n| graph[n].is_workspace_member))
+        .map(|idx| (graph[idx].name.as_str(), graph[idx].version.as_str()))
+        .collect()
+}
+
+/// `$XDG_CACHE_HOME/rust-grapher`, `~/.cache/rust-grapher`, or under the temp directory
+pub fn default_cache_dir() -> PathBuf {
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").or_else(|| std::env::var_os("LOCALAPPDATA")).map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("rust-grapher")
+}
+
+/// Days since the Unix epoch of an RFC 3339 timestamp such as `2024-05-01T12:00:00.123+00:00`
+pub fn days_since_epoch(timestamp: &str) -> Option<i64> {
+    let mut parts = timestamp.get(..10)?.split('-');
+    let (year, month, day): (i64, i64, i64) = (parts.next()?.parse().ok()?, parts.next()?.parse().ok()?, parts.next()?.parse().ok()?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // Howard Hinnant's days_from_civil
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some(era * 146_097 + day_of_era - 719_468)
+}
+
+/// Today, in days since the Unix epoch
+pub fn today() -> i64 {
+    SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map_or(0, |d| (d.as_secs() / 86_400) as i64)
+}
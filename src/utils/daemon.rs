@@ -0,0 +1,301 @@
+// ============================================================================
+// Daemon Mode (rust-grapher daemon)
+// ============================================================================
+//
+// Keeps the function and dependency graphs in memory and answers JSON-RPC 2.0
+// requests from stdin, one per line or `Content-Length` framed as in LSP.
+// Responses use the framing of their request. Each graph is built on first use
+// and kept until `reload`.
+//
+//   --> {"jsonrpc": "2.0", "id": 1, "method": "callers", "params": {"name": "run_deps"}}
+//   <-- {"jsonrpc": "2.0", "id": 1, "result": [{"id": "fn:main#ef61edc9", "name": "main", "file": "main.rs", "line": 12}]}
+//
+// Methods; `name` takes the --focus syntax: a function's name, qualified name or
+// `::` path suffix, or a crate name (`-`, `.` and `_` alike), without wildcards:
+//
+//   callers, callees            {name}   functions calling / called by it
+//   dependents, dependencies    {name}   crates depending on / used by it
+//   focus    {graph: "fn" | "deps", name, depth?, format?}
+//            the neighborhood as --focus output (json by default, vscode-graph,
+//            mermaid or dot)
+//   reload   {}   rebuild the graphs on their next use, e.g. after a save
+//   shutdown {}   answer, then exit
+
+use std::{
+    error::Error,
+    io::{self, BufRead, Write},
+    path::PathBuf,
+};
+
+use clap::{Args, Command, FromArgMatches, ValueEnum};
+use petgraph::{graph::NodeIndex, Direction};
+use serde_json::{json, Value};
+
+use crate::{types::{DaemonArgs, DepsArgs, FnGraphArgs, FnGraphData, FnNodeInfo, GraphData, NodeInfo, OutputFormat, SourceLocations}, utils};
+
+use utils::generator::{generate_deps_dot, generate_deps_json, generate_deps_mermaid, generate_deps_vscode_graph, generate_fn_dot, generate_fn_json, generate_fn_mermaid, generate_fn_vscode_graph};
+use utils::graph::{extract_focus, focus_nodes, GraphNode};
+use utils::grapher::{load_deps_graph, load_fn_graph};
+
+/// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError { code, message: message.into() }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Framing {
+    Lines,
+    ContentLength,
+}
+
+pub fn run_daemon(args: &DaemonArgs) -> Result<(String, Option<PathBuf>), Box<dyn Error>> {
+    let mut daemon = Daemon::new(args)?;
+    let mut input = io::stdin().lock();
+    let mut output = io::stdout().lock();
+
+    while let Some((request, framing)) = read_request(&mut input)? {
+        let (response, exit) = match request {
+            Ok(request) => daemon.handle(&request),
+            Err(e) => (Some(error_response(Value::Null, RpcError::new(PARSE_ERROR, e))), false),
+        };
+        if let Some(response) = response {
+            write_response(&mut output, &response, framing)?;
+        }
+        if exit {
+            break;
+        }
+    }
+
+    Ok((String::new(), None))
+}
+
+struct Daemon {
+    fn_args: FnGraphArgs,
+    deps_args: DepsArgs,
+    fn_graph: Option<FnGraphData>,
+    /// File paths and call sites of `fn_graph`, for vscode-graph URIs
+    fn_locations: SourceLocations,
+    deps_graph: Option<GraphData>,
+}
+
+impl Daemon {
+    fn new(args: &DaemonArgs) -> Result<Self, Box<dyn Error>> {
+        let mut fn_args: FnGraphArgs = cli_defaults()?;
+        fn_args.source_dir = args.source_dir.clone();
+        fn_args.manifest_path = args.manifest_path.clone();
+        fn_args.workspace = args.workspace;
+        fn_args.resolver = args.resolver;
+
+        let mut deps_args: DepsArgs = cli_defaults()?;
+        deps_args.manifest_path = args.manifest_path.clone();
+
+        Ok(Daemon { fn_args, deps_args, fn_graph: None, fn_locations: SourceLocations::default(), deps_graph: None })
+    }
+
+    /// Response (none for notifications), and whether to exit afterwards
+    fn handle(&mut self, request: &Value) -> (Option<Value>, bool) {
+        let id = request.get("id").cloned();
+        let Some(method) = request["method"].as_str() else {
+            return (Some(error_response(id.unwrap_or(Value::Null), RpcError::new(INVALID_REQUEST, "missing method"))), false);
+        };
+        let params = &request["params"];
+
+        let result = match method {
+            "callers" => self.fn_graph().and_then(|graph| neighbors(graph, params, Direction::Incoming, fn_node_json)),
+            "callees" => self.fn_graph().and_then(|graph| neighbors(graph, params, Direction::Outgoing, fn_node_json)),
+            "dependents" => self.deps_graph().and_then(|graph| neighbors(graph, params, Direction::Incoming, dep_node_json)),
+            "dependencies" => self.deps_graph().and_then(|graph| neighbors(graph, params, Direction::Outgoing, dep_node_json)),
+            "focus" => self.focus(params),
+            "reload" => {
+                self.fn_graph = None;
+                self.deps_graph = None;
+                Ok(Value::Null)
+            }
+            "shutdown" | "exit" => Ok(Value::Null),
+            _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("unknown method '{}'", method))),
+        };
+
+        let exit = matches!(method, "shutdown" | "exit");
+        let response = id.map(|id| match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(e) => error_response(id, e),
+        });
+        (response, exit)
+    }
+
+    fn fn_graph(&mut self) -> Result<&FnGraphData, RpcError> {
+        let graph = match self.fn_graph.take() {
+            Some(graph) => graph,
+            None => {
+                let (graph, locations) = load_fn_graph(&self.fn_args).map_err(|e| RpcError::new(SERVER_ERROR, e.to_string()))?;
+                self.fn_locations = locations;
+                graph
+            }
+        };
+        Ok(self.fn_graph.insert(graph))
+    }
+
+    fn deps_graph(&mut self) -> Result<&GraphData, RpcError> {
+        let graph = match self.deps_graph.take() {
+            Some(graph) => graph,
+            None => load_deps_graph(&self.deps_args).map_err(|e| RpcError::new(SERVER_ERROR, e.to_string()))?.0,
+        };
+        Ok(self.deps_graph.insert(graph))
+    }
+
+    fn focus(&mut self, params: &Value) -> Result<Value, RpcError> {
+        let name = name_param(params)?;
+        let depth = match &params["depth"] {
+            Value::Null => 0,
+            depth => depth.as_u64().ok_or_else(|| RpcError::new(INVALID_PARAMS, "'depth' must be a non-negative integer"))? as usize,
+        };
+        let format = match params["format"].as_str() {
+            None => OutputFormat::Json,
+            Some(format) => match OutputFormat::from_str(format, true) {
+                Ok(format @ (OutputFormat::Mermaid | OutputFormat::Dot | OutputFormat::Json | OutputFormat::VscodeGraph)) => format,
+                _ => return Err(RpcError::new(INVALID_PARAMS, format!("unsupported format '{}' (mermaid, dot, json or vscode-graph)", format))),
+            },
+        };
+        let no_match = || RpcError::new(INVALID_PARAMS, format!("no node matches '{}'", name));
+
+        let output = match params["graph"].as_str().unwrap_or("fn") {
+            "fn" => {
+                let graph = extract_focus(self.fn_graph()?, name, depth).ok_or_else(no_match)?;
+                let args = &self.fn_args;
+                match format {
+                    OutputFormat::Mermaid => generate_fn_mermaid(&graph, args),
+                    OutputFormat::Dot => generate_fn_dot(&graph, args),
+                    OutputFormat::VscodeGraph => generate_fn_vscode_graph(&graph, args, &self.fn_locations),
+                    _ => generate_fn_json(&graph, args),
+                }
+            }
+            "deps" => {
+                let graph = extract_focus(self.deps_graph()?, name, depth).ok_or_else(no_match)?;
+                let args = &self.deps_args;
+                match format {
+                    OutputFormat::Mermaid => generate_deps_mermaid(&graph, args),
+                    OutputFormat::Dot => generate_deps_dot(&graph, args),
+                    OutputFormat::VscodeGraph => generate_deps_vscode_graph(&graph, args),
+                    _ => generate_deps_json(&graph, args),
+                }
+            }
+            other => return Err(RpcError::new(INVALID_PARAMS, format!("unknown graph '{}' (fn or deps)", other))),
+        };
+
+        // JSON output is returned as an object, the text formats as a string
+        Ok(match format {
+            OutputFormat::Json | OutputFormat::VscodeGraph => serde_json::from_str(&output).unwrap_or(Value::String(output)),
+            _ => Value::String(output),
+        })
+    }
+}
+
+/// Options as the CLI would parse them without any flags
+fn cli_defaults<A: Args + FromArgMatches>() -> Result<A, clap::Error> {
+    let matches = A::augment_args(Command::new("rust-grapher")).try_get_matches_from(["rust-grapher"])?;
+    A::from_arg_matches(&matches)
+}
+
+fn name_param(params: &Value) -> Result<&str, RpcError> {
+    params["name"].as_str().ok_or_else(|| RpcError::new(INVALID_PARAMS, "missing string parameter 'name'"))
+}
+
+/// Neighbors of every node matching `params.name`, sorted by id
+fn neighbors<K, N: GraphNode, E>(
+    graph_data: &crate::types::Graph<K, N, E>,
+    params: &Value,
+    direction: Direction,
+    node_json: fn(&N) -> Value,
+) -> Result<Value, RpcError> {
+    let name = name_param(params)?;
+    let graph = &graph_data.graph;
+    let matched = focus_nodes(graph, name);
+    if matched.is_empty() {
+        return Err(RpcError::new(INVALID_PARAMS, format!("no node matches '{}'", name)));
+    }
+
+    let mut found: Vec<NodeIndex> = matched.iter()
+        .flat_map(|&idx| graph.neighbors_directed(idx, direction))
+        .collect();
+    found.sort_by_key(|&idx| graph[idx].id());
+    found.dedup();
+    Ok(Value::Array(found.into_iter().map(|idx| node_json(&graph[idx])).collect()))
+}
+
+fn fn_node_json(info: &FnNodeInfo) -> Value {
+    json!({
+        "id": info.id(),
+        "name": info.name,
+        "file": info.file_path,
+        "line": info.line,
+    })
+}
+
+fn dep_node_json(info: &NodeInfo) -> Value {
+    json!({
+        "id": info.id(),
+        "name": info.name,
+        "version": info.version,
+    })
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": error.code, "message": error.message } })
+}
+
+/// Next request and its framing, `None` at end of input
+fn read_request(input: &mut impl BufRead) -> io::Result<Option<(Result<Value, String>, Framing)>> {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        if !line.trim().is_empty() {
+            break;
+        }
+    }
+
+    let Some(length) = line.trim_end().strip_prefix("Content-Length:") else {
+        return Ok(Some((serde_json::from_str(line.trim()).map_err(|e| e.to_string()), Framing::Lines)));
+    };
+    let length: usize = length.trim().parse()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid Content-Length header"))?;
+
+    // Skip the remaining headers up to the blank line
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        if line.trim().is_empty() {
+            break;
+        }
+    }
+
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    Ok(Some((serde_json::from_slice(&body).map_err(|e| e.to_string()), Framing::ContentLength)))
+}
+
+fn write_response(output: &mut impl Write, response: &Value, framing: Framing) -> io::Result<()> {
+    let body = response.to_string();
+    match framing {
+        Framing::Lines => writeln!(output, "{}", body)?,
+        Framing::ContentLength => write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?,
+    }
+    output.flush()
+}
@@ -0,0 +1,585 @@
+// ============================================================================
+// Symbol Demangling
+// ============================================================================
+//
+// External inputs name functions by their linker symbol (`_ZN...E` legacy or
+// `_R...` v0 mangling) or by an already demangled path. `normalize_symbol`
+// turns all of these into the `path::to::function` shape of fn-graph qualified
+// names, and `SymbolIndex` joins them onto the nodes of a function graph.
+
+use std::collections::{HashMap, HashSet};
+
+use petgraph::graph::NodeIndex;
+
+use crate::types::FnGraphData;
+
+/// Deepest nesting of paths and types followed in a v0 symbol
+const MAX_DEPTH: usize = 100;
+
+/// Demangle a Rust symbol, `None` if it is not a (valid) mangled Rust symbol
+pub fn demangle(symbol: &str) -> Option<String> {
+    // LLVM appends `.llvm.<hash>` to symbols it made local
+    let symbol = symbol.split_once(".llvm.").map_or(symbol, |(symbol, _)| symbol);
+
+    if let Some(mangled) = symbol.strip_prefix("_ZN").or_else(|| symbol.strip_prefix("__ZN")) {
+        demangle_legacy(mangled)
+    } else if let Some(mangled) = symbol.strip_prefix("_R").or_else(|| symbol.strip_prefix("__R")) {
+        V0 { bytes: mangled.as_bytes(), pos: 0, depth: 0 }.symbol()
+    } else {
+        None
+    }
+}
+
+/// Mangled or demangled symbol reduced to `path::to::function`: no address offset,
+/// symbol hash, generic arguments or closure segments; `<Type as Trait>::method`
+/// becomes `Type::method`
+pub fn normalize_symbol(symbol: &str) -> String {
+    let mut symbol = symbol.trim();
+    if let Some((name, offset)) = symbol.rsplit_once("+0x") {
+        if offset.chars().all(|c| c.is_ascii_hexdigit()) {
+            symbol = name;
+        }
+    }
+
+    let demangled = demangle(symbol);
+    let mut symbol = demangled.as_deref().unwrap_or(symbol);
+    if let Some((name, hash)) = symbol.rsplit_once("::h") {
+        if is_legacy_hash(hash) {
+            symbol = name;
+        }
+    }
+
+    // Keep only the self type of `<Type as Trait>` and drop every other `<...>`
+    let mut path = String::new();
+    let mut depth = 0;
+    let mut qualified_self = false;
+    let mut skipping_trait = false;
+    let mut rest = symbol;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '<' => {
+                if path.is_empty() && !qualified_self {
+                    qualified_self = true;
+                } else {
+                    depth += 1;
+                }
+            }
+            '>' if depth > 0 => depth -= 1,
+            '>' if qualified_self => {
+                qualified_self = false;
+                skipping_trait = false;
+            }
+            _ if depth > 0 || skipping_trait => {}
+            ' ' if qualified_self && rest.starts_with(" as ") => skipping_trait = true,
+            _ => path.push(c),
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+
+    // `{closure#0}`, `{{closure}}` and `call_once{{vtable.shim}}` belong to the enclosing function
+    path.split("::")
+        .map(|segment| segment.split('{').next().unwrap_or_default())
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+fn is_legacy_hash(segment: &str) -> bool {
+    segment.len() == 16 && segment.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// ============================================================================
+// Joining Onto the Function Graph
+// ============================================================================
+
+/// Finds the fn-graph node an external symbol refers to: the longest `::` suffix
+/// of the normalized symbol that is a qualified name in the graph, or else a
+/// function name only one node has. Once the analyzed crates are known, paths
+/// starting with any other crate (`std::`, dependencies) match nothing; short
+/// frames like `run` or `Parser::parse` are still matched by suffix.
+pub struct SymbolIndex<'g> {
+    graph_data: &'g FnGraphData,
+    by_name: HashMap<&'g str, Vec<NodeIndex>>,
+    crates: HashSet<String>,
+}
+
+impl<'g> SymbolIndex<'g> {
+    /// `crates` adds to the crate names of the nodes, e.g. the package of `--source-dir`
+    pub fn new(graph_data: &'g FnGraphData, crates: &[String]) -> Self {
+        let mut by_name: HashMap<&str, Vec<NodeIndex>> = HashMap::new();
+        for idx in graph_data.graph.node_indices() {
+            by_name.entry(&graph_data.graph[idx].name).or_default().push(idx);
+        }
+        let crates = graph_data.graph.node_weights()
+            .filter_map(|info| info.crate_name.as_ref())
+            .chain(crates)
+            .map(|name| name.replace('-', "_"))
+            .collect();
+        SymbolIndex { graph_data, by_name, crates }
+    }
+
+    pub fn lookup(&self, symbol: &str) -> Option<NodeIndex> {
+        let path = normalize_symbol(symbol);
+        let segments: Vec<&str> = path.split("::").collect();
+        let crate_like = segments.len() > 1 && segments[0].starts_with(|c: char| c.is_ascii_lowercase() || c == '_');
+        if crate_like && !self.crates.is_empty() && !self.crates.contains(segments[0]) {
+            return None;
+        }
+        for start in 0..segments.len() {
+            if let Some(&idx) = self.graph_data.node_indices.get(&segments[start..].join("::")) {
+                return Some(idx);
+            }
+        }
+        match self.by_name.get(segments.last()?)?.as_slice() {
+            [idx] => Some(*idx),
+            _ => None,
+        }
+    }
+}
+
+// ============================================================================
+// Legacy Mangling (_ZN...E)
+// ============================================================================
+
+fn demangle_legacy(mangled: &str) -> Option<String> {
+    let mut rest = mangled;
+    let mut segments = Vec::new();
+
+    while !rest.starts_with('E') {
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let len: usize = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        segments.push(unescape_legacy(rest.get(..len)?)?);
+        rest = &rest[len..];
+    }
+
+    if segments.last().is_some_and(|last| last.strip_prefix('h').is_some_and(is_legacy_hash)) {
+        segments.pop();
+    }
+    (!segments.is_empty()).then(|| segments.join("::"))
+}
+
+fn unescape_legacy(segment: &str) -> Option<String> {
+    // A leading `_` only keeps the segment from starting with `$`
+    let mut rest = if segment.starts_with("_$") { &segment[1..] } else { segment };
+    let mut output = String::new();
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('$') {
+            let (escape, tail) = after.split_once('$')?;
+            output.push(match escape {
+                "SP" => '@',
+                "BP" => '*',
+                "RF" => '&',
+                "LT" => '<',
+                "GT" => '>',
+                "LP" => '(',
+                "RP" => ')',
+                "C" => ',',
+                _ => char::from_u32(u32::from_str_radix(escape.strip_prefix('u')?, 16).ok()?)?,
+            });
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("..") {
+            output.push_str("::");
+            rest = tail;
+        } else {
+            let c = rest.chars().next()?;
+            output.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    Some(output)
+}
+
+// ============================================================================
+// v0 Mangling (_R...)
+// ============================================================================
+
+struct V0<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl V0<'_> {
+    fn symbol(&mut self) -> Option<String> {
+        // Optional encoding version
+        if self.peek()?.is_ascii_digit() {
+            self.decimal()?;
+        }
+        let mut output = String::new();
+        self.path(&mut output, true)?;
+        // The instantiating crate and any vendor suffix are not part of the name
+        Some(output)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let matched = self.peek() == Some(byte);
+        if matched {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    /// `0` or digits without a leading zero
+    fn decimal(&mut self) -> Option<usize> {
+        let start = self.pos;
+        if self.eat(b'0') {
+            return Some(0);
+        }
+        while self.peek()?.is_ascii_digit() {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.bytes[start..self.pos]).ok()?.parse().ok()
+    }
+
+    /// `_` is 0, otherwise digits 0-9a-zA-Z terminated by `_`, plus one
+    fn base62(&mut self) -> Option<u64> {
+        if self.eat(b'_') {
+            return Some(0);
+        }
+        let mut value: u64 = 0;
+        loop {
+            let digit = match self.next()? {
+                b'_' => return value.checked_add(1),
+                c @ b'0'..=b'9' => c - b'0',
+                c @ b'a'..=b'z' => c - b'a' + 10,
+                c @ b'A'..=b'Z' => c - b'A' + 36,
+                _ => return None,
+            };
+            value = value.checked_mul(62)?.checked_add(u64::from(digit))?;
+        }
+    }
+
+    fn disambiguator(&mut self) -> Option<u64> {
+        if self.eat(b's') { self.base62()?.checked_add(1) } else { Some(0) }
+    }
+
+    /// Identifier and its disambiguator
+    fn ident(&mut self) -> Option<(String, u64)> {
+        let disambiguator = self.disambiguator()?;
+        let punycode = self.eat(b'u');
+        let len = self.decimal()?;
+        self.eat(b'_');
+        let bytes = self.bytes.get(self.pos..self.pos + len)?;
+        self.pos += len;
+
+        let mut name = std::str::from_utf8(bytes).ok()?.to_string();
+        if punycode {
+            // Shown as-is rather than decoded
+            name = format!("{}[punycode]", name);
+        }
+        Some((name, disambiguator))
+    }
+
+    /// Run `f` at the position a backref points to, then continue after the backref
+    fn backref<T>(&mut self, f: impl FnOnce(&mut Self) -> Option<T>) -> Option<T> {
+        let start = self.pos - 1;
+        let target = usize::try_from(self.base62()?).ok()?;
+        if target >= start {
+            return None;
+        }
+        let resume = self.pos;
+        self.pos = target;
+        let result = f(self);
+        self.pos = resume;
+        result
+    }
+
+    fn enter(&mut self) -> Option<()> {
+        self.depth += 1;
+        (self.depth <= MAX_DEPTH).then_some(())
+    }
+
+    fn path(&mut self, output: &mut String, in_value: bool) -> Option<()> {
+        self.enter()?;
+        match self.next()? {
+            b'C' => output.push_str(&self.ident()?.0),
+            b'N' => {
+                let namespace = self.next()?;
+                self.path(output, in_value)?;
+                let (name, disambiguator) = self.ident()?;
+                if namespace.is_ascii_uppercase() {
+                    let kind = match namespace {
+                        b'C' => "closure".to_string(),
+                        b'S' => "shim".to_string(),
+                        other => (other as char).to_string(),
+                    };
+                    output.push_str("::{");
+                    output.push_str(&kind);
+                    if !name.is_empty() {
+                        output.push(':');
+                        output.push_str(&name);
+                    }
+                    output.push_str(&format!("#{}}}", disambiguator));
+                } else if !name.is_empty() {
+                    output.push_str("::");
+                    output.push_str(&name);
+                }
+            }
+            b'M' => {
+                self.disambiguator()?;
+                self.path(&mut String::new(), false)?;
+                output.push('<');
+                self.ty(output)?;
+                output.push('>');
+            }
+            b'X' => {
+                self.disambiguator()?;
+                self.path(&mut String::new(), false)?;
+                output.push('<');
+                self.ty(output)?;
+                output.push_str(" as ");
+                self.path(output, false)?;
+                output.push('>');
+            }
+            b'Y' => {
+                output.push('<');
+                self.ty(output)?;
+                output.push_str(" as ");
+                self.path(output, false)?;
+                output.push('>');
+            }
+            b'I' => {
+                self.path(output, in_value)?;
+                if in_value {
+                    output.push_str("::");
+                }
+                output.push('<');
+                self.list(output, b'E', ", ", Self::generic_arg)?;
+                output.push('>');
+            }
+            b'B' => self.backref(|this| this.path(output, in_value))?,
+            _ => return None,
+        }
+        self.depth -= 1;
+        Some(())
+    }
+
+    /// Items produced by `item` up to the `end` byte, joined with `separator`
+    fn list(&mut self, output: &mut String, end: u8, separator: &str, item: fn(&mut Self, &mut String) -> Option<()>) -> Option<usize> {
+        let mut count = 0;
+        while !self.eat(end) {
+            if count > 0 {
+                output.push_str(separator);
+            }
+            item(self, output)?;
+            count += 1;
+        }
+        Some(count)
+    }
+
+    fn generic_arg(&mut self, output: &mut String) -> Option<()> {
+        if self.eat(b'L') {
+            self.base62()?;
+            output.push_str("'_");
+            Some(())
+        } else if self.eat(b'K') {
+            // Integer arguments carry their type: `<10: usize>`
+            let ty = self.peek().filter(|tag| !matches!(tag, b'b' | b'c' | b'p' | b'B')).and_then(basic_type);
+            self.constant(output)?;
+            if let Some(ty) = ty {
+                output.push_str(": ");
+                output.push_str(ty);
+            }
+            Some(())
+        } else {
+            self.ty(output)
+        }
+    }
+
+    fn binder(&mut self) -> Option<()> {
+        if self.eat(b'G') {
+            self.base62()?;
+        }
+        Some(())
+    }
+
+    fn ty(&mut self, output: &mut String) -> Option<()> {
+        self.enter()?;
+        let tag = self.peek()?;
+        if let Some(name) = basic_type(tag) {
+            self.pos += 1;
+            output.push_str(name);
+            self.depth -= 1;
+            return Some(());
+        }
+
+        match tag {
+            b'A' | b'S' => {
+                self.pos += 1;
+                output.push('[');
+                self.ty(output)?;
+                if tag == b'A' {
+                    output.push_str("; ");
+                    self.constant(output)?;
+                }
+                output.push(']');
+            }
+            b'T' => {
+                self.pos += 1;
+                output.push('(');
+                if self.list(output, b'E', ", ", Self::ty)? == 1 {
+                    output.push(',');
+                }
+                output.push(')');
+            }
+            b'R' | b'Q' => {
+                self.pos += 1;
+                if self.eat(b'L') {
+                    self.base62()?;
+                }
+                output.push_str(if tag == b'R' { "&" } else { "&mut " });
+                self.ty(output)?;
+            }
+            b'P' | b'O' => {
+                self.pos += 1;
+                output.push_str(if tag == b'P' { "*const " } else { "*mut " });
+                self.ty(output)?;
+            }
+            b'F' => {
+                self.pos += 1;
+                self.binder()?;
+                if self.eat(b'U') {
+                    output.push_str("unsafe ");
+                }
+                if self.eat(b'K') {
+                    if self.eat(b'C') {
+                        output.push_str("extern \"C\" ");
+                    } else {
+                        let (abi, _) = self.ident()?;
+                        output.push_str(&format!("extern \"{}\" ", abi.replace('_', "-")));
+                    }
+                }
+                output.push_str("fn(");
+                self.list(output, b'E', ", ", Self::ty)?;
+                output.push(')');
+                let mut ret = String::new();
+                self.ty(&mut ret)?;
+                if ret != "()" {
+                    output.push_str(" -> ");
+                    output.push_str(&ret);
+                }
+            }
+            b'D' => {
+                self.pos += 1;
+                self.binder()?;
+                output.push_str("dyn ");
+                self.list(output, b'E', " + ", Self::dyn_trait)?;
+                if !self.eat(b'L') {
+                    return None;
+                }
+                self.base62()?;
+            }
+            b'B' => {
+                self.pos += 1;
+                self.backref(|this| this.ty(output))?;
+            }
+            _ => self.path(output, false)?,
+        }
+        self.depth -= 1;
+        Some(())
+    }
+
+    fn dyn_trait(&mut self, output: &mut String) -> Option<()> {
+        let mut path = String::new();
+        self.path(&mut path, false)?;
+
+        // Associated type bindings join the trait's generic arguments: `Fn<(A,), Output = B>`
+        let mut open = false;
+        while self.eat(b'p') {
+            if !open {
+                match path.strip_suffix('>') {
+                    Some(generics) => {
+                        path.truncate(generics.len());
+                        path.push_str(", ");
+                    }
+                    None => path.push('<'),
+                }
+                open = true;
+            } else {
+                path.push_str(", ");
+            }
+            let (name, _) = self.ident()?;
+            path.push_str(&format!("{} = ", name));
+            self.ty(&mut path)?;
+        }
+        if open {
+            path.push('>');
+        }
+
+        output.push_str(&path);
+        Some(())
+    }
+
+    fn constant(&mut self, output: &mut String) -> Option<()> {
+        if self.eat(b'p') {
+            output.push('_');
+            return Some(());
+        }
+        if self.eat(b'B') {
+            return self.backref(|this| this.constant(output));
+        }
+
+        let tag = self.next()?;
+        basic_type(tag)?;
+        let negative = self.eat(b'n');
+        let start = self.pos;
+        while self.next()? != b'_' {}
+        let hex = std::str::from_utf8(&self.bytes[start..self.pos - 1]).ok()?;
+
+        match tag {
+            b'b' => output.push_str(if hex == "1" { "true" } else { "false" }),
+            b'c' => {
+                let c = char::from_u32(u32::from_str_radix(hex, 16).ok()?)?;
+                output.push_str(&format!("{:?}", c));
+            }
+            _ => {
+                let value = if hex.is_empty() { 0 } else { u128::from_str_radix(hex, 16).ok()? };
+                if negative {
+                    output.push('-');
+                }
+                output.push_str(&value.to_string());
+            }
+        }
+        Some(())
+    }
+}
+
+fn basic_type(tag: u8) -> Option<&'static str> {
+    Some(match tag {
+        b'a' => "i8",
+        b'b' => "bool",
+        b'c' => "char",
+        b'd' => "f64",
+        b'e' => "str",
+        b'f' => "f32",
+        b'h' => "u8",
+        b'i' => "isize",
+        b'j' => "usize",
+        b'l' => "i32",
+        b'm' => "u32",
+        b'n' => "i128",
+        b'o' => "u128",
+        b's' => "i16",
+        b't' => "u16",
+        b'u' => "()",
+        b'v' => "...",
+        b'x' => "i64",
+        b'y' => "u64",
+        b'z' => "!",
+        b'p' => "_",
+        _ => return None,
+    })
+}
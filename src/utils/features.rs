@@ -0,0 +1,142 @@
+// ============================================================================
+// Feature Unification (`explain-features`)
+// ============================================================================
+//
+// Starting from the enabled features of a crate (cargo metadata's resolve),
+// walk back to whatever turned each one on: another feature of the same
+// crate, a dependent's declaration (`features = [...]` or default features),
+// or a dependent's own feature forwarding it (`dep/feature`, `dep?/feature`),
+// whose activation is then explained in turn.
+
+use std::{collections::{HashMap, HashSet, VecDeque}, error::Error, path::PathBuf};
+
+use cargo_metadata::{Metadata, MetadataCommand, Node, Package, PackageId};
+use petgraph::graph::NodeIndex;
+
+use crate::{types::{ExplainFeaturesArgs, FeatureEdge, FeatureGraph, FeatureNode, OutputFormat}, utils::generator::{generate_features_dot, generate_features_json, generate_features_mermaid}};
+
+pub fn run_explain_features(args: &ExplainFeaturesArgs) -> Result<(String, Option<PathBuf>), Box<dyn Error>> {
+    if !matches!(args.format, OutputFormat::Mermaid | OutputFormat::Dot | OutputFormat::Json) {
+        return Err(format!("--format {} is not available for explain-features", args.format.name()).into());
+    }
+
+    let metadata = MetadataCommand::new().manifest_path(&args.manifest_path).exec()?;
+    let graph = explain_features(&metadata, &args.crate_name)?;
+
+    let output = match args.format {
+        OutputFormat::Dot => generate_features_dot(&graph, args),
+        OutputFormat::Json => generate_features_json(&graph),
+        _ => generate_features_mermaid(&graph, args),
+    };
+    Ok((output, args.output.clone()))
+}
+
+/// Activation chains of every enabled feature of every version of `crate_name`
+pub fn explain_features(metadata: &Metadata, crate_name: &str) -> Result<FeatureGraph, Box<dyn Error>> {
+    let resolve = metadata.resolve.as_ref().ok_or("No resolve data")?;
+    let packages: HashMap<&PackageId, &Package> = metadata.packages.iter().map(|p| (&p.id, p)).collect();
+    let nodes: HashMap<&PackageId, &Node> = resolve.nodes.iter().map(|n| (&n.id, n)).collect();
+
+    let targets: Vec<&Package> = resolve.nodes.iter()
+        .filter_map(|node| packages.get(&node.id).copied())
+        .filter(|pkg| pkg.name == crate_name)
+        .collect();
+    if targets.is_empty() {
+        return Err(format!("No package named '{}' in the resolved dependency graph", crate_name).into());
+    }
+
+    let mut explainer = Explainer {
+        graph: FeatureGraph::new(),
+        indices: HashMap::new(),
+        workspace_members: metadata.workspace_members.iter().collect(),
+        target_ids: targets.iter().map(|pkg| &pkg.id).collect(),
+    };
+    let mut queue: VecDeque<(&Package, String)> = VecDeque::new();
+    let mut explained: HashSet<(&PackageId, String)> = HashSet::new();
+
+    for &target in &targets {
+        for feature in &nodes[&target.id].features {
+            explainer.node(target, Some(feature));
+            queue.push_back((target, feature.to_string()));
+        }
+    }
+
+    while let Some((pkg, feature)) = queue.pop_front() {
+        if !explained.insert((&pkg.id, feature.clone())) {
+            continue;
+        }
+        let enabled = |pkg: &Package, name: &str| nodes.get(&pkg.id).is_some_and(|node| node.features.iter().any(|f| f.as_ref() == name));
+
+        // Another feature of the same package lists it
+        for (other, entries) in &pkg.features {
+            if enabled(pkg, other) && entries.iter().any(|entry| entry == &feature) {
+                explainer.edge((pkg, Some(other)), (pkg, Some(&feature)), FeatureEdge::Enables);
+                queue.push_back((pkg, other.clone()));
+            }
+        }
+
+        // Dependents of this package
+        for node in &resolve.nodes {
+            if !node.deps.iter().any(|dep| dep.pkg == pkg.id) {
+                continue;
+            }
+            let Some(&dependent) = packages.get(&node.id) else { continue };
+
+            for declaration in dependent.dependencies.iter().filter(|d| d.name == pkg.name.as_str()) {
+                let key = declaration.rename.as_deref().unwrap_or(&declaration.name);
+                let requested = declaration.features.contains(&feature)
+                    || (feature == "default" && declaration.uses_default_features);
+                if requested {
+                    explainer.edge((dependent, None), (pkg, Some(&feature)), FeatureEdge::Requests);
+                }
+
+                // Features of the dependent that forward to this one (or pull in an optional dependency)
+                let forwards = [format!("{}/{}", key, feature), format!("{}?/{}", key, feature)];
+                let activations = [format!("dep:{}", key), key.to_string()];
+                for (other, entries) in &dependent.features {
+                    let forwarded = entries.iter().any(|entry| forwards.contains(entry))
+                        || (feature == "default" && declaration.optional && declaration.uses_default_features
+                            && entries.iter().any(|entry| activations.contains(entry)));
+                    if forwarded && enabled(dependent, other) {
+                        explainer.edge((dependent, Some(other)), (pkg, Some(&feature)), FeatureEdge::Enables);
+                        queue.push_back((dependent, other.clone()));
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(explainer.graph)
+}
+
+struct Explainer<'a> {
+    graph: FeatureGraph,
+    indices: HashMap<(PackageId, Option<String>), NodeIndex>,
+    workspace_members: HashSet<&'a PackageId>,
+    target_ids: HashSet<&'a PackageId>,
+}
+
+impl Explainer<'_> {
+    fn node(&mut self, pkg: &Package, feature: Option<&str>) -> NodeIndex {
+        let key = (pkg.id.clone(), feature.map(str::to_string));
+        if let Some(&idx) = self.indices.get(&key) {
+            return idx;
+        }
+        let idx = self.graph.add_node(FeatureNode {
+            package: format!("{}@{}", pkg.name, pkg.version),
+            feature: feature.map(str::to_string),
+            is_workspace_member: self.workspace_members.contains(&pkg.id),
+            is_target: self.target_ids.contains(&pkg.id),
+        });
+        self.indices.insert(key, idx);
+        idx
+    }
+
+    fn edge(&mut self, from: (&Package, Option<&str>), to: (&Package, Option<&str>), kind: FeatureEdge) {
+        let from = self.node(from.0, from.1);
+        let to = self.node(to.0, to.1);
+        if self.graph.find_edge(from, to).is_none() {
+            self.graph.add_edge(from, to, kind);
+        }
+    }
+}
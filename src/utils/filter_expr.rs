@@ -0,0 +1,355 @@
+// ============================================================================
+// Filter Expressions
+// ============================================================================
+//
+// Grammar:
+//   expr       := and ("||" and)*
+//   and        := unary ("&&" unary)*
+//   unary      := "!" unary | "(" expr ")" | comparison
+//   comparison := operand (("==" | "!=" | "<" | "<=" | ">" | ">=" | "~" | "!~") operand)?
+//   operand    := attribute | "string" | 'string' | number | true | false
+//
+// A bare operand is tested for truthiness; `~` matches `*` wildcard patterns.
+// An attribute list entry ending in `.*` (e.g. `metric.*`) admits any attribute
+// with that prefix.
+
+use std::cmp::Ordering;
+
+use crate::utils::helper::matches_any_pattern;
+
+#[derive(Clone, PartialEq)]
+pub enum FilterValue {
+    Bool(bool),
+    Num(f64),
+    Str(String),
+}
+
+pub struct FilterExpr {
+    root: Node,
+}
+
+enum Node {
+    Or(Box<Node>, Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    Compare(Operand, CmpOp, Operand),
+    Truthy(Operand),
+}
+
+enum Operand {
+    Attr(String),
+    Literal(FilterValue),
+}
+
+#[derive(Clone, Copy)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Match,
+    NotMatch,
+}
+
+#[derive(Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+    Cmp(&'static str),
+}
+
+impl FilterExpr {
+    /// Parse `source`, rejecting attributes that are not in `attributes`
+    pub fn parse(source: &str, attributes: &[&str]) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0, attributes };
+        let root = parser.parse_or()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(format!("Unexpected {} in filter expression", describe(token)));
+        }
+        Ok(FilterExpr { root })
+    }
+
+    /// Evaluate against one node; `lookup` returns `None` for attributes without a value
+    pub fn matches(&self, lookup: &dyn Fn(&str) -> Option<FilterValue>) -> bool {
+        eval(&self.root, lookup)
+    }
+}
+
+impl FilterValue {
+    fn truthy(&self) -> bool {
+        match self {
+            FilterValue::Bool(b) => *b,
+            FilterValue::Num(n) => *n != 0.0,
+            FilterValue::Str(s) => !s.is_empty(),
+        }
+    }
+
+    fn text(&self) -> String {
+        match self {
+            FilterValue::Bool(b) => b.to_string(),
+            FilterValue::Num(n) => n.to_string(),
+            FilterValue::Str(s) => s.clone(),
+        }
+    }
+}
+
+fn eval(node: &Node, lookup: &dyn Fn(&str) -> Option<FilterValue>) -> bool {
+    match node {
+        Node::Or(a, b) => eval(a, lookup) || eval(b, lookup),
+        Node::And(a, b) => eval(a, lookup) && eval(b, lookup),
+        Node::Not(inner) => !eval(inner, lookup),
+        Node::Truthy(operand) => resolve(operand, lookup).is_some_and(|v| v.truthy()),
+        Node::Compare(left, op, right) => {
+            match (resolve(left, lookup), resolve(right, lookup)) {
+                (Some(left), Some(right)) => compare(&left, *op, &right),
+                _ => false,
+            }
+        }
+    }
+}
+
+fn resolve(operand: &Operand, lookup: &dyn Fn(&str) -> Option<FilterValue>) -> Option<FilterValue> {
+    match operand {
+        Operand::Attr(name) => lookup(name),
+        Operand::Literal(value) => Some(value.clone()),
+    }
+}
+
+fn compare(left: &FilterValue, op: CmpOp, right: &FilterValue) -> bool {
+    if let CmpOp::Match | CmpOp::NotMatch = op {
+        let matched = matches_any_pattern(&left.text(), &[right.text()]);
+        return matches!(op, CmpOp::Match) == matched;
+    }
+
+    let ordering = match (left, right) {
+        (FilterValue::Num(a), FilterValue::Num(b)) => a.partial_cmp(b),
+        (FilterValue::Bool(a), FilterValue::Bool(b)) => Some(a.cmp(b)),
+        (a, b) => Some(a.text().cmp(&b.text())),
+    };
+
+    match op {
+        CmpOp::Eq => ordering == Some(Ordering::Equal),
+        CmpOp::Ne => ordering != Some(Ordering::Equal),
+        CmpOp::Lt => ordering == Some(Ordering::Less),
+        CmpOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        CmpOp::Gt => ordering == Some(Ordering::Greater),
+        CmpOp::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        CmpOp::Match | CmpOp::NotMatch => unreachable!(),
+    }
+}
+
+// ============================================================================
+// Tokenizer
+// ============================================================================
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let two: Option<&'static str> = match (c, next) {
+            ('&', Some('&')) => Some("&&"),
+            ('|', Some('|')) => Some("||"),
+            ('=', Some('=')) => Some("=="),
+            ('!', Some('=')) => Some("!="),
+            ('!', Some('~')) => Some("!~"),
+            ('<', Some('=')) => Some("<="),
+            ('>', Some('=')) => Some(">="),
+            _ => None,
+        };
+        if let Some(op) = two {
+            tokens.push(match op {
+                "&&" => Token::And,
+                "||" => Token::Or,
+                op => Token::Cmp(op),
+            });
+            i += 2;
+            continue;
+        }
+
+        match c {
+            '(' => tokens.push(Token::LParen),
+            ')' => tokens.push(Token::RParen),
+            '!' => tokens.push(Token::Not),
+            '<' => tokens.push(Token::Cmp("<")),
+            '>' => tokens.push(Token::Cmp(">")),
+            '~' => tokens.push(Token::Cmp("~")),
+            '"' | '\'' => {
+                let quote = c;
+                let mut value = String::new();
+                i += 1;
+                while i < chars.len() && chars[i] != quote {
+                    if chars[i] == '\\' && i + 1 < chars.len() {
+                        i += 1;
+                    }
+                    value.push(chars[i]);
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    return Err("Unterminated string in filter expression".to_string());
+                }
+                tokens.push(Token::Str(value));
+            }
+            c if c.is_ascii_digit() => {
+                let start = i;
+                while i + 1 < chars.len() && (chars[i + 1].is_ascii_digit() || chars[i + 1] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..=i].iter().collect();
+                let value = text.parse().map_err(|_| format!("Invalid number '{}' in filter expression", text))?;
+                tokens.push(Token::Num(value));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i + 1 < chars.len() && (chars[i + 1].is_alphanumeric() || matches!(chars[i + 1], '_' | '.')) {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(chars[start..=i].iter().collect()));
+            }
+            c => return Err(format!("Unexpected character '{}' in filter expression", c)),
+        }
+        i += 1;
+    }
+
+    Ok(tokens)
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Ident(name) => format!("'{}'", name),
+        Token::Str(value) => format!("\"{}\"", value),
+        Token::Num(value) => value.to_string(),
+        Token::And => "'&&'".to_string(),
+        Token::Or => "'||'".to_string(),
+        Token::Not => "'!'".to_string(),
+        Token::LParen => "'('".to_string(),
+        Token::RParen => "')'".to_string(),
+        Token::Cmp(op) => format!("'{}'", op),
+    }
+}
+
+// ============================================================================
+// Parser
+// ============================================================================
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    attributes: &'a [&'a str],
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Node, String> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            let right = self.parse_and()?;
+            left = Node::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Node, String> {
+        let mut left = self.parse_unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            let right = self.parse_unary()?;
+            left = Node::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Node, String> {
+        match self.peek() {
+            Some(Token::Not) => {
+                self.pos += 1;
+                Ok(Node::Not(Box::new(self.parse_unary()?)))
+            }
+            Some(Token::LParen) => {
+                self.pos += 1;
+                let inner = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(inner),
+                    _ => Err("Missing ')' in filter expression".to_string()),
+                }
+            }
+            _ => self.parse_comparison(),
+        }
+    }
+
+    fn parse_comparison(&mut self) -> Result<Node, String> {
+        let left = self.parse_operand()?;
+
+        let op = match self.peek() {
+            Some(Token::Cmp(op)) => match *op {
+                "==" => CmpOp::Eq,
+                "!=" => CmpOp::Ne,
+                "<" => CmpOp::Lt,
+                "<=" => CmpOp::Le,
+                ">" => CmpOp::Gt,
+                ">=" => CmpOp::Ge,
+                "~" => CmpOp::Match,
+                _ => CmpOp::NotMatch,
+            },
+            _ => return Ok(Node::Truthy(left)),
+        };
+        self.pos += 1;
+
+        let right = self.parse_operand()?;
+        Ok(Node::Compare(left, op, right))
+    }
+
+    fn parse_operand(&mut self) -> Result<Operand, String> {
+        match self.next() {
+            Some(Token::Ident(name)) if name == "true" => Ok(Operand::Literal(FilterValue::Bool(true))),
+            Some(Token::Ident(name)) if name == "false" => Ok(Operand::Literal(FilterValue::Bool(false))),
+            Some(Token::Ident(name)) => {
+                let known = self.attributes.iter().any(|attr| match attr.strip_suffix('*') {
+                    Some(prefix) => name.len() > prefix.len() && name.starts_with(prefix),
+                    None => *attr == name,
+                });
+                if known {
+                    Ok(Operand::Attr(name))
+                } else {
+                    Err(format!(
+                        "Unknown attribute '{}' in filter expression (available: {})",
+                        name,
+                        self.attributes.join(", ")
+                    ))
+                }
+            }
+            Some(Token::Str(value)) => Ok(Operand::Literal(FilterValue::Str(value))),
+            Some(Token::Num(value)) => Ok(Operand::Literal(FilterValue::Num(value))),
+            Some(token) => Err(format!("Unexpected {} in filter expression", describe(&token))),
+            None => Err("Unexpected end of filter expression".to_string()),
+        }
+    }
+}
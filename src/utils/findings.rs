@@ -0,0 +1,77 @@
+// ============================================================================
+// Findings - Analyses Reported via --format sarif
+// ============================================================================
+
+use std::{collections::HashSet, path::Path};
+
+use petgraph::{graph::NodeIndex, visit::Bfs};
+
+use crate::{types::{FnGraphData, FnNodeInfo, Finding, WarningCode}, utils::{graph::GraphNode, helper::{matches_any_pattern, read_patterns}, warnings::warn}};
+
+/// Rule id, short description and help text of every analysis
+pub const RULES: &[(&str, &str, &str)] = &[
+    (
+        UNREACHABLE_FUNCTION,
+        "Function is not reachable from any entry point",
+        "No call path leads here from main, a public function, a test, an exported symbol or a trait impl method. \
+         Calls are resolved heuristically, so check for dynamic dispatch or macro-generated calls before deleting it. \
+         Known-live functions can be listed in a --roots file or marked `#[cfg_attr(grapher, allow(grapher::unreachable))]`.",
+    ),
+];
+
+pub const UNREACHABLE_FUNCTION: &str = "unreachable-function";
+
+/// Extra roots, one function per line (name, qualified name or `*` pattern); `#` starts a comment
+pub fn load_roots(path: &Path) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    Ok(read_patterns(path)?)
+}
+
+/// Functions no entry point (main, pub fns, tests, exported symbols, trait impl methods),
+/// `roots` pattern or `allow(grapher::unreachable)` function can reach
+pub fn find_unreachable_functions(graph_data: &FnGraphData, roots: &[String]) -> Vec<Finding> {
+    let graph = &graph_data.graph;
+    let is_listed = |info: &FnNodeInfo, pattern: &String| {
+        info.matches_focus(pattern) || matches_any_pattern(&info.qualified_name, std::slice::from_ref(pattern))
+    };
+    for pattern in roots {
+        if !graph.node_weights().any(|info| is_listed(info, pattern)) {
+            warn(WarningCode::NoMatch, format!("root '{}' matches no function", pattern));
+        }
+    }
+
+    let roots: Vec<NodeIndex> = graph.node_indices()
+        .filter(|&idx| {
+            let info = &graph[idx];
+            info.is_entry_point || info.is_public || info.allow_unreachable
+                || roots.iter().any(|pattern| is_listed(info, pattern))
+        })
+        .collect();
+
+    let mut reachable: HashSet<NodeIndex> = HashSet::new();
+    for root in roots {
+        if reachable.contains(&root) {
+            continue;
+        }
+        let mut bfs = Bfs::new(graph, root);
+        while let Some(idx) = bfs.next(graph) {
+            reachable.insert(idx);
+        }
+    }
+
+    let mut findings: Vec<Finding> = graph.node_indices()
+        .filter(|idx| !reachable.contains(idx))
+        .map(|idx| {
+            let info = &graph[idx];
+            Finding {
+                rule_id: UNREACHABLE_FUNCTION,
+                message: format!("Function `{}` is never reached from an entry point", info.qualified_name),
+                file_path: info.file_path.clone(),
+                line: info.line,
+                column: info.column,
+            }
+        })
+        .collect();
+
+    findings.sort_by(|a, b| (&a.file_path, a.line).cmp(&(&b.file_path, b.line)));
+    findings
+}
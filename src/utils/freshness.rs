@@ -0,0 +1,178 @@
+// ============================================================================
+// Dependency Freshness (`freshness`)
+// ============================================================================
+//
+// Every crates.io package in the resolved graph is compared with the latest
+// stable release of its crate: how many versions behind it is (patch, minor
+// or major, by the first component that differs) and how many days ago the
+// used release was published. --fail-on and --max-age turn the report into a
+// CI check that exits with status 2.
+
+use std::{error::Error, path::PathBuf};
+
+use cargo_metadata::semver::Version;
+
+use crate::{types::{Behind, CheckFailed, DepsArgs, FreshnessArgs, ReportFormat, WarningCode}, utils::{crates_io::{crates_io_packages, days_since_epoch, today, CratesIo}, grapher::load_deps_graph, warnings::warn}};
+
+struct Freshness {
+    name: String,
+    version: String,
+    latest: String,
+    behind: Behind,
+    /// Publication date (YYYY-MM-DD) of the used and the latest release
+    released: Option<String>,
+    latest_released: Option<String>,
+    age_days: Option<i64>,
+}
+
+pub fn run_freshness(args: &FreshnessArgs) -> Result<(String, Option<PathBuf>), Box<dyn Error>> {
+    let deps_args = DepsArgs {
+        manifest_path: args.manifest_path.clone(),
+        no_dev: args.no_dev,
+        no_build: args.no_build,
+        direction: "LR".to_string(),
+        ..Default::default()
+    };
+    let (graph_data, _) = load_deps_graph(&deps_args)?;
+
+    let packages = crates_io_packages(&graph_data, args.direct_only);
+
+    let mut client = CratesIo::new(args.cache_dir.as_deref());
+    let today = today();
+    let mut report = Vec::new();
+    for (name, version) in packages {
+        match client.crate_info(name) {
+            Ok(info) => match freshness(name, version, &info, today) {
+                Some(entry) => report.push(entry),
+                None => warn(WarningCode::MissingData, format!("no stable release of {} on crates.io to compare {} with", name, version)),
+            },
+            Err(e) => warn(WarningCode::MissingData, format!("skipping {}: {}", name, e)),
+        }
+    }
+    // Furthest behind first, then oldest
+    report.sort_by(|a, b| b.behind.cmp(&a.behind).then(b.age_days.cmp(&a.age_days)).then(a.name.cmp(&b.name)));
+
+    let output = match args.format {
+        ReportFormat::Markdown => freshness_markdown(&report),
+        ReportFormat::Json => freshness_json(&report),
+    };
+
+    let mut failures = Vec::new();
+    if let Some(threshold) = args.fail_on {
+        let count = report.iter().filter(|entry| entry.behind >= threshold).count();
+        if count > 0 {
+            failures.push(format!("{} dependencies are at least a {} release behind", count, threshold.as_str()));
+        }
+    }
+    if let Some(max_age) = args.max_age {
+        let count = report.iter()
+            .filter(|entry| entry.behind != Behind::UpToDate && entry.age_days.is_some_and(|age| age > max_age as i64))
+            .count();
+        if count > 0 {
+            failures.push(format!("{} outdated dependencies were released more than {} days ago", count, max_age));
+        }
+    }
+    if !failures.is_empty() {
+        return Err(Box::new(CheckFailed { message: failures.join("; "), output, output_path: args.output.clone() }));
+    }
+
+    Ok((output, args.output.clone()))
+}
+
+/// Latest stable release in the crates.io response `info`
+pub fn latest_stable(info: &serde_json::Value) -> Option<&str> {
+    info["crate"]["max_stable_version"].as_str()
+}
+
+/// How far `version` is behind `latest`, by the first component that differs
+pub fn behind(version: &str, latest: &str) -> Option<Behind> {
+    let (used, newest) = (Version::parse(version).ok()?, Version::parse(latest).ok()?);
+    Some(if used >= newest {
+        Behind::UpToDate
+    } else if used.major != newest.major {
+        Behind::Major
+    } else if used.minor != newest.minor {
+        Behind::Minor
+    } else {
+        Behind::Patch
+    })
+}
+
+/// Compare `version` with the latest stable release in the crates.io response `info`
+fn freshness(name: &str, version: &str, info: &serde_json::Value, today: i64) -> Option<Freshness> {
+    let latest = latest_stable(info)?;
+    let behind = behind(version, latest)?;
+
+    let release_date = |num: &str| info["versions"].as_array()?
+        .iter()
+        .find(|v| v["num"].as_str() == Some(num))
+        .and_then(|v| v["created_at"].as_str())
+        .map(str::to_string);
+    let released = release_date(version);
+    let age_days = released.as_deref().and_then(days_since_epoch).map(|day| today - day);
+
+    Some(Freshness {
+        name: name.to_string(),
+        version: version.to_string(),
+        latest: latest.to_string(),
+        behind,
+        released: released.map(|date| date.chars().take(10).collect()),
+        latest_released: release_date(latest).map(|date| date.chars().take(10).collect()),
+        age_days,
+    })
+}
+
+// ============================================================================
+// Output
+// ============================================================================
+
+fn freshness_markdown(report: &[Freshness]) -> String {
+    let count = |behind: Behind| report.iter().filter(|entry| entry.behind == behind).count();
+    let mut output = String::from("# Dependency freshness\n\n");
+    output.push_str(&format!(
+        "{} crates.io dependencies: {} up to date, {} a patch behind, {} a minor behind, {} a major behind.\n\n",
+        report.len(), count(Behind::UpToDate), count(Behind::Patch), count(Behind::Minor), count(Behind::Major)
+    ));
+
+    output.push_str("| Crate | Used | Latest | Behind | Released | Age (days) |\n");
+    output.push_str("|-------|------|--------|--------|----------|------------|\n");
+    for entry in report {
+        output.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} |\n",
+            entry.name,
+            entry.version,
+            entry.latest,
+            entry.behind.as_str(),
+            entry.released.as_deref().unwrap_or("-"),
+            entry.age_days.map_or("-".to_string(), |age| age.to_string()),
+        ));
+    }
+    output
+}
+
+fn freshness_json(report: &[Freshness]) -> String {
+    let count = |behind: Behind| report.iter().filter(|entry| entry.behind == behind).count();
+    let crates: Vec<serde_json::Value> = report.iter()
+        .map(|entry| serde_json::json!({
+            "name": entry.name,
+            "version": entry.version,
+            "latest": entry.latest,
+            "behind": entry.behind.as_str(),
+            "released": entry.released,
+            "latest_released": entry.latest_released,
+            "age_days": entry.age_days
+        }))
+        .collect();
+
+    serde_json::to_string_pretty(&serde_json::json!({
+        "summary": {
+            "total": report.len(),
+            "up_to_date": count(Behind::UpToDate),
+            "patch": count(Behind::Patch),
+            "minor": count(Behind::Minor),
+            "major": count(Behind::Major)
+        },
+        "crates": crates
+    }))
+    .unwrap_or_else(|_| "{}".to_string())
+}
@@ -1,52 +1,70 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
-use crate::{types, utils::helper};
+use clap::ValueEnum;
+use petgraph::{graph::{DiGraph, NodeIndex}, Direction};
 
-use types::{DepsArgs, DepKind, FnGraphArgs, FnNodeInfo, GraphData, CallKind, FnGraphData, Theme};
-use helper::{format_node_label, sanitize_name};
+use crate::{types, utils::{categories::{category_color, CATEGORIES, OTHER}, churn::{AGE, CHURN}, findings::RULES, graph::{GraphNode, HOPS}, helper, pages::page_link, size::SIZE, warnings::warn}};
+
+use types::{AppliedFilter, Behind, Capability, ConfigRead, DepsArgs, DepKind, DepKinds, ExplainFeaturesArgs, FeatureEdge, FeatureGraph, FeatureNode, FnGraphArgs, FnNodeInfo, GraphData, CallKind, ColorBy, FnGraphData, Finding, MermaidVersion, NodeGroup, NodeInfo, OutputFormat, PageFormat, RankWorkspace, SourceKind, SourceLocations, Theme, WarningCode};
+use helper::format_metric;
+use helper::{depth_color, depth_font_color, file_uri, format_node_label, git_commit, is_copyleft, pin_label, sanitize_name, source_kind};
 
 // ============================================================================
 // Output Generators
 // ============================================================================
 
 pub fn generate_deps_mermaid(graph_data: &GraphData, args: &DepsArgs) -> String {
-    let mut output = String::new();
+    let mut output = mermaid_header(args.no_fence, &args.direction, &args.theme, args.mermaid_version);
+    let ids = deps_node_ids(graph_data, args, true);
 
-    if !args.no_fence {
-        output.push_str("```mermaid\n");
+    // build.rs pseudo-nodes are drawn as hexagons
+    let mut script_ids: BTreeSet<String> = BTreeSet::new();
+    for idx in graph_data.graph.node_indices().filter(|&idx| graph_data.graph[idx].is_build_script) {
+        let info = &graph_data.graph[idx];
+        let id = ids.get(idx).to_string();
+        if script_ids.insert(id.clone()) {
+            output.push_str(&format!("    {}{{{{\"{}\"}}}}\n", id, build_script_label(info)));
+        }
     }
 
-    output.push_str(&format!("flowchart {}\n", args.direction));
-
-    // Theme styling
-    match args.theme {
-        Theme::Dark => {
-            output.push_str("    %%{init: {'theme': 'dark'}}%%\n");
-        }
-        Theme::Light => {
-            output.push_str("    %%{init: {'theme': 'default'}}%%\n");
+    // Git commits, checksums and licenses go into the label, keeping the ID
+    for idx in graph_data.graph.node_indices().filter(|&idx| !graph_data.graph[idx].is_build_script) {
+        let info = &graph_data.graph[idx];
+        let id = ids.get(idx);
+        let pin = pin_label(info).map(|pin| format!(" {}", pin)).unwrap_or_default();
+        let lines: Vec<String> = updated_label(info).into_iter()
+            .chain(registry_label(info))
+            .chain(license_label(info, args))
+            .chain(outdated_label(info))
+            .map(|line| line.replace('"', "'"))
+            .collect();
+        if !lines.is_empty() {
+            output.push_str(&format!("    {}[\"{}{}<br/>{}\"]\n", id, id, pin, lines.join("<br/>")));
+        } else if !pin.is_empty() {
+            output.push_str(&format!("    {}[\"{}{}\"]\n", id, id, pin));
+        } else if graph_data.graph.neighbors_undirected(idx).next().is_none() {
+            // No edge declares it (e.g. a --page of leftovers)
+            output.push_str(&format!("    {}\n", id));
         }
-        Theme::Default => {}
     }
 
-    // Collect edges by kind for grouping
-    let mut normal_edges: Vec<(String, String)> = Vec::new();
-    let mut dev_edges: Vec<(String, String)> = Vec::new();
-    let mut build_edges: Vec<(String, String)> = Vec::new();
+    // Collect edges by primary kind for grouping
+    let mut normal_edges: Vec<(String, String, Option<String>)> = Vec::new();
+    let mut dev_edges: Vec<(String, String, Option<String>)> = Vec::new();
+    let mut build_edges: Vec<(String, String, Option<String>)> = Vec::new();
 
     for edge in graph_data.graph.edge_indices() {
         if let Some((from, to)) = graph_data.graph.edge_endpoints(edge) {
-            let from_info = &graph_data.graph[from];
-            let to_info = &graph_data.graph[to];
-            let edge_kind = graph_data.graph[edge];
+            let edge_kinds = graph_data.graph[edge];
+            let label = edge_label(edge_kinds, feature_gates_label(&graph_data.graph[from], &graph_data.graph[to]));
 
-            let from_label = format_node_label(from_info, args);
-            let to_label = format_node_label(to_info, args);
+            let from_label = ids.get(from).to_string();
+            let to_label = ids.get(to).to_string();
 
-            match edge_kind {
-                DepKind::Dev => dev_edges.push((from_label, to_label)),
-                DepKind::Build => build_edges.push((from_label, to_label)),
-                DepKind::Normal => normal_edges.push((from_label, to_label)),
+            match edge_kinds.primary() {
+                DepKind::Dev => dev_edges.push((from_label, to_label, label)),
+                DepKind::Build => build_edges.push((from_label, to_label, label)),
+                DepKind::Normal => normal_edges.push((from_label, to_label, label)),
             }
         }
     }
@@ -55,217 +73,347 @@ pub fn generate_deps_mermaid(graph_data: &GraphData, args: &DepsArgs) -> String
         // Grouped output
         if !normal_edges.is_empty() {
             output.push_str("    subgraph normal[\"Dependencies\"]\n");
-            for (from, to) in &normal_edges {
-                output.push_str(&format!("        {} --> {}\n", from, to));
+            for (from, to, label) in &normal_edges {
+                output.push_str(&format!("        {} {} {}\n", from, mermaid_arrow("-->", label.as_deref()), to));
             }
             output.push_str("    end\n");
         }
         if !dev_edges.is_empty() {
             output.push_str("    subgraph dev[\"Dev Dependencies\"]\n");
-            for (from, to) in &dev_edges {
-                output.push_str(&format!("        {} -.-> {}\n", from, to));
+            for (from, to, label) in &dev_edges {
+                output.push_str(&format!("        {} {} {}\n", from, mermaid_arrow("-.->", label.as_deref()), to));
             }
             output.push_str("    end\n");
         }
         if !build_edges.is_empty() {
             output.push_str("    subgraph build[\"Build Dependencies\"]\n");
-            for (from, to) in &build_edges {
-                output.push_str(&format!("        {} ==> {}\n", from, to));
+            for (from, to, label) in &build_edges {
+                output.push_str(&format!("        {} {} {}\n", from, mermaid_arrow("==>", label.as_deref()), to));
             }
             output.push_str("    end\n");
         }
     } else {
         // Flat output with different arrow styles
-        for (from, to) in &normal_edges {
-            output.push_str(&format!("    {} --> {}\n", from, to));
+        for (from, to, label) in &normal_edges {
+            output.push_str(&format!("    {} {} {}\n", from, mermaid_arrow("-->", label.as_deref()), to));
         }
-        for (from, to) in &dev_edges {
-            output.push_str(&format!("    {} -.-> {}\n", from, to));
+        for (from, to, label) in &dev_edges {
+            output.push_str(&format!("    {} {} {}\n", from, mermaid_arrow("-.->", label.as_deref()), to));
         }
-        for (from, to) in &build_edges {
-            output.push_str(&format!("    {} ==> {}\n", from, to));
+        for (from, to, label) in &build_edges {
+            output.push_str(&format!("    {} {} {}\n", from, mermaid_arrow("==>", label.as_deref()), to));
         }
     }
 
-    // Highlight styling
-    for highlight in &args.highlight {
-        let sanitized = sanitize_name(highlight);
-        output.push_str(&format!("    style {} fill:#f9f,stroke:#333,stroke-width:4px\n", sanitized));
+    // User-defined clusters
+    let graph = &graph_data.graph;
+    let grouped = graph.node_indices()
+        .filter_map(|idx| graph[idx].group.as_ref().map(|group| (group, ids.get(idx).to_string())));
+    output.push_str(&mermaid_group_clusters(grouped, args.mermaid_version));
+    output.push_str(&mermaid_page_links(graph, &ids, args.detail_pages));
+
+    // Depth / metric coloring
+    if let Some(scale) = ColorScale::new(args.color_by, args.color_metric.as_deref(), graph.node_weights().map(|info| &info.metrics)) {
+        let nodes = graph.node_indices()
+            .filter_map(|idx| scale.value(graph[idx].depth, &graph[idx].metrics).map(|value| (value, ids.get(idx).to_string())));
+        output.push_str(&mermaid_color_styles(&scale, nodes));
+    }
+    if args.color_by == Some(ColorBy::Category) {
+        let nodes = graph.node_indices()
+            .filter_map(|idx| graph[idx].category.as_deref().map(|category| (category, ids.get(idx).to_string())));
+        output.push_str(&mermaid_category_styles(nodes));
     }
 
-    if !args.no_fence {
-        output.push_str("```\n");
+    // Crates in several versions
+    let duplicates: Vec<&str> = graph.node_indices()
+        .filter(|&idx| graph[idx].duplicate_versions.is_some())
+        .map(|idx| ids.get(idx))
+        .collect();
+    output.push_str(&mermaid_duplicates(&duplicates, &duplicate_summary(graph_data)));
+
+    // Copyleft licenses
+    if args.highlight_copyleft {
+        let copyleft: Vec<&str> = graph.node_indices()
+            .filter(|&idx| graph[idx].license.as_deref().is_some_and(is_copyleft))
+            .map(|idx| ids.get(idx))
+            .collect();
+        if !copyleft.is_empty() {
+            output.push_str(&format!("    classDef copyleft fill:{},stroke:{},stroke-width:2px\n", COPYLEFT_FILL, COPYLEFT_STROKE));
+            output.push_str(&format!("    class {} copyleft\n", copyleft.join(",")));
+        }
     }
 
-    output
-}
+    // Crates pulled in only by optional dependencies
+    let optional: Vec<&str> = graph.node_indices()
+        .filter(|&idx| graph[idx].is_optional)
+        .map(|idx| ids.get(idx))
+        .collect();
+    if !optional.is_empty() {
+        output.push_str("    classDef optional stroke-dasharray:5 5\n");
+        output.push_str(&format!("    class {} optional\n", optional.join(",")));
+    }
 
-pub fn generate_deps_dot(graph_data: &GraphData, args: &DepsArgs) -> String {
-    let mut output = String::new();
+    // Crates from git repositories and local paths
+    for (kind, stroke) in [(SourceKind::Git, GIT_STROKE), (SourceKind::Path, PATH_STROKE)] {
+        let nodes: Vec<&str> = graph.node_indices()
+            .filter(|&idx| !graph[idx].is_build_script && source_kind(&graph[idx]) == kind)
+            .map(|idx| ids.get(idx))
+            .collect();
+        if !nodes.is_empty() {
+            output.push_str(&format!("    classDef source_{} stroke:{},stroke-width:2px\n", kind.name(), stroke));
+            output.push_str(&format!("    class {} source_{}\n", nodes.join(","), kind.name()));
+        }
+    }
 
-    output.push_str("digraph dependencies {\n");
-    output.push_str("    rankdir=LR;\n");
-    output.push_str("    node [shape=box, style=rounded];\n");
+    // Crates behind their latest release
+    let outdated: Vec<&str> = graph.node_indices()
+        .filter(|&idx| is_outdated(&graph[idx]))
+        .map(|idx| ids.get(idx))
+        .collect();
+    if !outdated.is_empty() {
+        output.push_str(&format!("    classDef outdated fill:{},stroke:{},stroke-dasharray:4 2\n", OUTDATED_FILL, OUTDATED_STROKE));
+        output.push_str(&format!("    class {} outdated\n", outdated.join(",")));
+    }
 
-    // Theme
-    match args.theme {
-        Theme::Dark => {
-            output.push_str("    bgcolor=\"#1e1e1e\";\n");
-            output.push_str("    node [fontcolor=white, color=white];\n");
-            output.push_str("    edge [color=white];\n");
-        }
-        Theme::Light => {
-            output.push_str("    bgcolor=white;\n");
-        }
-        Theme::Default => {}
+    // Crates with RustSec advisories
+    let vulnerable: Vec<&str> = graph.node_indices()
+        .filter(|&idx| is_vulnerable(&graph[idx]))
+        .map(|idx| ids.get(idx))
+        .collect();
+    if !vulnerable.is_empty() {
+        output.push_str(&format!("    classDef vulnerable fill:{},stroke:{},stroke-width:3px\n", VULNERABLE_FILL, VULNERABLE_STROKE));
+        output.push_str(&format!("    class {} vulnerable\n", vulnerable.join(",")));
     }
 
-    // Node definitions
-    let mut defined_nodes: HashSet<String> = HashSet::new();
-    for idx in graph_data.graph.node_indices() {
-        let info = &graph_data.graph[idx];
-        let label = format_node_label(info, args);
-        let sanitized = sanitize_name(&info.name);
+    if args.fade_by_hops {
+        output.push_str(&mermaid_hop_fade(graph.node_indices().map(|idx| (&graph[idx].metrics, ids.get(idx)))));
+    }
 
-        if defined_nodes.insert(sanitized.clone()) {
-            let mut node_attrs = vec![format!("label=\"{}\"", label.replace('_', "-"))];
+    output.push_str(&mermaid_filter_legend(&graph_data.filters));
+    let highlighted = highlighted_ids(graph, &ids, &args.highlight, |info| &info.name);
+    output.push_str(&mermaid_footer(args.no_fence, &highlighted));
+    output
+}
 
-            if args.highlight.contains(&info.name) {
-                node_attrs.push("fillcolor=\"#ff99ff\"".to_string());
-                node_attrs.push("style=\"filled,rounded\"".to_string());
-            }
+// ============================================================================
+// Node IDs
+// ============================================================================
 
-            if info.is_workspace_member {
-                node_attrs.push("penwidth=2".to_string());
-            }
+/// Diagram ID of every node. Nodes that would share an ID (`foo-bar` and `foo_bar`,
+/// two versions of a crate, `new` in several impls) get a qualified one instead,
+/// with a `_2`, `_3`, ... suffix if even that is taken, so edges never merge.
+pub struct NodeIds {
+    ids: Vec<String>,
+    /// Shared ID -> (node, ID it got instead) for each colliding node
+    pub collisions: BTreeMap<String, Vec<(String, String)>>,
+}
 
-            output.push_str(&format!("    {} [{}];\n", sanitized, node_attrs.join(", ")));
-        }
+impl NodeIds {
+    pub fn get(&self, idx: NodeIndex) -> &str {
+        &self.ids[idx.index()]
     }
 
-    // Edges
-    for edge in graph_data.graph.edge_indices() {
-        if let Some((from, to)) = graph_data.graph.edge_endpoints(edge) {
-            let from_name = sanitize_name(&graph_data.graph[from].name);
-            let to_name = sanitize_name(&graph_data.graph[to].name);
-            let kind = graph_data.graph[edge];
-
-            let style = match kind {
-                DepKind::Dev => " [style=dashed, color=blue]",
-                DepKind::Build => " [style=bold, color=green]",
-                DepKind::Normal => "",
-            };
+    /// The ID as a quoted DOT identifier
+    pub fn quoted(&self, idx: NodeIndex) -> String {
+        format!("\"{}\"", self.get(idx).replace('"', "\\\""))
+    }
+}
 
-            output.push_str(&format!("    {} -> {}{};\n", from_name, to_name, style));
-        }
+/// IDs of crates as drawn in Mermaid (`mermaid`, also showing versions with -v), or the
+/// `GraphNode::id` used by DOT and JSON. Mermaid cannot hold the `:`, `/` and `@` of those.
+pub fn deps_node_ids(graph_data: &GraphData, args: &DepsArgs, mermaid: bool) -> NodeIds {
+    if !mermaid {
+        return stable_node_ids(&graph_data.graph);
     }
+    node_ids(
+        &graph_data.graph,
+        |info| format_node_label(info, args),
+        |info| format!("{}_{}", sanitize_name(&info.name), info.version.replace(['.', '-', '+'], "_")),
+    )
+}
 
-    output.push_str("}\n");
-    output
+/// `deps_node_ids` for functions, qualified as `Type__method` in Mermaid
+pub fn fn_node_ids(graph_data: &FnGraphData, args: &FnGraphArgs, mermaid: bool) -> NodeIds {
+    if !mermaid {
+        return stable_node_ids(&graph_data.graph);
+    }
+    node_ids(
+        &graph_data.graph,
+        |info| format_fn_label(info, args),
+        |info| sanitize_name(&info.qualified_name.replace("::", "__")),
+    )
 }
 
-pub fn generate_deps_json(graph_data: &GraphData, args: &DepsArgs) -> String {
-    let mut nodes: Vec<serde_json::Value> = Vec::new();
-    let mut edges: Vec<serde_json::Value> = Vec::new();
+/// `GraphNode::id` of every node; only a function defined twice in one file (under
+/// different `#[cfg]`s) shares one, and gets a suffix
+fn stable_node_ids<N: GraphNode, E>(graph: &DiGraph<N, E>) -> NodeIds {
+    node_ids(graph, GraphNode::id, GraphNode::id)
+}
 
-    for idx in graph_data.graph.node_indices() {
-        let info = &graph_data.graph[idx];
-        nodes.push(serde_json::json!({
-            "id": sanitize_name(&info.name),
-            "name": info.name,
-            "version": info.version,
-            "is_workspace_member": info.is_workspace_member,
-            "highlighted": args.highlight.contains(&info.name)
-        }));
+fn node_ids<N: GraphNode, E>(graph: &DiGraph<N, E>, plain: impl Fn(&N) -> String, qualified: impl Fn(&N) -> String) -> NodeIds {
+    let mut ids: Vec<String> = graph.node_weights().map(&plain).collect();
+    let mut sharing: BTreeMap<String, Vec<NodeIndex>> = BTreeMap::new();
+    for idx in graph.node_indices() {
+        sharing.entry(ids[idx.index()].clone()).or_default().push(idx);
+    }
+    sharing.retain(|_, nodes| nodes.len() > 1);
+
+    let mut taken: HashSet<String> = ids.iter().filter(|id| !sharing.contains_key(*id)).cloned().collect();
+    let mut collisions = BTreeMap::new();
+    for (shared, mut nodes) in sharing {
+        nodes.sort_by_cached_key(|&idx| (qualified(&graph[idx]), graph[idx].id(), idx));
+        let renamed = nodes.into_iter()
+            .map(|idx| {
+                let base = qualified(&graph[idx]);
+                let id = std::iter::once(base.clone())
+                    .chain((2..).map(|n| format!("{}_{}", base, n)))
+                    .find(|id| !taken.contains(id))
+                    .unwrap_or(base);
+                taken.insert(id.clone());
+                ids[idx.index()] = id.clone();
+                (graph[idx].id(), id)
+            })
+            .collect();
+        collisions.insert(shared, renamed);
     }
 
-    for edge in graph_data.graph.edge_indices() {
-        if let Some((from, to)) = graph_data.graph.edge_endpoints(edge) {
-            let kind = graph_data.graph[edge];
-            edges.push(serde_json::json!({
-                "from": sanitize_name(&graph_data.graph[from].name),
-                "to": sanitize_name(&graph_data.graph[to].name),
-                "kind": match kind {
-                    DepKind::Normal => "normal",
-                    DepKind::Dev => "dev",
-                    DepKind::Build => "build",
-                }
-            }));
-        }
+    NodeIds { ids, collisions }
+}
+
+/// Print `--warn-collisions` lines for IDs more than one node wanted
+pub fn warn_collisions(ids: &NodeIds) {
+    for (shared, renamed) in &ids.collisions {
+        let renamed: Vec<String> = renamed.iter().map(|(node, id)| format!("{} as {}", node, id)).collect();
+        warn(WarningCode::IdCollision, format!("{} nodes share the ID '{}'; drawn {}", renamed.len(), shared, renamed.join(", ")));
     }
+}
 
-    serde_json::to_string_pretty(&serde_json::json!({
-        "nodes": nodes,
-        "edges": edges
-    }))
-    .unwrap_or_else(|_| "{}".to_string())
+/// Nodes the `--highlight` names select
+fn highlighted_ids<'a, N, E>(graph: &DiGraph<N, E>, ids: &'a NodeIds, highlight: &[String], name: impl Fn(&N) -> &str) -> Vec<&'a str> {
+    graph.node_indices()
+        .filter(|&idx| highlight.iter().any(|h| h == name(&graph[idx])))
+        .map(|idx| ids.get(idx))
+        .collect()
 }
 
 // ============================================================================
-// Function Graph - Output Generators
+// Shared Pieces
 // ============================================================================
 
-pub fn generate_fn_mermaid(graph_data: &FnGraphData, args: &FnGraphArgs) -> String {
+/// Code fence, flowchart direction and theme init shared by every Mermaid graph. For
+/// `--mermaid-version 11` the theme is frontmatter config, for 8 and 10 an init directive
+/// ahead of the flowchart (older releases ignore one after it)
+fn mermaid_header(no_fence: bool, direction: &str, theme: &Theme, version: Option<MermaidVersion>) -> String {
     let mut output = String::new();
 
-    if !args.no_fence {
+    if !no_fence {
         output.push_str("```mermaid\n");
     }
 
-    output.push_str(&format!("flowchart {}\n", args.direction));
+    let theme_name = match theme {
+        Theme::Dark => Some("dark"),
+        Theme::Light => Some("default"),
+        Theme::Default => None,
+    };
+    match (theme_name, version) {
+        (Some(name), Some(MermaidVersion::V11)) => output.push_str(&format!("---\nconfig:\n  theme: {}\n---\n", name)),
+        (Some(name), Some(MermaidVersion::V8 | MermaidVersion::V10)) => output.push_str(&format!("%%{{init: {{'theme': '{}'}}}}%%\n", name)),
+        _ => {}
+    }
+
+    output.push_str(&format!("flowchart {}\n", direction));
 
     // Theme styling
-    match args.theme {
-        Theme::Dark => {
-            output.push_str("    %%{init: {'theme': 'dark'}}%%\n");
-        }
-        Theme::Light => {
-            output.push_str("    %%{init: {'theme': 'default'}}%%\n");
-        }
-        Theme::Default => {}
+    if let (Some(name), None) = (theme_name, version) {
+        output.push_str(&format!("    %%{{init: {{'theme': '{}'}}}}%%\n", name));
     }
 
-    // Edges
-    for edge in graph_data.graph.edge_indices() {
-        if let Some((from, to)) = graph_data.graph.edge_endpoints(edge) {
-            let from_info = &graph_data.graph[from];
-            let to_info = &graph_data.graph[to];
-            let edge_kind = graph_data.graph[edge];
-
-            let from_label = format_fn_label(from_info, args);
-            let to_label = format_fn_label(to_info, args);
+    output
+}
 
-            let arrow = match edge_kind {
-                CallKind::Direct => "-->",
-                CallKind::Method => "-.->",
-            };
+/// Highlight styles and the closing fence
+fn mermaid_footer(no_fence: bool, highlighted: &[&str]) -> String {
+    let mut output = String::new();
 
-            output.push_str(&format!("    {} {} {}\n", from_label, arrow, to_label));
-        }
+    for id in highlighted {
+        output.push_str(&format!("    style {} fill:#f9f,stroke:#333,stroke-width:4px\n", id));
     }
 
-    // Highlight styling
-    for highlight in &args.highlight {
-        let sanitized = sanitize_name(highlight);
-        output.push_str(&format!("    style {} fill:#f9f,stroke:#333,stroke-width:4px\n", sanitized));
+    if !no_fence {
+        output.push_str("```\n");
     }
 
-    if !args.no_fence {
-        output.push_str("```\n");
+    output
+}
+
+/// One line per applied filter, e.g. `--focus serde: 12 nodes, 15 edges removed`
+fn filter_lines(filters: &[AppliedFilter]) -> Vec<String> {
+    filters.iter()
+        .map(|filter| match filter.removed {
+            Some((nodes, edges)) => format!("{}: {} nodes, {} edges removed", filter.description, nodes, edges),
+            None => filter.description.clone(),
+        })
+        .collect()
+}
+
+/// Legend listing the filters applied, so the diagram doesn't pass for the whole graph
+fn mermaid_filter_legend(filters: &[AppliedFilter]) -> String {
+    let mut output = String::new();
+    if filters.is_empty() {
+        return output;
     }
 
+    output.push_str("    subgraph legend_filters[\"Filtered subset\"]\n");
+    for (i, line) in filter_lines(filters).iter().enumerate() {
+        output.push_str(&format!("        legend_filter_{}[\"{}\"]\n", i, line.replace('"', "'")));
+    }
+    output.push_str("    end\n");
+    output.push_str("    style legend_filters stroke-dasharray:5 5\n");
     output
 }
 
-pub fn generate_fn_dot(graph_data: &FnGraphData, args: &FnGraphArgs) -> String {
+/// The applied filters as the graph label, left-aligned below the drawing
+fn dot_filter_legend(filters: &[AppliedFilter]) -> String {
+    if filters.is_empty() {
+        return String::new();
+    }
+    let lines: String = filter_lines(filters).iter()
+        .map(|line| format!("{}\\l", line.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect();
+    format!("    label=\"Filtered subset:\\l{}\";\n    labelloc=b;\n    labeljust=l;\n", lines)
+}
+
+/// `filters` object of the JSON outputs: each filter with what it removed, and the known totals
+fn json_filters(filters: &[AppliedFilter]) -> Option<serde_json::Value> {
+    if filters.is_empty() {
+        return None;
+    }
+    let applied: Vec<serde_json::Value> = filters.iter()
+        .map(|filter| serde_json::json!({
+            "filter": filter.description,
+            "removed_nodes": filter.removed.map(|(nodes, _)| nodes),
+            "removed_edges": filter.removed.map(|(_, edges)| edges)
+        }))
+        .collect();
+    let known = filters.iter().filter_map(|filter| filter.removed);
+    let (nodes, edges) = known.fold((0, 0), |(n, e), (nodes, edges)| (n + nodes, e + edges));
+    Some(serde_json::json!({
+        "applied": applied,
+        "removed_nodes": nodes,
+        "removed_edges": edges
+    }))
+}
+
+/// Opening of a DOT digraph with the default node style and theme colors
+fn dot_header(name: &str, theme: &Theme) -> String {
     let mut output = String::new();
 
-    output.push_str("digraph call_graph {\n");
+    output.push_str(&format!("digraph {} {{\n", name));
     output.push_str("    rankdir=LR;\n");
     output.push_str("    node [shape=box, style=rounded];\n");
 
     // Theme
-    match args.theme {
+    match theme {
         Theme::Dark => {
             output.push_str("    bgcolor=\"#1e1e1e\";\n");
             output.push_str("    node [fontcolor=white, color=white];\n");
@@ -277,109 +425,2002 @@ pub fn generate_fn_dot(graph_data: &FnGraphData, args: &FnGraphArgs) -> String {
         Theme::Default => {}
     }
 
-    // Node definitions
-    let mut defined_nodes: HashSet<String> = HashSet::new();
-    for idx in graph_data.graph.node_indices() {
-        let info = &graph_data.graph[idx];
-        let sanitized = sanitize_name(&info.name);
+    output
+}
 
-        if defined_nodes.insert(sanitized.clone()) {
-            let label = if args.show_signatures {
-                info.signature.as_ref().unwrap_or(&info.name).clone()
-            } else {
-                info.name.clone()
-            };
+/// Display text for a build.rs pseudo-node ("foo build.rs")
+fn build_script_label(info: &NodeInfo) -> String {
+    let package = info.name.strip_suffix("-build.rs").unwrap_or(&info.name);
+    format!("{} build.rs", package)
+}
 
-            let mut node_attrs = vec![format!("label=\"{}\"", label.replace('"', "\\\""))];
+/// How `--color-by` maps nodes onto the depth palette
+enum ColorScale<'a> {
+    Depth,
+    /// Metric values spread over the palette between the graph's minimum and maximum
+    Metric { name: &'a str, min: f64, max: f64 },
+}
 
-            if args.highlight.contains(&info.name) {
-                node_attrs.push("fillcolor=\"#ff99ff\"".to_string());
-                node_attrs.push("style=\"filled,rounded\"".to_string());
+impl<'a> ColorScale<'a> {
+    /// Highest palette level a metric is mapped to
+    const METRIC_LEVELS: f64 = 7.0;
+
+    /// `None` without `--color-by`, or when no node has the metric being colored by
+    fn new<'n>(
+        color_by: Option<ColorBy>,
+        metric: Option<&'a str>,
+        metrics: impl Iterator<Item = &'n BTreeMap<String, f64>>,
+    ) -> Option<Self> {
+        match color_by? {
+            ColorBy::Depth => Some(ColorScale::Depth),
+            // Categorical: deps_category_* color by name, not along the palette
+            ColorBy::Category => None,
+            color_by @ (ColorBy::Metric | ColorBy::Size | ColorBy::Churn | ColorBy::Age) => {
+                let name = match color_by {
+                    ColorBy::Size => SIZE,
+                    ColorBy::Churn => CHURN,
+                    ColorBy::Age => AGE,
+                    _ => metric?,
+                };
+                let (min, max) = metrics
+                    .filter_map(|values| values.get(name).copied())
+                    .fold(None, |range: Option<(f64, f64)>, value| match range {
+                        Some((min, max)) => Some((min.min(value), max.max(value))),
+                        None => Some((value, value)),
+                    })?;
+                Some(ColorScale::Metric { name, min, max })
             }
+        }
+    }
 
-            if info.is_public {
-                node_attrs.push("penwidth=2".to_string());
-            }
+    /// Value the node is colored by, `None` if it stays uncolored
+    fn value(&self, depth: Option<usize>, metrics: &BTreeMap<String, f64>) -> Option<f64> {
+        match self {
+            ColorScale::Depth => depth.map(|depth| depth as f64),
+            ColorScale::Metric { name, .. } => metrics.get(*name).copied(),
+        }
+    }
 
-            if info.is_async {
-                node_attrs.push("color=blue".to_string());
+    fn level(&self, value: f64) -> usize {
+        match *self {
+            ColorScale::Depth => value as usize,
+            ColorScale::Metric { min, max, .. } if max > min => {
+                ((value - min) / (max - min) * Self::METRIC_LEVELS).round() as usize
             }
+            ColorScale::Metric { .. } => 0,
+        }
+    }
 
-            output.push_str(&format!("    {} [{}];\n", sanitized, node_attrs.join(", ")));
+    fn level_of(&self, depth: Option<usize>, metrics: &BTreeMap<String, f64>) -> Option<usize> {
+        self.value(depth, metrics).map(|value| self.level(value))
+    }
+
+    fn title(&self) -> &str {
+        match self {
+            ColorScale::Depth => "Depth",
+            ColorScale::Metric { name, .. } => name,
         }
     }
 
-    // Edges
-    for edge in graph_data.graph.edge_indices() {
-        if let Some((from, to)) = graph_data.graph.edge_endpoints(edge) {
-            let from_name = sanitize_name(&graph_data.graph[from].name);
-            let to_name = sanitize_name(&graph_data.graph[to].name);
-            let kind = graph_data.graph[edge];
+    /// Prefix of the class and legend ids
+    fn prefix(&self) -> &'static str {
+        match self {
+            ColorScale::Depth => "depth",
+            ColorScale::Metric { .. } => "heat",
+        }
+    }
 
-            let style = match kind {
-                CallKind::Direct => "",
-                CallKind::Method => " [style=dashed]",
-            };
+    /// Legend text for a level holding values from `low` to `high`
+    fn label(&self, low: f64, high: f64) -> String {
+        if low == high {
+            format_metric(low)
+        } else {
+            format!("{} - {}", format_metric(low), format_metric(high))
+        }
+    }
 
-            output.push_str(&format!("    {} -> {}{};\n", from_name, to_name, style));
+    /// Nodes per level, with the range of values each level holds
+    fn levels<T: Ord>(&self, nodes: impl Iterator<Item = (f64, T)>) -> BTreeMap<usize, (f64, f64, BTreeSet<T>)> {
+        let mut levels: BTreeMap<usize, (f64, f64, BTreeSet<T>)> = BTreeMap::new();
+        for (value, id) in nodes {
+            let (low, high, ids) = levels.entry(self.level(value)).or_insert((value, value, BTreeSet::new()));
+            *low = low.min(value);
+            *high = high.max(value);
+            ids.insert(id);
         }
+        levels
+    }
+}
+
+/// classDef per color level plus a legend subgraph
+fn mermaid_color_styles(scale: &ColorScale, nodes: impl Iterator<Item = (f64, String)>) -> String {
+    let levels = scale.levels(nodes);
+
+    let mut output = String::new();
+    if levels.is_empty() {
+        return output;
+    }
+
+    let prefix = scale.prefix();
+    output.push_str(&format!("    subgraph legend_{}[\"{}\"]\n", prefix, scale.title().replace('"', "'")));
+    for (level, (low, high, _)) in &levels {
+        output.push_str(&format!("        legend_{}_{}[\"{}\"]\n", prefix, level, scale.label(*low, *high)));
+    }
+    output.push_str("    end\n");
+
+    for (level, (_, _, ids)) in &levels {
+        output.push_str(&format!(
+            "    classDef {}{} fill:{},color:{},stroke:#333\n",
+            prefix, level, depth_color(*level), depth_font_color(*level)
+        ));
+        let members: Vec<&str> = ids.iter().map(String::as_str).collect();
+        output.push_str(&format!("    class {},legend_{}_{} {}{}\n", members.join(","), prefix, level, prefix, level));
     }
 
-    output.push_str("}\n");
     output
 }
 
-pub fn generate_fn_json(graph_data: &FnGraphData, args: &FnGraphArgs) -> String {
-    let mut nodes: Vec<serde_json::Value> = Vec::new();
-    let mut edges: Vec<serde_json::Value> = Vec::new();
+/// Legend and fill per crate category, in the order of the category table (`other` last)
+fn mermaid_category_styles<'a>(nodes: impl Iterator<Item = (&'a str, String)>) -> String {
+    let mut by_category: BTreeMap<usize, (&str, Vec<String>)> = BTreeMap::new();
+    for (category, id) in nodes {
+        let rank = category_rank(category);
+        by_category.entry(rank).or_insert_with(|| (category_name(rank), Vec::new())).1.push(id);
+    }
 
-    for idx in graph_data.graph.node_indices() {
-        let info = &graph_data.graph[idx];
-        let mut node = serde_json::json!({
-            "id": sanitize_name(&info.name),
-            "name": info.name,
-            "qualified_name": info.qualified_name,
-            "file": info.file_path,
-            "line": info.line,
-            "is_public": info.is_public,
-            "is_async": info.is_async,
-            "highlighted": args.highlight.contains(&info.name)
-        });
+    let mut output = String::new();
+    if by_category.is_empty() {
+        return output;
+    }
 
-        if let Some(ref sig) = info.signature {
-            node["signature"] = serde_json::json!(sig);
+    output.push_str("    subgraph legend_category[\"Category\"]\n");
+    for (rank, (category, _)) in &by_category {
+        output.push_str(&format!("        legend_category_{}[\"{}\"]\n", rank, category));
+    }
+    output.push_str("    end\n");
+
+    for (rank, (category, ids)) in &by_category {
+        output.push_str(&format!("    classDef category{} fill:{},stroke:#333\n", rank, category_color(category)));
+        output.push_str(&format!("    class {},legend_category_{} category{}\n", ids.join(","), rank, rank));
+    }
+    output
+}
+
+fn dot_category_legend<'a>(categories: impl Iterator<Item = &'a str>) -> String {
+    let categories: BTreeMap<usize, &str> = categories.map(category_rank).map(|rank| (rank, category_name(rank))).collect();
+    let mut output = String::new();
+    if categories.is_empty() {
+        return output;
+    }
+
+    output.push_str("    subgraph cluster_legend_category {\n");
+    output.push_str("        label=\"Category\";\n");
+    for (rank, category) in categories {
+        output.push_str(&format!(
+            "        legend_category_{} [label=\"{}\", fillcolor=\"{}\", style=\"filled,rounded\"];\n",
+            rank, category, category_color(category)
+        ));
+    }
+    output.push_str("    }\n");
+    output
+}
+
+/// Position of a category in the legend: the table order, then `other` (also for unknown names)
+fn category_rank(category: &str) -> usize {
+    CATEGORIES.iter().position(|c| c.name == category).unwrap_or(CATEGORIES.len())
+}
+
+fn category_name(rank: usize) -> &'static str {
+    CATEGORIES.get(rank).map_or(OTHER, |c| c.name)
+}
+
+/// Mermaid subgraph per `--groups` cluster, filled with the group's color. Mermaid 8 has no
+/// `direction` inside subgraphs, so group directions are left out for it
+fn mermaid_group_clusters<'a>(nodes: impl Iterator<Item = (&'a NodeGroup, String)>, version: Option<MermaidVersion>) -> String {
+    let mut groups: BTreeMap<&str, (&NodeGroup, BTreeSet<String>)> = BTreeMap::new();
+    for (group, id) in nodes {
+        groups.entry(&group.name).or_insert_with(|| (group, BTreeSet::new())).1.insert(id);
+    }
+
+    let mut output = String::new();
+    for (name, (group, ids)) in &groups {
+        let cluster_id = group_cluster_id(name);
+        output.push_str(&format!("    subgraph {}[\"{}\"]\n", cluster_id, name.replace('"', "'")));
+        if let Some(direction) = group.direction.as_ref().filter(|_| version != Some(MermaidVersion::V8)) {
+            output.push_str(&format!("        direction {}\n", direction));
         }
+        for id in ids {
+            output.push_str(&format!("        {}\n", id));
+        }
+        output.push_str("    end\n");
+        output.push_str(&format!("    style {} fill:{},stroke:#333\n", cluster_id, group.color));
+    }
+    output
+}
 
-        nodes.push(node);
+/// `click` lines linking nodes to their --detail-pages
+fn mermaid_page_links<N: GraphNode, E>(graph: &DiGraph<N, E>, ids: &NodeIds, format: Option<PageFormat>) -> String {
+    let mut linked: HashSet<&str> = HashSet::new();
+    let mut output = String::new();
+    for idx in graph.node_indices() {
+        if let Some(link) = page_link(&graph[idx].id(), format) {
+            if linked.insert(ids.get(idx)) {
+                output.push_str(&format!("    click {} \"{}\"\n", ids.get(idx), link));
+            }
+        }
     }
+    output
+}
 
-    for edge in graph_data.graph.edge_indices() {
-        if let Some((from, to)) = graph_data.graph.edge_endpoints(edge) {
-            let kind = graph_data.graph[edge];
-            edges.push(serde_json::json!({
-                "from": sanitize_name(&graph_data.graph[from].name),
-                "to": sanitize_name(&graph_data.graph[to].name),
-                "kind": match kind {
-                    CallKind::Direct => "direct",
-                    CallKind::Method => "method",
-                }
-            }));
+/// DOT cluster per `--groups` entry
+fn dot_group_clusters<'a>(nodes: impl Iterator<Item = (&'a NodeGroup, String)>) -> String {
+    let mut groups: BTreeMap<&str, (&str, BTreeSet<String>)> = BTreeMap::new();
+    for (group, id) in nodes {
+        groups.entry(&group.name).or_insert_with(|| (&group.color, BTreeSet::new())).1.insert(id);
+    }
+
+    let mut output = String::new();
+    for (name, (color, ids)) in &groups {
+        output.push_str(&format!("    subgraph cluster_{} {{\n", group_cluster_id(name)));
+        output.push_str(&format!("        label=\"{}\";\n", name.replace('"', "\\\"")));
+        output.push_str("        style=filled;\n");
+        output.push_str(&format!("        fillcolor=\"{}\";\n", color));
+        for id in ids {
+            output.push_str(&format!("        {};\n", id));
         }
+        output.push_str("    }\n");
     }
+    output
+}
 
-    serde_json::to_string_pretty(&serde_json::json!({
-        "nodes": nodes,
-        "edges": edges
-    }))
-    .unwrap_or_else(|_| "{}".to_string())
+/// --group-by-kind clusters, as in Mermaid. A DOT node sits in one cluster, so each crate goes
+/// to the strongest kind it is depended on with (normal, then build, then dev); crates nothing
+/// depends on, like the workspace roots, stay outside, and so do crates already drawn in a
+/// group cluster (a `group` read by `render`).
+fn dot_kind_clusters(graph_data: &GraphData, ids: &NodeIds) -> String {
+    let mut clusters: [(&str, &str, Vec<String>); 3] = [
+        ("normal", "Dependencies", Vec::new()),
+        ("build", "Build Dependencies", Vec::new()),
+        ("dev", "Dev Dependencies", Vec::new()),
+    ];
+    for idx in graph_data.graph.node_indices().filter(|&idx| graph_data.graph[idx].group.is_none()) {
+        let kinds: Vec<DepKind> = graph_data.graph.edges_directed(idx, Direction::Incoming)
+            .map(|edge| edge.weight().primary())
+            .collect();
+        let cluster = [DepKind::Normal, DepKind::Build, DepKind::Dev].iter().position(|kind| kinds.contains(kind));
+        if let Some(cluster) = cluster {
+            clusters[cluster].2.push(ids.quoted(idx));
+        }
+    }
+
+    let mut output = String::new();
+    for (name, label, members) in clusters.iter().filter(|(_, _, members)| !members.is_empty()) {
+        output.push_str(&format!("    subgraph cluster_{} {{\n", name));
+        output.push_str(&format!("        label=\"{}\";\n", label));
+        output.push_str("        style=dashed;\n");
+        for id in members {
+            output.push_str(&format!("        {};\n", id));
+        }
+        output.push_str("    }\n");
+    }
+    output
 }
 
-pub fn format_fn_label(info: &FnNodeInfo, args: &FnGraphArgs) -> String {
-    let sanitized = sanitize_name(&info.name);
-    if args.show_signatures {
-        if let Some(ref sig) = info.signature {
-            return sanitize_name(&sig.replace(['(', ')', ',', ' ', '-', '>'], "_"));
+/// Opacity of a node with --fade-by-hops: full for the focus and its neighbors, then a quarter
+/// less per hop, down to a quarter
+fn hop_opacity(metrics: &BTreeMap<String, f64>) -> Option<f64> {
+    let hops = *metrics.get(HOPS)?;
+    (hops >= 2.0).then(|| (1.0 - 0.25 * (hops - 1.0)).max(0.25))
+}
+
+/// classDef per faded hop distance
+fn mermaid_hop_fade<'a>(nodes: impl Iterator<Item = (&'a BTreeMap<String, f64>, &'a str)>) -> String {
+    let mut by_opacity: BTreeMap<u32, Vec<&str>> = BTreeMap::new();
+    for (metrics, id) in nodes {
+        if let Some(opacity) = hop_opacity(metrics) {
+            by_opacity.entry((opacity * 100.0).round() as u32).or_default().push(id);
         }
     }
-    sanitized
+
+    let mut output = String::new();
+    for (percent, ids) in by_opacity.iter().rev() {
+        output.push_str(&format!("    classDef faded{} opacity:{:.2}\n", percent, *percent as f64 / 100.0));
+        output.push_str(&format!("    class {} faded{}\n", ids.join(","), percent));
+    }
+    output
+}
+
+/// DOT nodes have no opacity, so a faded node's border and text get an alpha channel,
+/// unless another style already set their color
+fn push_hop_fade_attrs(node_attrs: &mut Vec<String>, metrics: &BTreeMap<String, f64>) {
+    let Some(opacity) = hop_opacity(metrics) else {
+        return;
+    };
+    let alpha = (opacity * 255.0).round() as u8;
+    for attr in ["color", "fontcolor"] {
+        if !node_attrs.iter().any(|existing| existing.starts_with(&format!("{}=", attr))) {
+            node_attrs.push(format!("{}=\"#000000{:02x}\"", attr, alpha));
+        }
+    }
+}
+
+/// Identifier for a group's subgraph, safe for both Mermaid and DOT
+fn group_cluster_id(name: &str) -> String {
+    let id: String = name.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    format!("group_{}", id)
+}
+
+const DUPLICATE_COLOR: &str = "#e03131";
+
+/// Borders of crates from git and from local paths outside the workspace (Mermaid; DOT uses shapes)
+const GIT_STROKE: &str = "#9c36b5";
+const PATH_STROKE: &str = "#2f9e44";
+
+/// Crates marked by --highlight-copyleft
+const COPYLEFT_FILL: &str = "#fff3bf";
+const COPYLEFT_STROKE: &str = "#f08c00";
+
+/// Crates behind their latest release, from --check-outdated
+const OUTDATED_FILL: &str = "#e7f5ff";
+const OUTDATED_STROKE: &str = "#1971c2";
+
+fn is_outdated(info: &NodeInfo) -> bool {
+    info.behind.is_some_and(|behind| behind != Behind::UpToDate)
+}
+
+/// Latest release line of an outdated crate's label
+fn outdated_label(info: &NodeInfo) -> Option<String> {
+    info.latest_version.as_ref().filter(|_| is_outdated(info)).map(|latest| format!("latest {}", latest))
+}
+
+/// Crates with advisories, from --audit
+const VULNERABLE_FILL: &str = "#ffe3e3";
+const VULNERABLE_STROKE: &str = "#e03131";
+
+fn is_vulnerable(info: &NodeInfo) -> bool {
+    info.advisories.as_ref().is_some_and(|ids| !ids.is_empty())
+}
+
+/// Version change of an updated crate, from `combine --op difference`
+fn updated_label(info: &NodeInfo) -> Option<String> {
+    info.previous_version.as_ref().map(|previous| format!("{} → {}", previous, info.version))
+}
+
+/// Registry line of a crate's label, for registries other than crates.io
+fn registry_label(info: &NodeInfo) -> Option<String> {
+    info.registry.as_ref().filter(|registry| *registry != "crates-io").map(|registry| format!("registry: {}", registry))
+}
+
+/// License line of a crate's label with --show-licenses
+fn license_label(info: &NodeInfo, args: &DepsArgs) -> Option<String> {
+    args.show_licenses.then(|| info.license.clone().unwrap_or_else(|| "no license".to_string()))
+}
+
+/// Each crate marked by --show-duplicates with its versions, by name
+fn duplicate_summary(graph_data: &GraphData) -> BTreeMap<&str, &[String]> {
+    graph_data.graph.node_weights()
+        .filter_map(|info| Some((info.name.as_str(), info.duplicate_versions.as_deref()?)))
+        .collect()
+}
+
+fn mermaid_duplicates(ids: &[&str], summary: &BTreeMap<&str, &[String]>) -> String {
+    let mut output = String::new();
+    if ids.is_empty() {
+        return output;
+    }
+
+    output.push_str(&format!("    classDef duplicate stroke:{},stroke-width:3px\n", DUPLICATE_COLOR));
+    output.push_str(&format!("    class {} duplicate\n", ids.join(",")));
+    output.push_str("    subgraph legend_duplicates[\"Duplicate versions\"]\n");
+    for (i, (name, versions)) in summary.iter().enumerate() {
+        output.push_str(&format!("        legend_duplicate_{}[\"{}: {}\"]\n", i, name, versions.join(", ")));
+    }
+    output.push_str("    end\n");
+    output
+}
+
+fn dot_duplicates_legend(summary: &BTreeMap<&str, &[String]>) -> String {
+    let mut output = String::new();
+    if summary.is_empty() {
+        return output;
+    }
+
+    output.push_str("    subgraph cluster_duplicates {\n");
+    output.push_str("        label=\"Duplicate versions\";\n");
+    for (i, (name, versions)) in summary.iter().enumerate() {
+        output.push_str(&format!(
+            "        legend_duplicate_{} [label=\"{}: {}\", shape=note, color=\"{}\"];\n",
+            i, name, versions.join(", "), DUPLICATE_COLOR
+        ));
+    }
+    output.push_str("    }\n");
+    output
+}
+
+/// With --capability-view, the first capability of a function that the view asks for
+fn sink_capability(info: &FnNodeInfo, args: &FnGraphArgs) -> Option<Capability> {
+    let kinds = args.capability_view.as_ref()?;
+    info.capabilities.iter().copied().find(|capability| kinds.is_empty() || kinds.contains(capability))
+}
+
+fn capability_color(capability: Capability) -> &'static str {
+    match capability {
+        Capability::Fs => "#ffd8a8",
+        Capability::Net => "#a5d8ff",
+        Capability::Process => "#ffc9c9",
+    }
+}
+
+fn dot_depth_attrs(depth: usize) -> [String; 3] {
+    [
+        format!("fillcolor=\"{}\"", depth_color(depth)),
+        format!("fontcolor=\"{}\"", depth_font_color(depth)),
+        "style=\"filled,rounded\"".to_string(),
+    ]
+}
+
+fn dot_color_legend(scale: &ColorScale, values: impl Iterator<Item = f64>) -> String {
+    let levels = scale.levels(values.map(|value| (value, ())));
+    let mut output = String::new();
+    if levels.is_empty() {
+        return output;
+    }
+
+    output.push_str("    subgraph cluster_legend {\n");
+    output.push_str(&format!("        label=\"{}\";\n", scale.title().replace('"', "\\\"")));
+    for (level, (low, high, _)) in levels {
+        output.push_str(&format!(
+            "        legend_{}_{} [label=\"{}\", {}];\n",
+            scale.prefix(), level, scale.label(low, high), dot_depth_attrs(level).join(", ")
+        ));
+    }
+    output.push_str("    }\n");
+    output
+}
+
+/// Mermaid arrow with the edge label, if any
+fn mermaid_arrow(arrow: &str, label: Option<&str>) -> String {
+    match label {
+        Some(label) => format!("{}|{}|", arrow, label),
+        None => arrow.to_string(),
+    }
+}
+
+/// Every kind when an edge carries more than one, and the features enabling it
+fn edge_label(kinds: DepKinds, feature_gates: Option<String>) -> Option<String> {
+    let kinds = Some(kinds_label(kinds)).filter(|_| kinds.len() > 1);
+    let parts: Vec<String> = kinds.into_iter().chain(feature_gates).collect();
+    Some(parts.join("; ")).filter(|label| !label.is_empty())
+}
+
+/// `normal+dev` for an edge of several kinds
+fn kinds_label(kinds: DepKinds) -> String {
+    kinds.iter().map(DepKind::as_str).collect::<Vec<_>>().join("+")
+}
+
+/// Edge color of a dependency kind in DOT
+fn dot_kind_color(kind: DepKind) -> &'static str {
+    match kind {
+        DepKind::Normal => "black",
+        DepKind::Build => "green",
+        DepKind::Dev => "blue",
+    }
+}
+
+/// Features of `from` enabling its optional dependency on `to`, from --show-feature-gates
+fn feature_gates_label(from: &NodeInfo, to: &NodeInfo) -> Option<String> {
+    from.feature_gates.get(&to.name).map(|features| format!("features: {}", features.join(", ")))
+}
+
+/// Width (inches, from Graphviz's default 0.75 to 3) and border of a node sized by --size-by,
+/// growing with the square root of its share of the `(min, max)` range. A border another
+/// highlight already set is kept.
+fn push_size_attrs(node_attrs: &mut Vec<String>, size: u64, (min, max): (u64, u64)) {
+    let share = if max > min { ((size - min) as f64 / (max - min) as f64).sqrt() } else { 0.0 };
+    node_attrs.push(format!("width={:.2}", 0.75 + 2.25 * share));
+    if !node_attrs.iter().any(|attr| attr.starts_with("penwidth=")) {
+        node_attrs.push(format!("penwidth={:.1}", 1.0 + 3.0 * share));
+    }
+}
+
+pub fn generate_deps_dot(graph_data: &GraphData, args: &DepsArgs) -> String {
+    let mut output = dot_header("dependencies", &args.theme);
+    let size_range = graph_data.graph.node_weights().filter_map(|info| info.size)
+        .fold((u64::MAX, 0), |(min, max), size| (min.min(size), max.max(size)));
+    let scale = ColorScale::new(args.color_by, args.color_metric.as_deref(), graph_data.graph.node_weights().map(|info| &info.metrics));
+    let ids = deps_node_ids(graph_data, args, false);
+
+    // Node definitions
+    let mut defined_nodes: HashSet<String> = HashSet::new();
+    for idx in graph_data.graph.node_indices() {
+        let info = &graph_data.graph[idx];
+        let label = format_node_label(info, args);
+        let sanitized = ids.quoted(idx);
+
+        if defined_nodes.insert(sanitized.clone()) {
+            let mut node_attrs = if info.is_build_script {
+                vec![format!("label=\"{}\"", build_script_label(info)), "shape=hexagon".to_string()]
+            } else {
+                let lines: Vec<String> = std::iter::once(label.replace('_', "-"))
+                    .chain(pin_label(info))
+                    .chain(updated_label(info).into_iter().chain(registry_label(info)).chain(license_label(info, args)).chain(outdated_label(info)).map(|line| line.replace('"', "\\\"")))
+                    .collect();
+                vec![format!("label=\"{}\"", lines.join("\\n"))]
+            };
+
+            if args.highlight.contains(&info.name) {
+                node_attrs.push("fillcolor=\"#ff99ff\"".to_string());
+                node_attrs.push("style=\"filled,rounded\"".to_string());
+            } else if is_vulnerable(info) {
+                node_attrs.push(format!("fillcolor=\"{}\"", VULNERABLE_FILL));
+                node_attrs.push(format!("color=\"{}\"", VULNERABLE_STROKE));
+                node_attrs.push("penwidth=3".to_string());
+                node_attrs.push("style=\"filled,rounded\"".to_string());
+            } else if args.highlight_copyleft && info.license.as_deref().is_some_and(is_copyleft) {
+                node_attrs.push(format!("fillcolor=\"{}\"", COPYLEFT_FILL));
+                node_attrs.push(format!("color=\"{}\"", COPYLEFT_STROKE));
+                node_attrs.push("style=\"filled,rounded\"".to_string());
+            } else if is_outdated(info) {
+                node_attrs.push(format!("fillcolor=\"{}\"", OUTDATED_FILL));
+                node_attrs.push(format!("color=\"{}\"", OUTDATED_STROKE));
+                node_attrs.push("style=\"filled,rounded,dashed\"".to_string());
+            } else if let Some(level) = scale.as_ref().and_then(|scale| scale.level_of(info.depth, &info.metrics)) {
+                node_attrs.extend(dot_depth_attrs(level));
+            } else if let Some(category) = info.category.as_deref().filter(|_| args.color_by == Some(ColorBy::Category)) {
+                node_attrs.push(format!("fillcolor=\"{}\"", category_color(category)));
+                node_attrs.push("style=\"filled,rounded\"".to_string());
+            }
+
+            if info.is_optional {
+                match node_attrs.iter_mut().find(|attr| attr.starts_with("style=")) {
+                    Some(style) if !style.contains("dashed") => style.insert_str(style.len() - 1, ",dashed"),
+                    Some(_) => {}
+                    None => node_attrs.push("style=\"rounded,dashed\"".to_string()),
+                }
+            }
+
+            if info.is_workspace_member {
+                node_attrs.push("penwidth=2".to_string());
+            }
+
+            match source_kind(info) {
+                SourceKind::Git if !info.is_build_script => node_attrs.push("shape=component".to_string()),
+                SourceKind::Path if !info.is_build_script => node_attrs.push("shape=folder".to_string()),
+                _ => {}
+            }
+
+            if info.duplicate_versions.is_some() {
+                node_attrs.push(format!("color=\"{}\"", DUPLICATE_COLOR));
+                node_attrs.push("penwidth=3".to_string());
+            }
+
+            if let Some(size) = info.size {
+                push_size_attrs(&mut node_attrs, size, size_range);
+            }
+
+            if args.fade_by_hops {
+                push_hop_fade_attrs(&mut node_attrs, &info.metrics);
+            }
+
+            if let Some(link) = page_link(&info.id(), args.detail_pages) {
+                node_attrs.push(format!("URL=\"{}\"", link));
+            }
+
+            output.push_str(&format!("    {} [{}];\n", sanitized, node_attrs.join(", ")));
+        }
+    }
+
+    // Workspace members share a rank
+    if let Some(rank) = args.rank_workspace {
+        let members: BTreeSet<String> = graph_data.graph.node_indices()
+            .filter(|&idx| graph_data.graph[idx].is_workspace_member && !graph_data.graph[idx].is_build_script)
+            .map(|idx| ids.quoted(idx))
+            .collect();
+        if !members.is_empty() {
+            let rank = match rank {
+                RankWorkspace::Same => "same",
+                RankWorkspace::Source => "source",
+            };
+            let members: Vec<&str> = members.iter().map(String::as_str).collect();
+            output.push_str(&format!("    {{rank={}; {};}}\n", rank, members.join("; ")));
+        }
+    }
+
+    // Edges
+    for edge in graph_data.graph.edge_indices() {
+        if let Some((from, to)) = graph_data.graph.edge_endpoints(edge) {
+            let from_name = ids.quoted(from);
+            let to_name = ids.quoted(to);
+            let kinds = graph_data.graph[edge];
+
+            let mut edge_attrs: Vec<String> = match kinds.primary() {
+                DepKind::Dev => vec!["style=dashed".to_string()],
+                DepKind::Build => vec!["style=bold".to_string()],
+                DepKind::Normal => Vec::new(),
+            };
+            // One parallel line per kind, e.g. black and blue for normal+dev
+            if kinds.len() > 1 || kinds.primary() != DepKind::Normal {
+                let colors: Vec<&str> = kinds.iter().map(dot_kind_color).collect();
+                edge_attrs.push(format!("color=\"{}\"", colors.join(":")));
+            }
+            if let Some(label) = edge_label(kinds, feature_gates_label(&graph_data.graph[from], &graph_data.graph[to])) {
+                edge_attrs.push(format!("label=\"{}\"", label));
+            }
+
+            let style = if edge_attrs.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", edge_attrs.join(", "))
+            };
+
+            output.push_str(&format!("    {} -> {}{};\n", from_name, to_name, style));
+        }
+    }
+
+    let grouped = graph_data.graph.node_indices()
+        .filter_map(|idx| graph_data.graph[idx].group.as_ref().map(|group| (group, ids.quoted(idx))));
+    output.push_str(&dot_group_clusters(grouped));
+    if args.group_by_kind {
+        output.push_str(&dot_kind_clusters(graph_data, &ids));
+    }
+
+    if let Some(ref scale) = scale {
+        let values = graph_data.graph.node_weights().filter_map(|info| scale.value(info.depth, &info.metrics));
+        output.push_str(&dot_color_legend(scale, values));
+    }
+    if args.color_by == Some(ColorBy::Category) {
+        output.push_str(&dot_category_legend(graph_data.graph.node_weights().filter_map(|info| info.category.as_deref())));
+    }
+    output.push_str(&dot_duplicates_legend(&duplicate_summary(graph_data)));
+    output.push_str(&dot_filter_legend(&graph_data.filters));
+
+    output.push_str("}\n");
+    output
+}
+
+pub fn generate_deps_json(graph_data: &GraphData, args: &DepsArgs) -> String {
+    let mut nodes: Vec<serde_json::Value> = Vec::new();
+    let mut edges: Vec<serde_json::Value> = Vec::new();
+    let ids = deps_node_ids(graph_data, args, false);
+
+    for idx in graph_data.graph.node_indices() {
+        let info = &graph_data.graph[idx];
+        let mut node = serde_json::json!({
+            "id": ids.get(idx),
+            "name": info.name,
+            "version": info.version,
+            "is_workspace_member": info.is_workspace_member,
+            "is_build_script": info.is_build_script,
+            "highlighted": args.highlight.contains(&info.name)
+        });
+
+        if info.is_optional {
+            node["optional"] = serde_json::json!(true);
+        }
+        if !info.is_build_script {
+            node["source_kind"] = serde_json::json!(source_kind(info).name());
+        }
+        if let Some(ref registry) = info.registry {
+            node["registry"] = serde_json::json!(registry);
+        }
+        if let Some(ref source) = info.source {
+            node["source"] = serde_json::json!(source);
+            if let Some(commit) = git_commit(source) {
+                node["git_commit"] = serde_json::json!(commit);
+            }
+        }
+        if let Some(ref checksum) = info.checksum {
+            node["checksum"] = serde_json::json!(checksum);
+        }
+        if args.show_licenses {
+            node["license"] = serde_json::json!(info.license);
+            node["copyleft"] = serde_json::json!(info.license.as_deref().is_some_and(is_copyleft));
+        }
+        if let Some(ref owners) = info.owners {
+            node["owners"] = serde_json::json!(owners);
+        }
+        if let Some(ref advisories) = info.advisories {
+            node["advisories"] = serde_json::json!(advisories);
+        }
+        if let Some(behind) = info.behind {
+            node["outdated"] = serde_json::json!(behind != Behind::UpToDate);
+            node["behind"] = serde_json::json!(behind.as_str());
+            node["latest_version"] = serde_json::json!(info.latest_version);
+        }
+        if let Some(ref versions) = info.duplicate_versions {
+            node["duplicate_versions"] = serde_json::json!(versions);
+        }
+        if let Some(category) = info.category.as_ref().filter(|_| args.color_by == Some(ColorBy::Category)) {
+            node["category"] = serde_json::json!(category);
+        }
+        if let Some(size) = info.size {
+            node["size"] = serde_json::json!(size);
+        }
+        if let Some(ref previous) = info.previous_version {
+            node["previous_version"] = serde_json::json!(previous);
+        }
+
+        if let Some(depth) = info.depth {
+            node["depth"] = serde_json::json!(depth);
+        }
+        if let Some(distance) = info.distance {
+            node["distance"] = serde_json::json!(distance);
+        }
+
+        if let Some(ref group) = info.group {
+            node["group"] = serde_json::json!(group.name);
+            node["group_color"] = serde_json::json!(group.color);
+            if let Some(ref direction) = group.direction {
+                node["group_direction"] = serde_json::json!(direction);
+            }
+        }
+
+        if !info.metrics.is_empty() {
+            node["metrics"] = serde_json::json!(info.metrics);
+        }
+
+        if let Some(link) = page_link(&info.id(), args.detail_pages) {
+            node["page"] = serde_json::json!(link);
+        }
+
+        nodes.push(node);
+    }
+
+    for edge in graph_data.graph.edge_indices() {
+        if let Some((from, to)) = graph_data.graph.edge_endpoints(edge) {
+            let kinds = graph_data.graph[edge];
+            let mut json_edge = serde_json::json!({
+                "from": ids.get(from),
+                "to": ids.get(to),
+                "kind": kinds.primary().as_str(),
+                "kinds": kinds.iter().map(DepKind::as_str).collect::<Vec<_>>()
+            });
+            if let Some(features) = graph_data.graph[from].feature_gates.get(&graph_data.graph[to].name) {
+                json_edge["features"] = serde_json::json!(features);
+            }
+            edges.push(json_edge);
+        }
+    }
+
+    let mut graph = serde_json::json!({
+        "nodes": nodes,
+        "edges": edges
+    });
+    let duplicates = duplicate_summary(graph_data);
+    if !duplicates.is_empty() {
+        let duplicates: Vec<serde_json::Value> = duplicates.iter()
+            .map(|(name, versions)| serde_json::json!({ "name": name, "versions": versions }))
+            .collect();
+        graph["duplicates"] = serde_json::json!(duplicates);
+    }
+    if let Some(filters) = json_filters(&graph_data.filters) {
+        graph["filters"] = filters;
+    }
+    serde_json::to_string_pretty(&graph).unwrap_or_else(|_| "{}".to_string())
+}
+
+/// Nodes and edges in the `id`/`label`/`group`/`metadata` shape of VS Code graph webviews
+pub fn generate_deps_vscode_graph(graph_data: &GraphData, args: &DepsArgs) -> String {
+    let graph = &graph_data.graph;
+
+    let nodes: Vec<serde_json::Value> = graph.node_weights()
+        .map(|info| {
+            let mut metadata = serde_json::json!({
+                "name": info.name,
+                "version": info.version,
+                "kind": info.kind.as_str(),
+                "workspaceMember": info.is_workspace_member,
+                "buildScript": info.is_build_script,
+                "highlighted": args.highlight.contains(&info.name)
+            });
+            for (key, value) in [
+                ("license", &info.license),
+                ("description", &info.description),
+                ("repository", &info.repository),
+                ("homepage", &info.homepage),
+                ("documentation", &info.documentation),
+                ("source", &info.source),
+                ("checksum", &info.checksum),
+            ] {
+                if let Some(value) = value {
+                    metadata[key] = serde_json::json!(value);
+                }
+            }
+            if let Some(commit) = info.source.as_deref().and_then(git_commit) {
+                metadata["gitCommit"] = serde_json::json!(commit);
+            }
+            if let Some(distance) = info.distance {
+                metadata["distance"] = serde_json::json!(distance);
+            }
+            if let Some(ref owners) = info.owners {
+                metadata["owners"] = serde_json::json!(owners);
+            }
+            if let Some(ref advisories) = info.advisories {
+                metadata["advisories"] = serde_json::json!(advisories);
+            }
+            if let Some(ref versions) = info.duplicate_versions {
+                metadata["duplicateVersions"] = serde_json::json!(versions);
+            }
+            vscode_common_metadata(&mut metadata, info.depth, info.group.as_ref(), &info.metrics);
+
+            serde_json::json!({
+                "id": info.id(),
+                "label": info.name,
+                "group": info.group.as_ref().map_or(info.kind.as_str(), |group| group.name.as_str()),
+                "metadata": metadata
+            })
+        })
+        .collect();
+
+    let edges: Vec<serde_json::Value> = graph.edge_indices()
+        .filter_map(|edge| {
+            let (from, to) = graph.edge_endpoints(edge)?;
+            let kinds = graph[edge];
+            Some(vscode_edge(graph[from].id(), graph[to].id(), kinds.primary().as_str(), serde_json::json!({
+                "kinds": kinds.iter().map(DepKind::as_str).collect::<Vec<_>>()
+            })))
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&serde_json::json!({
+        "nodes": nodes,
+        "edges": edges
+    }))
+    .unwrap_or_else(|_| "{}".to_string())
+}
+
+// ============================================================================
+// Function Graph - Output Generators
+// ============================================================================
+
+pub fn generate_fn_mermaid(graph_data: &FnGraphData, args: &FnGraphArgs) -> String {
+    let mut output = mermaid_header(args.no_fence, &args.direction, &args.theme, args.mermaid_version);
+    let ids = fn_node_ids(graph_data, args, true);
+
+    // Edges
+    let max_samples = max_edge_samples(graph_data);
+    let mut hot_links = Vec::new();
+    for (link, edge) in graph_data.graph.edge_indices().enumerate() {
+        if let Some((from, to)) = graph_data.graph.edge_endpoints(edge) {
+            let call = graph_data.graph[edge];
+
+            let from_label = ids.get(from);
+            let to_label = ids.get(to);
+
+            let arrow = match call.kind {
+                CallKind::Direct => "-->",
+                CallKind::Method => "-.->",
+                CallKind::Macro => "--o",
+                CallKind::Await => "==>",
+            };
+
+            output.push_str(&format!("    {} {} {}\n", from_label, arrow, to_label));
+
+            if let Some(samples) = call.samples {
+                hot_links.push((link, hot_edge_width(samples, max_samples)));
+            }
+        }
+    }
+
+    // Profile heat on edges
+    for (link, width) in hot_links {
+        output.push_str(&format!("    linkStyle {} stroke:{},stroke-width:{:.1}px\n", link, HOT_EDGE_COLOR, width));
+    }
+
+    // User-defined clusters
+    let graph = &graph_data.graph;
+    let grouped = graph.node_indices()
+        .filter_map(|idx| graph[idx].group.as_ref().map(|group| (group, ids.get(idx).to_string())));
+    output.push_str(&mermaid_group_clusters(grouped, args.mermaid_version));
+    output.push_str(&mermaid_page_links(graph, &ids, args.detail_pages));
+
+    // Depth / metric coloring
+    if let Some(scale) = ColorScale::new(args.color_by, args.color_metric.as_deref(), graph.node_weights().map(|info| &info.metrics)) {
+        let nodes = graph.node_indices()
+            .filter_map(|idx| scale.value(graph[idx].depth, &graph[idx].metrics).map(|value| (value, ids.get(idx).to_string())));
+        output.push_str(&mermaid_color_styles(&scale, nodes));
+    }
+
+    // Capability sinks
+    for capability in Capability::value_variants() {
+        let members: Vec<&str> = graph.node_indices()
+            .filter(|&idx| sink_capability(&graph[idx], args) == Some(*capability))
+            .map(|idx| ids.get(idx))
+            .collect();
+        if !members.is_empty() {
+            output.push_str(&format!("    classDef cap_{} fill:{},stroke:#333
+", capability.as_str(), capability_color(*capability)));
+            output.push_str(&format!("    class {} cap_{}
+", members.join(","), capability.as_str()));
+        }
+    }
+
+    // Shortest public path of re-exported functions, and the ones public under several
+    for idx in graph.node_indices() {
+        if let Some(path) = reexported_label(&graph[idx]) {
+            output.push_str(&format!("    {}[\"{}<br/>{}\"]\n", ids.get(idx), ids.get(idx), path));
+        }
+    }
+    let multipath: Vec<&str> = graph.node_indices()
+        .filter(|&idx| graph[idx].public_paths.len() > 1)
+        .map(|idx| ids.get(idx))
+        .collect();
+    if !multipath.is_empty() {
+        output.push_str(&format!("    classDef multipath stroke:{},stroke-width:3px\n", MULTIPATH_STROKE));
+        output.push_str(&format!("    class {} multipath\n", multipath.join(",")));
+    }
+
+    // Neighbors outside --only-files
+    let context: Vec<&str> = graph.node_indices()
+        .filter(|&idx| graph[idx].is_context)
+        .map(|idx| ids.get(idx))
+        .collect();
+    if !context.is_empty() {
+        output.push_str(&format!("    classDef context fill:{},color:{},stroke:{},stroke-dasharray:3 3\n", CONTEXT_FILL, CONTEXT_TEXT, CONTEXT_STROKE));
+        output.push_str(&format!("    class {} context\n", context.join(",")));
+    }
+
+    if args.fade_by_hops {
+        output.push_str(&mermaid_hop_fade(graph.node_indices().map(|idx| (&graph[idx].metrics, ids.get(idx)))));
+    }
+
+    output.push_str(&mermaid_filter_legend(&graph_data.filters));
+    let highlighted = highlighted_ids(graph, &ids, &args.highlight, |info| &info.name);
+    output.push_str(&mermaid_footer(args.no_fence, &highlighted));
+    output
+}
+
+/// Functions public under several paths, with --reexports
+const MULTIPATH_STROKE: &str = "#7048e8";
+
+/// Shortest public path of a function re-exported by `pub use`
+fn reexported_label(info: &FnNodeInfo) -> Option<&str> {
+    info.public_paths.first().filter(|_| !info.reexports.is_empty()).map(String::as_str)
+}
+
+/// Grayed functions outside --only-files
+const CONTEXT_FILL: &str = "#f1f3f5";
+const CONTEXT_TEXT: &str = "#868e96";
+const CONTEXT_STROKE: &str = "#adb5bd";
+
+pub fn generate_fn_dot(graph_data: &FnGraphData, args: &FnGraphArgs) -> String {
+    let mut output = dot_header("call_graph", &args.theme);
+    let scale = ColorScale::new(args.color_by, args.color_metric.as_deref(), graph_data.graph.node_weights().map(|info| &info.metrics));
+    let ids = fn_node_ids(graph_data, args, false);
+
+    // Node definitions
+    let mut defined_nodes: HashSet<String> = HashSet::new();
+    for idx in graph_data.graph.node_indices() {
+        let info = &graph_data.graph[idx];
+        let sanitized = ids.quoted(idx);
+
+        if defined_nodes.insert(sanitized.clone()) {
+            let label = if args.show_signatures {
+                info.signature.as_ref().unwrap_or(&info.name).clone()
+            } else {
+                info.name.clone()
+            };
+
+            let label = match reexported_label(info) {
+                Some(path) => format!("{}\\n{}", label.replace('"', "\\\""), path),
+                None => label.replace('"', "\\\""),
+            };
+            let mut node_attrs = vec![format!("label=\"{}\"", label)];
+            if info.public_paths.len() > 1 {
+                node_attrs.push("peripheries=2".to_string());
+                node_attrs.push(format!("color=\"{}\"", MULTIPATH_STROKE));
+            }
+
+            if args.highlight.contains(&info.name) {
+                node_attrs.push("fillcolor=\"#ff99ff\"".to_string());
+                node_attrs.push("style=\"filled,rounded\"".to_string());
+            } else if info.is_context {
+                node_attrs.push(format!("fillcolor=\"{}\"", CONTEXT_FILL));
+                node_attrs.push(format!("fontcolor=\"{}\"", CONTEXT_TEXT));
+                node_attrs.push(format!("color=\"{}\"", CONTEXT_STROKE));
+                node_attrs.push("style=\"filled,rounded,dashed\"".to_string());
+            } else if let Some(capability) = sink_capability(info, args) {
+                node_attrs.push(format!("fillcolor=\"{}\"", capability_color(capability)));
+                node_attrs.push("style=\"filled,rounded\"".to_string());
+            } else if let Some(level) = scale.as_ref().and_then(|scale| scale.level_of(info.depth, &info.metrics)) {
+                node_attrs.extend(dot_depth_attrs(level));
+            }
+
+            if info.is_public {
+                node_attrs.push("penwidth=2".to_string());
+            }
+
+            if info.is_async {
+                node_attrs.push("color=blue".to_string());
+            }
+
+            if args.fade_by_hops {
+                push_hop_fade_attrs(&mut node_attrs, &info.metrics);
+            }
+
+            if let Some(link) = page_link(&info.id(), args.detail_pages) {
+                node_attrs.push(format!("URL=\"{}\"", link));
+            }
+
+            output.push_str(&format!("    {} [{}];\n", sanitized, node_attrs.join(", ")));
+        }
+    }
+
+    // Edges
+    let max_samples = max_edge_samples(graph_data);
+    for edge in graph_data.graph.edge_indices() {
+        if let Some((from, to)) = graph_data.graph.edge_endpoints(edge) {
+            let from_name = ids.quoted(from);
+            let to_name = ids.quoted(to);
+            let call = graph_data.graph[edge];
+
+            let mut edge_attrs: Vec<String> = match call.kind {
+                CallKind::Direct => Vec::new(),
+                CallKind::Method => vec!["style=dashed".to_string()],
+                CallKind::Macro => vec!["style=dotted".to_string()],
+                CallKind::Await => vec!["style=bold".to_string(), "color=blue".to_string()],
+            };
+            if let Some(samples) = call.samples {
+                edge_attrs.push(format!("penwidth={:.1}", hot_edge_width(samples, max_samples)));
+                edge_attrs.push(format!("color=\"{}\"", HOT_EDGE_COLOR));
+                edge_attrs.push(format!("label=\"{}\"", samples));
+            }
+
+            let style = if edge_attrs.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", edge_attrs.join(", "))
+            };
+
+            output.push_str(&format!("    {} -> {}{};\n", from_name, to_name, style));
+        }
+    }
+
+    let grouped = graph_data.graph.node_indices()
+        .filter_map(|idx| graph_data.graph[idx].group.as_ref().map(|group| (group, ids.quoted(idx))));
+    output.push_str(&dot_group_clusters(grouped));
+
+    if let Some(ref scale) = scale {
+        let values = graph_data.graph.node_weights().filter_map(|info| scale.value(info.depth, &info.metrics));
+        output.push_str(&dot_color_legend(scale, values));
+    }
+    output.push_str(&dot_filter_legend(&graph_data.filters));
+
+    output.push_str("}\n");
+    output
+}
+
+pub fn generate_fn_json(graph_data: &FnGraphData, args: &FnGraphArgs) -> String {
+    let mut nodes: Vec<serde_json::Value> = Vec::new();
+    let mut edges: Vec<serde_json::Value> = Vec::new();
+    let ids = fn_node_ids(graph_data, args, false);
+
+    for idx in graph_data.graph.node_indices() {
+        let info = &graph_data.graph[idx];
+        let mut node = serde_json::json!({
+            "id": ids.get(idx),
+            "name": info.name,
+            "qualified_name": info.qualified_name,
+            "file": info.file_path,
+            "line": info.line,
+            "is_public": info.is_public,
+            "is_async": info.is_async,
+            "is_unsafe": info.is_unsafe,
+            "highlighted": args.highlight.contains(&info.name)
+        });
+
+        if let Some(ref sig) = info.signature {
+            node["signature"] = serde_json::json!(sig);
+        }
+
+        if let Some(hash) = info.body_hash {
+            node["body_hash"] = serde_json::json!(format!("{:016x}", hash));
+        }
+        if let Some(hash) = info.content_hash {
+            node["content_hash"] = serde_json::json!(format!("{:016x}", hash));
+        }
+        if !info.config.is_empty() {
+            let config: Vec<serde_json::Value> = info.config.iter()
+                .map(|read| serde_json::json!({ "name": read.name, "kind": read.kind.as_str() }))
+                .collect();
+            node["config"] = serde_json::json!(config);
+        }
+        if !info.capabilities.is_empty() {
+            let capabilities: Vec<&str> = info.capabilities.iter().map(|capability| capability.as_str()).collect();
+            node["capabilities"] = serde_json::json!(capabilities);
+        }
+        if !info.types.is_empty() {
+            node["types"] = serde_json::json!(info.types);
+        }
+        if !info.features.is_empty() {
+            node["features"] = serde_json::json!(info.features);
+        }
+        if !info.public_paths.is_empty() {
+            node["public_paths"] = serde_json::json!(info.public_paths);
+        }
+        if !info.reexports.is_empty() {
+            let reexports: Vec<serde_json::Value> = info.reexports.iter()
+                .map(|reexport| serde_json::json!({ "path": reexport.path, "via": reexport.via }))
+                .collect();
+            node["reexports"] = serde_json::json!(reexports);
+        }
+        if args.logs && !info.logs.is_empty() {
+            let logs: Vec<serde_json::Value> = info.logs.iter()
+                .map(|call| serde_json::json!({
+                    "level": call.level,
+                    "target": call.target,
+                    "message": call.message,
+                    "line": call.line
+                }))
+                .collect();
+            node["logs"] = serde_json::json!(logs);
+        }
+
+        if let Some(ref crate_name) = info.crate_name {
+            node["crate"] = serde_json::json!(crate_name);
+        }
+        if info.is_context {
+            node["context"] = serde_json::json!(true);
+        }
+
+        if let Some(depth) = info.depth {
+            node["depth"] = serde_json::json!(depth);
+        }
+
+        if let Some(ref group) = info.group {
+            node["group"] = serde_json::json!(group.name);
+            node["group_color"] = serde_json::json!(group.color);
+            if let Some(ref direction) = group.direction {
+                node["group_direction"] = serde_json::json!(direction);
+            }
+        }
+
+        if !info.metrics.is_empty() {
+            node["metrics"] = serde_json::json!(info.metrics);
+        }
+        if let Some(ref date) = info.last_modified {
+            node["last_modified"] = serde_json::json!(date);
+        }
+
+        if let Some(link) = page_link(&info.id(), args.detail_pages) {
+            node["page"] = serde_json::json!(link);
+        }
+
+        nodes.push(node);
+    }
+
+    for edge in graph_data.graph.edge_indices() {
+        if let Some((from, to)) = graph_data.graph.edge_endpoints(edge) {
+            let call = graph_data.graph[edge];
+            let mut edge_json = serde_json::json!({
+                "from": ids.get(from),
+                "to": ids.get(to),
+                "kind": call.kind.as_str()
+            });
+            if let Some(samples) = call.samples {
+                edge_json["samples"] = serde_json::json!(samples);
+            }
+            edges.push(edge_json);
+        }
+    }
+
+    let mut graph = serde_json::json!({
+        "nodes": nodes,
+        "edges": edges
+    });
+    if let Some(filters) = json_filters(&graph_data.filters) {
+        graph["filters"] = filters;
+    }
+    serde_json::to_string_pretty(&graph).unwrap_or_else(|_| "{}".to_string())
+}
+
+/// `generate_deps_vscode_graph` for functions, with absolute file URIs and every call site
+pub fn generate_fn_vscode_graph(graph_data: &FnGraphData, args: &FnGraphArgs, locations: &SourceLocations) -> String {
+    let graph = &graph_data.graph;
+
+    let nodes: Vec<serde_json::Value> = graph.node_weights()
+        .map(|info| {
+            let uri = locations.files.get(&info.file_path)
+                .filter(|path| path.is_absolute())
+                .map_or_else(|| info.file_path.clone(), |path| file_uri(path));
+            let mut metadata = serde_json::json!({
+                "name": info.name,
+                "qualifiedName": info.qualified_name,
+                "uri": uri,
+                "line": info.line,
+                "column": info.column,
+                "endLine": info.end_line,
+                "isPublic": info.is_public,
+                "isAsync": info.is_async,
+                "isUnsafe": info.is_unsafe,
+                "isMethod": info.is_method,
+                "isEntryPoint": info.is_entry_point,
+                "highlighted": args.highlight.contains(&info.name)
+            });
+            if let Some(ref signature) = info.signature {
+                metadata["signature"] = serde_json::json!(signature);
+            }
+            if let Some(ref crate_name) = info.crate_name {
+                metadata["crate"] = serde_json::json!(crate_name);
+            }
+            vscode_common_metadata(&mut metadata, info.depth, info.group.as_ref(), &info.metrics);
+
+            // Without --groups, functions are grouped by crate, else by file
+            let group = info.group.as_ref().map(|group| &group.name)
+                .or(info.crate_name.as_ref())
+                .unwrap_or(&info.file_path);
+
+            serde_json::json!({
+                "id": info.id(),
+                "label": info.name,
+                "group": group,
+                "metadata": metadata
+            })
+        })
+        .collect();
+
+    let edges: Vec<serde_json::Value> = graph.edge_indices()
+        .filter_map(|edge| {
+            let (from, to) = graph.edge_endpoints(edge)?;
+            let call = graph[edge];
+            let key = (graph[from].qualified_name.clone(), graph[to].qualified_name.clone());
+            let call_sites: Vec<serde_json::Value> = locations.call_sites.get(&key).into_iter().flatten()
+                .map(|&(line, column)| serde_json::json!({ "line": line, "column": column }))
+                .collect();
+            let kind = call.kind.as_str();
+
+            let mut metadata = serde_json::json!({ "callSites": call_sites });
+            if let Some(samples) = call.samples {
+                metadata["samples"] = serde_json::json!(samples);
+            }
+            Some(vscode_edge(graph[from].id(), graph[to].id(), kind, metadata))
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&serde_json::json!({
+        "nodes": nodes,
+        "edges": edges
+    }))
+    .unwrap_or_else(|_| "{}".to_string())
+}
+
+fn vscode_common_metadata(metadata: &mut serde_json::Value, depth: Option<usize>, group: Option<&NodeGroup>, metrics: &BTreeMap<String, f64>) {
+    if let Some(depth) = depth {
+        metadata["depth"] = serde_json::json!(depth);
+    }
+    if let Some(group) = group {
+        metadata["groupColor"] = serde_json::json!(group.color);
+    }
+    if !metrics.is_empty() {
+        metadata["metrics"] = serde_json::json!(metrics);
+    }
+}
+
+fn vscode_edge(source: String, target: String, label: &str, mut metadata: serde_json::Value) -> serde_json::Value {
+    metadata["kind"] = serde_json::json!(label);
+    serde_json::json!({
+        "id": format!("{}->{}", source, target),
+        "source": source,
+        "target": target,
+        "label": label,
+        "metadata": metadata
+    })
+}
+
+/// LSP `callHierarchy` shapes for each focus function: the item plus its
+/// incoming (`CallHierarchyIncomingCall`) and outgoing (`CallHierarchyOutgoingCall`) calls
+pub fn generate_fn_call_hierarchy(graph_data: &FnGraphData, focus_nodes: &[NodeIndex], locations: &SourceLocations) -> String {
+    let graph = &graph_data.graph;
+    let hierarchy_item = |idx: NodeIndex| call_hierarchy_item(&graph[idx], locations);
+    // Ranges of the callee name at every call from `caller` to `callee`
+    let from_ranges = |caller: NodeIndex, callee: NodeIndex| -> Vec<serde_json::Value> {
+        let key = (graph[caller].qualified_name.clone(), graph[callee].qualified_name.clone());
+        let width = graph[callee].name.chars().count();
+        locations.call_sites.get(&key).into_iter().flatten()
+            .map(|&(line, column)| lsp_range((line, column), (line, column + width)))
+            .collect()
+    };
+
+    let mut entries: Vec<serde_json::Value> = Vec::new();
+    for &idx in focus_nodes {
+        let mut callers: Vec<NodeIndex> = graph.neighbors_directed(idx, Direction::Incoming).collect();
+        let mut callees: Vec<NodeIndex> = graph.neighbors(idx).collect();
+        callers.sort_by(|a, b| graph[*a].qualified_name.cmp(&graph[*b].qualified_name));
+        callees.sort_by(|a, b| graph[*a].qualified_name.cmp(&graph[*b].qualified_name));
+        callers.dedup();
+        callees.dedup();
+
+        let incoming: Vec<serde_json::Value> = callers.into_iter()
+            .map(|caller| serde_json::json!({ "from": hierarchy_item(caller), "fromRanges": from_ranges(caller, idx) }))
+            .collect();
+        let outgoing: Vec<serde_json::Value> = callees.into_iter()
+            .map(|callee| serde_json::json!({ "to": hierarchy_item(callee), "fromRanges": from_ranges(idx, callee) }))
+            .collect();
+
+        entries.push(serde_json::json!({
+            "item": hierarchy_item(idx),
+            "incoming": incoming,
+            "outgoing": outgoing
+        }));
+    }
+
+    serde_json::to_string_pretty(&entries).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// `CallHierarchyItem`; `range` spans the whole item, `selectionRange` its name
+fn call_hierarchy_item(info: &FnNodeInfo, locations: &SourceLocations) -> serde_json::Value {
+    // SymbolKind: 6 = Method, 12 = Function
+    let kind = if info.is_method { 6 } else { 12 };
+    let uri = locations.files.get(&info.file_path)
+        .map(|path| file_uri(path))
+        .unwrap_or_else(|| info.file_path.clone());
+    let name_start = (info.line, info.column);
+    let name_end = (info.line, info.column + info.name.chars().count());
+
+    serde_json::json!({
+        "name": info.name,
+        "kind": kind,
+        "detail": info.qualified_name,
+        "uri": uri,
+        // The range ends (exclusively) at the start of the line after the item
+        "range": lsp_range((info.line, 0), (info.end_line.max(info.line) + 1, 0)),
+        "selectionRange": lsp_range(name_start, name_end)
+    })
+}
+
+/// LSP range from 1-based lines and 0-based columns
+fn lsp_range(start: (usize, usize), end: (usize, usize)) -> serde_json::Value {
+    serde_json::json!({
+        "start": { "line": start.0.saturating_sub(1), "character": start.1 },
+        "end": { "line": end.0.saturating_sub(1), "character": end.1 }
+    })
+}
+
+/// SARIF 2.1.0 log with one run; locations are relative to the working directory when possible
+pub fn generate_sarif(findings: &[Finding], locations: &SourceLocations) -> String {
+    let current_dir = std::env::current_dir().ok();
+
+    let rules: Vec<serde_json::Value> = RULES.iter()
+        .map(|(id, description, help)| serde_json::json!({
+            "id": id,
+            "shortDescription": { "text": description },
+            "help": { "text": help },
+            "defaultConfiguration": { "level": "warning" }
+        }))
+        .collect();
+
+    let results: Vec<serde_json::Value> = findings.iter()
+        .map(|finding| {
+            let absolute = locations.files.get(&finding.file_path);
+            let relative = absolute.zip(current_dir.as_ref())
+                .and_then(|(path, dir)| path.strip_prefix(dir).ok());
+            let artifact = match (relative, absolute) {
+                (Some(relative), _) => serde_json::json!({
+                    "uri": relative.to_string_lossy().replace('\\', "/"),
+                    "uriBaseId": "%SRCROOT%"
+                }),
+                (None, Some(absolute)) => serde_json::json!({ "uri": file_uri(absolute) }),
+                (None, None) => serde_json::json!({ "uri": finding.file_path }),
+            };
+
+            serde_json::json!({
+                "ruleId": finding.rule_id,
+                "level": "warning",
+                "message": { "text": finding.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": artifact,
+                        "region": {
+                            "startLine": finding.line.max(1),
+                            "startColumn": finding.column + 1
+                        }
+                    }
+                }]
+            })
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "rust-grapher",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                    "rules": rules
+                }
+            },
+            "results": results
+        }]
+    }))
+    .unwrap_or_else(|_| "{}".to_string())
+}
+
+/// Stroke color of edges carrying profile samples
+const HOT_EDGE_COLOR: &str = "#e31a1c";
+
+fn max_edge_samples(graph_data: &FnGraphData) -> u64 {
+    graph_data.graph.edge_weights().filter_map(|call| call.samples).max().unwrap_or(0)
+}
+
+/// Stroke width from 1 to 6, relative to the hottest edge
+fn hot_edge_width(samples: u64, max_samples: u64) -> f64 {
+    1.0 + 5.0 * samples as f64 / max_samples.max(1) as f64
+}
+
+pub fn format_fn_label(info: &FnNodeInfo, args: &FnGraphArgs) -> String {
+    let sanitized = sanitize_name(&info.name);
+    if args.show_signatures {
+        if let Some(ref sig) = info.signature {
+            return sanitize_name(&sig.replace(['(', ')', ',', ' ', '-', '>'], "_"));
+        }
+    }
+    sanitized
+}
+
+// ============================================================================
+// GitHub Step Summary
+// ============================================================================
+
+/// GitHub stops rendering Mermaid diagrams past these sizes
+const GH_MERMAID_MAX_CHARS: usize = 50_000;
+const GH_MERMAID_MAX_EDGES: usize = 500;
+/// `$GITHUB_STEP_SUMMARY` accepts at most 1 MiB per step
+const GH_SUMMARY_MAX_BYTES: usize = 1024 * 1024;
+/// Table rows listed before the rest is counted as "more"
+const GH_SUMMARY_MAX_ROWS: usize = 200;
+
+/// Markdown for `$GITHUB_STEP_SUMMARY`: totals, the Mermaid diagram while GitHub can render it, and a crate table
+pub fn generate_deps_gh_summary(graph_data: &GraphData, args: &DepsArgs) -> String {
+    let graph = &graph_data.graph;
+    let members = graph.node_weights().filter(|info| info.is_workspace_member).count();
+    let max_depth = graph.node_weights().filter_map(|info| info.depth).max();
+
+    let mut output = String::from("### Dependency graph\n\n");
+    output.push_str("| Crates | Workspace members | Edges | Normal | Build | Dev |\n");
+    output.push_str("|-------:|------------------:|------:|-------:|------:|----:|\n");
+    let kind_count = |kind: DepKind| graph.edge_weights().filter(|kinds| kinds.contains(kind)).count();
+    output.push_str(&format!(
+        "| {} | {} | {} | {} | {} | {} |\n\n",
+        graph.node_count(),
+        members,
+        graph.edge_count(),
+        kind_count(DepKind::Normal),
+        kind_count(DepKind::Build),
+        kind_count(DepKind::Dev),
+    ));
+
+    push_gh_diagram(&mut output, graph.node_count(), graph.edge_count(), args.no_fence, || generate_deps_mermaid(graph_data, args));
+
+    let mut rows: Vec<NodeIndex> = graph.node_indices().collect();
+    rows.sort_by(|&a, &b| graph[a].name.cmp(&graph[b].name).then_with(|| graph[a].version.cmp(&graph[b].version)));
+    let rows: Vec<String> = rows.into_iter()
+        .map(|idx| {
+            let info = &graph[idx];
+            format!(
+                "| {}{} | {} | {} | {} |",
+                markdown_cell(&info.name),
+                if info.is_workspace_member { " (workspace)" } else { "" },
+                info.version,
+                info.license.as_deref().map(markdown_cell).unwrap_or_else(|| "-".to_string()),
+                graph.neighbors_directed(idx, Direction::Incoming).count(),
+            )
+        })
+        .collect();
+    let header = match max_depth {
+        Some(depth) => format!("Crates ({}, depth up to {})", rows.len(), depth),
+        None => format!("Crates ({})", rows.len()),
+    };
+    push_gh_table(&mut output, &header, "| Crate | Version | License | Used by |\n|-------|---------|---------|--------:|", &rows);
+
+    truncate_gh_summary(output)
+}
+
+/// `generate_deps_gh_summary` for functions, with a table of callers and callees per function
+pub fn generate_fn_gh_summary(graph_data: &FnGraphData, args: &FnGraphArgs) -> String {
+    let graph = &graph_data.graph;
+    let public = graph.node_weights().filter(|info| info.is_public).count();
+    let entry_points = graph.node_weights().filter(|info| info.is_entry_point).count();
+    let files: BTreeSet<&str> = graph.node_weights().map(|info| info.file_path.as_str()).collect();
+
+    let mut output = String::from("### Function call graph\n\n");
+    output.push_str("| Functions | Public | Entry points | Calls | Files |\n");
+    output.push_str("|----------:|-------:|-------------:|------:|------:|\n");
+    output.push_str(&format!(
+        "| {} | {} | {} | {} | {} |\n\n",
+        graph.node_count(),
+        public,
+        entry_points,
+        graph.edge_count(),
+        files.len(),
+    ));
+
+    push_gh_diagram(&mut output, graph.node_count(), graph.edge_count(), args.no_fence, || generate_fn_mermaid(graph_data, args));
+
+    let mut rows: Vec<NodeIndex> = graph.node_indices().collect();
+    rows.sort_by(|&a, &b| graph[a].qualified_name.cmp(&graph[b].qualified_name));
+    let rows: Vec<String> = rows.into_iter()
+        .map(|idx| {
+            let info = &graph[idx];
+            format!(
+                "| `{}` | {}:{} | {} | {} |",
+                markdown_cell(&info.qualified_name),
+                markdown_cell(&info.file_path),
+                info.line,
+                graph.neighbors_directed(idx, Direction::Incoming).count(),
+                graph.neighbors_directed(idx, Direction::Outgoing).count(),
+            )
+        })
+        .collect();
+    let header = format!("Functions ({})", rows.len());
+    push_gh_table(&mut output, &header, "| Function | Location | Callers | Callees |\n|----------|----------|--------:|--------:|", &rows);
+
+    truncate_gh_summary(output)
+}
+
+/// Fenced Mermaid block, or a note when there is nothing to draw or GitHub would refuse to render it
+fn push_gh_diagram(output: &mut String, nodes: usize, edges: usize, no_fence: bool, mermaid: impl FnOnce() -> String) {
+    if nodes == 0 {
+        output.push_str("_The graph is empty._\n");
+        return;
+    }
+    if edges > GH_MERMAID_MAX_EDGES {
+        output.push_str(&format!(
+            "> [!NOTE]\n> Diagram omitted: {} edges, GitHub renders at most {}. Narrow it with --depth, --focus or --filter.\n\n",
+            edges, GH_MERMAID_MAX_EDGES
+        ));
+        return;
+    }
+
+    let mut diagram = mermaid();
+    if no_fence {
+        diagram = format!("```mermaid\n{}```\n", diagram);
+    }
+    if diagram.len() > GH_MERMAID_MAX_CHARS {
+        output.push_str(&format!(
+            "> [!NOTE]\n> Diagram omitted: {} characters of Mermaid, GitHub renders at most {}. Narrow it with --depth, --focus or --filter.\n\n",
+            diagram.len(), GH_MERMAID_MAX_CHARS
+        ));
+        return;
+    }
+    output.push_str(&diagram);
+    output.push('\n');
+}
+
+/// Collapsed table of at most `GH_SUMMARY_MAX_ROWS` rows
+fn push_gh_table(output: &mut String, summary: &str, header: &str, rows: &[String]) {
+    if rows.is_empty() {
+        return;
+    }
+    output.push_str(&format!("<details><summary>{}</summary>\n\n{}\n", summary, header));
+    for row in rows.iter().take(GH_SUMMARY_MAX_ROWS) {
+        output.push_str(row);
+        output.push('\n');
+    }
+    if rows.len() > GH_SUMMARY_MAX_ROWS {
+        output.push_str(&format!("\n_... and {} more_\n", rows.len() - GH_SUMMARY_MAX_ROWS));
+    }
+    output.push_str("\n</details>\n");
+}
+
+/// Cut at the last full line that keeps the summary under GitHub's size limit
+fn truncate_gh_summary(mut output: String) -> String {
+    const NOTICE: &str = "\n_Summary truncated to GitHub's 1 MiB limit._\n";
+    if output.len() <= GH_SUMMARY_MAX_BYTES {
+        return output;
+    }
+    let mut end = GH_SUMMARY_MAX_BYTES - NOTICE.len();
+    while !output.is_char_boundary(end) {
+        end -= 1;
+    }
+    end = output[..end].rfind('\n').map_or(0, |pos| pos + 1);
+    output.truncate(end);
+    output.push_str(NOTICE);
+    output
+}
+
+// ============================================================================
+// Index Generators
+// ============================================================================
+
+pub fn generate_deps_index(graph_data: &GraphData, args: &DepsArgs) -> String {
+    let ids = deps_node_ids(graph_data, args, uses_mermaid_ids(&args.format));
+    let mut rows: Vec<(String, &NodeInfo)> = graph_data.graph.node_indices()
+        .map(|idx| (ids.get(idx).to_string(), &graph_data.graph[idx]))
+        .collect();
+    rows.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.version.cmp(&b.1.version)));
+
+    let mut output = String::new();
+    output.push_str("# Dependency Index\n\n");
+    output.push_str("| ID | Crate | Version | License | Description | Links |\n");
+    output.push_str("|----|-------|---------|---------|-------------|-------|\n");
+
+    for (id, info) in rows {
+        let links: Vec<String> = [
+            ("repository", &info.repository),
+            ("docs", &info.documentation),
+            ("homepage", &info.homepage),
+        ]
+        .into_iter()
+        .filter_map(|(label, url)| url.as_ref().map(|url| format!("[{}]({})", label, url)))
+        .collect();
+
+        output.push_str(&format!(
+            "| `{}` | {}{} | {} | {} | {} | {} |\n",
+            id,
+            markdown_cell(&info.name),
+            if info.is_workspace_member { " (workspace)" } else { "" },
+            info.version,
+            info.license.as_deref().map(markdown_cell).unwrap_or_else(|| "-".to_string()),
+            info.description.as_deref().map(markdown_cell).unwrap_or_default(),
+            links.join(" "),
+        ));
+    }
+
+    output
+}
+
+pub fn generate_fn_index(graph_data: &FnGraphData, args: &FnGraphArgs) -> String {
+    let ids = fn_node_ids(graph_data, args, uses_mermaid_ids(&args.format));
+    let mut rows: Vec<(String, &FnNodeInfo)> = graph_data.graph.node_indices()
+        .map(|idx| (ids.get(idx).to_string(), &graph_data.graph[idx]))
+        .collect();
+    rows.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.qualified_name.cmp(&b.1.qualified_name)));
+
+    let mut output = String::new();
+    output.push_str("# Function Index\n\n");
+    output.push_str("| ID | Function | File | Visibility | Signature |\n");
+    output.push_str("|----|----------|------|------------|-----------|\n");
+
+    for (id, info) in rows {
+        let visibility = match (info.is_public, info.is_async) {
+            (true, true) => "pub async",
+            (true, false) => "pub",
+            (false, true) => "async",
+            (false, false) => "private",
+        };
+
+        output.push_str(&format!(
+            "| `{}` | {} | {} | {} | {} |\n",
+            id,
+            markdown_cell(&info.qualified_name),
+            markdown_cell(&info.file_path),
+            visibility,
+            info.signature.as_deref().map(|sig| format!("`{}`", markdown_cell(sig))).unwrap_or_default(),
+        ));
+    }
+
+    output
+}
+
+/// Whether `format` draws nodes with Mermaid IDs (`deps_node_ids`), as opposed to DOT/JSON ones
+pub fn uses_mermaid_ids(format: &OutputFormat) -> bool {
+    matches!(format, OutputFormat::Mermaid | OutputFormat::GhSummary)
+}
+
+pub fn markdown_cell(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").replace('|', "\\|")
+}
+
+// ============================================================================
+// Feature Graph - Output Generators (explain-features)
+// ============================================================================
+
+fn feature_node_id(node: &FeatureNode) -> String {
+    let id = match node.feature {
+        Some(ref feature) => format!("{}__{}", node.package, feature),
+        None => node.package.clone(),
+    };
+    id.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect()
+}
+
+/// `name version` for packages, `name/feature` for features
+fn feature_node_label(node: &FeatureNode) -> String {
+    let (name, version) = node.package.split_once('@').unwrap_or((&node.package, ""));
+    match node.feature {
+        Some(ref feature) => format!("{}/{}", name, feature),
+        None => format!("{} {}", name, version),
+    }
+}
+
+pub fn generate_features_mermaid(graph: &FeatureGraph, args: &ExplainFeaturesArgs) -> String {
+    let mut output = mermaid_header(args.no_fence, &args.direction, &args.theme, args.mermaid_version);
+
+    // Packages are boxes, features rounded
+    for node in graph.node_weights() {
+        let label = feature_node_label(node).replace('"', "'");
+        match node.feature {
+            Some(_) => output.push_str(&format!("    {}(\"{}\")\n", feature_node_id(node), label)),
+            None => output.push_str(&format!("    {}[\"{}\"]\n", feature_node_id(node), label)),
+        }
+    }
+
+    for edge in graph.edge_indices() {
+        if let Some((from, to)) = graph.edge_endpoints(edge) {
+            let arrow = match graph[edge] {
+                FeatureEdge::Requests => "-->",
+                FeatureEdge::Enables => "-.->",
+            };
+            output.push_str(&format!("    {} {} {}\n", feature_node_id(&graph[from]), arrow, feature_node_id(&graph[to])));
+        }
+    }
+
+    let targets: Vec<String> = graph.node_weights()
+        .filter(|node| node.is_target && node.feature.is_some())
+        .map(feature_node_id)
+        .collect();
+    let targets: Vec<&str> = targets.iter().map(String::as_str).collect();
+    output.push_str(&mermaid_footer(args.no_fence, &targets));
+    output
+}
+
+pub fn generate_features_dot(graph: &FeatureGraph, args: &ExplainFeaturesArgs) -> String {
+    let mut output = dot_header("features", &args.theme);
+
+    for node in graph.node_weights() {
+        let mut attrs = vec![format!("label=\"{}\"", feature_node_label(node).replace('"', "\\\""))];
+        if node.feature.is_some() {
+            attrs.push("shape=ellipse".to_string());
+        }
+        if node.is_target && node.feature.is_some() {
+            attrs.push("fillcolor=\"#ff99ff\"".to_string());
+            attrs.push("style=filled".to_string());
+        }
+        if node.is_workspace_member && node.feature.is_none() {
+            attrs.push("penwidth=2".to_string());
+        }
+        output.push_str(&format!("    {} [{}];\n", feature_node_id(node), attrs.join(", ")));
+    }
+
+    for edge in graph.edge_indices() {
+        if let Some((from, to)) = graph.edge_endpoints(edge) {
+            let style = match graph[edge] {
+                FeatureEdge::Requests => "",
+                FeatureEdge::Enables => " [style=dashed]",
+            };
+            output.push_str(&format!("    {} -> {}{};\n", feature_node_id(&graph[from]), feature_node_id(&graph[to]), style));
+        }
+    }
+
+    output.push_str("}\n");
+    output
+}
+
+pub fn generate_features_json(graph: &FeatureGraph) -> String {
+    let nodes: Vec<serde_json::Value> = graph.node_weights()
+        .map(|node| serde_json::json!({
+            "id": feature_node_id(node),
+            "package": node.package,
+            "feature": node.feature,
+            "is_workspace_member": node.is_workspace_member,
+            "is_target": node.is_target
+        }))
+        .collect();
+
+    let edges: Vec<serde_json::Value> = graph.edge_indices()
+        .filter_map(|edge| {
+            let (from, to) = graph.edge_endpoints(edge)?;
+            Some(serde_json::json!({
+                "from": feature_node_id(&graph[from]),
+                "to": feature_node_id(&graph[to]),
+                "kind": match graph[edge] {
+                    FeatureEdge::Requests => "requests",
+                    FeatureEdge::Enables => "enables",
+                }
+            }))
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&serde_json::json!({
+        "nodes": nodes,
+        "edges": edges
+    }))
+    .unwrap_or_else(|_| "{}".to_string())
+}
+
+// ============================================================================
+// Config Usage Map - Output Generators (fn-graph --config-map)
+// ============================================================================
+
+fn config_node_id(read: &ConfigRead) -> String {
+    format!("{}_{}", read.kind.as_str(), sanitize_name(&read.name.replace("::", "__")))
+}
+
+/// Every config item read by a function of the graph, sorted, with the functions reading it
+fn config_readers(graph_data: &FnGraphData) -> Vec<(&ConfigRead, Vec<NodeIndex>)> {
+    let mut readers: BTreeMap<&ConfigRead, Vec<NodeIndex>> = BTreeMap::new();
+    for idx in graph_data.graph.node_indices() {
+        for read in &graph_data.graph[idx].config {
+            readers.entry(read).or_default().push(idx);
+        }
+    }
+    readers.into_iter().collect()
+}
+
+pub fn generate_config_map_mermaid(graph_data: &FnGraphData, args: &FnGraphArgs) -> String {
+    let mut output = mermaid_header(args.no_fence, &args.direction, &args.theme, args.mermaid_version);
+    let ids = fn_node_ids(graph_data, args, true);
+    let readers = config_readers(graph_data);
+
+    // Config items are hexagons, functions boxes
+    for (read, _) in &readers {
+        output.push_str(&format!("    {}{{{{\"{} {}\"}}}}\n", config_node_id(read), read.kind.as_str(), read.name.replace('"', "'")));
+    }
+    let mut functions: Vec<NodeIndex> = readers.iter().flat_map(|(_, idxs)| idxs.iter().copied()).collect();
+    functions.sort();
+    functions.dedup();
+    for &idx in &functions {
+        output.push_str(&format!("    {}[\"{}\"]\n", ids.get(idx), graph_data.graph[idx].qualified_name));
+    }
+
+    for (read, idxs) in &readers {
+        for &idx in idxs {
+            output.push_str(&format!("    {} --> {}\n", config_node_id(read), ids.get(idx)));
+        }
+    }
+
+    output.push_str(&mermaid_footer(args.no_fence, &[]));
+    output
+}
+
+pub fn generate_config_map_dot(graph_data: &FnGraphData, args: &FnGraphArgs) -> String {
+    let mut output = dot_header("config_map", &args.theme);
+    let ids = fn_node_ids(graph_data, args, false);
+    let readers = config_readers(graph_data);
+
+    for (read, _) in &readers {
+        output.push_str(&format!(
+            "    {} [label=\"{} {}\", shape=hexagon];\n",
+            config_node_id(read), read.kind.as_str(), read.name.replace('"', "\\\"")
+        ));
+    }
+    let mut functions: Vec<NodeIndex> = readers.iter().flat_map(|(_, idxs)| idxs.iter().copied()).collect();
+    functions.sort();
+    functions.dedup();
+    for &idx in &functions {
+        output.push_str(&format!("    {} [label=\"{}\"];\n", ids.quoted(idx), graph_data.graph[idx].qualified_name));
+    }
+
+    for (read, idxs) in &readers {
+        for &idx in idxs {
+            output.push_str(&format!("    {} -> {};\n", config_node_id(read), ids.quoted(idx)));
+        }
+    }
+
+    output.push_str("}\n");
+    output
+}
+
+pub fn generate_config_map_json(graph_data: &FnGraphData, args: &FnGraphArgs) -> String {
+    let ids = fn_node_ids(graph_data, args, false);
+    let config: Vec<serde_json::Value> = config_readers(graph_data).into_iter()
+        .map(|(read, idxs)| {
+            let functions: Vec<serde_json::Value> = idxs.iter()
+                .map(|&idx| {
+                    let info = &graph_data.graph[idx];
+                    serde_json::json!({
+                        "id": ids.get(idx),
+                        "qualified_name": info.qualified_name,
+                        "file_path": info.file_path,
+                        "line": info.line
+                    })
+                })
+                .collect();
+            serde_json::json!({
+                "id": config_node_id(read),
+                "name": read.name,
+                "kind": read.kind.as_str(),
+                "read_by": functions
+            })
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&serde_json::json!({ "config": config }))
+        .unwrap_or_else(|_| "{}".to_string())
+}
+
+// ============================================================================
+// Type Usage Map - Output Generators (fn-graph --type-map)
+// ============================================================================
+
+fn type_node_id(name: &str) -> String {
+    format!("type_{}", sanitize_name(&name.replace("::", "__")))
+}
+
+/// Every type in a signature of the graph, sorted, with the functions using it
+fn type_users(graph_data: &FnGraphData) -> Vec<(&str, Vec<NodeIndex>)> {
+    let mut users: BTreeMap<&str, Vec<NodeIndex>> = BTreeMap::new();
+    for idx in graph_data.graph.node_indices() {
+        for name in &graph_data.graph[idx].types {
+            users.entry(name).or_default().push(idx);
+        }
+    }
+    users.into_iter().collect()
+}
+
+fn function_count(count: usize) -> String {
+    format!("{} function{}", count, if count == 1 { "" } else { "s" })
+}
+
+pub fn generate_type_map_mermaid(graph_data: &FnGraphData, args: &FnGraphArgs) -> String {
+    let mut output = mermaid_header(args.no_fence, &args.direction, &args.theme, args.mermaid_version);
+    let ids = fn_node_ids(graph_data, args, true);
+    let users = type_users(graph_data);
+
+    // Types are subroutine shapes with their number of users, functions boxes
+    for (name, idxs) in &users {
+        output.push_str(&format!("    {}[[\"{}<br/>{}\"]]\n", type_node_id(name), name, function_count(idxs.len())));
+    }
+    let mut functions: Vec<NodeIndex> = users.iter().flat_map(|(_, idxs)| idxs.iter().copied()).collect();
+    functions.sort();
+    functions.dedup();
+    for &idx in &functions {
+        output.push_str(&format!("    {}[\"{}\"]\n", ids.get(idx), graph_data.graph[idx].qualified_name));
+    }
+
+    for (name, idxs) in &users {
+        for &idx in idxs {
+            output.push_str(&format!("    {} --> {}\n", ids.get(idx), type_node_id(name)));
+        }
+    }
+
+    output.push_str(&mermaid_footer(args.no_fence, &[]));
+    output
+}
+
+pub fn generate_type_map_dot(graph_data: &FnGraphData, args: &FnGraphArgs) -> String {
+    let mut output = dot_header("type_map", &args.theme);
+    let ids = fn_node_ids(graph_data, args, false);
+    let users = type_users(graph_data);
+
+    for (name, idxs) in &users {
+        output.push_str(&format!(
+            "    {} [label=\"{}\\n{}\", shape=component];\n",
+            type_node_id(name), name, function_count(idxs.len())
+        ));
+    }
+    let mut functions: Vec<NodeIndex> = users.iter().flat_map(|(_, idxs)| idxs.iter().copied()).collect();
+    functions.sort();
+    functions.dedup();
+    for &idx in &functions {
+        output.push_str(&format!("    {} [label=\"{}\"];\n", ids.quoted(idx), graph_data.graph[idx].qualified_name));
+    }
+
+    for (name, idxs) in &users {
+        for &idx in idxs {
+            output.push_str(&format!("    {} -> {};\n", ids.quoted(idx), type_node_id(name)));
+        }
+    }
+
+    output.push_str("}\n");
+    output
+}
+
+pub fn generate_type_map_json(graph_data: &FnGraphData, args: &FnGraphArgs) -> String {
+    let ids = fn_node_ids(graph_data, args, false);
+    let types: Vec<serde_json::Value> = type_users(graph_data).into_iter()
+        .map(|(name, idxs)| {
+            let functions: Vec<serde_json::Value> = idxs.iter()
+                .map(|&idx| {
+                    let info = &graph_data.graph[idx];
+                    serde_json::json!({
+                        "id": ids.get(idx),
+                        "qualified_name": info.qualified_name,
+                        "file_path": info.file_path,
+                        "line": info.line
+                    })
+                })
+                .collect();
+            serde_json::json!({
+                "id": type_node_id(name),
+                "name": name,
+                "used_by": functions
+            })
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&serde_json::json!({ "types": types }))
+        .unwrap_or_else(|_| "{}".to_string())
 }
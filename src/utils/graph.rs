@@ -0,0 +1,794 @@
+// ============================================================================
+// Generic Graph Model
+// ============================================================================
+//
+// `Graph<K, N, E>` backs both the dependency graph (`GraphData`) and the
+// function graph (`FnGraphData`). Node types describe themselves through
+// `GraphNode`, so the filters below are written once for every graph kind.
+
+use std::{collections::{hash_map::Entry, BTreeMap, HashMap, HashSet, VecDeque}, hash::Hash};
+
+use petgraph::{graph::{DiGraph, EdgeIndex, NodeIndex}, graphmap::DiGraphMap, visit::EdgeRef, Direction};
+
+use crate::{types::{AppliedFilter, Capability, FnNodeInfo, Graph, NodeInfo}, utils::{filter_expr::{FilterExpr, FilterValue}, helper::{fnv1a_64, sanitize_name, source_kind}}};
+
+/// `--filter` attributes computed from the graph itself rather than the node,
+/// plus `metric.<name>` for values attached by `utils::metrics`
+const STRUCTURAL_ATTRIBUTES: &[&str] = &["depth", "fan_in", "fan_out", "metric.*"];
+
+/// Typed attribute bag of a node
+pub trait GraphNode {
+    /// Node-specific `--filter` attributes
+    const ATTRIBUTES: &'static [&'static str];
+
+    /// Value of one of `ATTRIBUTES`, `None` when the node has no value for it
+    fn attribute(&self, name: &str) -> Option<FilterValue>;
+
+    /// Whether `--focus <focus>` selects this node
+    fn matches_focus(&self, focus: &str) -> bool;
+
+    /// Identifier that stays the same across runs and diagram ID changes: `pkg:cargo/<name>@<version>`
+    /// for crates, `fn:<qualified name>#<file hash>` for functions. It is the node `id` of JSON and
+    /// DOT output, and what `--metric-script`, detail pages and `combine` match nodes by.
+    fn id(&self) -> String;
+
+    /// Custom per-node metrics, by name
+    fn metrics(&self) -> &BTreeMap<String, f64>;
+    fn metrics_mut(&mut self) -> &mut BTreeMap<String, f64>;
+}
+
+impl<K, N, E> Default for Graph<K, N, E> {
+    fn default() -> Self {
+        Graph { graph: DiGraph::new(), node_indices: HashMap::new(), filters: Vec::new() }
+    }
+}
+
+impl<K: Eq + Hash, N, E> Graph<K, N, E> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy of the graph with only the nodes for which `keep` returns true (and the
+    /// edges between them), in one pass; `node_indices` is remapped to the new indices
+    pub fn filter_nodes(&self, keep: impl Fn(NodeIndex) -> bool) -> Self
+    where
+        K: Clone,
+        N: Clone,
+        E: Clone,
+    {
+        let graph = self.graph.filter_map(
+            |idx, node| keep(idx).then(|| node.clone()),
+            |_, edge| Some(edge.clone()),
+        );
+
+        // filter_map keeps the relative order of surviving nodes
+        let remapped: HashMap<NodeIndex, NodeIndex> = self.graph.node_indices()
+            .filter(|&idx| keep(idx))
+            .enumerate()
+            .map(|(new, old)| (old, NodeIndex::new(new)))
+            .collect();
+        let node_indices = self.node_indices.iter()
+            .filter_map(|(key, old)| Some((key.clone(), *remapped.get(old)?)))
+            .collect();
+
+        Graph { graph, node_indices, filters: self.filters.clone() }
+    }
+
+    /// Run `filter` on the graph and record it for the filter legend, with what it removed
+    pub fn apply_filter<T>(&mut self, description: String, filter: impl FnOnce(&mut Self) -> T) -> T {
+        let (nodes, edges) = (self.graph.node_count(), self.graph.edge_count());
+        let result = filter(self);
+        let removed = (nodes.saturating_sub(self.graph.node_count()), edges.saturating_sub(self.graph.edge_count()));
+        self.filters.push(AppliedFilter { description, removed: Some(removed) });
+        result
+    }
+
+    /// Record a filter applied while the graph was built, whose effect isn't known
+    pub fn note_filter(&mut self, description: String) {
+        self.filters.push(AppliedFilter { description, removed: None });
+    }
+
+    /// Remove every node for which `keep` returns false.
+    ///
+    /// `DiGraph::remove_node` moves the last node into the freed slot, which would leave
+    /// `node_indices` pointing at the wrong nodes, so the graph is rebuilt instead and
+    /// surviving entries are remapped (removed ones are dropped).
+    pub fn retain_nodes(&mut self, keep: impl Fn(NodeIndex) -> bool) {
+        let (nodes, edges) = std::mem::take(&mut self.graph).into_nodes_edges();
+
+        let mut remapped = vec![None; nodes.len()];
+        for (i, node) in nodes.into_iter().enumerate() {
+            if keep(NodeIndex::new(i)) {
+                remapped[i] = Some(self.graph.add_node(node.weight));
+            }
+        }
+        for edge in edges {
+            if let (Some(from), Some(to)) = (remapped[edge.source().index()], remapped[edge.target().index()]) {
+                self.graph.add_edge(from, to, edge.weight);
+            }
+        }
+
+        self.node_indices.retain(|_, idx| match remapped.get(idx.index()).copied().flatten() {
+            Some(new) => {
+                *idx = new;
+                true
+            }
+            None => false,
+        });
+    }
+
+    /// Index of the node stored under `key`
+    pub fn index_of(&self, key: &K) -> Option<NodeIndex> {
+        self.node_indices.get(key).copied()
+    }
+
+    /// The node stored under `key`
+    pub fn node(&self, key: &K) -> Option<&N> {
+        self.index_of(key).map(|idx| &self.graph[idx])
+    }
+
+    /// Every node with its index, in index order
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeIndex, &N)> {
+        self.graph.node_indices().map(|idx| (idx, &self.graph[idx]))
+    }
+
+    /// Every edge as (source, target, weight)
+    pub fn edges(&self) -> impl Iterator<Item = (&N, &N, &E)> {
+        self.graph.edge_indices().filter_map(|edge| {
+            let (from, to) = self.graph.edge_endpoints(edge)?;
+            Some((&self.graph[from], &self.graph[to], &self.graph[edge]))
+        })
+    }
+
+    /// Direct neighbors of the node stored under `key`: what it points to with
+    /// `Direction::Outgoing`, what points to it with `Direction::Incoming`
+    pub fn neighbors(&self, key: &K, direction: Direction) -> impl Iterator<Item = &N> {
+        self.index_of(key).into_iter()
+            .flat_map(move |idx| self.graph.neighbors_directed(idx, direction))
+            .map(|idx| &self.graph[idx])
+    }
+}
+
+impl<K, N: GraphNode, E> Graph<K, N, E> {
+    /// The node whose `stable_id` is `id`
+    pub fn node_by_stable_id(&self, id: u64) -> Option<(NodeIndex, &N)> {
+        self.graph.node_indices().find(|&idx| stable_id(&self.graph[idx]) == id).map(|idx| (idx, &self.graph[idx]))
+    }
+}
+
+/// Identifier of a node that is the same in every run and across filters: the hash
+/// of `GraphNode::id`, also used for detail page file names
+pub fn stable_id<N: GraphNode>(node: &N) -> u64 {
+    fnv1a_64(node.id().as_bytes())
+}
+
+/// The graph as a `DiGraphMap` keyed by `stable_id`, for petgraph algorithms that
+/// want copyable node identifiers; look nodes up again with `node_by_stable_id`
+impl<K, N: GraphNode, E: Clone> From<&Graph<K, N, E>> for DiGraphMap<u64, E> {
+    fn from(graph_data: &Graph<K, N, E>) -> Self {
+        let graph = &graph_data.graph;
+        let mut map = DiGraphMap::with_capacity(graph.node_count(), graph.edge_count());
+        for node in graph.node_weights() {
+            map.add_node(stable_id(node));
+        }
+        for edge in graph.edge_indices() {
+            if let Some((from, to)) = graph.edge_endpoints(edge) {
+                map.add_edge(stable_id(&graph[from]), stable_id(&graph[to]), graph[edge].clone());
+            }
+        }
+        map
+    }
+}
+
+/// Every attribute `--filter` accepts for nodes of type `N`
+pub fn filter_attributes<N: GraphNode>() -> Vec<&'static str> {
+    N::ATTRIBUTES.iter().chain(STRUCTURAL_ATTRIBUTES).copied().collect()
+}
+
+/// Keep only nodes matching `expr`; `depth` is measured from `roots`
+pub fn filter_by_expr<K: Eq + Hash, N: GraphNode, E>(graph_data: &mut Graph<K, N, E>, expr: &FilterExpr, roots: &[NodeIndex]) {
+    let depths = compute_depths(&graph_data.graph, roots);
+    let graph = &graph_data.graph;
+
+    let keep: HashSet<NodeIndex> = graph
+        .node_indices()
+        .filter(|&idx| {
+            expr.matches(&|attr| match attr {
+                "depth" => depths.get(&idx).map(|&d| FilterValue::Num(d as f64)),
+                "fan_in" => Some(FilterValue::Num(graph.neighbors_directed(idx, Direction::Incoming).count() as f64)),
+                "fan_out" => Some(FilterValue::Num(graph.neighbors(idx).count() as f64)),
+                attr => match attr.strip_prefix("metric.") {
+                    Some(metric) => graph[idx].metrics().get(metric).map(|&value| FilterValue::Num(value)),
+                    None => graph[idx].attribute(attr),
+                },
+            })
+        })
+        .collect();
+
+    graph_data.retain_nodes(|idx| keep.contains(&idx));
+}
+
+/// Nodes selected by `--focus`
+pub fn focus_nodes<N: GraphNode, E>(graph: &DiGraph<N, E>, focus: &str) -> Vec<NodeIndex> {
+    graph.node_indices().filter(|&idx| graph[idx].matches_focus(focus)).collect()
+}
+
+/// Metric set by `extract_focus`: edges between a node and the nearest focus node
+pub const HOPS: &str = "hops";
+
+/// New graph holding the focus nodes and everything connected to them in either
+/// direction, at most `max_depth` edges away (0 = unlimited); `None` if nothing matches `focus`.
+/// Each node gets its distance from the focus as the `hops` metric.
+///
+/// The input is left untouched and the result's `node_indices` point into the new graph.
+pub fn extract_focus<K, N, E>(graph_data: &Graph<K, N, E>, focus: &str, max_depth: usize) -> Option<Graph<K, N, E>>
+where
+    K: Eq + Hash + Clone,
+    N: GraphNode + Clone,
+    E: Clone,
+{
+    let start = focus_nodes(&graph_data.graph, focus);
+    if start.is_empty() {
+        return None;
+    }
+
+    let distances = connected_within(&graph_data.graph, &start, max_depth);
+    let mut focused = graph_data.filter_nodes(|idx| distances.contains_key(&idx));
+
+    // filter_nodes keeps the relative order of surviving nodes
+    let kept = graph_data.graph.node_indices().filter_map(|idx| distances.get(&idx));
+    for (new, &hops) in focused.graph.node_indices().zip(kept) {
+        focused.graph[new].metrics_mut().insert(HOPS.to_string(), hops as f64);
+    }
+    Some(focused)
+}
+
+/// In-place `extract_focus`; without a match the graph is left alone
+pub fn filter_by_focus<K, N, E>(graph_data: &mut Graph<K, N, E>, focus: &str, max_depth: usize)
+where
+    K: Eq + Hash + Clone,
+    N: GraphNode + Clone,
+    E: Clone,
+{
+    if let Some(focused) = extract_focus(graph_data, focus, max_depth) {
+        *graph_data = focused;
+    }
+}
+
+/// Keep only nodes on some path from a `from` node to a `to` node (both in
+/// `--focus` syntax): reachable from `from` and reaching `to`. Errors name the
+/// side that matches nothing.
+pub fn slice_between<K: Eq + Hash, N: GraphNode, E>(graph_data: &mut Graph<K, N, E>, from: &str, to: &str) -> Result<(), String> {
+    let graph = &graph_data.graph;
+    let sources = focus_nodes(graph, from);
+    if sources.is_empty() {
+        return Err(format!("--slice-from '{}' matches no node", from));
+    }
+    let targets = focus_nodes(graph, to);
+    if targets.is_empty() {
+        return Err(format!("--slice-to '{}' matches no node", to));
+    }
+
+    let forward = reachable_from(graph, &sources, Direction::Outgoing);
+    let backward = reachable_from(graph, &targets, Direction::Incoming);
+    graph_data.retain_nodes(|idx| forward.contains(&idx) && backward.contains(&idx));
+    Ok(())
+}
+
+/// Once the filters have left no edges, which of them removed what, in the order applied;
+/// `None` while edges remain or when there were none to remove
+pub fn empty_graph_explanation<K, N, E>(graph_data: &Graph<K, N, E>) -> Option<String> {
+    let graph = &graph_data.graph;
+    if graph.edge_count() > 0 {
+        return None;
+    }
+    let (removed_nodes, removed_edges) = graph_data.filters.iter()
+        .filter_map(|filter| filter.removed)
+        .fold((0, 0), |(n, e), (nodes, edges)| (n + nodes, e + edges));
+    if removed_edges == 0 && (removed_nodes == 0 || graph.node_count() > 0) {
+        return None;
+    }
+
+    let (mut nodes, mut edges) = (graph.node_count() + removed_nodes, removed_edges);
+    let mut lines = vec![format!(
+        "the filters left {} nodes and no edges (of {} nodes and {} edges); nothing to draw",
+        graph.node_count(), nodes, edges
+    )];
+    for filter in &graph_data.filters {
+        match filter.removed {
+            Some((removed_nodes, removed_edges)) => {
+                nodes -= removed_nodes;
+                edges -= removed_edges;
+                lines.push(format!(
+                    "  {}: removed {} nodes and {} edges, leaving {} nodes and {} edges",
+                    filter.description, removed_nodes, removed_edges, nodes, edges
+                ));
+            }
+            None => lines.push(format!("  {}: applied while building the graph (not counted)", filter.description)),
+        }
+    }
+    Some(lines.join("\n"))
+}
+
+/// Reverse every edge, so that arrows point at dependents (callers); with `target` (in
+/// `--focus` syntax), keep only its matches and the nodes that reach them
+pub fn invert<K: Eq + Hash, N: GraphNode, E>(graph_data: &mut Graph<K, N, E>, target: Option<&str>) -> Result<(), String> {
+    if let Some(target) = target {
+        let start = focus_nodes(&graph_data.graph, target);
+        if start.is_empty() {
+            return Err(format!("--invert '{}' matches no node", target));
+        }
+        let dependents = reachable_from(&graph_data.graph, &start, Direction::Incoming);
+        graph_data.retain_nodes(|idx| dependents.contains(&idx));
+    }
+    graph_data.graph.reverse();
+    Ok(())
+}
+
+/// `start` and every node reachable from it following edges in `direction`
+pub fn reachable_from<N, E>(graph: &DiGraph<N, E>, start: &[NodeIndex], direction: Direction) -> HashSet<NodeIndex> {
+    let mut seen: HashSet<NodeIndex> = start.iter().copied().collect();
+    let mut stack: Vec<NodeIndex> = start.to_vec();
+    while let Some(idx) = stack.pop() {
+        for neighbor in graph.neighbors_directed(idx, direction) {
+            if seen.insert(neighbor) {
+                stack.push(neighbor);
+            }
+        }
+    }
+    seen
+}
+
+/// One cycle through each strongly connected component (Tarjan) among the nodes `keep`
+/// accepts, as its nodes in order; the edge from the last back to the first closes it
+pub fn cycles_among<N, E>(graph: &DiGraph<N, E>, keep: impl Fn(NodeIndex) -> bool) -> Vec<Vec<NodeIndex>> {
+    let sub = graph.filter_map(|idx, _| keep(idx).then_some(idx), |_, _| Some(()));
+
+    let mut cycles = Vec::new();
+    for component in petgraph::algo::tarjan_scc(&sub) {
+        let start = component[0];
+        if component.len() == 1 && sub.find_edge(start, start).is_none() {
+            continue;
+        }
+        // Shortest way around, breadth first inside the component
+        let mut parent: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+        let mut queue: VecDeque<NodeIndex> = VecDeque::from([start]);
+        'search: while let Some(idx) = queue.pop_front() {
+            for next in sub.neighbors(idx).filter(|next| component.contains(next)) {
+                if next == start {
+                    let mut cycle = vec![sub[idx]];
+                    let mut current = idx;
+                    while let Some(&previous) = parent.get(&current) {
+                        cycle.push(sub[previous]);
+                        current = previous;
+                    }
+                    cycle.reverse();
+                    cycles.push(cycle);
+                    break 'search;
+                }
+                if let Entry::Vacant(entry) = parent.entry(next) {
+                    entry.insert(idx);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    cycles
+}
+
+/// Shrink the graph to `size` nodes for a quick preview: the best-connected half,
+/// then random neighbors of kept nodes so the hubs stay linked. The random picks
+/// use a fixed seed, so the same graph always gives the same sample.
+pub fn sample_graph<K: Eq + Hash, N: GraphNode, E>(graph_data: &mut Graph<K, N, E>, size: usize) {
+    let graph = &graph_data.graph;
+    if graph.node_count() <= size {
+        return;
+    }
+
+    let degree = |idx: NodeIndex| graph.neighbors_undirected(idx).count();
+    let mut by_degree: Vec<NodeIndex> = graph.node_indices().collect();
+    by_degree.sort_by(|&a, &b| degree(b).cmp(&degree(a)).then_with(|| graph[a].id().cmp(&graph[b].id())));
+
+    let mut keep: HashSet<NodeIndex> = HashSet::new();
+    let mut frontier: Vec<NodeIndex> = Vec::new();
+    let mut hubs = by_degree.into_iter();
+    let mut rng = fnv1a_64(b"rust-grapher --sample");
+
+    while keep.len() < size {
+        let next = if keep.len() < size.div_ceil(2) || frontier.is_empty() {
+            // Hubs first; later also whenever the kept nodes have no unvisited neighbors
+            match hubs.next() {
+                Some(idx) => idx,
+                None => break,
+            }
+        } else {
+            // xorshift64
+            rng ^= rng << 13;
+            rng ^= rng >> 7;
+            rng ^= rng << 17;
+            frontier.swap_remove((rng % frontier.len() as u64) as usize)
+        };
+        if keep.insert(next) {
+            frontier.extend(graph.neighbors_undirected(next).filter(|idx| !keep.contains(idx)));
+        }
+    }
+
+    graph_data.retain_nodes(|idx| keep.contains(&idx));
+}
+
+/// Keep at most `max` outgoing edges per node, for readability of dense graphs: the heaviest by
+/// `weight`, then the ones leading to the largest subtrees (ties in edge order)
+pub fn thin_edges<K, N, E>(graph_data: &mut Graph<K, N, E>, max: usize, weight: impl Fn(&E) -> u64) {
+    let graph = &graph_data.graph;
+    let mut subtree_sizes: HashMap<NodeIndex, usize> = HashMap::new();
+    let mut keep: HashSet<EdgeIndex> = HashSet::new();
+
+    for idx in graph.node_indices() {
+        let mut edges: Vec<_> = graph.edges(idx).collect();
+        if edges.len() > max {
+            for edge in &edges {
+                subtree_sizes.entry(edge.target())
+                    .or_insert_with(|| reachable_from(graph, &[edge.target()], Direction::Outgoing).len());
+            }
+            edges.sort_by(|a, b| weight(b.weight()).cmp(&weight(a.weight()))
+                .then_with(|| subtree_sizes[&b.target()].cmp(&subtree_sizes[&a.target()]))
+                .then_with(|| a.id().cmp(&b.id())));
+        }
+        keep.extend(edges.iter().take(max).map(|edge| edge.id()));
+    }
+
+    graph_data.graph.retain_edges(|_, edge| keep.contains(&edge));
+}
+
+/// Remove the leaves (nodes without outgoing edges) `rounds` times over, each round the ones
+/// the previous left behind; stops early once the graph has no leaves
+pub fn prune_leaves<K: Eq + Hash, N, E>(graph_data: &mut Graph<K, N, E>, rounds: usize) {
+    for _ in 0..rounds {
+        let graph = &graph_data.graph;
+        let leaves: HashSet<NodeIndex> = graph.node_indices()
+            .filter(|&idx| graph.neighbors_directed(idx, Direction::Outgoing).next().is_none())
+            .collect();
+        if leaves.is_empty() {
+            break;
+        }
+        graph_data.retain_nodes(|idx| !leaves.contains(&idx));
+    }
+}
+
+/// Repeatedly remove nodes with fewer than `min` incoming edges, until every node left has
+/// enough. The roots (nodes nothing pointed to beforehand) stay, so the core keeps its entry.
+pub fn prune_min_fanin<K: Eq + Hash, N: GraphNode, E>(graph_data: &mut Graph<K, N, E>, min: usize) {
+    let graph = &graph_data.graph;
+    let roots: HashSet<String> = graph.node_indices()
+        .filter(|&idx| graph.neighbors_directed(idx, Direction::Incoming).next().is_none())
+        .map(|idx| graph[idx].id())
+        .collect();
+
+    loop {
+        let graph = &graph_data.graph;
+        let below: HashSet<NodeIndex> = graph.node_indices()
+            .filter(|&idx| graph.edges_directed(idx, Direction::Incoming).count() < min && !roots.contains(&graph[idx].id()))
+            .collect();
+        if below.is_empty() {
+            break;
+        }
+        graph_data.retain_nodes(|idx| !below.contains(&idx));
+    }
+}
+
+/// Keep page `page` (from 1) of the graph cut into pages of `page_size` nodes, returning the
+/// number of pages. Nodes are laid out component by component, largest first, each breadth-first
+/// from its sources in id order, so neighbors mostly share a page and every run cuts the same way.
+pub fn page_graph<K: Eq + Hash, N: GraphNode, E>(graph_data: &mut Graph<K, N, E>, page: usize, page_size: usize) -> Result<usize, String> {
+    let graph = &graph_data.graph;
+    let by_id = |nodes: &mut Vec<NodeIndex>| nodes.sort_by_cached_key(|&idx| graph[idx].id());
+
+    let mut order: Vec<NodeIndex> = Vec::with_capacity(graph.node_count());
+    for component in weak_components(graph) {
+        let mut sources: Vec<NodeIndex> = component.iter().copied()
+            .filter(|&idx| graph.neighbors_directed(idx, Direction::Incoming).next().is_none())
+            .collect();
+        // A component that is one big cycle starts at its first node
+        if sources.is_empty() {
+            sources.push(component[0]);
+        }
+        by_id(&mut sources);
+
+        let mut seen: HashSet<NodeIndex> = sources.iter().copied().collect();
+        let mut queue: VecDeque<NodeIndex> = sources.into_iter().collect();
+        while let Some(idx) = queue.pop_front() {
+            order.push(idx);
+            let mut next: Vec<NodeIndex> = graph.neighbors_undirected(idx).filter(|n| !seen.contains(n)).collect();
+            by_id(&mut next);
+            next.dedup();
+            seen.extend(next.iter().copied());
+            queue.extend(next);
+        }
+    }
+
+    let pages = order.len().div_ceil(page_size.max(1)).max(1);
+    if page == 0 || page > pages {
+        return Err(format!("--page {} is out of range: the graph has {} pages of {} nodes", page, pages, page_size));
+    }
+    let keep: HashSet<NodeIndex> = order.into_iter().skip((page - 1) * page_size).take(page_size).collect();
+    graph_data.retain_nodes(|idx| keep.contains(&idx));
+    Ok(pages)
+}
+
+/// Weakly connected components, largest first (ties in node order)
+pub fn weak_components<N, E>(graph: &DiGraph<N, E>) -> Vec<Vec<NodeIndex>> {
+    let mut seen: HashSet<NodeIndex> = HashSet::new();
+    let mut components: Vec<Vec<NodeIndex>> = Vec::new();
+    for idx in graph.node_indices() {
+        if seen.contains(&idx) {
+            continue;
+        }
+        let mut component: Vec<NodeIndex> = connected_within(graph, &[idx], 0).into_keys().collect();
+        component.sort();
+        seen.extend(component.iter().copied());
+        components.push(component);
+    }
+    components.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a[0].cmp(&b[0])));
+    components
+}
+
+/// Nodes within `max_depth` edges of `start`, following edges both ways (0 = unlimited),
+/// with their distance from it
+fn connected_within<N, E>(graph: &DiGraph<N, E>, start: &[NodeIndex], max_depth: usize) -> HashMap<NodeIndex, usize> {
+    let mut distances: HashMap<NodeIndex, usize> = start.iter().map(|&idx| (idx, 0)).collect();
+    let mut queue: VecDeque<NodeIndex> = start.iter().copied().collect();
+
+    while let Some(idx) = queue.pop_front() {
+        let distance = distances[&idx];
+        if max_depth > 0 && distance >= max_depth {
+            continue;
+        }
+        let neighbors = graph.neighbors_directed(idx, Direction::Outgoing)
+            .chain(graph.neighbors_directed(idx, Direction::Incoming));
+        for neighbor in neighbors {
+            if let Entry::Vacant(entry) = distances.entry(neighbor) {
+                entry.insert(distance + 1);
+                queue.push_back(neighbor);
+            }
+        }
+    }
+
+    distances
+}
+
+/// Shortest distance (in edges) from any of `roots` to every reachable node
+pub fn compute_depths<N, E>(graph: &DiGraph<N, E>, roots: &[NodeIndex]) -> HashMap<NodeIndex, usize> {
+    let mut depths: HashMap<NodeIndex, usize> = HashMap::new();
+    let mut queue: VecDeque<NodeIndex> = VecDeque::new();
+
+    for &root in roots {
+        if depths.insert(root, 0).is_none() {
+            queue.push_back(root);
+        }
+    }
+
+    while let Some(idx) = queue.pop_front() {
+        let depth = depths[&idx];
+        for neighbor in graph.neighbors(idx) {
+            if let Entry::Vacant(entry) = depths.entry(neighbor) {
+                entry.insert(depth + 1);
+                queue.push_back(neighbor);
+            }
+        }
+    }
+
+    depths
+}
+
+// ============================================================================
+// Node Attribute Bags
+// ============================================================================
+
+impl GraphNode for NodeInfo {
+    const ATTRIBUTES: &'static [&'static str] = &["name", "version", "is_workspace", "is_workspace_member", "is_optional", "license", "category", "source", "registry"];
+
+    fn attribute(&self, name: &str) -> Option<FilterValue> {
+        match name {
+            "name" => Some(FilterValue::Str(self.name.clone())),
+            "version" => Some(FilterValue::Str(self.version.clone())),
+            "is_workspace" | "is_workspace_member" => Some(FilterValue::Bool(self.is_workspace_member)),
+            "is_optional" => Some(FilterValue::Bool(self.is_optional)),
+            "license" => self.license.clone().map(FilterValue::Str),
+            "category" => self.category.clone().map(FilterValue::Str),
+            "source" => Some(FilterValue::Str(source_kind(self).name().to_string())),
+            "registry" => self.registry.clone().map(FilterValue::Str),
+            _ => None,
+        }
+    }
+
+    fn matches_focus(&self, focus: &str) -> bool {
+        sanitize_name(&self.name) == sanitize_name(focus)
+    }
+
+    fn id(&self) -> String {
+        match self.name.strip_suffix("-build.rs").filter(|_| self.is_build_script) {
+            Some(package) => format!("pkg:cargo/{}@{}#build-script", package, self.version),
+            None => format!("pkg:cargo/{}@{}", self.name, self.version),
+        }
+    }
+
+    fn metrics(&self) -> &BTreeMap<String, f64> {
+        &self.metrics
+    }
+
+    fn metrics_mut(&mut self) -> &mut BTreeMap<String, f64> {
+        &mut self.metrics
+    }
+}
+
+impl GraphNode for FnNodeInfo {
+    const ATTRIBUTES: &'static [&'static str] = &["name", "qualified_name", "file", "line", "is_public", "is_async", "is_unsafe", "crate", "uses_fs", "uses_net", "uses_process"];
+
+    fn attribute(&self, name: &str) -> Option<FilterValue> {
+        match name {
+            "name" => Some(FilterValue::Str(self.name.clone())),
+            "qualified_name" => Some(FilterValue::Str(self.qualified_name.clone())),
+            "file" => Some(FilterValue::Str(self.file_path.clone())),
+            "line" => Some(FilterValue::Num(self.line as f64)),
+            "is_public" => Some(FilterValue::Bool(self.is_public)),
+            "is_async" => Some(FilterValue::Bool(self.is_async)),
+            "is_unsafe" => Some(FilterValue::Bool(self.is_unsafe)),
+            "crate" => self.crate_name.clone().map(FilterValue::Str),
+            "uses_fs" => Some(FilterValue::Bool(self.capabilities.contains(&Capability::Fs))),
+            "uses_net" => Some(FilterValue::Bool(self.capabilities.contains(&Capability::Net))),
+            "uses_process" => Some(FilterValue::Bool(self.capabilities.contains(&Capability::Process))),
+            _ => None,
+        }
+    }
+
+    fn matches_focus(&self, focus: &str) -> bool {
+        self.name == focus || self.qualified_name == focus
+            || self.qualified_name.ends_with(&format!("::{}", focus))
+    }
+
+    /// The file hash tells apart functions whose qualified names match, e.g. `helper` in two
+    /// files of a `--source-dir` graph
+    fn id(&self) -> String {
+        format!("fn:{}#{:08x}", self.qualified_name, fnv1a_64(self.file_path.as_bytes()) as u32)
+    }
+
+    fn metrics(&self) -> &BTreeMap<String, f64> {
+        &self.metrics
+    }
+
+    fn metrics_mut(&mut self) -> &mut BTreeMap<String, f64> {
+        &mut self.metrics
+    }
+}
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::{types::{DepKind, DepsArgs, GraphData, NodeGroup, PackageId}, utils::generator::{generate_deps_dot, generate_deps_json, generate_deps_mermaid}};
+
+    fn package_id(name: &str) -> PackageId {
+        PackageId { repr: format!("{}@1.0.0", name) }
+    }
+
+    /// app -> web -> core -> serde, app -> cli -> core, web -> tokio
+    fn sample() -> GraphData {
+        let mut graph_data = GraphData::new();
+        for name in ["app", "web", "cli", "core", "serde", "tokio"] {
+            let info = NodeInfo { name: name.to_string(), version: "1.0.0".to_string(), is_workspace_member: name == "app", ..Default::default() };
+            let idx = graph_data.graph.add_node(info);
+            graph_data.node_indices.insert(package_id(name), idx);
+        }
+        for (from, to) in [("app", "web"), ("app", "cli"), ("web", "core"), ("cli", "core"), ("core", "serde"), ("web", "tokio")] {
+            let (from, to) = (graph_data.node_indices[&package_id(from)], graph_data.node_indices[&package_id(to)]);
+            graph_data.graph.add_edge(from, to, DepKind::Normal.into());
+        }
+        graph_data
+    }
+
+    /// Every `node_indices` entry points at the node of its key
+    fn assert_indices(graph_data: &GraphData) {
+        assert_eq!(graph_data.node_indices.len(), graph_data.graph.node_count());
+        for (id, &idx) in &graph_data.node_indices {
+            assert_eq!(format!("{}@1.0.0", graph_data.graph[idx].name), id.repr);
+        }
+    }
+
+    fn names(graph_data: &GraphData) -> Vec<&str> {
+        let mut names: Vec<&str> = graph_data.graph.node_weights().map(|info| info.name.as_str()).collect();
+        names.sort();
+        names
+    }
+
+    fn edges(graph_data: &GraphData) -> Vec<(&str, &str)> {
+        let mut edges: Vec<(&str, &str)> = graph_data.edges().map(|(from, to, _)| (from.name.as_str(), to.name.as_str())).collect();
+        edges.sort();
+        edges
+    }
+
+    fn deps_args() -> DepsArgs {
+        DepsArgs { direction: "LR".to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn retain_nodes_remaps_indices() {
+        let mut graph_data = sample();
+        let web = graph_data.node_indices[&package_id("web")];
+        graph_data.retain_nodes(|idx| idx != web);
+
+        assert_indices(&graph_data);
+        assert_eq!(names(&graph_data), ["app", "cli", "core", "serde", "tokio"]);
+        assert_eq!(edges(&graph_data), [("app", "cli"), ("cli", "core"), ("core", "serde")]);
+    }
+
+    #[test]
+    fn filter_nodes_remaps_indices() {
+        let graph_data = sample();
+        let app = graph_data.node_indices[&package_id("app")];
+        let filtered = graph_data.filter_nodes(|idx| idx != app);
+
+        assert_indices(&filtered);
+        assert_indices(&graph_data);
+        assert_eq!(names(&filtered), ["cli", "core", "serde", "tokio", "web"]);
+        assert_eq!(edges(&filtered), [("cli", "core"), ("core", "serde"), ("web", "core"), ("web", "tokio")]);
+    }
+
+    #[test]
+    fn extract_focus_keeps_neighborhood() {
+        let focused = extract_focus(&sample(), "core", 1).expect("core is in the graph");
+
+        assert_indices(&focused);
+        assert_eq!(names(&focused), ["cli", "core", "serde", "web"]);
+        let core = focused.node(&package_id("core")).unwrap();
+        let serde = focused.node(&package_id("serde")).unwrap();
+        assert_eq!(core.metrics.get(HOPS), Some(&0.0));
+        assert_eq!(serde.metrics.get(HOPS), Some(&1.0));
+        assert!(extract_focus(&sample(), "missing", 1).is_none());
+    }
+
+    #[test]
+    fn prune_leaves_remaps_indices() {
+        let mut graph_data = sample();
+        prune_leaves(&mut graph_data, 1);
+
+        assert_indices(&graph_data);
+        assert_eq!(names(&graph_data), ["app", "cli", "core", "web"]);
+    }
+
+    #[test]
+    fn filter_then_generate() {
+        let mut graph_data = sample();
+        let web = graph_data.node_indices[&package_id("web")];
+        graph_data.apply_filter("--exclude web".to_string(), |graph_data| graph_data.retain_nodes(|idx| idx != web));
+        prune_leaves(&mut graph_data, 1);
+
+        let mermaid = generate_deps_mermaid(&graph_data, &deps_args());
+        assert!(mermaid.contains("app --> cli"), "{}", mermaid);
+        assert!(mermaid.contains("cli --> core"), "{}", mermaid);
+        assert!(!mermaid.contains("--> web") && !mermaid.contains("web -->"), "{}", mermaid);
+        assert!(!mermaid.contains("serde"), "{}", mermaid);
+        assert!(mermaid.contains("--exclude web: 1 nodes, 3 edges removed"), "{}", mermaid);
+
+        let json: serde_json::Value = serde_json::from_str(&generate_deps_json(&graph_data, &deps_args())).unwrap();
+        let nodes: Vec<&str> = json["nodes"].as_array().unwrap().iter().filter_map(|node| node["name"].as_str()).collect();
+        assert_eq!(nodes, ["app", "cli", "core"]);
+        let edges: Vec<(&str, &str)> = json["edges"].as_array().unwrap().iter()
+            .filter_map(|edge| Some((edge["from"].as_str()?, edge["to"].as_str()?)))
+            .collect();
+        assert_eq!(edges, [("pkg:cargo/app@1.0.0", "pkg:cargo/cli@1.0.0"), ("pkg:cargo/cli@1.0.0", "pkg:cargo/core@1.0.0")]);
+    }
+
+    #[test]
+    fn kind_clusters_leave_grouped_nodes_alone() {
+        let mut graph_data = sample();
+        let core = graph_data.node_indices[&package_id("core")];
+        graph_data.graph[core].group = Some(NodeGroup { name: "base".to_string(), color: "#eee".to_string(), direction: None });
+
+        let dot = generate_deps_dot(&graph_data, &DepsArgs { group_by_kind: true, ..deps_args() });
+        // Cluster members are the lines holding nothing but a node ID
+        let memberships = |name: &str| dot.lines()
+            .filter(|line| line.starts_with("        \"") && line.ends_with("\";") && line.contains(&format!("/{}@", name)))
+            .count();
+        assert_eq!(memberships("core"), 1, "{}", dot);
+        assert_eq!(memberships("web"), 1, "{}", dot);
+    }
+}
@@ -2,19 +2,65 @@
 // Graph Building
 // ============================================================================
 
-use std::{collections::{HashMap, HashSet}, fs, path::PathBuf};
-
-use cargo_metadata::{Package, PackageId, DependencyKind};
-use petgraph::graph::{DiGraph, NodeIndex};
-use syn::visit::Visit;
+use std::{collections::{BTreeMap, BTreeSet, HashMap, HashSet}, path::{Path, PathBuf}};
+#[cfg(feature = "native")]
+use std::fs;
+
+#[cfg(feature = "native")]
+use cargo_metadata::{semver::{Version, VersionReq}, CargoOpt, DependencyKind, MetadataCommand, Package, PackageId};
+use petgraph::{graph::NodeIndex, visit::Bfs, Direction};
+use syn::{punctuated::Punctuated, spanned::Spanned, visit::Visit};
+#[cfg(feature = "native")]
 use walkdir::WalkDir;
 
-use crate::{types::{self, CallCollector, CallInfo, FunctionCollector, FunctionDef, OutputFormat}, utils};
-
-use types::{DepsArgs, DepKind, NodeInfo, GraphData, FnGraphArgs, FnGraphData, FnNodeInfo, CallKind, };
-use utils::helper::{matches_any_pattern, sanitize_name};
-use utils::generator::{generate_fn_mermaid, generate_fn_dot, generate_fn_json};
-
+use crate::{types::{self, CallCollector, ColorBy, CallInfo, ConfigKind, ConfigRead, ConfigUse, FunctionCollector, LogCall, FunctionDef, OutputFormat, SourceLocations, WarningCode}, utils};
+
+use types::{DepKind, GraphData, FnGraphArgs, FnGraphData, FnNodeInfo, CallKind, NodeGroup, UseItem};
+use utils::capabilities::{capability_of, capability_view};
+use utils::filter_expr::FilterExpr;
+use utils::findings::find_unreachable_functions;
+use utils::logs::{apply_log_counts, parse_log_macro};
+use utils::graph::{compute_depths, empty_graph_explanation, filter_attributes, filter_by_expr, filter_by_focus, focus_nodes, page_graph, prune_leaves, prune_min_fanin, sample_graph, slice_between, thin_edges};
+use utils::groups::{assign_group, GroupRule};
+use utils::helper::{fnv1a_64, group_color, matches_any_pattern};
+use utils::reexports::{file_modules, flatten_use_tree, is_public_path, reexports_of, resolve_use, ResolvedUse};
+use utils::metrics::has_metric;
+use utils::warnings::warn;
+use utils::generator::{generate_fn_mermaid, generate_fn_dot, generate_fn_json, generate_fn_vscode_graph, generate_fn_gh_summary, generate_fn_call_hierarchy, generate_sarif};
+use utils::generator::{fn_node_ids, uses_mermaid_ids, warn_collisions};
+use utils::generator::{generate_config_map_dot, generate_config_map_json, generate_config_map_mermaid};
+use utils::generator::{generate_type_map_dot, generate_type_map_json, generate_type_map_mermaid};
+
+#[cfg(feature = "native")]
+use types::{CheckFailed, DepsArgs, DepKinds, NodeInfo, Resolver, SizeBy, SourceRoot};
+#[cfg(feature = "native")]
+use utils::{
+    advisories::{advisory_db, attach_advisories},
+    categories::crate_category,
+    analyzer::RustAnalyzer,
+    badges::{deps_badges, fn_badges, write_badges},
+    findings::load_roots,
+    cache::cached_metadata,
+    churn::apply_churn,
+    logs::generate_log_table,
+    crates_io::CratesIo,
+    freshness::{behind, latest_stable},
+    generator::{generate_deps_mermaid, generate_deps_dot, generate_deps_json, generate_deps_vscode_graph, generate_deps_gh_summary, generate_deps_index, generate_fn_index, deps_node_ids},
+    graph::{cycles_among, invert, reachable_from, weak_components},
+    groups::load_groups,
+    layers::Layers,
+    helper::{is_crates_io, write_atomic},
+    metrics::{attach_metrics, MetricScript},
+    pages::{diagram_file_name, write_deps_pages, write_fn_pages},
+    profile::{apply_profile, load_profile},
+    registries::{attach_registries, configured_registries},
+    size::{apply_sizes, load_sizes},
+    stats::generate_deps_stats,
+    toml,
+};
+
+#[cfg(feature = "native")]
+#[allow(clippy::too_many_arguments)]
 pub fn add_package_to_graph(
     pkg: &Package,
     packages: &HashMap<&PackageId, &Package>,
@@ -23,25 +69,21 @@ pub fn add_package_to_graph(
     graph_data: &mut GraphData,
     args: &DepsArgs,
     current_depth: usize,
-    visited: &mut HashSet<PackageId>,
+    visited: &mut HashMap<PackageId, usize>,
 ) {
-    // Depth check
-    if args.depth > 0 && current_depth > args.depth {
-        return;
-    }
-
-    // Cycle detection
-    if visited.contains(&pkg.id) {
+    // Cycle detection. With a depth limit, a package reached again by a shorter path is
+    // expanded again, so the limit counts hops along the shortest path from the root
+    if visited.get(&pkg.id).is_some_and(|&depth| args.depth == 0 || depth <= current_depth) {
         return;
     }
 
     // Exclusion check (supports wildcards: *tauri*, serde-*)
-    if matches_any_pattern(&pkg.name.to_string(), &args.exclude) {
+    if matches_package(pkg, &args.exclude) {
         return;
     }
 
     // Include filter (supports wildcards)
-    if !args.include.is_empty() && !matches_any_pattern(&pkg.name.to_string(), &args.include) {
+    if !args.include.is_empty() && !matches_package(pkg, &args.include) {
         // Still process if this is depth 0 (root package)
         if current_depth > 0 {
             return;
@@ -53,54 +95,53 @@ pub fn add_package_to_graph(
         return;
     }
 
-    visited.insert(pkg.id.clone());
+    visited.insert(pkg.id.clone(), current_depth);
 
     // Add node
     let is_workspace = workspace_members.contains(&pkg.id);
-    let node_info = NodeInfo {
-        name: pkg.name.to_string(),
-        version: pkg.version.to_string(),
-        kind: DepKind::Normal,
-        is_workspace_member: is_workspace,
-    };
+    let node_info = package_node_info(pkg, DepKind::Normal, is_workspace);
 
     let node_idx = *graph_data
         .node_indices
         .entry(pkg.id.clone())
         .or_insert_with(|| graph_data.graph.add_node(node_info));
 
-    // No transitive check
-    if args.no_transitive && current_depth >= 1 {
+    // No transitive check, and no dependencies past the depth limit
+    if (args.no_transitive && current_depth >= 1) || (args.depth > 0 && current_depth >= args.depth) {
         return;
     }
 
     // Find dependencies
     if let Some(node) = nodes.iter().find(|n| n.id == pkg.id) {
         for dep in &node.deps {
-            let dep_kind = dep
-                .dep_kinds
-                .iter()
-                .map(|dk| &dk.kind)
-                .next()
-                .unwrap_or(&DependencyKind::Normal);
-
-            let kind = match dep_kind {
-                DependencyKind::Development => DepKind::Dev,
-                DependencyKind::Build => DepKind::Build,
-                _ => DepKind::Normal,
-            };
+            // Record every kind the dependency is declared with
+            let mut kinds = DepKinds::default();
+            for dep_kind in &dep.dep_kinds {
+                kinds.insert(match dep_kind.kind {
+                    DependencyKind::Development => DepKind::Dev,
+                    DependencyKind::Build => DepKind::Build,
+                    _ => DepKind::Normal,
+                });
+            }
+            if dep.dep_kinds.is_empty() {
+                kinds.insert(DepKind::Normal);
+            }
 
-            // Filter by dependency kind
-            if args.no_dev && kind == DepKind::Dev {
-                continue;
+            // Filter by dependency kind (only the excluded kinds are dropped)
+            if args.no_dev {
+                kinds.remove(DepKind::Dev);
+            }
+            if args.no_build {
+                kinds.remove(DepKind::Build);
             }
-            if args.no_build && kind == DepKind::Build {
+            if kinds.is_empty() {
                 continue;
             }
+            let kind = kinds.primary();
 
             // Exclusion check for dependency (supports wildcards)
             if let Some(dep_pkg) = packages.get(&dep.pkg) {
-                if matches_any_pattern(&dep_pkg.name.to_string(), &args.exclude) {
+                if matches_package(dep_pkg, &args.exclude) {
                     continue;
                 }
 
@@ -117,32 +158,39 @@ pub fn add_package_to_graph(
                     if let Some(existing) = graph_data.node_indices.get(&dep.pkg) {
                         *existing
                     } else {
-                        let dep_info = NodeInfo {
-                            name: dep_pkg.name.to_string(),
-                            version: dep_pkg.version.to_string(),
-                            kind,
-                            is_workspace_member: dep_is_workspace,
-                        };
+                        let dep_info = package_node_info(dep_pkg, kind, dep_is_workspace);
                         let idx = graph_data.graph.add_node(dep_info);
                         graph_data.node_indices.insert(dep.pkg.clone(), idx);
                         idx
                     }
                 } else {
-                    let dep_info = NodeInfo {
-                        name: dep_pkg.name.to_string(),
-                        version: dep_pkg.version.to_string(),
-                        kind,
-                        is_workspace_member: dep_is_workspace,
-                    };
+                    let dep_info = package_node_info(dep_pkg, kind, dep_is_workspace);
                     *graph_data
                         .node_indices
                         .entry(dep.pkg.clone())
                         .or_insert_with(|| graph_data.graph.add_node(dep_info))
                 };
 
-                // Add edge if not exists
-                if !graph_data.graph.contains_edge(node_idx, dep_node_idx) {
-                    graph_data.graph.add_edge(node_idx, dep_node_idx, kind);
+                mark_optional(graph_data, dep_node_idx, is_optional_dependency(pkg, dep_pkg.name.as_ref(), kinds));
+
+                let gates = if args.show_feature_gates { feature_gates(pkg, dep_pkg.name.as_ref()) } else { Vec::new() };
+                if !gates.is_empty() {
+                    graph_data.graph[node_idx].feature_gates.insert(dep_pkg.name.to_string(), gates.clone());
+                }
+
+                // Build-dependencies hang off the package's build.rs pseudo-node
+                let mut kinds = kinds;
+                if args.split_build_scripts && kinds.contains(DepKind::Build) && has_build_script(pkg) {
+                    let script_idx = build_script_node(pkg, node_idx, graph_data);
+                    if !gates.is_empty() {
+                        graph_data.graph[script_idx].feature_gates.insert(dep_pkg.name.to_string(), gates);
+                    }
+                    add_dep_edge(graph_data, script_idx, dep_node_idx, DepKind::Build.into());
+                    kinds.remove(DepKind::Build);
+                }
+
+                if !kinds.is_empty() {
+                    add_dep_edge(graph_data, node_idx, dep_node_idx, kinds);
                 }
 
                 // Recurse
@@ -161,288 +209,1664 @@ pub fn add_package_to_graph(
     }
 }
 
-pub fn filter_by_focus(graph_data: &mut GraphData, focus_crate: &str) {
-    let focus_name = sanitize_name(focus_crate);
+#[cfg(feature = "native")]
+/// Add an edge if not exists, otherwise merge the kinds
+fn add_dep_edge(graph_data: &mut GraphData, from: NodeIndex, to: NodeIndex, kinds: DepKinds) {
+    if let Some(edge) = graph_data.graph.find_edge(from, to) {
+        graph_data.graph[edge] = graph_data.graph[edge].union(kinds);
+    } else {
+        graph_data.graph.add_edge(from, to, kinds);
+    }
+}
 
-    // Find the focus node
-    let focus_nodes: Vec<_> = graph_data
-        .graph
-        .node_indices()
-        .filter(|&idx| sanitize_name(&graph_data.graph[idx].name) == focus_name)
+#[cfg(feature = "native")]
+/// Whether `pkg` declares `name` only as `optional = true` among the dependencies of `kinds`
+fn is_optional_dependency(pkg: &Package, name: &str, kinds: DepKinds) -> bool {
+    let mut declared = pkg.dependencies.iter()
+        .filter(|dep| dep.name == name && kinds.contains(match dep.kind {
+            DependencyKind::Development => DepKind::Dev,
+            DependencyKind::Build => DepKind::Build,
+            _ => DepKind::Normal,
+        }))
+        .peekable();
+    declared.peek().is_some() && declared.all(|dep| dep.optional)
+}
+
+#[cfg(feature = "native")]
+/// Features of `pkg` that enable its optional dependency on `name`: the ones listing `dep:name`,
+/// `name` or `name/feature` (a weak `name?/feature` does not enable it)
+fn feature_gates(pkg: &Package, name: &str) -> Vec<String> {
+    let keys: BTreeSet<&str> = pkg.dependencies.iter()
+        .filter(|dep| dep.name == name && dep.optional)
+        .map(|dep| dep.rename.as_deref().unwrap_or(dep.name.as_str()))
         .collect();
+    let enables = |value: &str| keys.iter().any(|&key| {
+        value.strip_prefix("dep:") == Some(key)
+            || value == key
+            || value.strip_prefix(key).is_some_and(|rest| rest.starts_with('/'))
+    });
+
+    pkg.features.iter()
+        .filter(|(_, values)| values.iter().any(|value| enables(value)))
+        .map(|(feature, _)| feature.clone())
+        .collect()
+}
 
-    if focus_nodes.is_empty() {
-        return;
-    }
+#[cfg(feature = "native")]
+/// Record whether one more edge into `idx` is optional: a package stays optional only while
+/// every dependent declares it so, and workspace members never are
+fn mark_optional(graph_data: &mut GraphData, idx: NodeIndex, optional: bool) {
+    let first_dependent = graph_data.graph.neighbors_directed(idx, Direction::Incoming).next().is_none();
+    let info = &mut graph_data.graph[idx];
+    info.is_optional = !info.is_workspace_member && optional && (first_dependent || info.is_optional);
+}
+
+#[cfg(feature = "native")]
+fn has_build_script(pkg: &Package) -> bool {
+    pkg.targets.iter().any(|t| t.is_custom_build())
+}
 
-    // Collect all connected nodes (both directions)
-    let mut connected: HashSet<NodeIndex> = HashSet::new();
-    for &focus_idx in &focus_nodes {
-        connected.insert(focus_idx);
-        collect_connected(&graph_data.graph, focus_idx, &mut connected);
+#[cfg(feature = "native")]
+/// Pseudo-node for a package's build.rs, linked from the package itself
+fn build_script_node(pkg: &Package, pkg_idx: NodeIndex, graph_data: &mut GraphData) -> NodeIndex {
+    let script_id = PackageId { repr: format!("{}#build-script", pkg.id.repr) };
+    if let Some(&idx) = graph_data.node_indices.get(&script_id) {
+        return idx;
     }
 
-    // Remove unconnected nodes
-    let to_remove: Vec<_> = graph_data
-        .graph
-        .node_indices()
-        .filter(|idx| !connected.contains(idx))
-        .collect();
+    let pkg_info = &graph_data.graph[pkg_idx];
+    let script_info = NodeInfo {
+        name: format!("{}-build.rs", pkg.name),
+        version: pkg.version.to_string(),
+        kind: DepKind::Build,
+        is_workspace_member: pkg_info.is_workspace_member,
+        is_build_script: true,
+        ..Default::default()
+    };
+
+    let idx = graph_data.graph.add_node(script_info);
+    graph_data.node_indices.insert(script_id, idx);
+    graph_data.graph.add_edge(pkg_idx, idx, DepKind::Build.into());
+    idx
+}
 
-    for idx in to_remove.into_iter().rev() {
-        graph_data.graph.remove_node(idx);
+#[cfg(feature = "native")]
+fn package_node_info(pkg: &Package, kind: DepKind, is_workspace_member: bool) -> NodeInfo {
+    NodeInfo {
+        name: pkg.name.to_string(),
+        version: pkg.version.to_string(),
+        kind,
+        is_workspace_member,
+        license: pkg.license.clone(),
+        description: pkg.description.clone(),
+        repository: pkg.repository.clone(),
+        homepage: pkg.homepage.clone(),
+        documentation: pkg.documentation.clone(),
+        source: pkg.source.as_ref().map(|source| source.repr.clone()),
+        registry: None,
+        checksum: None,
+        owners: None,
+        advisories: None,
+        latest_version: None,
+        behind: None,
+        feature_gates: BTreeMap::new(),
+        duplicate_versions: None,
+        is_build_script: false,
+        is_optional: false,
+        depth: None,
+        distance: None,
+        group: None,
+        category: crate_category(&pkg.categories, &pkg.keywords).filter(|_| !is_workspace_member).map(str::to_string),
+        size: None,
+        previous_version: None,
+        metrics: BTreeMap::new(),
     }
 }
 
-fn collect_connected(graph: &DiGraph<NodeInfo, DepKind>, start: NodeIndex, connected: &mut HashSet<NodeIndex>) {
-    // Outgoing edges
-    for neighbor in graph.neighbors(start) {
-        if connected.insert(neighbor) {
-            collect_connected(graph, neighbor, connected);
-        }
+#[cfg(feature = "native")]
+/// Build member-to-member edges from declared path dependencies, for
+/// `cargo metadata --no-deps` output where no resolve graph is available
+pub fn add_declared_package_to_graph(
+    pkg: &Package,
+    members: &HashMap<&str, &Package>,
+    graph_data: &mut GraphData,
+    args: &DepsArgs,
+    current_depth: usize,
+    visited: &mut HashMap<PackageId, usize>,
+) {
+    // As in add_package_to_graph, shorter paths expand a package again under a depth limit
+    if visited.get(&pkg.id).is_some_and(|&depth| args.depth == 0 || depth <= current_depth) {
+        return;
     }
-    // Incoming edges
-    for neighbor in graph.neighbors_directed(start, petgraph::Direction::Incoming) {
-        if connected.insert(neighbor) {
-            collect_connected(graph, neighbor, connected);
-        }
+
+    if matches_package(pkg, &args.exclude) {
+        return;
     }
-}
 
-// ============================================================================
-// Function Graph - Visitor Implementation
-// ============================================================================
-impl FunctionCollector {
-    fn new() -> Self {
-        FunctionCollector {
-            module_path: Vec::new(),
-            functions: Vec::new(),
-            current_impl_type: None,
-        }
+    if !args.include.is_empty() && !matches_package(pkg, &args.include) && current_depth > 0 {
+        return;
     }
 
-    fn qualified_name(&self, name: &str) -> String {
-        let mut parts = self.module_path.clone();
-        if let Some(ref impl_type) = self.current_impl_type {
-            parts.push(impl_type.clone());
-        }
-        parts.push(name.to_string());
-        parts.join("::")
+    visited.insert(pkg.id.clone(), current_depth);
+
+    let node_info = package_node_info(pkg, DepKind::Normal, true);
+    let node_idx = *graph_data
+        .node_indices
+        .entry(pkg.id.clone())
+        .or_insert_with(|| graph_data.graph.add_node(node_info));
+
+    if (args.no_transitive && current_depth >= 1) || (args.depth > 0 && current_depth >= args.depth) {
+        return;
     }
 
-    fn format_signature(sig: &syn::Signature) -> String {
-        let inputs: Vec<String> = sig.inputs.iter().map(|arg| {
-            match arg {
-                syn::FnArg::Receiver(r) => {
-                    if r.reference.is_some() {
-                        if r.mutability.is_some() { "&mut self".to_string() }
-                        else { "&self".to_string() }
-                    } else {
-                        "self".to_string()
-                    }
-                }
-                syn::FnArg::Typed(pat) => {
-                    format!("{}", quote::quote!(#pat))
-                }
-            }
-        }).collect();
+    for dep in &pkg.dependencies {
+        // Only path dependencies can point at another member
+        if dep.path.is_none() {
+            continue;
+        }
+        let Some(dep_pkg) = members.get(dep.name.as_str()) else {
+            continue;
+        };
 
-        let output = match &sig.output {
-            syn::ReturnType::Default => String::new(),
-            syn::ReturnType::Type(_, ty) => format!(" -> {}", quote::quote!(#ty)),
+        let kind = match dep.kind {
+            DependencyKind::Development => DepKind::Dev,
+            DependencyKind::Build => DepKind::Build,
+            _ => DepKind::Normal,
         };
+        if (args.no_dev && kind == DepKind::Dev) || (args.no_build && kind == DepKind::Build) {
+            continue;
+        }
 
-        format!("fn {}({}){}", sig.ident, inputs.join(", "), output)
+        if matches_package(dep_pkg, &args.exclude) {
+            continue;
+        }
+
+        let dep_info = package_node_info(dep_pkg, kind, true);
+        let dep_node_idx = *graph_data
+            .node_indices
+            .entry(dep_pkg.id.clone())
+            .or_insert_with(|| graph_data.graph.add_node(dep_info));
+        mark_optional(graph_data, dep_node_idx, dep.optional);
+        let gates = if args.show_feature_gates { feature_gates(pkg, dep_pkg.name.as_ref()) } else { Vec::new() };
+        if !gates.is_empty() {
+            graph_data.graph[node_idx].feature_gates.insert(dep_pkg.name.to_string(), gates);
+        }
+
+        if args.split_build_scripts && kind == DepKind::Build && has_build_script(pkg) {
+            let script_idx = build_script_node(pkg, node_idx, graph_data);
+            add_dep_edge(graph_data, script_idx, dep_node_idx, kind.into());
+        } else {
+            add_dep_edge(graph_data, node_idx, dep_node_idx, kind.into());
+        }
+
+        add_declared_package_to_graph(dep_pkg, members, graph_data, args, current_depth + 1, visited);
     }
 }
 
-impl<'ast> Visit<'ast> for FunctionCollector {
-    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
-        let is_public = matches!(node.vis, syn::Visibility::Public(_));
-        let name = node.sig.ident.to_string();
-        let qualified = self.qualified_name(&name);
-
-        self.functions.push(FunctionDef {
-            name,
-            qualified_name: qualified,
-            is_public,
-            line: 0, // Line info requires span-locations feature
-            signature: Self::format_signature(&node.sig),
-            is_async: node.sig.asyncness.is_some(),
-        });
+/// Keep only runtime dependencies reachable from `roots`, dropping dev/build-only branches
+pub fn filter_reachable_from(graph_data: &mut GraphData, roots: &[NodeIndex]) {
+    // Only normal edges end up in the built artifact
+    graph_data.graph.retain_edges(|graph, edge| graph[edge].contains(DepKind::Normal));
+    for kinds in graph_data.graph.edge_weights_mut() {
+        *kinds = DepKind::Normal.into();
+    }
 
-        syn::visit::visit_item_fn(self, node);
+    let mut reachable: HashSet<NodeIndex> = HashSet::new();
+    for &root in roots {
+        let mut bfs = Bfs::new(&graph_data.graph, root);
+        while let Some(idx) = bfs.next(&graph_data.graph) {
+            reachable.insert(idx);
+        }
     }
 
-    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
-        // Extract impl type name
-        let type_name = if let syn::Type::Path(type_path) = &*node.self_ty {
-            type_path.path.segments.last()
-                .map(|seg| seg.ident.to_string())
-        } else {
-            None
-        };
+    graph_data.retain_nodes(|idx| reachable.contains(&idx));
+}
 
-        let old_impl = self.current_impl_type.take();
-        self.current_impl_type = type_name;
+// ============================================================================
+// Dependency Graph - Main Logic
+// ============================================================================
 
-        syn::visit::visit_item_impl(self, node);
+#[cfg(feature = "native")]
+pub fn run_deps(args: &DepsArgs) -> Result<(String, Option<PathBuf>), Box<dyn std::error::Error>> {
+    let filter = args.filter.as_deref()
+        .map(|source| FilterExpr::parse(source, &filter_attributes::<NodeInfo>()))
+        .transpose()?;
+    let groups = args.groups.as_deref().map(load_groups).transpose()?;
+    let layers = args.check_layers.as_deref().map(Layers::load).transpose()?;
+    if matches!(args.format, OutputFormat::CallHierarchy | OutputFormat::Sarif) {
+        return Err(format!("--format {} is only available for fn-graph", args.format.name()).into());
+    }
 
-        self.current_impl_type = old_impl;
+    let (mut graph_data, root_packages) = load_deps_graph(args)?;
+
+    if let Some(ref command) = args.metric_script {
+        attach_metrics(&mut graph_data, "deps", &mut MetricScript { command: command.clone() })?;
     }
 
-    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
-        let is_public = matches!(node.vis, syn::Visibility::Public(_));
-        let name = node.sig.ident.to_string();
-        let qualified = self.qualified_name(&name);
+    // Before any filter hides a crate
+    let cycles = if args.fail_on_cycles { workspace_cycles(&graph_data) } else { Vec::new() };
+    let violations = layers.map(|layers| layers.violations(&graph_data)).unwrap_or_default();
 
-        self.functions.push(FunctionDef {
-            name,
-            qualified_name: qualified,
-            is_public,
-            line: 0, // Line info requires span-locations feature
-            signature: FunctionCollector::format_signature(&node.sig),
-            is_async: node.sig.asyncness.is_some(),
-        });
+    let output = render_deps_graph(&mut graph_data, args, filter.as_ref(), groups.as_deref(), &root_packages)?;
 
-        syn::visit::visit_impl_item_fn(self, node);
+    if let Some(ref index_path) = args.index {
+        write_atomic(index_path, generate_deps_index(&graph_data, args))?;
+        eprintln!("Index written to: {}", index_path.display());
     }
 
-    fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
-        self.module_path.push(node.ident.to_string());
-        syn::visit::visit_item_mod(self, node);
-        self.module_path.pop();
+    if let Some(ref badges_dir) = args.badges {
+        write_badges(badges_dir, &deps_badges(&graph_data))?;
     }
-}
 
-impl CallCollector {
-    fn new(current_function: String) -> Self {
-        CallCollector {
-            current_function,
-            calls: Vec::new(),
+    let output_path = match args.out_dir {
+        Some(ref dir) => {
+            let diagram = diagram_file_name(&args.format, args.no_fence);
+            if let Some(format) = args.detail_pages {
+                write_deps_pages(&graph_data, dir, format, diagram)?;
+            }
+            Some(dir.join(diagram))
         }
+        None => args.output.clone(),
+    };
+
+    let mut failures = Vec::new();
+    for cycle in &cycles {
+        eprintln!("Cycle: {}", cycle);
+    }
+    if !cycles.is_empty() {
+        failures.push(format!("{} dependency cycle{} between workspace crates", cycles.len(), if cycles.len() == 1 { "" } else { "s" }));
+    }
+    for violation in &violations {
+        eprintln!("Layer violation: {}", violation);
+    }
+    if let (false, Some(path)) = (violations.is_empty(), &args.check_layers) {
+        failures.push(format!("{} {} the layers of {}", violations.len(), if violations.len() == 1 { "dependency breaks" } else { "dependencies break" }, path.display()));
     }
+    if !failures.is_empty() {
+        return Err(Box::new(CheckFailed { message: failures.join("; "), output, output_path }));
+    }
+    Ok((output, output_path))
 }
 
-impl<'ast> Visit<'ast> for CallCollector {
-    fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
-        // Extract callee name from the function expression
-        let callee = extract_call_name(&node.func);
-        if let Some(name) = callee {
-            self.calls.push(CallInfo {
-                caller: self.current_function.clone(),
-                callee: name,
-                kind: CallKind::Direct,
-            });
+/// Cycles among workspace members, each as `a -> b -[dev]-> a` with the kinds of non-normal edges
+#[cfg(feature = "native")]
+fn workspace_cycles(graph_data: &GraphData) -> Vec<String> {
+    let graph = &graph_data.graph;
+    cycles_among(graph, |idx| graph[idx].is_workspace_member && !graph[idx].is_build_script)
+        .into_iter()
+        .map(|cycle| {
+            let mut text = graph[cycle[0]].name.clone();
+            for (i, &from) in cycle.iter().enumerate() {
+                let to = cycle[(i + 1) % cycle.len()];
+                let kinds = graph.find_edge(from, to).map(|edge| graph[edge]).unwrap_or_default();
+                let label: Vec<&str> = kinds.iter().filter(|&kind| kind != DepKind::Normal).map(DepKind::as_str).collect();
+                match label.is_empty() {
+                    true => text.push_str(" -> "),
+                    false => text.push_str(&format!(" -[{}]-> ", label.join("+"))),
+                }
+                text.push_str(&graph[to].name);
+            }
+            text
+        })
+        .collect()
+}
+
+/// Filters, coloring and output of a loaded dependency graph
+#[cfg(feature = "native")]
+pub fn render_deps_graph(
+    graph_data: &mut GraphData,
+    args: &DepsArgs,
+    filter: Option<&FilterExpr>,
+    groups: Option<&[GroupRule]>,
+    root_packages: &[(String, String)],
+) -> Result<String, Box<dyn std::error::Error>> {
+    // Dependents instead of dependencies
+    if let Some(ref target) = args.invert {
+        match target {
+            Some(target) => graph_data.apply_filter(format!("--invert {}", target), |graph_data| invert(graph_data, Some(target)))?,
+            None => invert(graph_data, None)?,
         }
-        syn::visit::visit_expr_call(self, node);
     }
 
-    fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
-        let method_name = node.method.to_string();
-        self.calls.push(CallInfo {
-            caller: self.current_function.clone(),
-            callee: method_name,
-            kind: CallKind::Method,
-        });
-        syn::visit::visit_expr_method_call(self, node);
+    // Apply filter expression, with depth measured from the root packages
+    if let (Some(expr), Some(source)) = (&filter, &args.filter) {
+        let roots = root_node_indices(graph_data, root_packages, args.invert.is_some());
+        graph_data.apply_filter(format!("--filter '{}'", source), |graph_data| filter_by_expr(graph_data, expr, &roots));
     }
-}
 
-fn extract_call_name(expr: &syn::Expr) -> Option<String> {
-    match expr {
-        syn::Expr::Path(path) => {
-            Some(path.path.segments.iter()
-                .map(|seg| seg.ident.to_string())
-                .collect::<Vec<_>>()
-                .join("::"))
+    // Apply focus filter
+    if let Some(ref focus_crate) = args.focus {
+        graph_data.apply_filter(format!("--focus {}", focus_crate), |graph_data| filter_by_focus(graph_data, focus_crate, 0));
+    }
+
+    if let (Some(from), Some(to)) = (&args.from, &args.to) {
+        for (flag, name) in [("--from", from), ("--to", to)] {
+            if focus_nodes(&graph_data.graph, name).is_empty() {
+                return Err(format!("{} '{}' matches no crate in the graph", flag, name).into());
+            }
+        }
+        graph_data.apply_filter(format!("--from {} --to {}", from, to), |graph_data| slice_between(graph_data, from, to))?;
+        if graph_data.graph.node_count() == 0 {
+            warn(WarningCode::NoMatch, format!("no dependency path leads from '{}' to '{}'", from, to));
         }
-        _ => None,
     }
-}
 
-// ============================================================================
-// Function Graph - Main Logic
-// ============================================================================
+    if let Some(rounds) = args.prune_leaves {
+        graph_data.apply_filter(format!("--prune-leaves {}", rounds), |graph_data| prune_leaves(graph_data, rounds));
+    }
+    if let Some(min) = args.min_fanin {
+        graph_data.apply_filter(format!("--min-fanin {}", min), |graph_data| prune_min_fanin(graph_data, min));
+    }
+    if let Some(size) = args.sample {
+        graph_data.apply_filter(format!("--sample {}", size), |graph_data| sample_graph(graph_data, size));
+    }
+    if let Some(max) = args.max_edges_per_node.filter(|_| !matches!(args.format, OutputFormat::Json | OutputFormat::CallHierarchy | OutputFormat::Sarif)) {
+        graph_data.apply_filter(format!("--max-edges-per-node {}", max), |graph_data| thin_edges(graph_data, max, |_| 0));
+    }
+    if let Some(explanation) = empty_graph_explanation(graph_data) {
+        return Err(explanation.into());
+    }
 
-pub fn run_fn_graph(args: &FnGraphArgs) -> Result<(String, Option<PathBuf>), Box<dyn std::error::Error>> {
-    let source_dir = &args.source_dir;
+    // After the check above: a page may hold a few unconnected nodes
+    if let Some(page_size) = args.page_size {
+        let description = format!("--page {} --page-size {}", args.page, page_size);
+        let pages = graph_data.apply_filter(description, |graph_data| page_graph(graph_data, args.page, page_size))?;
+        if args.page < pages {
+            eprintln!("Page {} of {}; --page {} for the next", args.page, pages, args.page + 1);
+        } else {
+            eprintln!("Page {} of {}", args.page, pages);
+        }
+    }
 
-    if !source_dir.exists() {
-        return Err(format!("Source directory not found: {}", source_dir.display()).into());
+    // Transitive distance from the workspace, which also becomes the depth colored by
+    if args.relative_depth {
+        let members: Vec<_> = graph_data.graph.node_indices()
+            .filter(|&idx| graph_data.graph[idx].is_workspace_member && !graph_data.graph[idx].is_build_script)
+            .collect();
+        for (idx, distance) in compute_depths(&graph_data.graph, &members) {
+            graph_data.graph[idx].distance = Some(distance);
+            if args.color_by == Some(ColorBy::Depth) {
+                graph_data.graph[idx].depth = Some(distance);
+            }
+        }
+    } else if args.color_by == Some(ColorBy::Depth) {
+        // Depth coloring
+        let roots = root_node_indices(graph_data, root_packages, args.invert.is_some());
+        let depths = compute_depths(&graph_data.graph, &roots);
+        for (idx, depth) in depths {
+            graph_data.graph[idx].depth = Some(depth);
+        }
     }
 
-    // Collect all Rust files
-    let rust_files: Vec<_> = WalkDir::new(source_dir)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension().map_or(false, |ext| ext == "rs"))
-        .collect();
+    if let Some(rules) = groups {
+        for info in graph_data.graph.node_weights_mut() {
+            info.group = assign_group(rules, &[&info.name]);
+        }
+    }
 
-    let mut all_functions: Vec<(FunctionDef, String)> = Vec::new();
-    let mut all_calls: Vec<CallInfo> = Vec::new();
+    if args.separate_components {
+        group_components(graph_data);
+    }
+    if args.cluster_workspace {
+        group_workspace_members(graph_data);
+    }
 
-    // Parse each file
-    for entry in rust_files {
-        let file_path = entry.path();
-        let content = match fs::read_to_string(file_path) {
-            Ok(c) => c,
-            Err(_) => continue,
-        };
+    if let (Some(ColorBy::Metric), Some(metric)) = (args.color_by, &args.color_metric) {
+        if !has_metric(graph_data, metric) {
+            warn(WarningCode::NoMatch, format!("no crate has metric '{}'; nothing is colored", metric));
+        }
+    }
 
-        let syntax = match syn::parse_file(&content) {
-            Ok(s) => s,
-            Err(_) => continue,
-        };
+    if args.warn_collisions {
+        warn_collisions(&deps_node_ids(graph_data, args, uses_mermaid_ids(&args.format)));
+    }
 
-        let relative_path = file_path.strip_prefix(source_dir)
-            .unwrap_or(file_path)
-            .to_string_lossy()
-            .to_string();
+    if args.stats {
+        let roots = root_node_indices(graph_data, root_packages, args.invert.is_some());
+        return Ok(generate_deps_stats(graph_data, &roots, matches!(args.format, OutputFormat::Json)));
+    }
 
-        // Collect function definitions
-        let mut collector = FunctionCollector::new();
-        collector.visit_file(&syntax);
+    // Generate output
+    Ok(match args.format {
+        OutputFormat::Mermaid => generate_deps_mermaid(graph_data, args),
+        OutputFormat::Dot => generate_deps_dot(graph_data, args),
+        OutputFormat::Json => generate_deps_json(graph_data, args),
+        OutputFormat::VscodeGraph => generate_deps_vscode_graph(graph_data, args),
+        OutputFormat::GhSummary => generate_deps_gh_summary(graph_data, args),
+        OutputFormat::CallHierarchy | OutputFormat::Sarif => unreachable!(),
+    })
+}
 
-        for func in collector.functions {
-            all_functions.push((func, relative_path.clone()));
-        }
+/// Root packages of a dependency graph, as (name, version)
+pub type RootPackages = Vec<(String, String)>;
+
+/// Run cargo metadata for `args` and build the dependency graph, before filtering
+#[cfg(feature = "native")]
+pub fn load_deps_graph(args: &DepsArgs) -> Result<(GraphData, RootPackages), Box<dyn std::error::Error>> {
+    let mut command = MetadataCommand::new();
+    command.manifest_path(&args.manifest_path);
+    // cargo reads .cargo/config.toml (registries, source replacement) from where it runs, so run
+    // it beside the manifest, as `cargo` in that directory would
+    if let Some((manifest, dir)) = fs::canonicalize(&args.manifest_path).ok().and_then(|path| Some((path.clone(), path.parent()?.to_path_buf()))) {
+        command.manifest_path(manifest).current_dir(dir);
     }
 
-    // Collect function calls by re-parsing with call collector
-    for entry in WalkDir::new(source_dir)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension().map_or(false, |ext| ext == "rs"))
-    {
-        let file_path = entry.path();
-        let content = match fs::read_to_string(file_path) {
-            Ok(c) => c,
-            Err(_) => continue,
-        };
-
-        let syntax = match syn::parse_file(&content) {
-            Ok(s) => s,
-            Err(_) => continue,
-        };
+    // Member-to-member edges come from declared dependencies, so skip resolution
+    if args.workspace_only {
+        command.no_deps();
+    }
 
-        // For each function, collect calls
-        collect_calls_from_file(&syntax, &mut all_calls, &all_functions);
+    // The same feature selection as `cargo build`; cargo reports only the dependencies it enables
+    if !args.features.is_empty() {
+        command.features(CargoOpt::SomeFeatures(args.features.clone()));
     }
+    if args.all_features {
+        command.features(CargoOpt::AllFeatures);
+    }
+    if args.no_default_features {
+        command.features(CargoOpt::NoDefaultFeatures);
+    }
+    // cargo evaluates the `[target.'cfg(...)'.dependencies]` and drops the ones of other platforms
+    let mut other_options = Vec::new();
+    if let Some(ref triple) = args.target {
+        other_options.extend(["--filter-platform".to_string(), triple.clone()]);
+    }
+    if args.offline {
+        other_options.push("--offline".to_string());
+    }
+    command.other_options(other_options);
 
-    // Build graph
-    let mut graph_data = FnGraphData {
-        graph: DiGraph::new(),
-        node_indices: HashMap::new(),
+    let metadata = match args.cache_dir {
+        Some(ref cache_dir) => cached_metadata(&command, &args.manifest_path, cache_dir)?,
+        None => command.exec()?,
     };
 
-    // Create function name -> qualified_name lookup
-    let fn_lookup: HashMap<String, String> = all_functions.iter()
-        .map(|(f, _)| (f.name.clone(), f.qualified_name.clone()))
-        .collect();
+    let workspace_members: HashSet<_> = metadata.workspace_members.iter().collect();
+
+    // Build package lookup map
+    let packages: HashMap<&PackageId, &Package> =
+        metadata.packages.iter().map(|p| (&p.id, p)).collect();
+
+    // Get root packages
+    let root_packages: Vec<&Package> = if !args.package.is_empty() {
+        if let Some(missing) = args.package.iter().find(|name| !metadata.packages.iter().any(|p| p.name == **name)) {
+            return Err(format!("No package named '{}'", missing).into());
+        }
+        metadata
+            .packages
+            .iter()
+            .filter(|p| args.package.contains(&p.name))
+            .collect()
+    } else {
+        metadata
+            .workspace_members
+            .iter()
+            .filter_map(|id| packages.get(id).copied())
+            .collect()
+    };
+
+    if root_packages.is_empty() {
+        return Err("No packages found".into());
+    }
+    for (flag, patterns) in [("--exclude", &args.exclude), ("--include", &args.include)] {
+        for (pattern, spec) in patterns.iter().filter_map(|pattern| pattern.split_once('@').map(|(_, spec)| (pattern, spec))) {
+            if Version::parse(spec).is_err() {
+                VersionReq::parse(spec).map_err(|e| format!("{} {}: invalid version requirement '{}': {}", flag, pattern, spec, e))?;
+            }
+        }
+        for pattern in patterns.iter().filter(|pattern| !args.file_patterns.contains(pattern)) {
+            if !metadata.packages.iter().any(|p| matches_package(p, std::slice::from_ref(pattern))) {
+                warn(WarningCode::NoMatch, format!("{} {} matches no crate", flag, pattern));
+            }
+        }
+    }
+
+    // Build graph
+    let mut graph_data = GraphData::new();
+
+    if args.workspace_only {
+        let members: HashMap<&str, &Package> = metadata
+            .workspace_packages()
+            .into_iter()
+            .map(|p| (p.name.as_str(), p))
+            .collect();
+
+        for root_pkg in &root_packages {
+            add_declared_package_to_graph(root_pkg, &members, &mut graph_data, args, 0, &mut HashMap::new());
+        }
+    } else {
+        let resolve = metadata.resolve.as_ref().ok_or("No resolve data")?;
+
+        for root_pkg in &root_packages {
+            add_package_to_graph(
+                root_pkg,
+                &packages,
+                &resolve.nodes,
+                &workspace_members,
+                &mut graph_data,
+                args,
+                0,
+                &mut HashMap::new(),
+            );
+        }
+    }
+
+    for description in deps_build_filters(args) {
+        graph_data.note_filter(description);
+    }
+
+    attach_registries(&mut graph_data, &configured_registries(metadata.workspace_root.as_std_path()));
+    filter_registries(&mut graph_data, &root_packages, args);
+
+    // Where the trees of several --package selections meet
+    if args.package.len() > 1 {
+        let mut reached_by: HashMap<NodeIndex, usize> = HashMap::new();
+        for root in root_packages.iter().filter_map(|pkg| graph_data.node_indices.get(&pkg.id)) {
+            for idx in reachable_from(&graph_data.graph, &[*root], Direction::Outgoing) {
+                *reached_by.entry(idx).or_default() += 1;
+            }
+        }
+        let shared = reached_by.values().filter(|&&count| count > 1).count();
+        eprintln!("{} of {} crates are in the trees of several selected packages", shared, graph_data.graph.node_count());
+    }
+
+    // Prune to what workspace binaries actually link
+    if args.reachable_from_bins {
+        let bin_roots: Vec<_> = metadata
+            .workspace_members
+            .iter()
+            .filter_map(|id| packages.get(id))
+            .filter(|pkg| pkg.targets.iter().any(|t| t.is_bin()))
+            .filter_map(|pkg| graph_data.node_indices.get(&pkg.id).copied())
+            .collect();
+
+        if bin_roots.is_empty() {
+            return Err("No workspace member with a binary target in the graph".into());
+        }
+
+        graph_data.apply_filter("--reachable-from-bins".to_string(), |graph_data| filter_reachable_from(graph_data, &bin_roots));
+    }
+
+    if args.show_checksums {
+        let lock_path = metadata.workspace_root.join("Cargo.lock");
+        if let Err(e) = attach_lock_checksums(&mut graph_data, lock_path.as_std_path()) {
+            warn(WarningCode::MissingData, format!("no checksums from {}: {}", lock_path, e));
+        }
+    }
+
+    if args.show_owners {
+        attach_owners(&mut graph_data, args.cache_dir.as_deref());
+    }
+
+    if args.check_outdated {
+        attach_latest_versions(&mut graph_data, args.cache_dir.as_deref());
+    }
+
+    if args.audit {
+        let db = advisory_db(args.advisory_db.as_deref(), args.cache_dir.as_deref(), args.offline)?;
+        let vulnerable = attach_advisories(&mut graph_data, &db);
+        eprintln!("{} crates affected by RustSec advisories", vulnerable);
+    }
+
+    if args.show_duplicates {
+        mark_duplicates(&mut graph_data);
+    }
+
+    if let Some(size_by) = args.size_by {
+        attach_code_sizes(&mut graph_data, &packages, size_by);
+    }
+
+    let roots = root_packages.iter().map(|p| (p.name.to_string(), p.version.to_string())).collect();
+    Ok((graph_data, roots))
+}
+
+/// Whether a package matches one of the --exclude/--include patterns: a name with * wildcards,
+/// optionally with `@` and a version (`serde@1.0.219`) or requirement (`serde@1.*`, `tokio@>=1.30`)
+#[cfg(feature = "native")]
+fn matches_package(pkg: &Package, patterns: &[String]) -> bool {
+    patterns.iter().any(|pattern| match pattern.split_once('@') {
+        None => matches_any_pattern(&pkg.name, std::slice::from_ref(pattern)),
+        Some((name, spec)) => {
+            let version_matches = match Version::parse(spec) {
+                Ok(version) => pkg.version == version,
+                Err(_) => VersionReq::parse(spec).is_ok_and(|req| req.matches(&pkg.version)),
+            };
+            version_matches && matches_any_pattern(&pkg.name, &[name.to_string()])
+        }
+    })
+}
+
+/// Apply --exclude-registry and --only-registry, then drop what no root reaches any more
+#[cfg(feature = "native")]
+fn filter_registries(graph_data: &mut GraphData, root_packages: &[&Package], args: &DepsArgs) {
+    for (flag, patterns) in [("--exclude-registry", &args.exclude_registry), ("--only-registry", &args.only_registry)] {
+        for pattern in patterns {
+            let used = graph_data.graph.node_weights()
+                .any(|info| info.registry.as_deref().is_some_and(|registry| matches_any_pattern(registry, std::slice::from_ref(pattern))));
+            if !used {
+                warn(WarningCode::NoMatch, format!("{} {} matches no crate's registry", flag, pattern));
+            }
+        }
+    }
+    if args.exclude_registry.is_empty() && args.only_registry.is_empty() {
+        return;
+    }
+
+    let mut description: Vec<String> = args.exclude_registry.iter().map(|name| format!("--exclude-registry {}", name)).collect();
+    description.extend(args.only_registry.iter().map(|name| format!("--only-registry {}", name)));
+    graph_data.apply_filter(description.join(" "), |graph_data| {
+        let roots = |graph_data: &GraphData| -> Vec<NodeIndex> {
+            root_packages.iter().filter_map(|pkg| graph_data.node_indices.get(&pkg.id).copied()).collect()
+        };
+        let before = roots(graph_data);
+        let graph = &graph_data.graph;
+        let kept: HashSet<NodeIndex> = graph.node_indices()
+            .filter(|&idx| {
+                let registry = graph[idx].registry.as_deref();
+                let excluded = registry.is_some_and(|registry| matches_any_pattern(registry, &args.exclude_registry));
+                let included = args.only_registry.is_empty() || graph[idx].is_workspace_member
+                    || registry.is_some_and(|registry| matches_any_pattern(registry, &args.only_registry));
+                before.contains(&idx) || (!excluded && included)
+            })
+            .collect();
+        graph_data.retain_nodes(|idx| kept.contains(&idx));
+
+        let reachable = reachable_from(&graph_data.graph, &roots(graph_data), Direction::Outgoing);
+        graph_data.retain_nodes(|idx| reachable.contains(&idx));
+    });
+}
+
+/// `NodeInfo::size` of every crate node, measured over the whole graph before any filter
+#[cfg(feature = "native")]
+fn attach_code_sizes(graph_data: &mut GraphData, packages: &HashMap<&PackageId, &Package>, size_by: SizeBy) {
+    let nodes: Vec<(PackageId, NodeIndex)> = graph_data.node_indices.iter().map(|(id, &idx)| (id.clone(), idx)).collect();
+    for (id, idx) in nodes {
+        if graph_data.graph[idx].is_build_script {
+            continue;
+        }
+        let size = match size_by {
+            SizeBy::Deps => Some(reachable_from(&graph_data.graph, &[idx], Direction::Outgoing).len().saturating_sub(1) as u64),
+            SizeBy::Loc | SizeBy::Files => packages.get(&id)
+                .and_then(|pkg| pkg.manifest_path.parent())
+                .map(|dir| rust_code_size(dir.as_std_path(), size_by)),
+        };
+        graph_data.graph[idx].size = size;
+    }
+}
+
+/// Lines or files of Rust under a package directory, leaving out target/, hidden directories
+/// and nested packages (workspace members below a virtual root)
+#[cfg(feature = "native")]
+fn rust_code_size(dir: &Path, size_by: SizeBy) -> u64 {
+    let files = WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            entry.depth() == 0 || !entry.file_type().is_dir()
+                || !(name.starts_with('.') || name == "target" || entry.path().join("Cargo.toml").is_file())
+        })
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file() && entry.path().extension().is_some_and(|ext| ext == "rs"));
+
+    match size_by {
+        SizeBy::Files => files.count() as u64,
+        _ => files.map(|entry| fs::read_to_string(entry.path()).map_or(0, |content| content.lines().count() as u64)).sum(),
+    }
+}
+
+/// The options applied while walking the resolve, as given on the command line
+#[cfg(feature = "native")]
+fn deps_build_filters(args: &DepsArgs) -> Vec<String> {
+    let mut filters = Vec::new();
+    filters.extend(args.package.iter().map(|package| format!("--package {}", package)));
+    if let Some(ref triple) = args.target {
+        filters.push(format!("--target {}", triple));
+    }
+    if args.workspace_only {
+        filters.push("--workspace-only".to_string());
+    }
+    if args.no_transitive {
+        filters.push("--no-transitive".to_string());
+    }
+    if args.depth > 0 {
+        filters.push(format!("--depth {}", args.depth));
+    }
+    if args.no_dev {
+        filters.push("--no-dev".to_string());
+    }
+    if args.no_build {
+        filters.push("--no-build".to_string());
+    }
+    let listed = |pattern: &&String| !args.file_patterns.contains(pattern);
+    filters.extend(args.exclude.iter().filter(listed).map(|pattern| format!("--exclude {}", pattern)));
+    filters.extend(args.exclude_from.iter().map(|file| format!("--exclude-from {}", file.display())));
+    filters.extend(args.include.iter().filter(listed).map(|pattern| format!("--include {}", pattern)));
+    filters.extend(args.include_from.iter().map(|file| format!("--include-from {}", file.display())));
+    filters
+}
+
+/// Give every crate that is in the graph in several versions the list of those versions
+#[cfg(feature = "native")]
+fn mark_duplicates(graph_data: &mut GraphData) {
+    let mut versions: HashMap<String, BTreeSet<Version>> = HashMap::new();
+    for info in graph_data.graph.node_weights().filter(|info| !info.is_build_script) {
+        if let Ok(version) = Version::parse(&info.version) {
+            versions.entry(info.name.clone()).or_default().insert(version);
+        }
+    }
+
+    for info in graph_data.graph.node_weights_mut().filter(|info| !info.is_build_script) {
+        if let Some(all) = versions.get(&info.name).filter(|all| all.len() > 1) {
+            info.duplicate_versions = Some(all.iter().map(Version::to_string).collect());
+        }
+    }
+}
+
+/// Look up the crates.io owners of each crates.io package, warning about the ones that fail
+#[cfg(feature = "native")]
+fn attach_owners(graph_data: &mut GraphData, cache_dir: Option<&Path>) {
+    let mut client = CratesIo::new(cache_dir);
+    let mut by_name: HashMap<String, Option<Vec<String>>> = HashMap::new();
+
+    for info in graph_data.graph.node_weights_mut() {
+        if !info.source.as_deref().is_some_and(is_crates_io) {
+            continue;
+        }
+        let owners = by_name.entry(info.name.clone()).or_insert_with(|| match client.owners(&info.name) {
+            Ok(owners) => Some(owners),
+            Err(e) => {
+                warn(WarningCode::MissingData, format!("no owners for {}: {}", info.name, e));
+                None
+            }
+        });
+        info.owners = owners.clone();
+    }
+}
+
+/// Look up the latest stable release of each crates.io package, warning about the ones that fail
+#[cfg(feature = "native")]
+fn attach_latest_versions(graph_data: &mut GraphData, cache_dir: Option<&Path>) {
+    let mut client = CratesIo::new(cache_dir);
+    let mut by_name: HashMap<String, Option<String>> = HashMap::new();
+
+    for info in graph_data.graph.node_weights_mut() {
+        if info.is_build_script || !info.source.as_deref().is_some_and(is_crates_io) {
+            continue;
+        }
+        let latest = by_name.entry(info.name.clone()).or_insert_with(|| match client.crate_info(&info.name) {
+            Ok(response) => latest_stable(&response).map(str::to_string),
+            Err(e) => {
+                warn(WarningCode::MissingData, format!("no latest version for {}: {}", info.name, e));
+                None
+            }
+        });
+        info.behind = latest.as_deref().and_then(|latest| behind(&info.version, latest));
+        info.latest_version = latest.clone();
+    }
+}
+
+/// Copy the `checksum` of each `[[package]]` in Cargo.lock onto the matching registry crates
+#[cfg(feature = "native")]
+fn attach_lock_checksums(graph_data: &mut GraphData, lock_path: &Path) -> Result<(), String> {
+    let content = fs::read_to_string(lock_path).map_err(|e| e.to_string())?;
+    let lock = toml::parse(&content)?;
+
+    let mut checksums: HashMap<(&str, &str), &str> = HashMap::new();
+    if let Some(toml::TomlValue::Array(packages)) = lock.get("package") {
+        for package in packages {
+            let field = |key: &str| package.get(key).and_then(toml::TomlValue::as_str);
+            if let (Some(name), Some(version), Some(checksum)) = (field("name"), field("version"), field("checksum")) {
+                checksums.insert((name, version), checksum);
+            }
+        }
+    }
+
+    for info in graph_data.graph.node_weights_mut() {
+        if let Some(checksum) = checksums.get(&(info.name.as_str(), info.version.as_str())) {
+            info.checksum = Some(checksum.to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Put each weakly connected component in its own cluster, when there is more than one
+#[cfg(feature = "native")]
+fn group_components(graph_data: &mut GraphData) {
+    let components = weak_components(&graph_data.graph);
+    if components.len() < 2 {
+        return;
+    }
+
+    for (i, component) in components.iter().enumerate() {
+        let members: BTreeSet<&str> = component.iter()
+            .map(|&idx| &graph_data.graph[idx])
+            .filter(|info| info.is_workspace_member && !info.is_build_script)
+            .map(|info| info.name.as_str())
+            .collect();
+        let name = if members.is_empty() {
+            format!("Component {}", i + 1)
+        } else {
+            members.into_iter().collect::<Vec<_>>().join(", ")
+        };
+
+        let group = NodeGroup { name, color: group_color(i).to_string(), direction: None };
+        for &idx in component {
+            graph_data.graph[idx].group = Some(group.clone());
+        }
+    }
+}
+
+#[cfg(feature = "native")]
+/// One cluster per workspace member with its build.rs and the path dependencies (non-members
+/// without a source) it reaches through path dependencies alone; the ones reached by several
+/// members stay outside
+fn group_workspace_members(graph_data: &mut GraphData) {
+    let graph = &graph_data.graph;
+    let mut members: Vec<NodeIndex> = graph.node_indices()
+        .filter(|&idx| graph[idx].is_workspace_member && !graph[idx].is_build_script)
+        .collect();
+    members.sort_by(|&a, &b| graph[a].name.cmp(&graph[b].name));
+
+    let is_local = |idx: NodeIndex| {
+        let info = &graph[idx];
+        if info.is_workspace_member { info.is_build_script } else { info.source.is_none() }
+    };
+    let mut owners: HashMap<NodeIndex, Vec<usize>> = HashMap::new();
+    for (i, &member) in members.iter().enumerate() {
+        let mut seen: HashSet<NodeIndex> = HashSet::from([member]);
+        let mut stack = vec![member];
+        while let Some(idx) = stack.pop() {
+            owners.entry(idx).or_default().push(i);
+            stack.extend(graph.neighbors(idx).filter(|&next| is_local(next) && seen.insert(next)));
+        }
+    }
+
+    for (idx, owners) in owners {
+        if let [i] = owners[..] {
+            let name = graph_data.graph[members[i]].name.clone();
+            graph_data.graph[idx].group = Some(NodeGroup { name, color: group_color(i).to_string(), direction: None });
+        }
+    }
+}
+
+/// Where depth is measured from: the root packages, or in an inverted graph the crates
+/// nothing points to (the ones passed to --invert)
+#[cfg(feature = "native")]
+fn root_node_indices(graph_data: &GraphData, root_packages: &[(String, String)], inverted: bool) -> Vec<NodeIndex> {
+    let graph = &graph_data.graph;
+    if inverted {
+        return graph.node_indices()
+            .filter(|&idx| graph.neighbors_directed(idx, Direction::Incoming).next().is_none())
+            .collect();
+    }
+    graph
+        .node_indices()
+        .filter(|&idx| {
+            let info = &graph[idx];
+            root_packages.iter().any(|(name, version)| *name == info.name && *version == info.version)
+        })
+        .collect()
+}
+
+// ============================================================================
+// Function Graph - Visitor Implementation
+// ============================================================================
+impl FunctionCollector {
+    /// Qualified names start with the crate name when one is given
+    fn new(crate_name: Option<String>) -> Self {
+        FunctionCollector {
+            module_path: crate_name.iter().map(|name| name.replace('-', "_")).collect(),
+            crate_name,
+            functions: Vec::new(),
+            current_impl_type: None,
+            in_trait_impl: false,
+            config_items: Vec::new(),
+            type_items: Vec::new(),
+            pub_uses: Vec::new(),
+            modules: Vec::new(),
+            impl_features: Vec::new(),
+            gated_modules: Vec::new(),
+        }
+    }
+
+    fn qualified_name(&self, name: &str) -> String {
+        let mut parts = self.module_path.clone();
+        if let Some(ref impl_type) = self.current_impl_type {
+            parts.push(impl_type.clone());
+        }
+        parts.push(name.to_string());
+        parts.join("::")
+    }
+
+    fn format_signature(sig: &syn::Signature) -> String {
+        let inputs: Vec<String> = sig.inputs.iter().map(|arg| {
+            match arg {
+                syn::FnArg::Receiver(r) => {
+                    if r.reference.is_some() {
+                        if r.mutability.is_some() { "&mut self".to_string() }
+                        else { "&self".to_string() }
+                    } else {
+                        "self".to_string()
+                    }
+                }
+                syn::FnArg::Typed(pat) => {
+                    format!("{}", quote::quote!(#pat))
+                }
+            }
+        }).collect();
+
+        let output = match &sig.output {
+            syn::ReturnType::Default => String::new(),
+            syn::ReturnType::Type(_, ty) => format!(" -> {}", quote::quote!(#ty)),
+        };
+
+        format!("fn {}({}){}", sig.ident, inputs.join(", "), output)
+    }
+
+    /// Last segment of every path in the signature, with `Self` as the impl type, sorted
+    fn signature_types(&self, sig: &syn::Signature) -> Vec<String> {
+        struct PathNames(Vec<String>);
+        impl<'ast> Visit<'ast> for PathNames {
+            fn visit_path(&mut self, path: &'ast syn::Path) {
+                if let Some(segment) = path.segments.last() {
+                    self.0.push(segment.ident.to_string());
+                }
+                syn::visit::visit_path(self, path);
+            }
+        }
+
+        let mut names = PathNames(Vec::new());
+        names.visit_signature(sig);
+        let mut names: Vec<String> = names.0.into_iter()
+            .filter_map(|name| match name.as_str() {
+                "Self" => self.current_impl_type.clone(),
+                _ => Some(name),
+            })
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Hash of the token stream, which leaves out comments and formatting
+    fn body_hash(sig: &syn::Signature, block: &syn::Block) -> u64 {
+        let mut sig = sig.clone();
+        sig.ident = syn::Ident::new("f", sig.ident.span());
+        fnv1a_64(quote::quote!(#sig #block).to_string().as_bytes())
+    }
+
+    /// Like `body_hash`, but over the name, visibility and attributes too (doc comments excepted)
+    fn content_hash(attrs: &[syn::Attribute], vis: &syn::Visibility, sig: &syn::Signature, block: &syn::Block) -> u64 {
+        let attrs = attrs.iter().filter(|attr| !attr.path().is_ident("doc"));
+        fnv1a_64(quote::quote!(#(#attrs)* #vis #sig #block).to_string().as_bytes())
+    }
+}
+
+impl<'ast> Visit<'ast> for FunctionCollector {
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        let is_public = matches!(node.vis, syn::Visibility::Public(_));
+        let name = node.sig.ident.to_string();
+        let qualified = self.qualified_name(&name);
+
+        let start = node.sig.ident.span().start();
+        self.functions.push(FunctionDef {
+            name,
+            qualified_name: qualified,
+            is_public,
+            line: start.line,
+            column: start.column,
+            end_line: node.span().end().line,
+            is_method: false,
+            is_entry_point: node.sig.ident == "main" || has_entry_point_attr(&node.attrs),
+            signature: Self::format_signature(&node.sig),
+            is_async: node.sig.asyncness.is_some(),
+            is_unsafe: node.sig.unsafety.is_some(),
+            allow_unreachable: has_allow_unreachable_attr(&node.attrs),
+            crate_name: self.crate_name.clone(),
+            body_hash: Self::body_hash(&node.sig, &node.block),
+            content_hash: Self::content_hash(&node.attrs, &node.vis, &node.sig, &node.block),
+            signature_types: self.signature_types(&node.sig),
+            features: cfg_features(&node.attrs),
+        });
+
+        syn::visit::visit_item_fn(self, node);
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+        // Extract impl type name
+        let type_name = if let syn::Type::Path(type_path) = &*node.self_ty {
+            type_path.path.segments.last()
+                .map(|seg| seg.ident.to_string())
+        } else {
+            None
+        };
+
+        let old_impl = self.current_impl_type.take();
+        let old_in_trait_impl = self.in_trait_impl;
+        self.current_impl_type = type_name;
+        self.in_trait_impl = node.trait_.is_some();
+        let old_features = std::mem::replace(&mut self.impl_features, cfg_features(&node.attrs));
+
+        syn::visit::visit_item_impl(self, node);
+
+        self.current_impl_type = old_impl;
+        self.in_trait_impl = old_in_trait_impl;
+        self.impl_features = old_features;
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+        let is_public = matches!(node.vis, syn::Visibility::Public(_));
+        let name = node.sig.ident.to_string();
+        let qualified = self.qualified_name(&name);
+
+        let start = node.sig.ident.span().start();
+        self.functions.push(FunctionDef {
+            name,
+            qualified_name: qualified,
+            is_public,
+            line: start.line,
+            column: start.column,
+            end_line: node.span().end().line,
+            is_method: self.current_impl_type.is_some(),
+            is_entry_point: self.in_trait_impl || has_entry_point_attr(&node.attrs),
+            signature: FunctionCollector::format_signature(&node.sig),
+            is_async: node.sig.asyncness.is_some(),
+            is_unsafe: node.sig.unsafety.is_some(),
+            allow_unreachable: has_allow_unreachable_attr(&node.attrs),
+            crate_name: self.crate_name.clone(),
+            body_hash: Self::body_hash(&node.sig, &node.block),
+            content_hash: Self::content_hash(&node.attrs, &node.vis, &node.sig, &node.block),
+            signature_types: self.signature_types(&node.sig),
+            features: {
+                let mut features: Vec<String> = self.impl_features.iter().cloned().chain(cfg_features(&node.attrs)).collect();
+                features.sort();
+                features.dedup();
+                features
+            },
+        });
+
+        syn::visit::visit_impl_item_fn(self, node);
+    }
+
+    fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
+        self.module_path.push(node.ident.to_string());
+        self.modules.push((self.module_path.clone(), matches!(node.vis, syn::Visibility::Public(_))));
+        let features = cfg_features(&node.attrs);
+        if !features.is_empty() {
+            self.gated_modules.push((self.module_path.clone(), features));
+        }
+        syn::visit::visit_item_mod(self, node);
+        self.module_path.pop();
+    }
+
+    fn visit_item_const(&mut self, node: &'ast syn::ItemConst) {
+        self.config_items.push((self.qualified_name(&node.ident.to_string()), ConfigKind::Const));
+        syn::visit::visit_item_const(self, node);
+    }
+
+    fn visit_impl_item_const(&mut self, node: &'ast syn::ImplItemConst) {
+        self.config_items.push((self.qualified_name(&node.ident.to_string()), ConfigKind::Const));
+        syn::visit::visit_impl_item_const(self, node);
+    }
+
+    fn visit_item_static(&mut self, node: &'ast syn::ItemStatic) {
+        self.config_items.push((self.qualified_name(&node.ident.to_string()), ConfigKind::Static));
+        syn::visit::visit_item_static(self, node);
+    }
+
+    fn visit_item_use(&mut self, node: &'ast syn::ItemUse) {
+        if matches!(node.vis, syn::Visibility::Public(_)) {
+            let mut names = Vec::new();
+            flatten_use_tree(&node.tree, &mut Vec::new(), &mut names);
+            for (target, alias) in names {
+                self.pub_uses.push(UseItem { module: self.module_path.clone(), target, alias });
+            }
+        }
+    }
+
+    fn visit_item_struct(&mut self, node: &'ast syn::ItemStruct) {
+        self.type_items.push(self.qualified_name(&node.ident.to_string()));
+        syn::visit::visit_item_struct(self, node);
+    }
+
+    fn visit_item_enum(&mut self, node: &'ast syn::ItemEnum) {
+        self.type_items.push(self.qualified_name(&node.ident.to_string()));
+        syn::visit::visit_item_enum(self, node);
+    }
+
+    fn visit_item_union(&mut self, node: &'ast syn::ItemUnion) {
+        self.type_items.push(self.qualified_name(&node.ident.to_string()));
+        syn::visit::visit_item_union(self, node);
+    }
+
+    fn visit_item_trait(&mut self, node: &'ast syn::ItemTrait) {
+        self.type_items.push(self.qualified_name(&node.ident.to_string()));
+        syn::visit::visit_item_trait(self, node);
+    }
+
+    fn visit_item_type(&mut self, node: &'ast syn::ItemType) {
+        self.type_items.push(self.qualified_name(&node.ident.to_string()));
+        syn::visit::visit_item_type(self, node);
+    }
+}
+
+impl CallCollector {
+    fn new(current_function: String) -> Self {
+        CallCollector {
+            current_function,
+            calls: Vec::new(),
+            awaiting: false,
+            in_macro: 0,
+            config_uses: Vec::new(),
+            log_calls: Vec::new(),
+        }
+    }
+
+    fn config_use(&mut self, name: String, is_env: bool) {
+        self.config_uses.push(ConfigUse { function: self.current_function.clone(), name, is_env });
+    }
+
+    /// Kind of a call found now: awaited, else inside macro arguments, else `plain`
+    fn call_kind(&mut self, plain: CallKind) -> CallKind {
+        if std::mem::take(&mut self.awaiting) {
+            CallKind::Await
+        } else if self.in_macro > 0 {
+            CallKind::Macro
+        } else {
+            plain
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for CallCollector {
+    fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
+        // Extract callee name from the function expression
+        let kind = self.call_kind(CallKind::Direct);
+        let callee = extract_call_name(&node.func);
+        if let (Some(name), Some(variable)) = (&callee, node.args.first().and_then(string_literal)) {
+            if name.ends_with("env::var") || name.ends_with("env::var_os") {
+                self.config_use(variable, true);
+            }
+        }
+        if let Some(name) = callee {
+            let start = call_name_span(&node.func).start();
+            self.calls.push(CallInfo {
+                caller: self.current_function.clone(),
+                callee: name,
+                kind,
+                file_path: String::new(),
+                line: start.line,
+                column: start.column,
+            });
+        }
+        syn::visit::visit_expr_call(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
+        let kind = self.call_kind(CallKind::Method);
+        let method_name = node.method.to_string();
+        let start = node.method.span().start();
+        self.calls.push(CallInfo {
+            caller: self.current_function.clone(),
+            callee: method_name,
+            kind,
+            file_path: String::new(),
+            line: start.line,
+            column: start.column,
+        });
+        syn::visit::visit_expr_method_call(self, node);
+    }
+
+    /// Paths in SCREAMING_CASE may name a const or static; the ones defined in the sources are kept
+    fn visit_expr_path(&mut self, node: &'ast syn::ExprPath) {
+        let last = node.path.segments.last().map(|seg| seg.ident.to_string()).unwrap_or_default();
+        if last.chars().any(|c| c.is_ascii_uppercase()) && !last.chars().any(|c| c.is_ascii_lowercase()) {
+            let name = node.path.segments.iter().map(|seg| seg.ident.to_string()).collect::<Vec<_>>().join("::");
+            self.config_use(name, false);
+        }
+        syn::visit::visit_expr_path(self, node);
+    }
+
+    fn visit_expr_await(&mut self, node: &'ast syn::ExprAwait) {
+        self.awaiting = matches!(*node.base, syn::Expr::Call(_) | syn::Expr::MethodCall(_));
+        syn::visit::visit_expr_await(self, node);
+    }
+
+    /// Arguments of expression-like macros (`println!`, `vec!`, `assert_eq!`, ...);
+    /// other macro bodies are opaque tokens
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        if let Some(call) = parse_log_macro(node) {
+            self.log_calls.push((self.current_function.clone(), call));
+        }
+        let Ok(args) = node.parse_body_with(Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated) else {
+            return;
+        };
+        if node.path.is_ident("env") || node.path.is_ident("option_env") {
+            if let Some(variable) = args.first().and_then(string_literal) {
+                self.config_use(variable, true);
+            }
+        }
+        self.in_macro += 1;
+        for expr in &args {
+            Visit::visit_expr(self, expr);
+        }
+        self.in_macro -= 1;
+    }
+}
+
+fn extract_call_name(expr: &syn::Expr) -> Option<String> {
+    match expr {
+        syn::Expr::Path(path) => {
+            Some(path.path.segments.iter()
+                .map(|seg| seg.ident.to_string())
+                .collect::<Vec<_>>()
+                .join("::"))
+        }
+        _ => None,
+    }
+}
+
+fn string_literal(expr: &syn::Expr) -> Option<String> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit), .. }) => Some(lit.value()),
+        _ => None,
+    }
+}
+
+/// Attributes that make a function callable from outside the call graph
+fn has_entry_point_attr(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path().segments.last().is_some_and(|seg| {
+            matches!(seg.ident.to_string().as_str(), "test" | "bench" | "no_mangle" | "export_name")
+        })
+    })
+}
+
+/// One cluster per set of cargo features gating functions, named `a` or `a, b`, colored in
+/// name order after the "always" cluster of ungated ones
+fn group_by_features(graph_data: &mut FnGraphData) {
+    let names: BTreeSet<String> = graph_data.graph.node_weights()
+        .filter(|info| !info.features.is_empty())
+        .map(|info| info.features.join(", "))
+        .collect();
+    for info in graph_data.graph.node_weights_mut() {
+        let (name, index) = match info.features.is_empty() {
+            true => ("always".to_string(), 0),
+            false => {
+                let name = info.features.join(", ");
+                let index = names.iter().position(|n| *n == name).unwrap_or(0) + 1;
+                (name, index)
+            }
+        };
+        info.group = Some(NodeGroup { name, color: group_color(index).to_string(), direction: None });
+    }
+}
+
+/// Cargo features a `#[cfg]` requires: `feature = "x"`, also inside `all(...)` and `any(...)`
+/// (not `not(...)`), sorted
+fn cfg_features(attrs: &[syn::Attribute]) -> Vec<String> {
+    fn collect(meta: &syn::Meta, features: &mut Vec<String>) {
+        match meta {
+            syn::Meta::NameValue(name_value) if name_value.path.is_ident("feature") => {
+                if let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(feature), .. }) = &name_value.value {
+                    features.push(feature.value());
+                }
+            }
+            syn::Meta::List(list) if list.path.is_ident("all") || list.path.is_ident("any") => {
+                if let Ok(nested) = list.parse_args_with(Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated) {
+                    nested.iter().for_each(|meta| collect(meta, features));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut features = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("cfg")) {
+        if let Ok(meta) = attr.parse_args::<syn::Meta>() {
+            collect(&meta, &mut features);
+        }
+    }
+    features.sort();
+    features.dedup();
+    features
+}
+
+/// `#[allow(grapher::unreachable)]`, also inside `#[cfg_attr(<cfg>, allow(...))]`, the form
+/// that builds on stable (rustc rejects the unknown `grapher` lint tool otherwise)
+fn has_allow_unreachable_attr(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| allows_unreachable(&attr.meta))
+}
+
+fn allows_unreachable(meta: &syn::Meta) -> bool {
+    let syn::Meta::List(list) = meta else {
+        return false;
+    };
+    let Ok(nested) = list.parse_args_with(Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated) else {
+        return false;
+    };
+
+    if list.path.is_ident("allow") {
+        nested.iter().any(|meta| {
+            let segments: Vec<String> = meta.path().segments.iter().map(|seg| seg.ident.to_string()).collect();
+            segments == ["grapher", "unreachable"]
+        })
+    } else if list.path.is_ident("cfg_attr") {
+        // The first argument is the condition
+        nested.iter().skip(1).any(allows_unreachable)
+    } else {
+        false
+    }
+}
+
+/// Span of the last path segment (`bar` in `foo::bar()`), or the whole callee expression
+fn call_name_span(expr: &syn::Expr) -> proc_macro2::Span {
+    match expr {
+        syn::Expr::Path(path) => path.path.segments.last().map_or_else(|| expr.span(), |seg| seg.ident.span()),
+        _ => expr.span(),
+    }
+}
+
+// ============================================================================
+// Function Graph - Main Logic
+// ============================================================================
+
+/// Functions and calls collected from the parsed source files
+#[derive(Default)]
+struct ParsedSources {
+    functions: Vec<(FunctionDef, String)>,
+    calls: Vec<CallInfo>,
+    config_items: Vec<(String, ConfigKind)>,
+    config_uses: Vec<ConfigUse>,
+    type_items: Vec<String>,
+    /// `pub use` items and `mod` visibility, with module paths from the crate root
+    pub_uses: Vec<UseItem>,
+    modules: HashMap<String, bool>,
+    /// Crate root module names (`crate` for sources outside a crate) and each function's path from it
+    crate_roots: HashSet<String>,
+    definition_paths: HashMap<String, String>,
+    /// Cargo features of `#[cfg]`-gated modules (and `#![cfg]` files), by path from the crate root
+    module_features: HashMap<String, Vec<String>>,
+    log_calls: Vec<(String, LogCall)>,
+    locations: SourceLocations,
+}
+
+impl ParsedSources {
+    /// Collect the functions and calls of one file; `path` is the one shown on nodes
+    fn add_file(&mut self, path: String, absolute_path: PathBuf, content: &str, crate_name: Option<String>) -> syn::Result<()> {
+        let syntax = syn::parse_file(content)?;
+
+        // Collect function definitions
+        let mut collector = FunctionCollector::new(crate_name);
+        collector.visit_file(&syntax);
+
+        // Module paths from the crate root: the collector's follow the crate name with the inline modules only
+        let root = collector.module_path.first().cloned().unwrap_or_else(|| "crate".to_string());
+        let prefix_len = collector.module_path.len();
+        let file_modules = file_modules(&path);
+        let from_root = |path: &[String]| -> Vec<String> {
+            std::iter::once(root.clone()).chain(file_modules.iter().cloned()).chain(path[prefix_len..].iter().cloned()).collect()
+        };
+        for item in collector.pub_uses.drain(..) {
+            self.pub_uses.push(UseItem { module: from_root(&item.module), ..item });
+        }
+        for (module, is_pub) in collector.modules.drain(..) {
+            self.modules.insert(from_root(&module).join("::"), is_pub);
+        }
+        for (module, features) in collector.gated_modules.drain(..) {
+            self.module_features.entry(from_root(&module).join("::")).or_default().extend(features);
+        }
+        let file_features = cfg_features(&syntax.attrs);
+        if !file_features.is_empty() {
+            self.module_features.entry(from_root(&collector.module_path).join("::")).or_default().extend(file_features);
+        }
+        for func in &collector.functions {
+            let segments: Vec<String> = func.qualified_name.split("::").map(str::to_string).collect();
+            self.definition_paths.entry(func.qualified_name.clone()).or_insert_with(|| from_root(&segments).join("::"));
+        }
+        self.crate_roots.insert(root);
+
+        // For each function, collect calls
+        let first_call = self.calls.len();
+        for calls in collect_calls_from_file(&syntax, &collector.module_path) {
+            self.calls.extend(calls.calls);
+            self.config_uses.extend(calls.config_uses);
+            self.log_calls.extend(calls.log_calls);
+        }
+        for call in &mut self.calls[first_call..] {
+            call.file_path = path.clone();
+        }
+        self.config_items.extend(collector.config_items);
+        self.type_items.extend(collector.type_items);
+
+        for func in collector.functions {
+            self.functions.push((func, path.clone()));
+        }
+
+        self.locations.files.insert(path, absolute_path);
+        Ok(())
+    }
+
+    /// Cargo features gating a function: its own and its impl's, and those of its modules
+    fn features_of(&self, func: &FunctionDef) -> Vec<String> {
+        let mut features = func.features.clone();
+        if let Some(path) = self.definition_paths.get(&func.qualified_name) {
+            let segments: Vec<&str> = path.split("::").collect();
+            for end in 1..segments.len() {
+                if let Some(module_features) = self.module_features.get(&segments[..end].join("::")) {
+                    features.extend(module_features.iter().cloned());
+                }
+            }
+        }
+        features.sort();
+        features.dedup();
+        features
+    }
+}
+
+#[cfg(feature = "native")]
+pub fn run_fn_graph(args: &FnGraphArgs) -> Result<(String, Option<PathBuf>), Box<dyn std::error::Error>> {
+    let filter = args.filter.as_deref()
+        .map(|source| FilterExpr::parse(source, &filter_attributes::<FnNodeInfo>()))
+        .transpose()?;
+    let groups = args.groups.as_deref().map(load_groups).transpose()?;
+    let profile = args.profile_data.as_deref().map(load_profile).transpose()?;
+    let roots = args.roots.as_deref().map(load_roots).transpose()?.unwrap_or_default();
+    let sizes = args.size_data.as_deref().map(load_sizes).transpose()?;
+
+    check_fn_graph_args(args)?;
+
+    let (mut graph_data, locations) = load_fn_graph(args)?;
+
+    if let Some(ref command) = args.metric_script {
+        attach_metrics(&mut graph_data, "fn", &mut MetricScript { command: command.clone() })?;
+    }
+
+    // Nodes of --workspace and --crate-name graphs know their crate; a --source-dir graph gets it from the manifest
+    let knows_crate = args.workspace || args.crate_name.is_some() || args.root_name.is_some();
+    let symbol_crates: Vec<String> = if knows_crate { Vec::new() } else { source_package_name(args).into_iter().collect() };
+
+    if let Some(ref stacks) = profile {
+        let matched = apply_profile(&mut graph_data, stacks, &symbol_crates);
+        if matched.matched == 0 {
+            warn(WarningCode::NoMatch, format!("none of the {} profile samples matched a function in the graph", matched.total));
+        }
+    }
+
+    if let Some(ref sizes) = sizes {
+        let matched = apply_sizes(&mut graph_data, sizes, &symbol_crates);
+        if matched.matched == 0 {
+            warn(WarningCode::NoMatch, format!("none of the {} bytes of function code matched a function in the graph", matched.total));
+        }
+    }
+
+    if args.churn {
+        apply_churn(&mut graph_data, &locations);
+    }
+
+    let output = render_fn_graph(&mut graph_data, args, filter.as_ref(), groups.as_deref(), &roots, &locations)?;
+
+    if let Some(ref index_path) = args.index {
+        write_atomic(index_path, generate_fn_index(&graph_data, args))?;
+        eprintln!("Index written to: {}", index_path.display());
+    }
+
+    if let Some(ref table_path) = args.log_table {
+        write_atomic(table_path, generate_log_table(&graph_data))?;
+        eprintln!("Log table written to: {}", table_path.display());
+    }
+
+    if let Some(ref badges_dir) = args.badges {
+        write_badges(badges_dir, &fn_badges(&graph_data))?;
+    }
+
+    let output_path = match args.out_dir {
+        Some(ref dir) => {
+            let diagram = diagram_file_name(&args.format, args.no_fence);
+            if let Some(format) = args.detail_pages {
+                write_fn_pages(&graph_data, dir, format, diagram)?;
+            }
+            Some(dir.join(diagram))
+        }
+        None => args.output.clone(),
+    };
+
+    Ok((output, output_path))
+}
+
+/// Parse the sources `args` point at and build the unfiltered function graph
+#[cfg(feature = "native")]
+pub fn load_fn_graph(args: &FnGraphArgs) -> Result<(FnGraphData, SourceLocations), Box<dyn std::error::Error>> {
+    let source_roots = collect_source_roots(args)?;
+
+    let mut sources = ParsedSources::default();
+    for root in &source_roots {
+        // Collect all Rust files
+        let rust_files: Vec<_> = WalkDir::new(&root.dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().extension().is_some_and(|ext| ext == "rs"))
+            .collect();
+
+        // Parse each file; unreadable files and syntax errors are skipped
+        for entry in rust_files {
+            let file_path = entry.path();
+            let content = match fs::read_to_string(file_path) {
+                Ok(c) => c,
+                Err(_) => continue,
+            };
+
+            let relative_path = file_path.strip_prefix(&root.base)
+                .unwrap_or(file_path)
+                .to_string_lossy()
+                .to_string();
+            let absolute_path = fs::canonicalize(file_path).unwrap_or_else(|_| file_path.to_path_buf());
+
+            if let Err(e) = sources.add_file(relative_path.clone(), absolute_path, &content, root.crate_name.clone()) {
+                let start = e.span().start();
+                warn(WarningCode::ParseError, format!("skipping {}:{}:{}: {}", relative_path, start.line, start.column + 1, e));
+            }
+        }
+    }
+
+    let resolved = match args.resolver {
+        Resolver::Ra => resolve_calls_with_ra(&sources, &project_root(args)),
+        Resolver::Heuristic => Vec::new(),
+    };
+    let graph_data = build_fn_graph(&mut sources, &resolved, args);
+    Ok((graph_data, sources.locations))
+}
+
+/// Function graph of in-memory sources, given as `(path, content)` pairs, rendered as `args.format`.
+/// Nothing touches the disk: `--source-dir`, `--workspace`, `--resolver ra`, `--metric-script`
+/// and the file options (`--groups`, `--profile-data`, ...) are ignored. A file that does not
+/// parse is an error.
+pub fn fn_graph_from_sources(sources: &[(&str, &str)], args: &FnGraphArgs) -> Result<String, Box<dyn std::error::Error>> {
+    let filter = args.filter.as_deref()
+        .map(|source| FilterExpr::parse(source, &filter_attributes::<FnNodeInfo>()))
+        .transpose()?;
+    check_fn_graph_args(args)?;
+
+    let mut parsed = ParsedSources::default();
+    for &(path, content) in sources {
+        let crate_name = args.root_name.clone().filter(|name| !name.is_empty());
+        parsed.add_file(path.to_string(), PathBuf::from(path), content, crate_name).map_err(|e| {
+            let start = e.span().start();
+            format!("{}:{}:{}: {}", path, start.line, start.column + 1, e)
+        })?;
+    }
+
+    let mut graph_data = build_fn_graph(&mut parsed, &[], args);
+    render_fn_graph(&mut graph_data, args, filter.as_ref(), None, &[], &parsed.locations)
+}
+
+fn check_fn_graph_args(args: &FnGraphArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if matches!(args.format, OutputFormat::CallHierarchy) && args.focus.is_none() {
+        return Err("--format call-hierarchy requires --focus".into());
+    }
+    if args.config_map && !matches!(args.format, OutputFormat::Mermaid | OutputFormat::Dot | OutputFormat::Json) {
+        return Err(format!("--format {} is not available with --config-map", args.format.name()).into());
+    }
+    if args.type_map && !matches!(args.format, OutputFormat::Mermaid | OutputFormat::Dot | OutputFormat::Json) {
+        return Err(format!("--format {} is not available with --type-map", args.format.name()).into());
+    }
+    Ok(())
+}
+
+/// rust-analyzer's answer for each call: the qualified name of its definition, `Some(None)`
+/// for a definition outside the graph, `None` (or a shorter list) where name matching decides
+#[cfg(feature = "native")]
+fn resolve_calls_with_ra(sources: &ParsedSources, project_root: &Path) -> Vec<Option<Option<String>>> {
+    let mut analyzer = match RustAnalyzer::start(project_root) {
+        Ok(analyzer) => analyzer,
+        Err(e) => {
+            warn(WarningCode::UnresolvedCall, format!("{}; falling back to heuristic resolution", e));
+            return Vec::new();
+        }
+    };
+
+    // (absolute file, line of the name) -> qualified name, to map definitions back to nodes
+    let definitions: HashMap<(&PathBuf, usize), &String> = sources.functions.iter()
+        .filter_map(|(f, file_path)| Some(((sources.locations.files.get(file_path)?, f.line), &f.qualified_name)))
+        .collect();
+
+    let mut resolved = Vec::new();
+    for call in &sources.calls {
+        let Some(file) = sources.locations.files.get(&call.file_path) else {
+            resolved.push(None);
+            continue;
+        };
+        match analyzer.definition(file, call.line, call.column) {
+            Ok(Some((path, line))) => resolved.push(Some(definitions.get(&(&path, line)).map(|name| name.to_string()))),
+            Ok(None) => resolved.push(None),
+            Err(e) => {
+                warn(WarningCode::UnresolvedCall, format!("{}; falling back to heuristic resolution", e));
+                break;
+            }
+        }
+    }
+    resolved
+}
+
+/// A node per function and an edge per call between two of them; calls without an
+/// answer in `resolved` are matched to functions by name
+fn build_fn_graph(sources: &mut ParsedSources, resolved: &[Option<Option<String>>], args: &FnGraphArgs) -> FnGraphData {
+    let mut graph_data = FnGraphData::new();
+
+    // Create function name -> qualified_name lookup
+    let fn_lookup: HashMap<String, String> = sources.functions.iter()
+        .map(|(f, _)| (f.name.clone(), f.qualified_name.clone()))
+        .collect();
+
+    for pattern in args.exclude.iter().filter(|pattern| !args.file_patterns.contains(pattern)) {
+        let patterns = std::slice::from_ref(pattern);
+        if !sources.functions.iter().any(|(f, _)| matches_any_pattern(&f.name, patterns) || matches_any_pattern(&f.qualified_name, patterns)) {
+            warn(WarningCode::NoMatch, format!("--exclude {} matches no function", pattern));
+        }
+    }
 
     // Add nodes
-    for (func, file_path) in &all_functions {
+    for (func, file_path) in &sources.functions {
         // Apply filters
         if args.public_only && !func.is_public {
             continue;
@@ -459,9 +1883,30 @@ pub fn run_fn_graph(args: &FnGraphArgs) -> Result<(String, Option<PathBuf>), Box
             qualified_name: func.qualified_name.clone(),
             file_path: file_path.clone(),
             line: func.line,
+            column: func.column,
+            end_line: func.end_line,
+            is_method: func.is_method,
+            is_entry_point: func.is_entry_point,
             is_public: func.is_public,
             signature: if args.show_signatures { Some(func.signature.clone()) } else { None },
             is_async: func.is_async,
+            is_unsafe: func.is_unsafe,
+            allow_unreachable: func.allow_unreachable,
+            crate_name: func.crate_name.clone(),
+            body_hash: Some(func.body_hash),
+            content_hash: Some(func.content_hash),
+            config: Vec::new(),
+            logs: Vec::new(),
+            capabilities: Vec::new(),
+            types: Vec::new(),
+            features: sources.features_of(func),
+            public_paths: Vec::new(),
+            reexports: Vec::new(),
+            depth: None,
+            last_modified: None,
+            is_context: false,
+            group: None,
+            metrics: BTreeMap::new(),
         };
 
         let idx = graph_data.graph.add_node(node_info);
@@ -469,58 +1914,465 @@ pub fn run_fn_graph(args: &FnGraphArgs) -> Result<(String, Option<PathBuf>), Box
     }
 
     // Add edges
-    for call in &all_calls {
-        // Try to resolve callee to a known function
-        let callee_qualified = fn_lookup.get(&call.callee)
-            .cloned()
-            .unwrap_or_else(|| call.callee.clone());
+    for (i, call) in sources.calls.iter().enumerate() {
+        if !args.edge_kinds.is_empty() && !args.edge_kinds.contains(&call.kind) {
+            continue;
+        }
+        // rust-analyzer decides when it could; a definition outside the graph means no edge.
+        // Otherwise try to resolve callee to a known function by name
+        let callee_qualified = match resolved.get(i) {
+            Some(Some(Some(name))) => name.clone(),
+            Some(Some(None)) => continue,
+            _ => fn_lookup.get(&call.callee)
+                .cloned()
+                .unwrap_or_else(|| call.callee.clone()),
+        };
 
         if let (Some(&from_idx), Some(&to_idx)) = (
             graph_data.node_indices.get(&call.caller),
             graph_data.node_indices.get(&callee_qualified),
         ) {
             // Avoid self-loops and duplicate edges
-            if from_idx != to_idx && !graph_data.graph.contains_edge(from_idx, to_idx) {
-                graph_data.graph.add_edge(from_idx, to_idx, call.kind);
+            if from_idx == to_idx {
+                continue;
             }
+            if !graph_data.graph.contains_edge(from_idx, to_idx) {
+                graph_data.graph.add_edge(from_idx, to_idx, call.kind.into());
+            }
+            sources.locations.call_sites
+                .entry((call.caller.clone(), callee_qualified))
+                .or_default()
+                .push((call.line, call.column));
+        }
+    }
+
+    if args.public_only {
+        graph_data.note_filter("--public-only".to_string());
+    }
+    for pattern in args.exclude.iter().filter(|pattern| !args.file_patterns.contains(pattern)) {
+        graph_data.note_filter(format!("--exclude {}", pattern));
+    }
+    for file in &args.exclude_from {
+        graph_data.note_filter(format!("--exclude-from {}", file.display()));
+    }
+    if !args.edge_kinds.is_empty() {
+        let kinds: Vec<&str> = args.edge_kinds.iter().map(|kind| kind.as_str()).collect();
+        graph_data.note_filter(format!("--edge-kinds {}", kinds.join(",")));
+    }
+
+    attach_config_reads(&mut graph_data, sources);
+    attach_signature_types(&mut graph_data, sources);
+    if args.reexports {
+        attach_reexports(&mut graph_data, sources);
+    }
+    for call in &sources.calls {
+        let (Some(&idx), Some(capability)) = (graph_data.node_indices.get(&call.caller), capability_of(&call.callee)) else { continue };
+        let capabilities = &mut graph_data.graph[idx].capabilities;
+        if let Err(pos) = capabilities.binary_search(&capability) {
+            capabilities.insert(pos, capability);
+        }
+    }
+    for (function, call) in &sources.log_calls {
+        if let Some(&idx) = graph_data.node_indices.get(function) {
+            graph_data.graph[idx].logs.push(call.clone());
+        }
+    }
+    graph_data
+}
+
+/// Resolve the config uses to the const/static items of the sources (by the end of the path
+/// as written, the name alone when that is ambiguous) and record them on their functions
+fn attach_config_reads(graph_data: &mut FnGraphData, sources: &ParsedSources) {
+    let mut items: HashMap<&str, Vec<&(String, ConfigKind)>> = HashMap::new();
+    for item in &sources.config_items {
+        let name = item.0.rsplit("::").next().unwrap_or(&item.0);
+        items.entry(name).or_default().push(item);
+    }
+
+    for config_use in &sources.config_uses {
+        let Some(&idx) = graph_data.node_indices.get(&config_use.function) else { continue };
+        let read = if config_use.is_env {
+            ConfigRead { name: config_use.name.clone(), kind: ConfigKind::Env }
+        } else {
+            let name = config_use.name.rsplit("::").next().unwrap_or(&config_use.name);
+            let Some(candidates) = items.get(name) else { continue };
+            let suffix = format!("::{}", config_use.name);
+            let (qualified, kind) = candidates.iter()
+                .find(|(qualified, _)| qualified == &config_use.name || qualified.ends_with(&suffix))
+                .unwrap_or(&candidates[0]);
+            ConfigRead { name: qualified.clone(), kind: *kind }
+        };
+
+        let config = &mut graph_data.graph[idx].config;
+        if let Err(pos) = config.binary_search(&read) {
+            config.insert(pos, read);
+        }
+    }
+}
+
+/// Resolve the names in each function's signature to the types of the sources (the one sharing
+/// the most leading path segments with the function when several have the name) and record them
+fn attach_signature_types(graph_data: &mut FnGraphData, sources: &ParsedSources) {
+    let mut items: HashMap<&str, Vec<&str>> = HashMap::new();
+    for item in &sources.type_items {
+        items.entry(item.rsplit("::").next().unwrap_or(item)).or_default().push(item);
+    }
+
+    for (func, _) in &sources.functions {
+        let Some(&idx) = graph_data.node_indices.get(&func.qualified_name) else { continue };
+        let shared_segments = |qualified: &str| {
+            qualified.split("::").zip(func.qualified_name.split("::")).take_while(|(a, b)| a == b).count()
+        };
+        let mut types: Vec<String> = func.signature_types.iter()
+            .filter_map(|name| items.get(name.as_str()))
+            .filter_map(|candidates| candidates.iter().rev().max_by_key(|qualified| shared_segments(qualified)))
+            .map(|qualified| qualified.to_string())
+            .collect();
+        types.sort();
+        types.dedup();
+        graph_data.graph[idx].types = types;
+    }
+}
+
+/// Record on each function the paths `pub use` chains re-export it under, and the public ones
+fn attach_reexports(graph_data: &mut FnGraphData, sources: &ParsedSources) {
+    let uses: Vec<ResolvedUse> = sources.pub_uses.iter().filter_map(|item| resolve_use(item, &sources.crate_roots)).collect();
+
+    let mut multiple = 0;
+    for (func, _) in &sources.functions {
+        // Only `pub` items can be re-exported
+        if !func.is_public {
+            continue;
+        }
+        let (Some(&idx), Some(definition)) = (graph_data.node_indices.get(&func.qualified_name), sources.definition_paths.get(&func.qualified_name)) else { continue };
+        let reexports = reexports_of(definition, &uses);
+        let mut public_paths: Vec<String> = is_public_path(definition, &sources.modules)
+            .then(|| definition.clone())
+            .into_iter()
+            .chain(reexports.iter().map(|reexport| reexport.path.clone()).filter(|path| is_public_path(path, &sources.modules)))
+            .collect();
+        public_paths.sort_by(|a, b| (a.matches("::").count(), a).cmp(&(b.matches("::").count(), b)));
+        public_paths.dedup();
+
+        if public_paths.len() > 1 {
+            multiple += 1;
+        }
+        let info = &mut graph_data.graph[idx];
+        info.public_paths = public_paths;
+        info.reexports = reexports;
+    }
+    if multiple > 0 {
+        eprintln!("{} functions are public under several paths", multiple);
+    }
+}
+
+/// Analyses, filters, coloring and output, once all data is attached to the nodes
+pub fn render_fn_graph(
+    graph_data: &mut FnGraphData,
+    args: &FnGraphArgs,
+    filter: Option<&FilterExpr>,
+    groups: Option<&[GroupRule]>,
+    roots: &[String],
+    locations: &SourceLocations,
+) -> Result<String, Box<dyn std::error::Error>> {
+    if args.logs {
+        apply_log_counts(graph_data);
+    }
+
+    // Analyses look at the whole graph, before any filtering
+    let findings = if matches!(args.format, OutputFormat::Sarif) {
+        find_unreachable_functions(graph_data, roots)
+    } else {
+        Vec::new()
+    };
+
+    // Apply filter expression
+    if let Some(expr) = filter {
+        let roots = fn_depth_roots(graph_data, args.focus.as_deref());
+        let description = format!("--filter '{}'", args.filter.as_deref().unwrap_or_default());
+        graph_data.apply_filter(description, |graph_data| filter_by_expr(graph_data, expr, &roots));
+    }
+
+    if !args.only_files.is_empty() {
+        // --source-dir paths are shown relative to it, but may be given from the project root
+        let source_dir = (!args.workspace && args.crate_name.is_none()).then_some(args.source_dir.as_path());
+        let description = format!("--only-files {}", args.only_files.join(","));
+        graph_data.apply_filter(description, |graph_data| filter_by_files(graph_data, &args.only_files, source_dir));
+    }
+
+    if let (Some(from), Some(to)) = (&args.slice_from, &args.slice_to) {
+        let description = format!("--slice-from {} --slice-to {}", from, to);
+        graph_data.apply_filter(description, |graph_data| slice_between(graph_data, from, to))?;
+        if graph_data.graph.node_count() == 0 {
+            warn(WarningCode::NoMatch, format!("no call path leads from '{}' to '{}'", from, to));
         }
     }
 
+    if let Some(kinds) = &args.capability_view {
+        let kinds_list: Vec<&str> = kinds.iter().map(|kind| kind.as_str()).collect();
+        let description = format!("--capability-view {}", kinds_list.join(",")).trim_end().to_string();
+        graph_data.apply_filter(description, |graph_data| capability_view(graph_data, kinds));
+    }
+
     // Apply focus filter
     if let Some(ref focus_fn) = args.focus {
-        filter_fn_by_focus(&mut graph_data, focus_fn, args.depth);
+        let description = match args.depth {
+            0 => format!("--focus {}", focus_fn),
+            depth => format!("--focus {} --depth {}", focus_fn, depth),
+        };
+        graph_data.apply_filter(description, |graph_data| filter_by_focus(graph_data, focus_fn, args.depth));
+    }
+
+    if let Some(rounds) = args.prune_leaves {
+        graph_data.apply_filter(format!("--prune-leaves {}", rounds), |graph_data| prune_leaves(graph_data, rounds));
+    }
+    if let Some(min) = args.min_fanin {
+        graph_data.apply_filter(format!("--min-fanin {}", min), |graph_data| prune_min_fanin(graph_data, min));
+    }
+    if let Some(size) = args.sample {
+        graph_data.apply_filter(format!("--sample {}", size), |graph_data| sample_graph(graph_data, size));
+    }
+    if let Some(max) = args.max_edges_per_node.filter(|_| !matches!(args.format, OutputFormat::Json | OutputFormat::CallHierarchy | OutputFormat::Sarif)) {
+        let description = format!("--max-edges-per-node {}", max);
+        graph_data.apply_filter(description, |graph_data| thin_edges(graph_data, max, |edge| edge.samples.unwrap_or(0)));
+    }
+    if let Some(explanation) = empty_graph_explanation(graph_data) {
+        return Err(explanation.into());
+    }
+
+    // After the check above: a page may hold a few unconnected nodes
+    if let Some(page_size) = args.page_size {
+        let description = format!("--page {} --page-size {}", args.page, page_size);
+        let pages = graph_data.apply_filter(description, |graph_data| page_graph(graph_data, args.page, page_size))?;
+        if args.page < pages {
+            eprintln!("Page {} of {}; --page {} for the next", args.page, pages, args.page + 1);
+        } else {
+            eprintln!("Page {} of {}", args.page, pages);
+        }
+    }
+
+    // Depth coloring
+    if args.color_by == Some(ColorBy::Depth) {
+        let roots = fn_depth_roots(graph_data, args.focus.as_deref());
+        let depths = compute_depths(&graph_data.graph, &roots);
+        for (idx, depth) in depths {
+            graph_data.graph[idx].depth = Some(depth);
+        }
+    }
+
+    if let Some(rules) = groups {
+        for info in graph_data.graph.node_weights_mut() {
+            info.group = assign_group(rules, &[&info.name, &info.qualified_name]);
+        }
+    }
+    if args.cluster_features {
+        group_by_features(graph_data);
+    }
+
+    if let (Some(ColorBy::Metric), Some(metric)) = (args.color_by, &args.color_metric) {
+        if !has_metric(graph_data, metric) {
+            warn(WarningCode::NoMatch, format!("no function has metric '{}'; nothing is colored", metric));
+        }
+    }
+
+    if args.warn_collisions {
+        warn_collisions(&fn_node_ids(graph_data, args, uses_mermaid_ids(&args.format)));
+    }
+
+    // Config items and the functions reading them, instead of the calls
+    if args.config_map {
+        return Ok(match args.format {
+            OutputFormat::Dot => generate_config_map_dot(graph_data, args),
+            OutputFormat::Json => generate_config_map_json(graph_data, args),
+            _ => generate_config_map_mermaid(graph_data, args),
+        });
+    }
+
+    // Types and the functions taking or returning them, instead of the calls
+    if args.type_map {
+        return Ok(match args.format {
+            OutputFormat::Dot => generate_type_map_dot(graph_data, args),
+            OutputFormat::Json => generate_type_map_json(graph_data, args),
+            _ => generate_type_map_mermaid(graph_data, args),
+        });
     }
 
     // Generate output
-    let output = match args.format {
-        OutputFormat::Mermaid => generate_fn_mermaid(&graph_data, args),
-        OutputFormat::Dot => generate_fn_dot(&graph_data, args),
-        OutputFormat::Json => generate_fn_json(&graph_data, args),
-    };
+    Ok(match args.format {
+        OutputFormat::Mermaid => generate_fn_mermaid(graph_data, args),
+        OutputFormat::Dot => generate_fn_dot(graph_data, args),
+        OutputFormat::Json => generate_fn_json(graph_data, args),
+        OutputFormat::VscodeGraph => generate_fn_vscode_graph(graph_data, args, locations),
+        OutputFormat::GhSummary => generate_fn_gh_summary(graph_data, args),
+        OutputFormat::CallHierarchy => {
+            let focus_fn = args.focus.as_deref().unwrap_or_default();
+            let focus = focus_nodes(&graph_data.graph, focus_fn);
+            if focus.is_empty() {
+                return Err(format!("No function matches --focus '{}'", focus_fn).into());
+            }
+            generate_fn_call_hierarchy(graph_data, &focus, locations)
+        }
+        OutputFormat::Sarif => generate_sarif(&findings, locations),
+    })
+}
+
+/// Keep the functions defined in files matching `patterns` and, as context, the functions of
+/// other files calling or called by them; calls between two context functions are dropped
+fn filter_by_files(graph_data: &mut FnGraphData, patterns: &[String], source_dir: Option<&Path>) {
+    let graph = &graph_data.graph;
+    let inside: HashSet<NodeIndex> = graph.node_indices()
+        .filter(|&idx| {
+            let file = graph[idx].file_path.replace('\\', "/");
+            matches_any_pattern(&file, patterns)
+                || source_dir.is_some_and(|dir| matches_any_pattern(&dir.join(&file).to_string_lossy().replace('\\', "/"), patterns))
+        })
+        .collect();
+    let context: HashSet<NodeIndex> = inside.iter()
+        .flat_map(|&idx| graph.neighbors_undirected(idx))
+        .filter(|idx| !inside.contains(idx))
+        .collect();
+
+    graph_data.graph.retain_edges(|graph, edge| {
+        graph.edge_endpoints(edge).is_some_and(|(from, to)| inside.contains(&from) || inside.contains(&to))
+    });
+    for &idx in &context {
+        graph_data.graph[idx].is_context = true;
+    }
+    graph_data.retain_nodes(|idx| inside.contains(&idx) || context.contains(&idx));
+}
+
+/// Directory rust-analyzer should load: the workspace, the `--crate` package, or the package around `--source-dir`
+#[cfg(feature = "native")]
+fn project_root(args: &FnGraphArgs) -> PathBuf {
+    if let Some(pkg) = args.crate_name.as_deref().and_then(|spec| dependency_package(args, spec).ok()) {
+        return pkg.manifest_path.parent().map_or_else(|| PathBuf::from("."), |dir| dir.as_std_path().to_path_buf());
+    }
+    if args.workspace {
+        let manifest = fs::canonicalize(&args.manifest_path).unwrap_or_else(|_| args.manifest_path.clone());
+        return manifest.parent().map(PathBuf::from).unwrap_or_else(|| PathBuf::from("."));
+    }
 
-    Ok((output, args.output.clone()))
+    let source_dir = fs::canonicalize(&args.source_dir).unwrap_or_else(|_| args.source_dir.clone());
+    source_dir.ancestors()
+        .find(|dir| dir.join("Cargo.toml").is_file())
+        .unwrap_or(&source_dir)
+        .to_path_buf()
 }
 
-fn collect_calls_from_file(
-    file: &syn::File,
-    all_calls: &mut Vec<CallInfo>,
-    all_functions: &[(FunctionDef, String)],
-) {
-    // Create a set of known function qualified names
-    let known_fns: HashSet<String> = all_functions.iter()
-        .map(|(f, _)| f.qualified_name.clone())
+/// Package name from the Cargo.toml around `--source-dir`, if there is one
+#[cfg(feature = "native")]
+fn source_package_name(args: &FnGraphArgs) -> Option<String> {
+    let content = fs::read_to_string(project_root(args).join("Cargo.toml")).ok()?;
+    let manifest = toml::parse(&content).ok()?;
+    manifest.get("package")?.get("name")?.as_str().map(str::to_string)
+}
+
+/// Source directories to analyze: `--source-dir`, or every workspace member with `--workspace`
+#[cfg(feature = "native")]
+fn collect_source_roots(args: &FnGraphArgs) -> Result<Vec<SourceRoot>, Box<dyn std::error::Error>> {
+    if let Some(ref spec) = args.crate_name {
+        let pkg = dependency_package(args, spec)?;
+        let base = pkg.manifest_path.parent().map_or_else(|| PathBuf::from("."), |dir| dir.as_std_path().to_path_buf());
+        return Ok(package_source_roots(&pkg, &base));
+    }
+
+    if !args.workspace {
+        let source_dir = &args.source_dir;
+        if !source_dir.exists() {
+            return Err(format!("Source directory not found: {}", source_dir.display()).into());
+        }
+        let crate_name = match args.root_name.as_deref() {
+            Some("") => Some(source_package_name(args).ok_or_else(|| format!(
+                "--crate-name: no package name in a Cargo.toml around {}; give the name, e.g. --crate-name mycrate",
+                source_dir.display(),
+            ))?),
+            name => name.map(str::to_string),
+        };
+        return Ok(vec![SourceRoot {
+            crate_name,
+            dir: source_dir.clone(),
+            base: source_dir.clone(),
+        }]);
+    }
+
+    let metadata = MetadataCommand::new()
+        .manifest_path(&args.manifest_path)
+        .no_deps()
+        .exec()?;
+    let workspace_root = metadata.workspace_root.clone().into_std_path_buf();
+
+    let mut roots = Vec::new();
+    for pkg in metadata.workspace_packages() {
+        if !args.member.is_empty() && !matches_any_pattern(&pkg.name, &args.member) {
+            continue;
+        }
+
+        roots.extend(package_source_roots(pkg, &workspace_root));
+    }
+
+    if roots.is_empty() {
+        return Err("No workspace members found".into());
+    }
+
+    Ok(roots)
+}
+
+/// Source roots of the lib/bin targets of `pkg`, with paths reported relative to `base`
+#[cfg(feature = "native")]
+fn package_source_roots(pkg: &Package, base: &Path) -> Vec<SourceRoot> {
+    // Directories holding the crate roots of lib/bin targets
+    let mut dirs: Vec<PathBuf> = pkg.targets.iter()
+        .filter(|t| !(t.is_example() || t.is_test() || t.is_bench() || t.is_custom_build()))
+        .filter_map(|t| t.src_path.parent().map(|dir| dir.as_std_path().to_path_buf()))
         .collect();
+    dirs.sort();
+    dirs.dedup();
+
+    // Nested directories (src/bin inside src) are walked with their parent
+    dirs.iter()
+        .filter(|dir| !dirs.iter().any(|other| other != *dir && dir.starts_with(other)))
+        .map(|dir| SourceRoot {
+            crate_name: Some(pkg.name.to_string()),
+            dir: dir.clone(),
+            base: base.to_path_buf(),
+        })
+        .collect()
+}
+
+/// The resolved package `--crate NAME[@VERSION]` names, whose manifest is in the cargo registry (or git) checkout
+#[cfg(feature = "native")]
+fn dependency_package(args: &FnGraphArgs, spec: &str) -> Result<Package, Box<dyn std::error::Error>> {
+    let (name, version) = match spec.split_once('@') {
+        Some((name, version)) => (name, Some(version)),
+        None => (spec, None),
+    };
+    let metadata = MetadataCommand::new().manifest_path(&args.manifest_path).exec()?;
+
+    let mut candidates: Vec<Package> = metadata.packages.into_iter()
+        .filter(|pkg| pkg.name == name && version.is_none_or(|version| pkg.version.to_string() == version))
+        .collect();
+    match candidates.len() {
+        0 => Err(format!("No dependency named '{}' in the resolved graph of {}", spec, args.manifest_path.display()).into()),
+        1 => Ok(candidates.remove(0)),
+        _ => {
+            let versions: Vec<String> = candidates.iter().map(|pkg| format!("{}@{}", pkg.name, pkg.version)).collect();
+            Err(format!("Several versions of '{}' in the graph; pick one of {}", name, versions.join(", ")).into())
+        }
+    }
+}
 
+fn collect_calls_from_file(file: &syn::File, module_path: &[String]) -> Vec<CallCollector> {
     // Visit each function and collect calls
+    let mut collectors = Vec::new();
     for item in &file.items {
-        collect_calls_from_item(item, all_calls, &known_fns, &[]);
+        collect_calls_from_item(item, &mut collectors, module_path);
     }
+    collectors
 }
 
 fn collect_calls_from_item(
     item: &syn::Item,
-    all_calls: &mut Vec<CallInfo>,
-    known_fns: &HashSet<String>,
+    collectors: &mut Vec<CallCollector>,
     module_path: &[String],
 ) {
     match item {
@@ -531,7 +2383,7 @@ fn collect_calls_from_item(
 
             let mut collector = CallCollector::new(qualified);
             collector.visit_item_fn(item_fn);
-            all_calls.extend(collector.calls);
+            collectors.push(collector);
         }
         syn::Item::Impl(item_impl) => {
             let type_name = if let syn::Type::Path(type_path) = &*item_impl.self_ty {
@@ -552,7 +2404,7 @@ fn collect_calls_from_item(
 
                     let mut collector = CallCollector::new(qualified);
                     collector.visit_impl_item_fn(method);
-                    all_calls.extend(collector.calls);
+                    collectors.push(collector);
                 }
             }
         }
@@ -561,7 +2413,7 @@ fn collect_calls_from_item(
                 let mut path = module_path.to_vec();
                 path.push(item_mod.ident.to_string());
                 for sub_item in items {
-                    collect_calls_from_item(sub_item, all_calls, known_fns, &path);
+                    collect_calls_from_item(sub_item, collectors, &path);
                 }
             }
         }
@@ -569,62 +2421,54 @@ fn collect_calls_from_item(
     }
 }
 
-fn filter_fn_by_focus(graph_data: &mut FnGraphData, focus_fn: &str, max_depth: usize) {
-    // Find the focus node(s)
-    let focus_nodes: Vec<NodeIndex> = graph_data
-        .graph
-        .node_indices()
-        .filter(|&idx| {
-            let info = &graph_data.graph[idx];
-            info.name == focus_fn || info.qualified_name == focus_fn
-                || info.qualified_name.ends_with(&format!("::{}", focus_fn))
-        })
-        .collect();
-
-    if focus_nodes.is_empty() {
-        return;
-    }
+/// Depth is measured from the focus, else from `main`, else from functions nothing calls
+fn fn_depth_roots(graph_data: &FnGraphData, focus_fn: Option<&str>) -> Vec<NodeIndex> {
+    let graph = &graph_data.graph;
 
-    // Collect connected nodes with depth limit
-    let mut connected: HashSet<NodeIndex> = HashSet::new();
-    for &focus_idx in &focus_nodes {
-        connected.insert(focus_idx);
-        collect_fn_connected(&graph_data.graph, focus_idx, &mut connected, 0, max_depth);
+    let focus_roots: Vec<_> = match focus_fn {
+        Some(focus_fn) => focus_nodes(graph, focus_fn),
+        None => graph.node_indices().filter(|&idx| graph[idx].name == "main").collect(),
+    };
+    if !focus_roots.is_empty() {
+        return focus_roots;
     }
 
-    // Remove unconnected nodes
-    let to_remove: Vec<_> = graph_data
-        .graph
+    graph
         .node_indices()
-        .filter(|idx| !connected.contains(idx))
-        .collect();
-
-    for idx in to_remove.into_iter().rev() {
-        graph_data.graph.remove_node(idx);
-    }
+        .filter(|&idx| graph.neighbors_directed(idx, Direction::Incoming).next().is_none())
+        .collect()
 }
 
-fn collect_fn_connected(
-    graph: &DiGraph<FnNodeInfo, CallKind>,
-    start: NodeIndex,
-    connected: &mut HashSet<NodeIndex>,
-    current_depth: usize,
-    max_depth: usize,
-) {
-    if max_depth > 0 && current_depth >= max_depth {
-        return;
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::types::DepsArgs;
+
+    /// rust-grapher's own dependencies, resolved from the lockfile, and its node
+    fn own_deps(depth: usize) -> (types::GraphData, NodeIndex) {
+        let manifest_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        let args = DepsArgs { manifest_path, offline: true, depth, ..Default::default() };
+        let graph_data = load_deps_graph(&args).unwrap().0;
+        let root = graph_data.graph.node_indices().find(|&idx| graph_data.graph[idx].name == "rust-grapher").unwrap();
+        (graph_data, root)
     }
 
-    // Outgoing edges (callees)
-    for neighbor in graph.neighbors(start) {
-        if connected.insert(neighbor) {
-            collect_fn_connected(graph, neighbor, connected, current_depth + 1, max_depth);
+    #[test]
+    fn depth_limit_counts_the_shortest_path() {
+        for depth in [1, 2] {
+            let (graph_data, root) = own_deps(depth);
+            let distances = compute_depths(&graph_data.graph, &[root]);
+            assert_eq!(distances.len(), graph_data.graph.node_count());
+            assert!(distances.values().all(|&distance| distance <= depth), "--depth {}: {:?}", depth, distances);
         }
     }
-    // Incoming edges (callers)
-    for neighbor in graph.neighbors_directed(start, petgraph::Direction::Incoming) {
-        if connected.insert(neighbor) {
-            collect_fn_connected(graph, neighbor, connected, current_depth + 1, max_depth);
+
+    #[test]
+    fn depth_one_keeps_only_direct_dependencies() {
+        let (graph_data, root) = own_deps(1);
+        assert!(graph_data.graph.edge_count() > 0);
+        for edge in graph_data.graph.edge_indices() {
+            assert_eq!(graph_data.graph.edge_endpoints(edge).unwrap().0, root);
         }
     }
 }
@@ -0,0 +1,86 @@
+// ============================================================================
+// User-defined Node Groups (--groups)
+// ============================================================================
+//
+// groups.toml maps a group name to the node patterns it contains, either as a
+// plain list or as a table with an explicit color:
+//
+//   "ui" = ["egui*", "winit"]
+//
+//   [storage]
+//   patterns = ["sled", "rusqlite*"]
+//   color = "#ffe0b2"
+//   direction = "TB"   # inside the Mermaid subgraph
+//
+// A node joins the first group (in file order) with a matching pattern.
+
+use std::{fs, path::Path};
+
+use crate::{types::NodeGroup, utils::{helper::{group_color, matches_any_pattern}, toml::{self, TomlValue}}};
+
+/// Mermaid flowchart directions a group may lay out in
+const DIRECTIONS: [&str; 5] = ["TB", "TD", "BT", "LR", "RL"];
+
+pub struct GroupRule {
+    pub group: NodeGroup,
+    pub patterns: Vec<String>,
+}
+
+pub fn load_groups(path: &Path) -> Result<Vec<GroupRule>, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let document = toml::parse(&content)
+        .map_err(|e| format!("Invalid groups file {}: {}", path.display(), e))?;
+
+    let mut rules = Vec::new();
+    for (i, (name, value)) in document.as_table().unwrap_or_default().iter().enumerate() {
+        let invalid = |reason: &str| format!("Invalid group '{}' in {}: {}", name, path.display(), reason);
+
+        let (patterns, color, direction) = match value {
+            TomlValue::Table(_) => {
+                let patterns = value.get("patterns")
+                    .ok_or_else(|| invalid("missing \"patterns\""))?
+                    .as_string_list()
+                    .ok_or_else(|| invalid("\"patterns\" must be a list of strings"))?;
+                let color = match value.get("color") {
+                    Some(color) => Some(color.as_str().ok_or_else(|| invalid("\"color\" must be a string"))?.to_string()),
+                    None => None,
+                };
+                let direction = match value.get("direction") {
+                    Some(direction) => {
+                        let direction = direction.as_str()
+                            .map(str::to_ascii_uppercase)
+                            .filter(|direction| DIRECTIONS.contains(&direction.as_str()))
+                            .ok_or_else(|| invalid("\"direction\" must be one of TB, TD, BT, LR, RL"))?;
+                        Some(direction)
+                    }
+                    None => None,
+                };
+                (patterns, color, direction)
+            }
+            _ => {
+                let patterns = value.as_string_list()
+                    .ok_or_else(|| invalid(&format!("expected a list of pattern strings, found {}", value.type_name())))?;
+                (patterns, None, None)
+            }
+        };
+
+        rules.push(GroupRule {
+            group: NodeGroup {
+                name: name.clone(),
+                color: color.unwrap_or_else(|| group_color(i).to_string()),
+                direction,
+            },
+            patterns,
+        });
+    }
+
+    Ok(rules)
+}
+
+/// First group with a pattern matching any of `names`
+pub fn assign_group(rules: &[GroupRule], names: &[&str]) -> Option<NodeGroup> {
+    rules.iter()
+        .find(|rule| names.iter().any(|name| matches_any_pattern(name, &rule.patterns)))
+        .map(|rule| rule.group.clone())
+}
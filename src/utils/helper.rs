@@ -1,19 +1,177 @@
-use crate::types::{DepsArgs, NodeInfo};
+use std::{fs, io, path::{Path, PathBuf}};
+
+use crate::types::{DepsArgs, NodeInfo, SourceKind};
 // ============================================================================
 // Helpers
 // ============================================================================
 
 pub fn format_node_label(info: &NodeInfo, args: &DepsArgs) -> String {
     let sanitized = sanitize_name(&info.name);
-    if args.show_versions {
+    // Versions tell the copies of a duplicated crate apart
+    if args.show_versions || info.duplicate_versions.is_some() {
         format!("{}_{}", sanitized, info.version.replace('.', "_"))
     } else {
         sanitized
     }
 }
 
+/// Whether a cargo source ID is the crates.io index (git or sparse)
+pub fn is_crates_io(source: &str) -> bool {
+    source == "registry+https://github.com/rust-lang/crates.io-index" || source == "sparse+https://index.crates.io/"
+}
+
+/// Where a package comes from; packages without a source ID are local directories
+pub fn source_kind(info: &NodeInfo) -> SourceKind {
+    match info.source.as_deref() {
+        _ if info.is_workspace_member => SourceKind::Workspace,
+        None => SourceKind::Path,
+        Some(source) if is_crates_io(source) => SourceKind::CratesIo,
+        Some(source) if source.starts_with("git+") => SourceKind::Git,
+        Some(_) => SourceKind::Registry,
+    }
+}
+
+/// Resolved commit of a `git+...#<commit>` source ID
+pub fn git_commit(source: &str) -> Option<&str> {
+    source.strip_prefix("git+")?.rsplit_once('#').map(|(_, commit)| commit)
+}
+
+/// What a crate was pinned to, for labels: `@<short commit>` for git crates and
+/// `sha256:<prefix>` for registry crates with a known checksum
+pub fn pin_label(info: &NodeInfo) -> Option<String> {
+    if let Some(commit) = info.source.as_deref().and_then(git_commit) {
+        return Some(format!("@{}", commit.get(..7).unwrap_or(commit)));
+    }
+    info.checksum.as_ref().map(|checksum| format!("sha256:{}", checksum.get(..8).unwrap_or(checksum)))
+}
+
+/// Whether every alternative of an SPDX license expression includes a copyleft license,
+/// e.g. `GPL-3.0-only` or `MPL-2.0 AND MIT`, but not `MIT OR GPL-2.0` (also with the older `/` for OR)
+pub fn is_copyleft(license: &str) -> bool {
+    const COPYLEFT: [&str; 9] = ["GPL", "AGPL", "LGPL", "MPL", "EPL", "EUPL", "CDDL", "OSL", "CC-BY-SA"];
+    license.split(" OR ").flat_map(|alternative| alternative.split('/')).all(|alternative| {
+        alternative
+            .split(|c: char| c.is_whitespace() || c == '(' || c == ')')
+            .any(|id| COPYLEFT.iter().any(|prefix| id.starts_with(prefix)))
+    })
+}
+
+/// Fill color for a depth level (yellow near the roots, dark red far away)
+pub fn depth_color(depth: usize) -> &'static str {
+    const PALETTE: [&str; 8] = [
+        "#ffffcc", "#ffeda0", "#fed976", "#feb24c", "#fd8d3c", "#fc4e2a", "#e31a1c", "#b10026",
+    ];
+    PALETTE[depth.min(PALETTE.len() - 1)]
+}
+
+/// Text color that stays readable on `depth_color(depth)`
+pub fn depth_font_color(depth: usize) -> &'static str {
+    if depth >= 5 { "#ffffff" } else { "#000000" }
+}
+
+/// Metric value for labels: whole numbers without a fraction, otherwise at most two decimals
+pub fn format_metric(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        let text = format!("{:.2}", value);
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+/// Default cluster fill for the `index`-th `--groups` entry (pastel, cycles)
+pub fn group_color(index: usize) -> &'static str {
+    const PALETTE: [&str; 8] = [
+        "#e3f2fd", "#e8f5e9", "#fff3e0", "#f3e5f5", "#e0f7fa", "#fce4ec", "#f1f8e9", "#ede7f6",
+    ];
+    PALETTE[index % PALETTE.len()]
+}
+
 pub fn sanitize_name(name: &str) -> String {
-    name.replace('-', "_").replace('.', "_")
+    name.replace(['-', '.'], "_")
+}
+
+/// Write `contents` through a temp file in the same directory and rename it into place,
+/// creating missing parent directories, so readers never see a truncated file
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    fs::create_dir_all(dir)?;
+
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("{} is not a file path", path.display()))
+    })?;
+    let temp_path = dir.join(format!(".{}.{}.tmp", file_name.to_string_lossy(), std::process::id()));
+
+    let result = fs::write(&temp_path, contents).and_then(|()| fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// `file://` URI for an absolute path, percent-encoding anything outside the unreserved set
+pub fn file_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    let path = path.to_string_lossy().replace('\\', "/");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => uri.push(byte as char),
+            // Keep Windows drive letters readable (file:///C:/...)
+            b':' => uri.push(':'),
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// Inverse of `file_uri`; `None` for other schemes
+pub fn path_from_file_uri(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?;
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    let path = String::from_utf8(decoded).ok()?;
+    // file:///C:/dir -> C:/dir on Windows
+    let path = match path.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => path[1..].to_string(),
+        _ => path,
+    };
+    Some(PathBuf::from(path))
+}
+
+/// 64-bit FNV-1a hash, stable across runs and toolchains (unlike `DefaultHasher`)
+pub fn fnv1a_64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Patterns of a file holding one per line; `#` starts a comment
+pub fn read_patterns(path: &Path) -> Result<Vec<String>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    Ok(content.lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
 }
 
 /// Check if name matches any pattern in the list (supports * wildcard)
@@ -0,0 +1,199 @@
+// ============================================================================
+// History - Dependency Graph Over Git History (`history`)
+// ============================================================================
+//
+// Each point (every N-th first-parent commit, or every tag) is checked out in
+// a temporary detached worktree and analyzed like `deps`. The time series has
+// the node and edge counts of each point and the crates added and removed
+// since the previous one.
+
+use std::{collections::BTreeSet, error::Error, fs, path::{Path, PathBuf}, process::Command};
+
+use crate::{types::{DepsArgs, HistoryArgs, HistoryEvery, HistoryFormat, WarningCode}, utils::{generator::generate_deps_dot, grapher::load_deps_graph, helper::write_atomic, warnings::warn}};
+
+/// A commit the graph is analyzed at
+struct Point {
+    /// Tag name or abbreviated hash
+    label: String,
+    commit: String,
+    /// Committer date, ISO 8601
+    date: String,
+}
+
+struct Sample {
+    point: Point,
+    nodes: usize,
+    edges: usize,
+    added: Vec<String>,
+    removed: Vec<String>,
+}
+
+pub fn run_history(args: &HistoryArgs) -> Result<(String, Option<PathBuf>), Box<dyn Error>> {
+    let manifest = fs::canonicalize(&args.manifest_path)
+        .map_err(|e| format!("Failed to read {}: {}", args.manifest_path.display(), e))?;
+    let manifest_dir = manifest.parent().unwrap_or(Path::new("."));
+    let top = PathBuf::from(git(manifest_dir, &["rev-parse", "--show-toplevel"])?.trim());
+    let top = fs::canonicalize(&top).unwrap_or(top);
+    let manifest_in_repo = manifest.strip_prefix(&top)
+        .map_err(|_| format!("{} is not inside the git repository {}", manifest.display(), top.display()))?
+        .to_path_buf();
+
+    let points = history_points(&top, args.every)?;
+    if points.is_empty() {
+        return Err(match args.every {
+            HistoryEvery::Tags => "No tags reachable from HEAD; try --every <N>".into(),
+            HistoryEvery::Commits(_) => "No commits in the repository".into(),
+        });
+    }
+
+    if let Some(ref dir) = args.frames {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+
+    let worktree = std::env::temp_dir().join(format!("rust-grapher-history-{}", std::process::id()));
+    git(&top, &["worktree", "add", "--detach", "--force", &worktree.to_string_lossy(), &points[0].commit])?;
+    let samples = sample_points(args, points, &worktree, &manifest_in_repo);
+    let _ = git(&top, &["worktree", "remove", "--force", &worktree.to_string_lossy()]);
+    let samples = samples?;
+
+    let output = match args.format {
+        HistoryFormat::Json => history_json(&samples),
+        HistoryFormat::Csv => history_csv(&samples),
+    };
+    Ok((output, args.output.clone()))
+}
+
+/// Analyze each point in the worktree, skipping (with a warning) points that don't build a graph
+fn sample_points(args: &HistoryArgs, points: Vec<Point>, worktree: &Path, manifest: &Path) -> Result<Vec<Sample>, Box<dyn Error>> {
+    let deps_args = DepsArgs {
+        manifest_path: worktree.join(manifest),
+        no_dev: args.no_dev,
+        no_build: args.no_build,
+        direction: "LR".to_string(),
+        ..Default::default()
+    };
+
+    let mut samples = Vec::new();
+    let mut previous: BTreeSet<String> = BTreeSet::new();
+    for (i, point) in points.into_iter().enumerate() {
+        git(worktree, &["checkout", "--quiet", "--detach", "--force", &point.commit])?;
+        let graph_data = match load_deps_graph(&deps_args) {
+            Ok((graph_data, _)) => graph_data,
+            Err(e) => {
+                warn(WarningCode::MissingData, format!("skipping {}: {}", point.label, e));
+                continue;
+            }
+        };
+
+        if let Some(ref dir) = args.frames {
+            let path = dir.join(format!("frame-{:04}-{}.dot", i + 1, point.label.replace(['/', '\\'], "_")));
+            write_atomic(&path, generate_deps_dot(&graph_data, &deps_args))
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        }
+
+        let crates: BTreeSet<String> = graph_data.graph.node_weights().map(|info| info.name.clone()).collect();
+        // The first point has nothing to compare with
+        let (added, removed) = if samples.is_empty() {
+            (Vec::new(), Vec::new())
+        } else {
+            (crates.difference(&previous).cloned().collect(), previous.difference(&crates).cloned().collect())
+        };
+
+        samples.push(Sample {
+            point,
+            nodes: graph_data.graph.node_count(),
+            edges: graph_data.graph.edge_count(),
+            added,
+            removed,
+        });
+        previous = crates;
+    }
+
+    Ok(samples)
+}
+
+/// Points to analyze, oldest first
+fn history_points(top: &Path, every: HistoryEvery) -> Result<Vec<Point>, Box<dyn Error>> {
+    match every {
+        HistoryEvery::Commits(n) => {
+            let log = git(top, &["log", "--first-parent", "--reverse", "--format=%H %h %cI", "HEAD"])?;
+            let commits: Vec<Point> = log.lines()
+                .filter_map(|line| {
+                    let mut fields = line.split(' ');
+                    let (commit, short, date) = (fields.next()?, fields.next()?, fields.next()?);
+                    Some(Point { label: short.to_string(), commit: commit.to_string(), date: date.to_string() })
+                })
+                .collect();
+            let last = commits.len().saturating_sub(1);
+            Ok(commits.into_iter().enumerate().filter(|(i, _)| i % n == 0 || *i == last).map(|(_, point)| point).collect())
+        }
+        HistoryEvery::Tags => {
+            let tags = git(top, &["tag", "--merged", "HEAD", "--sort=creatordate"])?;
+            tags.lines()
+                .filter(|tag| !tag.is_empty())
+                .map(|tag| {
+                    let info = git(top, &["log", "-1", "--format=%H %cI", &format!("{}^{{commit}}", tag)])?;
+                    let (commit, date) = info.trim().split_once(' ').unwrap_or((info.trim(), ""));
+                    Ok(Point { label: tag.to_string(), commit: commit.to_string(), date: date.to_string() })
+                })
+                .collect()
+        }
+    }
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String, Box<dyn Error>> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// ============================================================================
+// Output
+// ============================================================================
+
+fn history_json(samples: &[Sample]) -> String {
+    let points: Vec<serde_json::Value> = samples.iter()
+        .map(|sample| serde_json::json!({
+            "rev": sample.point.label,
+            "commit": sample.point.commit,
+            "date": sample.point.date,
+            "nodes": sample.nodes,
+            "edges": sample.edges,
+            "added": sample.added,
+            "removed": sample.removed
+        }))
+        .collect();
+
+    serde_json::to_string_pretty(&serde_json::json!({ "points": points }))
+        .unwrap_or_else(|_| "{}".to_string())
+}
+
+/// One row per point; added and removed crates are space-separated
+fn history_csv(samples: &[Sample]) -> String {
+    let mut output = String::from("rev,commit,date,nodes,edges,added,removed\n");
+    for sample in samples {
+        let fields = [
+            csv_field(&sample.point.label),
+            sample.point.commit.clone(),
+            sample.point.date.clone(),
+            sample.nodes.to_string(),
+            sample.edges.to_string(),
+            csv_field(&sample.added.join(" ")),
+            csv_field(&sample.removed.join(" ")),
+        ];
+        output.push_str(&fields.join(","));
+        output.push('\n');
+    }
+    output
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,122 @@
+// ============================================================================
+// Inject Map - Diagrams Embedded in Markdown Files (--inject-map)
+// ============================================================================
+//
+// map.toml lists the blocks to regenerate, each with the arguments of its own
+// run (the invoking command, unless `command` says otherwise):
+//
+//   [[block]]
+//   file = "docs/storage.md"
+//   name = "storage"
+//   args = ["--focus", "sled", "--depth", "2"]
+//
+// In the target file, the block is everything between the two markers:
+//
+//   <!-- rust-grapher:begin storage -->
+//   <!-- rust-grapher:end storage -->
+//
+// `name` defaults to "graph", and files are relative to the map.
+
+use std::{error::Error, fs, path::{Path, PathBuf}};
+
+use crate::{types::{Commands, WarningCode}, utils::{cli::{parse_cli, run_cli}, helper::write_atomic, toml::{self, TomlValue}, warnings::warn}};
+
+struct Block {
+    file: PathBuf,
+    name: String,
+    command: String,
+    args: Vec<String>,
+}
+
+/// Regenerate every block of the map at `path`; `command` runs blocks that don't name one
+pub fn run_inject_map(path: &Path, command: &str) -> Result<(String, Option<PathBuf>), Box<dyn Error>> {
+    let blocks = load_inject_map(path, command)?;
+    if blocks.is_empty() {
+        warn(WarningCode::NoMatch, format!("{} lists no [[block]]", path.display()));
+    }
+
+    for block in &blocks {
+        let context = |e: &dyn std::fmt::Display| format!("Block '{}' of {}: {}", block.name, block.file.display(), e);
+
+        let argv = ["rust-grapher", block.command.as_str()].into_iter().chain(block.args.iter().map(String::as_str));
+        let cli = parse_cli(argv).map_err(|e| context(&e.render()))?;
+        let nested = match &cli.command {
+            Commands::Deps(args) => args.inject_map.is_some(),
+            Commands::FnGraph(args) => args.inject_map.is_some(),
+            _ => false,
+        };
+        if nested {
+            return Err(context(&"--inject-map cannot be nested").into());
+        }
+
+        let content = fs::read_to_string(&block.file).map_err(|e| context(&e))?;
+        let (output, output_path) = run_cli(&cli).map_err(|e| context(&e))?;
+        if output_path.is_some() {
+            return Err(context(&"--output is not allowed, the graph goes into the block").into());
+        }
+
+        let updated = replace_block(&content, &block.name, &output).map_err(|e| context(&e))?;
+        if updated != content {
+            write_atomic(&block.file, updated).map_err(|e| context(&e))?;
+            eprintln!("Block '{}' written to: {}", block.name, block.file.display());
+        }
+    }
+
+    Ok((String::new(), None))
+}
+
+fn load_inject_map(path: &Path, command: &str) -> Result<Vec<Block>, Box<dyn Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let document = toml::parse(&content)
+        .map_err(|e| format!("Invalid inject map {}: {}", path.display(), e))?;
+    let base = path.parent().unwrap_or(Path::new(""));
+
+    let entries = match document.get("block") {
+        Some(TomlValue::Array(entries)) => entries.as_slice(),
+        Some(_) => return Err(format!("Invalid inject map {}: \"block\" must be [[block]] tables", path.display()).into()),
+        None => &[],
+    };
+
+    let mut blocks = Vec::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let invalid = |reason: &str| format!("Invalid block {} in {}: {}", i + 1, path.display(), reason);
+        let string = |key: &str| match entry.get(key) {
+            Some(value) => value.as_str().map(|s| Some(s.to_string())).ok_or_else(|| invalid(&format!("\"{}\" must be a string", key))),
+            None => Ok(None),
+        };
+
+        let file = string("file")?.ok_or_else(|| invalid("missing \"file\""))?;
+        let name = string("name")?.unwrap_or_else(|| "graph".to_string());
+        let command = string("command")?.unwrap_or_else(|| command.to_string());
+        let args = match entry.get("args") {
+            Some(args) => args.as_string_list().ok_or_else(|| invalid("\"args\" must be a list of strings"))?,
+            None => Vec::new(),
+        };
+
+        blocks.push(Block { file: base.join(file), name, command, args });
+    }
+
+    Ok(blocks)
+}
+
+/// `content` with the lines between the begin and end markers of `name` replaced by `output`
+fn replace_block(content: &str, name: &str, output: &str) -> Result<String, String> {
+    let begin = format!("<!-- rust-grapher:begin {} -->", name);
+    let end = format!("<!-- rust-grapher:end {} -->", name);
+
+    let start = content.find(&begin).ok_or_else(|| format!("no {} marker", begin))?;
+    let inner_start = content[start..].find('\n').map_or(content.len(), |i| start + i + 1);
+    let inner_end = content[inner_start..].find(&end)
+        .map(|i| inner_start + i)
+        .ok_or_else(|| format!("no {} marker after the begin marker", end))?;
+
+    let mut updated = String::with_capacity(content.len() + output.len());
+    updated.push_str(&content[..inner_start]);
+    updated.push_str(output);
+    if !output.is_empty() && !output.ends_with('\n') {
+        updated.push('\n');
+    }
+    updated.push_str(&content[inner_end..]);
+    Ok(updated)
+}
@@ -0,0 +1,104 @@
+// ============================================================================
+// Architecture Layers (deps --check-layers)
+// ============================================================================
+//
+// The `layers` section of .rust-grapher.toml puts crates into layers and lists
+// the dependencies allowed between them, chains standing for every arrow:
+//
+//   [layers]
+//   allow = ["ui -> domain -> infra", "ui -> infra"]
+//
+//   [layers.crates]
+//   ui = ["web", "cli-*"]
+//   domain = ["core"]
+//   infra = ["db", "storage-*"]
+//
+// Crates match the first layer with a matching pattern (* wildcards). Edges
+// inside a layer are always allowed, and crates in no layer are not checked;
+// every other edge between layers must be listed.
+
+use std::{collections::HashSet, error::Error, fs, path::Path};
+
+use petgraph::visit::EdgeRef;
+
+use crate::{types::{GraphData, WarningCode}, utils::{helper::matches_any_pattern, toml::{self, TomlValue}, warnings::warn}};
+
+pub struct Layers {
+    /// Layer names with their crate patterns, in file order
+    crates: Vec<(String, Vec<String>)>,
+    /// Allowed (from, to) layer pairs
+    allowed: HashSet<(String, String)>,
+}
+
+impl Layers {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let document = toml::parse(&content)
+            .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?;
+        let invalid = |reason: String| format!("Invalid [layers] in {}: {}", path.display(), reason);
+        let section = document.get("layers").ok_or_else(|| invalid("missing".to_string()))?;
+
+        let mut crates = Vec::new();
+        for (name, patterns) in section.get("crates").and_then(TomlValue::as_table).unwrap_or_default() {
+            let patterns = patterns.as_string_list()
+                .ok_or_else(|| invalid(format!("layer '{}' must be a list of crate patterns", name)))?;
+            crates.push((name.clone(), patterns));
+        }
+        if crates.is_empty() {
+            return Err(invalid("no [layers.crates]".to_string()).into());
+        }
+
+        let chains = match section.get("allow") {
+            Some(allow) => allow.as_string_list().ok_or_else(|| invalid("\"allow\" must be a list of strings".to_string()))?,
+            None => Vec::new(),
+        };
+        let mut allowed = HashSet::new();
+        for chain in &chains {
+            let names: Vec<&str> = chain.split("->").map(str::trim).collect();
+            if let Some(unknown) = names.iter().find(|name| !crates.iter().any(|(layer, _)| layer == *name)) {
+                return Err(invalid(format!("'{}' in \"{}\" is not a layer", unknown, chain)).into());
+            }
+            if names.len() < 2 {
+                return Err(invalid(format!("expected \"A -> B\", found \"{}\"", chain)).into());
+            }
+            for pair in names.windows(2) {
+                allowed.insert((pair[0].to_string(), pair[1].to_string()));
+            }
+        }
+
+        Ok(Layers { crates, allowed })
+    }
+
+    /// The layer a crate belongs to
+    fn layer_of(&self, name: &str) -> Option<&str> {
+        self.crates.iter()
+            .find(|(_, patterns)| matches_any_pattern(name, patterns))
+            .map(|(layer, _)| layer.as_str())
+    }
+
+    /// Every edge between layers that no rule allows, as `web (ui) -> db (infra)`
+    pub fn violations(&self, graph_data: &GraphData) -> Vec<String> {
+        let graph = &graph_data.graph;
+        for (layer, patterns) in &self.crates {
+            for pattern in patterns {
+                if !graph.node_weights().any(|info| matches_any_pattern(&info.name, std::slice::from_ref(pattern))) {
+                    warn(WarningCode::NoMatch, format!("layer {} pattern {} matches no crate", layer, pattern));
+                }
+            }
+        }
+
+        let mut violations: Vec<String> = graph.edge_references()
+            .filter(|edge| !graph[edge.source()].is_build_script && !graph[edge.target()].is_build_script)
+            .filter_map(|edge| {
+                let (from, to) = (&graph[edge.source()], &graph[edge.target()]);
+                let (from_layer, to_layer) = (self.layer_of(&from.name)?, self.layer_of(&to.name)?);
+                let allowed = from_layer == to_layer || self.allowed.contains(&(from_layer.to_string(), to_layer.to_string()));
+                (!allowed).then(|| format!("{} ({}) -> {} ({})", from.name, from_layer, to.name, to_layer))
+            })
+            .collect();
+        violations.sort();
+        violations.dedup();
+        violations
+    }
+}
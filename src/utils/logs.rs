@@ -0,0 +1,118 @@
+// ============================================================================
+// Logging Inventory (--logs, --log-table)
+// ============================================================================
+//
+// Calls of the `log` and `tracing` level macros, recognized by name:
+//
+//   info!("...")  log::warn!(target: "db", "...")  tracing::debug!(id, "...")
+//   event!(Level::ERROR, "...")  log!(Level::Trace, "...")
+//
+// Bare names are taken as logging macros too, so a crate's own `error!` counts.
+// With --logs, the number of calls of each function becomes `metric.logs`.
+
+use proc_macro2::{Spacing, TokenStream, TokenTree};
+use syn::spanned::Spanned;
+
+use crate::{types::{FnGraphData, LogCall}, utils::generator::markdown_cell};
+
+/// Metric name set on function nodes
+pub const LOGS: &str = "logs";
+
+const LEVELS: [&str; 5] = ["error", "warn", "info", "debug", "trace"];
+
+/// The logging call `mac` makes, if it is one
+pub fn parse_log_macro(mac: &syn::Macro) -> Option<LogCall> {
+    let segments: Vec<String> = mac.path.segments.iter().map(|seg| seg.ident.to_string()).collect();
+    let (name, prefix) = segments.split_last()?;
+    if !matches!(prefix, [] | [_]) || prefix.first().is_some_and(|crate_name| crate_name != "log" && crate_name != "tracing") {
+        return None;
+    }
+
+    let mut args = split_args(mac.tokens.clone());
+    let level = match name.as_str() {
+        // The level is the first positional argument: `Level::INFO` (tracing) or `Level::Info` (log)
+        "event" | "log" => {
+            let position = args.iter().position(|arg| !is_key_value(arg))?;
+            let level = args.remove(position).last()?.to_string().to_lowercase();
+            LEVELS.contains(&level.as_str()).then_some(level)?
+        }
+        name if LEVELS.contains(&name) => name.to_string(),
+        _ => return None,
+    };
+
+    let mut call = LogCall { level, target: None, message: None, line: mac.path.span().start().line };
+    for arg in args {
+        match arg.as_slice() {
+            // `target: "db"`; the other `key: value` forms of tracing (`parent:`, `name:`) are skipped
+            [TokenTree::Ident(key), TokenTree::Punct(_), value @ ..] if is_key_value(&arg) && key == "target" => {
+                call.target = string_literal(value);
+            }
+            _ if is_key_value(&arg) => {}
+            value if call.message.is_none() => call.message = string_literal(value),
+            _ => {}
+        }
+    }
+    Some(call)
+}
+
+/// `target: "db"`, `parent: None`, ... (a single colon, unlike the path `Level::INFO`)
+fn is_key_value(arg: &[TokenTree]) -> bool {
+    matches!(arg, [TokenTree::Ident(_), TokenTree::Punct(colon), ..] if colon.as_char() == ':' && colon.spacing() == Spacing::Alone)
+}
+
+/// Top-level comma-separated arguments of a macro
+fn split_args(tokens: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut args = vec![Vec::new()];
+    for token in tokens {
+        match token {
+            TokenTree::Punct(ref punct) if punct.as_char() == ',' => args.push(Vec::new()),
+            token => args.last_mut().unwrap().push(token),
+        }
+    }
+    args.retain(|arg| !arg.is_empty());
+    args
+}
+
+fn string_literal(tokens: &[TokenTree]) -> Option<String> {
+    match tokens {
+        [TokenTree::Literal(literal)] => syn::parse_str::<syn::LitStr>(&literal.to_string()).ok().map(|lit| lit.value()),
+        _ => None,
+    }
+}
+
+/// Set `metric.logs` on every function that logs
+pub fn apply_log_counts(graph_data: &mut FnGraphData) {
+    for info in graph_data.graph.node_weights_mut() {
+        if !info.logs.is_empty() {
+            info.metrics.insert(LOGS.to_string(), info.logs.len() as f64);
+        }
+    }
+}
+
+/// Markdown table of every logging call, by function
+pub fn generate_log_table(graph_data: &FnGraphData) -> String {
+    let mut functions: Vec<_> = graph_data.graph.node_weights().filter(|info| !info.logs.is_empty()).collect();
+    functions.sort_by(|a, b| a.file_path.cmp(&b.file_path).then(a.line.cmp(&b.line)));
+    let total: usize = functions.iter().map(|info| info.logs.len()).sum();
+    let silent = graph_data.graph.node_count() - functions.len();
+
+    let mut output = String::from("# Logging Calls\n\n");
+    output.push_str(&format!("{} calls in {} functions; {} functions don't log.\n\n", total, functions.len(), silent));
+    output.push_str("| Function | Location | Level | Target | Message |\n");
+    output.push_str("|----------|----------|-------|--------|---------|\n");
+
+    for info in functions {
+        for call in &info.logs {
+            output.push_str(&format!(
+                "| {} | {}:{} | {} | {} | {} |\n",
+                markdown_cell(&info.qualified_name),
+                markdown_cell(&info.file_path),
+                call.line,
+                call.level,
+                call.target.as_deref().map(markdown_cell).unwrap_or_default(),
+                call.message.as_deref().map(|message| format!("`{}`", markdown_cell(message))).unwrap_or_default(),
+            ));
+        }
+    }
+    output
+}
@@ -0,0 +1,162 @@
+// ============================================================================
+// Custom Node Metrics (--metric-script)
+// ============================================================================
+//
+// A `MetricSource` attaches named numbers to nodes (ownership scores, profiling
+// counters, ...). They can be used with `--filter 'metric.<name> > 10'`,
+// `--color-by metric --color-metric <name>` and show up in JSON output.
+//
+// `--metric-script <CMD>` runs CMD through the shell, writes the nodes to its stdin
+//
+//   {"graph": "fn", "nodes": [{"id": "fn:crate::run#af2c3a3a", "name": "run", "attributes": {...}}]}
+//
+// and reads metrics per node id from its stdout:
+//
+//   {"fn:crate::run#af2c3a3a": {"cpu_ms": 120.5, "owners": 2}}
+//
+// Nodes the script leaves out get no metrics; ids it does not know are ignored.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+    io::Write,
+    process::{Command, Stdio},
+    thread,
+};
+
+use serde_json::{json, Map, Value};
+
+use crate::{types::Graph, utils::{filter_expr::FilterValue, graph::GraphNode}};
+
+/// Metric values by name, for each node id
+pub type NodeMetrics = HashMap<String, BTreeMap<String, f64>>;
+
+/// A node as handed to a `MetricSource`
+pub struct MetricNode {
+    pub id: String,
+    pub name: String,
+    /// Every `--filter` attribute the node has a value for
+    pub attributes: Map<String, Value>,
+}
+
+/// Computes custom metrics for the nodes of one graph
+pub trait MetricSource {
+    /// `graph` is "deps" or "fn"
+    fn compute(&mut self, graph: &str, nodes: &[MetricNode]) -> Result<NodeMetrics, String>;
+}
+
+/// External command speaking the JSON protocol above
+pub struct MetricScript {
+    pub command: String,
+}
+
+impl MetricSource for MetricScript {
+    fn compute(&mut self, graph: &str, nodes: &[MetricNode]) -> Result<NodeMetrics, String> {
+        let request = json!({
+            "graph": graph,
+            "nodes": nodes.iter().map(|node| json!({
+                "id": node.id,
+                "name": node.name,
+                "attributes": node.attributes,
+            })).collect::<Vec<_>>(),
+        });
+
+        let mut child = shell_command(&self.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| format!("Failed to run metric script '{}': {}", self.command, e))?;
+
+        // Feed stdin from a thread so a script that writes before reading everything can't deadlock
+        let mut stdin = child.stdin.take().ok_or("metric script has no stdin")?;
+        let body = request.to_string();
+        let writer = thread::spawn(move || stdin.write_all(body.as_bytes()));
+
+        let output = child.wait_with_output()
+            .map_err(|e| format!("Failed to run metric script '{}': {}", self.command, e))?;
+        // A script may exit without reading its input; only its exit status matters
+        let _ = writer.join();
+
+        if !output.status.success() {
+            return Err(format!("Metric script '{}' failed ({})", self.command, output.status));
+        }
+        parse_metrics(&output.stdout).map_err(|e| format!("Invalid output from metric script '{}': {}", self.command, e))
+    }
+}
+
+#[cfg(windows)]
+fn shell_command(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+#[cfg(not(windows))]
+fn shell_command(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+fn parse_metrics(stdout: &[u8]) -> Result<NodeMetrics, String> {
+    let value: Value = serde_json::from_slice(stdout).map_err(|e| e.to_string())?;
+    let Value::Object(nodes) = value else {
+        return Err("expected an object mapping node ids to metrics".to_string());
+    };
+
+    let mut result = NodeMetrics::new();
+    for (id, metrics) in nodes {
+        let Value::Object(metrics) = metrics else {
+            return Err(format!("metrics of '{}' must be an object", id));
+        };
+        let mut values = BTreeMap::new();
+        for (name, value) in metrics {
+            let value = value.as_f64().ok_or_else(|| format!("metric '{}' of '{}' is not a number", name, id))?;
+            values.insert(name, value);
+        }
+        result.insert(id, values);
+    }
+    Ok(result)
+}
+
+/// Run `source` over every node and merge the returned metrics into the nodes
+pub fn attach_metrics<K: Eq + Hash, N: GraphNode, E>(
+    graph_data: &mut Graph<K, N, E>,
+    graph: &str,
+    source: &mut dyn MetricSource,
+) -> Result<(), String> {
+    let nodes: Vec<MetricNode> = graph_data.graph.node_weights().map(metric_node).collect();
+    let mut metrics = source.compute(graph, &nodes)?;
+
+    for info in graph_data.graph.node_weights_mut() {
+        if let Some(values) = metrics.remove(&info.id()) {
+            info.metrics_mut().extend(values);
+        }
+    }
+    Ok(())
+}
+
+fn metric_node<N: GraphNode>(info: &N) -> MetricNode {
+    let attributes = N::ATTRIBUTES.iter()
+        .filter_map(|&name| {
+            let value = match info.attribute(name)? {
+                FilterValue::Bool(value) => json!(value),
+                FilterValue::Num(value) => json!(value),
+                FilterValue::Str(value) => json!(value),
+            };
+            Some((name.to_string(), value))
+        })
+        .collect::<Map<String, Value>>();
+
+    MetricNode {
+        id: info.id(),
+        name: attributes.get("name").and_then(Value::as_str).unwrap_or_default().to_string(),
+        attributes,
+    }
+}
+
+/// Whether any node carries the metric `name`
+pub fn has_metric<K, N: GraphNode, E>(graph_data: &Graph<K, N, E>, name: &str) -> bool {
+    graph_data.graph.node_weights().any(|info| info.metrics().contains_key(name))
+}
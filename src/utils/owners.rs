@@ -0,0 +1,132 @@
+// ============================================================================
+// Crate Owners (`owners`)
+// ============================================================================
+//
+// The crates.io owners of every crates.io dependency, and the "bus factor" of
+// the graph: how many crates a single user can publish alone (no second owner
+// and no team), and which owners can publish the most crates.
+
+use std::{collections::{BTreeMap, BTreeSet}, error::Error, path::PathBuf};
+
+use crate::{types::{DepsArgs, OwnersArgs, ReportFormat, WarningCode}, utils::{crates_io::{crates_io_packages, is_team, CratesIo}, grapher::load_deps_graph, warnings::warn}};
+
+struct CrateOwners {
+    name: String,
+    versions: Vec<String>,
+    owners: Vec<String>,
+}
+
+impl CrateOwners {
+    fn single_owner(&self) -> bool {
+        self.owners.len() == 1 && !is_team(&self.owners[0])
+    }
+
+    fn team_owned(&self) -> bool {
+        self.owners.iter().any(|owner| is_team(owner))
+    }
+}
+
+pub fn run_owners(args: &OwnersArgs) -> Result<(String, Option<PathBuf>), Box<dyn Error>> {
+    let deps_args = DepsArgs {
+        manifest_path: args.manifest_path.clone(),
+        no_dev: args.no_dev,
+        no_build: args.no_build,
+        direction: "LR".to_string(),
+        ..Default::default()
+    };
+    let (graph_data, _) = load_deps_graph(&deps_args)?;
+
+    let mut versions: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for (name, version) in crates_io_packages(&graph_data, args.direct_only) {
+        versions.entry(name).or_default().push(version.to_string());
+    }
+
+    let mut client = CratesIo::new(args.cache_dir.as_deref());
+    let mut report = Vec::new();
+    for (name, versions) in versions {
+        match client.owners(name) {
+            Ok(owners) => report.push(CrateOwners { name: name.to_string(), versions, owners }),
+            Err(e) => warn(WarningCode::MissingData, format!("skipping {}: {}", name, e)),
+        }
+    }
+    // Fewest owners first
+    report.sort_by(|a, b| a.owners.len().cmp(&b.owners.len()).then(a.name.cmp(&b.name)));
+
+    let output = match args.format {
+        ReportFormat::Markdown => owners_markdown(&report),
+        ReportFormat::Json => owners_json(&report),
+    };
+    Ok((output, args.output.clone()))
+}
+
+/// Each owner with the crates they can publish, most crates first
+fn crates_by_owner(report: &[CrateOwners]) -> Vec<(&str, BTreeSet<&str>)> {
+    let mut by_owner: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for entry in report {
+        for owner in &entry.owners {
+            by_owner.entry(owner).or_default().insert(&entry.name);
+        }
+    }
+    let mut by_owner: Vec<_> = by_owner.into_iter().collect();
+    by_owner.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.cmp(b.0)));
+    by_owner
+}
+
+// ============================================================================
+// Output
+// ============================================================================
+
+fn owners_markdown(report: &[CrateOwners]) -> String {
+    let single = report.iter().filter(|entry| entry.single_owner()).count();
+    let teams = report.iter().filter(|entry| entry.team_owned()).count();
+
+    let mut output = String::from("# Crate owners\n\n");
+    output.push_str(&format!(
+        "{} crates.io dependencies: {} with a single owner, {} owned by a team.\n\n",
+        report.len(), single, teams
+    ));
+
+    output.push_str("| Crate | Versions | Owners |\n");
+    output.push_str("|-------|----------|--------|\n");
+    for entry in report {
+        output.push_str(&format!("| {} | {} | {} |\n", entry.name, entry.versions.join(", "), entry.owners.join(", ")));
+    }
+
+    output.push_str("\n## Owners\n\n");
+    output.push_str("| Owner | Crates | Can publish |\n");
+    output.push_str("|-------|--------|-------------|\n");
+    for (owner, crates) in crates_by_owner(report) {
+        let names: Vec<&str> = crates.iter().copied().collect();
+        output.push_str(&format!("| {} | {} | {} |\n", owner, crates.len(), names.join(", ")));
+    }
+    output
+}
+
+fn owners_json(report: &[CrateOwners]) -> String {
+    let crates: Vec<serde_json::Value> = report.iter()
+        .map(|entry| serde_json::json!({
+            "name": entry.name,
+            "versions": entry.versions,
+            "owners": entry.owners,
+            "single_owner": entry.single_owner()
+        }))
+        .collect();
+    let owners: Vec<serde_json::Value> = crates_by_owner(report).into_iter()
+        .map(|(owner, crates)| serde_json::json!({
+            "login": owner,
+            "team": is_team(owner),
+            "crates": crates
+        }))
+        .collect();
+
+    serde_json::to_string_pretty(&serde_json::json!({
+        "summary": {
+            "total": report.len(),
+            "single_owner": report.iter().filter(|entry| entry.single_owner()).count(),
+            "team_owned": report.iter().filter(|entry| entry.team_owned()).count()
+        },
+        "crates": crates,
+        "owners": owners
+    }))
+    .unwrap_or_else(|_| "{}".to_string())
+}
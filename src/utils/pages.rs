@@ -0,0 +1,177 @@
+// ============================================================================
+// Detail Pages (--out-dir with --detail-pages)
+// ============================================================================
+//
+// One page per node under <out-dir>/nodes/, named by a hash of the node id so
+// links stay put as long as the node does, next to the diagram they are
+// linked from (`click` in Mermaid, `URL` in DOT, `page` in JSON):
+//
+//   out/graph.md
+//   out/nodes/3f1c0e5a9b7d2e41.md
+
+use std::{error::Error, fs, path::Path};
+
+use petgraph::{graph::DiGraph, Direction};
+
+use crate::{types::{FnGraphData, GraphData, OutputFormat, PageFormat}, utils::{graph::GraphNode, helper::{fnv1a_64, format_metric, write_atomic}}};
+
+/// Directory of the pages, inside --out-dir
+pub const PAGES_DIR: &str = "nodes";
+
+/// File name of the page of the node with this id
+pub fn page_name(id: &str, format: PageFormat) -> String {
+    let extension = match format {
+        PageFormat::Markdown => "md",
+        PageFormat::Json => "json",
+    };
+    format!("{:016x}.{}", fnv1a_64(id.as_bytes()), extension)
+}
+
+/// Link from the diagram to a node's page, with --detail-pages
+pub fn page_link(id: &str, format: Option<PageFormat>) -> Option<String> {
+    format.map(|format| format!("{}/{}", PAGES_DIR, page_name(id, format)))
+}
+
+/// File name of the diagram in --out-dir
+pub fn diagram_file_name(format: &OutputFormat, no_fence: bool) -> &'static str {
+    match format {
+        OutputFormat::Mermaid if no_fence => "graph.mmd",
+        OutputFormat::Mermaid | OutputFormat::GhSummary => "graph.md",
+        OutputFormat::Dot => "graph.dot",
+        OutputFormat::Json | OutputFormat::CallHierarchy | OutputFormat::VscodeGraph => "graph.json",
+        OutputFormat::Sarif => "graph.sarif",
+    }
+}
+
+pub fn write_deps_pages(graph_data: &GraphData, dir: &Path, format: PageFormat, diagram: &str) -> Result<(), Box<dyn Error>> {
+    write_pages(&graph_data.graph, dir, format, diagram, "Dependencies", "Dependents", |info| {
+        let mut fields = vec![("name", info.name.clone()), ("version", info.version.clone())];
+        for (key, value) in [
+            ("license", &info.license),
+            ("description", &info.description),
+            ("repository", &info.repository),
+            ("homepage", &info.homepage),
+            ("documentation", &info.documentation),
+            ("source", &info.source),
+            ("checksum", &info.checksum),
+        ] {
+            if let Some(value) = value {
+                fields.push((key, value.clone()));
+            }
+        }
+        if let Some(owners) = &info.owners {
+            fields.push(("owners", owners.join(", ")));
+        }
+        if let Some(advisories) = info.advisories.as_ref().filter(|ids| !ids.is_empty()) {
+            fields.push(("advisories", advisories.join(", ")));
+        }
+        if let Some(latest) = &info.latest_version {
+            fields.push(("latest version", latest.clone()));
+        }
+        if let Some(versions) = &info.duplicate_versions {
+            fields.push(("versions in graph", versions.join(", ")));
+        }
+        fields.push(("workspace member", info.is_workspace_member.to_string()));
+        if let Some(group) = &info.group {
+            fields.push(("group", group.name.clone()));
+        }
+        fields
+    })
+}
+
+pub fn write_fn_pages(graph_data: &FnGraphData, dir: &Path, format: PageFormat, diagram: &str) -> Result<(), Box<dyn Error>> {
+    write_pages(&graph_data.graph, dir, format, diagram, "Calls", "Called by", |info| {
+        let mut fields = vec![
+            ("name", info.name.clone()),
+            ("qualified name", info.qualified_name.clone()),
+            ("file", format!("{}:{}", info.file_path, info.line)),
+        ];
+        if let Some(signature) = &info.signature {
+            fields.push(("signature", signature.clone()));
+        }
+        if let Some(crate_name) = &info.crate_name {
+            fields.push(("crate", crate_name.clone()));
+        }
+        fields.push(("public", info.is_public.to_string()));
+        fields.push(("async", info.is_async.to_string()));
+        fields.push(("unsafe", info.is_unsafe.to_string()));
+        if let Some(group) = &info.group {
+            fields.push(("group", group.name.clone()));
+        }
+        fields
+    })
+}
+
+/// One page per node: its fields, metrics and direct neighbors both ways
+fn write_pages<N: GraphNode, E>(
+    graph: &DiGraph<N, E>,
+    dir: &Path,
+    format: PageFormat,
+    diagram: &str,
+    outgoing_title: &str,
+    incoming_title: &str,
+    fields: impl Fn(&N) -> Vec<(&'static str, String)>,
+) -> Result<(), Box<dyn Error>> {
+    let pages_dir = dir.join(PAGES_DIR);
+    fs::create_dir_all(&pages_dir).map_err(|e| format!("Failed to create {}: {}", pages_dir.display(), e))?;
+
+    for idx in graph.node_indices() {
+        let node = &graph[idx];
+        let id = node.id();
+        let neighbors = |direction| -> Vec<(String, String)> {
+            let mut ids: Vec<String> = graph.neighbors_directed(idx, direction).map(|n| graph[n].id()).collect();
+            ids.sort();
+            ids.dedup();
+            ids.into_iter().map(|id| (id.clone(), page_name(&id, format))).collect()
+        };
+        let (outgoing, incoming) = (neighbors(Direction::Outgoing), neighbors(Direction::Incoming));
+
+        let page = match format {
+            PageFormat::Markdown => {
+                let mut page = format!("# {}\n\n[Back to the graph](../{})\n\n", id, diagram);
+                for (key, value) in fields(node) {
+                    page.push_str(&format!("- **{}**: {}\n", key, value.replace('\n', " ")));
+                }
+                if !node.metrics().is_empty() {
+                    page.push_str("\n## Metrics\n\n");
+                    for (name, value) in node.metrics() {
+                        page.push_str(&format!("- {}: {}\n", name, format_metric(*value)));
+                    }
+                }
+                for (title, links) in [(outgoing_title, &outgoing), (incoming_title, &incoming)] {
+                    page.push_str(&format!("\n## {}\n\n", title));
+                    if links.is_empty() {
+                        page.push_str("_None._\n");
+                    }
+                    for (id, page_file) in links {
+                        page.push_str(&format!("- [{}]({})\n", id, page_file));
+                    }
+                }
+                page
+            }
+            PageFormat::Json => {
+                let fields: serde_json::Map<String, serde_json::Value> = fields(node).into_iter()
+                    .map(|(key, value)| (key.replace(' ', "_"), serde_json::json!(value)))
+                    .collect();
+                let links = |links: &[(String, String)]| -> Vec<serde_json::Value> {
+                    links.iter().map(|(id, page)| serde_json::json!({ "id": id, "page": page })).collect()
+                };
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "id": id,
+                    "graph": format!("../{}", diagram),
+                    "fields": fields,
+                    "metrics": node.metrics(),
+                    "outgoing": links(&outgoing),
+                    "incoming": links(&incoming)
+                }))
+                .unwrap_or_else(|_| "{}".to_string())
+            }
+        };
+
+        let path = pages_dir.join(page_name(&id, format));
+        write_atomic(&path, page).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+
+    eprintln!("{} detail pages written to: {}", graph.node_count(), pages_dir.display());
+    Ok(())
+}
@@ -0,0 +1,86 @@
+// ============================================================================
+// Presets
+// ============================================================================
+//
+// A preset stands for the options it bundles. They are appended to the
+// command line before it is parsed again, so clap checks them like typed
+// ones (`--preset docs --target x` fails as `--workspace-only --target x`
+// would), and left out where the command line already decides, e.g. with
+// --no-dedup or --dev.
+
+use clap::{parser::ValueSource, ArgMatches};
+
+use crate::types::Preset;
+
+/// Arguments the preset of `deps` matches adds, leaving out options given on the command line
+pub fn deps_preset_args(matches: &ArgMatches) -> Vec<&'static str> {
+    let Some(&preset) = matches.get_one::<Preset>("preset") else {
+        return Vec::new();
+    };
+
+    let given = |ids: &[&str]| ids.iter().any(|id| matches.value_source(id) == Some(ValueSource::CommandLine));
+    let mut args = Vec::new();
+    // Add `tokens` unless the command line gave one of `ids` (an option or the flag undoing it)
+    let mut add = |ids: &[&str], tokens: &[&'static str]| {
+        if !given(ids) {
+            args.extend_from_slice(tokens);
+        }
+    };
+
+    match preset {
+        Preset::Minimal => {
+            add(&["no_dev", "dev"], &["--no-dev"]);
+            add(&["no_build", "build"], &["--no-build"]);
+            add(&["dedup", "no_dedup"], &["--dedup"]);
+        }
+        Preset::Docs => {
+            add(&["workspace_only", "no_workspace_only"], &["--workspace-only"]);
+            add(&["dedup", "no_dedup"], &["--dedup"]);
+            // Clusters would clash with the other ways of grouping nodes
+            add(&["cluster_workspace", "no_cluster_workspace", "groups", "group_by_kind", "separate_components"], &["--cluster-workspace"]);
+            add(&["theme"], &["--theme", "dark"]);
+        }
+        Preset::Audit => {
+            add(&["show_versions", "no_show_versions"], &["--show-versions"]);
+            add(&["show_checksums", "no_show_checksums"], &["--show-checksums"]);
+            add(&["show_licenses", "no_show_licenses"], &["--show-licenses"]);
+            add(&["show_duplicates", "no_show_duplicates"], &["--show-duplicates"]);
+            add(&["audit", "no_audit"], &["--audit"]);
+        }
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{types::{Commands, DepsArgs, Theme}, utils::cli::parse_cli};
+
+    fn deps(args: &[&str]) -> Result<DepsArgs, clap::Error> {
+        let cli = parse_cli(["rust-grapher", "deps"].iter().chain(args))?;
+        match cli.command {
+            Commands::Deps(args) => Ok(args),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn presets_are_checked_like_typed_options() {
+        assert!(deps(&["--workspace-only", "--target", "x86_64-unknown-linux-gnu"]).is_err());
+        assert!(deps(&["--preset", "docs", "--target", "x86_64-unknown-linux-gnu"]).is_err());
+        assert!(deps(&["--preset", "docs", "--no-workspace-only", "--target", "x86_64-unknown-linux-gnu"]).is_ok());
+    }
+
+    #[test]
+    fn explicit_options_override_presets() {
+        let args = deps(&["--preset", "minimal"]).unwrap();
+        assert!(args.no_dev && args.no_build && args.dedup);
+        let args = deps(&["--preset", "minimal", "--dev", "--no-dedup"]).unwrap();
+        assert!(!args.no_dev && args.no_build && !args.dedup);
+
+        let args = deps(&["--preset", "docs", "--theme", "light", "--groups", "groups.toml"]).unwrap();
+        assert!(args.workspace_only && !args.cluster_workspace && matches!(args.theme, Theme::Light));
+
+        let args = deps(&["--preset", "audit", "--no-audit"]).unwrap();
+        assert!(args.show_versions && args.show_duplicates && !args.audit);
+    }
+}
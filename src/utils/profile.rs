@@ -0,0 +1,161 @@
+// ============================================================================
+// Profile Overlay (--profile-data)
+// ============================================================================
+//
+// Reads CPU samples as collapsed stacks (`main;run;parse 42`, as produced by
+// inferno/stackcollapse) or raw `perf script` output, and maps them onto the
+// function graph:
+//
+//   metric.samples       samples with the function anywhere on the stack
+//   metric.self_samples  samples where it is the innermost function of the graph
+//   edge samples         samples where the caller directly calls the callee
+//
+// Frames may be mangled or demangled; `demangle::SymbolIndex` matches them to nodes.
+
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fs,
+    path::Path,
+};
+
+use petgraph::graph::NodeIndex;
+
+use crate::{types::FnGraphData, utils::demangle::SymbolIndex};
+
+/// Metric names set on function nodes
+pub const SAMPLES: &str = "samples";
+pub const SELF_SAMPLES: &str = "self_samples";
+
+/// One distinct call stack, outermost frame first
+pub struct Stack {
+    pub frames: Vec<String>,
+    pub count: u64,
+}
+
+pub fn load_profile(path: &Path) -> Result<Vec<Stack>, Box<dyn Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let is_perf_script = content.lines().any(|line| line.starts_with([' ', '\t']) && !line.trim().is_empty());
+    let stacks = if is_perf_script {
+        parse_perf_script(&content)
+    } else {
+        parse_collapsed(&content).map_err(|e| format!("Invalid profile {}: {}", path.display(), e))?
+    };
+
+    if stacks.is_empty() {
+        return Err(format!("No samples in profile {}", path.display()).into());
+    }
+    Ok(stacks)
+}
+
+/// `frame;frame;frame count` per line
+fn parse_collapsed(content: &str) -> Result<Vec<Stack>, String> {
+    let mut stacks = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (stack, count) = line.rsplit_once(' ')
+            .and_then(|(stack, count)| Some((stack, count.parse::<u64>().ok()?)))
+            .ok_or_else(|| format!("line {}: expected `frame;frame;... count`", number + 1))?;
+        stacks.push(Stack {
+            frames: stack.split(';').map(str::to_string).collect(),
+            count,
+        });
+    }
+    Ok(stacks)
+}
+
+/// `perf script` samples: a header line, then one indented `addr symbol+off (dso)` line per frame, innermost first
+fn parse_perf_script(content: &str) -> Vec<Stack> {
+    let mut stacks = Vec::new();
+    let mut frames: Vec<String> = Vec::new();
+
+    for line in content.lines().chain(std::iter::once("")) {
+        if line.trim().is_empty() {
+            if !frames.is_empty() {
+                frames.reverse();
+                stacks.push(Stack { frames: std::mem::take(&mut frames), count: 1 });
+            }
+            continue;
+        }
+        if !line.starts_with([' ', '\t']) {
+            continue;
+        }
+
+        let line = line.trim();
+        let symbol = line.split_once(char::is_whitespace).map_or(line, |(_, rest)| rest.trim_start());
+        let symbol = match symbol.rfind(" (") {
+            Some(pos) if symbol.ends_with(')') => &symbol[..pos],
+            _ => symbol,
+        };
+        if symbol != "[unknown]" {
+            frames.push(symbol.to_string());
+        }
+    }
+
+    stacks
+}
+
+/// How many samples landed on graph nodes
+pub struct ProfileMatch {
+    pub total: u64,
+    pub matched: u64,
+}
+
+/// Attach sample counts to the nodes and edges of `graph_data`; `crates` as for `SymbolIndex::new`
+pub fn apply_profile(graph_data: &mut FnGraphData, stacks: &[Stack], crates: &[String]) -> ProfileMatch {
+    // Matched nodes of every stack, outermost first; recursion and inlined duplicates count once
+    let index = SymbolIndex::new(graph_data, crates);
+    let mut resolved: HashMap<&str, Option<NodeIndex>> = HashMap::new();
+    let paths: Vec<(Vec<NodeIndex>, u64)> = stacks.iter()
+        .map(|stack| {
+            let mut path: Vec<NodeIndex> = Vec::new();
+            for frame in &stack.frames {
+                let idx = *resolved.entry(frame).or_insert_with(|| index.lookup(frame));
+                if let Some(idx) = idx.filter(|idx| path.last() != Some(idx)) {
+                    path.push(idx);
+                }
+            }
+            (path, stack.count)
+        })
+        .collect();
+
+    let mut samples: HashMap<NodeIndex, u64> = HashMap::new();
+    let mut self_samples: HashMap<NodeIndex, u64> = HashMap::new();
+    let mut edge_samples: HashMap<(NodeIndex, NodeIndex), u64> = HashMap::new();
+    let mut result = ProfileMatch { total: 0, matched: 0 };
+
+    for (path, count) in paths {
+        result.total += count;
+        let Some(&innermost) = path.last() else {
+            continue;
+        };
+        result.matched += count;
+
+        *self_samples.entry(innermost).or_default() += count;
+        for idx in path.iter().copied().collect::<HashSet<_>>() {
+            *samples.entry(idx).or_default() += count;
+        }
+        let pairs: HashSet<(NodeIndex, NodeIndex)> = path.windows(2).map(|pair| (pair[0], pair[1])).collect();
+        for pair in pairs {
+            *edge_samples.entry(pair).or_default() += count;
+        }
+    }
+
+    for (idx, count) in samples {
+        let metrics = &mut graph_data.graph[idx].metrics;
+        metrics.insert(SAMPLES.to_string(), count as f64);
+        metrics.insert(SELF_SAMPLES.to_string(), self_samples.get(&idx).copied().unwrap_or(0) as f64);
+    }
+    for ((from, to), count) in edge_samples {
+        if let Some(edge) = graph_data.graph.find_edge(from, to) {
+            graph_data.graph[edge].samples = Some(count);
+        }
+    }
+
+    result
+}
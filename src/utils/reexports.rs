@@ -0,0 +1,126 @@
+// ============================================================================
+// Re-exports (fn-graph --reexports)
+// ============================================================================
+//
+// `pub use` items make a function public under other paths than the one it is
+// defined at, possibly through several hops in facade crates:
+//
+//   core/src/parse/mod.rs   pub fn parse()          core::parse::parse
+//   core/src/lib.rs         pub use parse::parse;   core::parse
+//   facade/src/lib.rs       pub use core::*;        facade::parse
+//
+// Module paths come from the file layout (src/a/b.rs and src/a/b/mod.rs are
+// module a::b) and inline modules. A path through a private `mod` is not
+// public, but a `pub use` there still counts as a hop of a chain.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::types::{Reexport, UseItem};
+
+/// Longest path followed: `pub use super::*` in a child module re-exports its parent's items
+/// under ever longer paths (`m::c::f`, `m::c::c::f`, ...)
+const MAX_SEGMENTS: usize = 16;
+
+/// A `pub use` with its paths resolved from the crate root
+pub struct ResolvedUse {
+    /// `module::alias`, or the module itself for a glob
+    pub path: String,
+    pub target: String,
+    pub is_glob: bool,
+}
+
+/// Module path of a source file: the components after its last `src` directory, without
+/// the `.rs` extension and the `lib`, `main` and `mod` file names
+pub fn file_modules(path: &str) -> Vec<String> {
+    let components: Vec<&str> = path.split(['/', '\\']).filter(|c| !c.is_empty()).collect();
+    let start = components.iter().rposition(|&c| c == "src").map_or(0, |pos| pos + 1);
+    let mut modules: Vec<String> = components[start..].iter()
+        .map(|c| c.strip_suffix(".rs").unwrap_or(c).to_string())
+        .collect();
+    if modules.last().is_some_and(|last| matches!(last.as_str(), "lib" | "main" | "mod")) {
+        modules.pop();
+    }
+    modules
+}
+
+/// One `(target, alias)` per name a use tree brings in; globs have the alias `*`
+pub fn flatten_use_tree(tree: &syn::UseTree, prefix: &mut Vec<String>, out: &mut Vec<(Vec<String>, String)>) {
+    // `use a::b::{self}` brings in `a::b` itself
+    let target = |prefix: &[String], name: &syn::Ident| -> Vec<String> {
+        match name == "self" {
+            true => prefix.to_vec(),
+            false => prefix.iter().cloned().chain([name.to_string()]).collect(),
+        }
+    };
+    match tree {
+        syn::UseTree::Path(path) => {
+            prefix.push(path.ident.to_string());
+            flatten_use_tree(&path.tree, prefix, out);
+            prefix.pop();
+        }
+        syn::UseTree::Name(name) => {
+            let alias = if name.ident == "self" { prefix.last().cloned().unwrap_or_default() } else { name.ident.to_string() };
+            out.push((target(prefix, &name.ident), alias));
+        }
+        syn::UseTree::Rename(rename) => out.push((target(prefix, &rename.ident), rename.rename.to_string())),
+        syn::UseTree::Glob(_) => out.push((prefix.clone(), "*".to_string())),
+        syn::UseTree::Group(group) => {
+            for tree in &group.items {
+                flatten_use_tree(tree, prefix, out);
+            }
+        }
+    }
+}
+
+/// Resolve a `pub use` from the crate root: `crate`, `self` and `super` as in Rust, a crate of
+/// `roots` as is, anything else relative to the module of the `use`
+pub fn resolve_use(item: &UseItem, roots: &HashSet<String>) -> Option<ResolvedUse> {
+    let (first, rest) = item.target.split_first()?;
+    let target: Vec<&str> = match first.as_str() {
+        "crate" => item.module.first().map(String::as_str).into_iter().chain(rest.iter().map(String::as_str)).collect(),
+        "self" => item.module.iter().chain(rest).map(String::as_str).collect(),
+        "super" => {
+            let supers = item.target.iter().take_while(|segment| *segment == "super").count();
+            let parent = item.module.get(..item.module.len().checked_sub(supers).filter(|&len| len > 0)?)?;
+            parent.iter().chain(&item.target[supers..]).map(String::as_str).collect()
+        }
+        first if roots.contains(first) => item.target.iter().map(String::as_str).collect(),
+        _ => item.module.iter().chain(&item.target).map(String::as_str).collect(),
+    };
+
+    let is_glob = item.alias == "*";
+    let path = if is_glob { item.module.join("::") } else { format!("{}::{}", item.module.join("::"), item.alias) };
+    Some(ResolvedUse { path, target: target.join("::"), is_glob })
+}
+
+/// Every path `definition` is re-exported under, following chains of re-exports
+pub fn reexports_of(definition: &str, uses: &[ResolvedUse]) -> Vec<Reexport> {
+    let mut reexports = Vec::new();
+    let mut seen: HashSet<String> = HashSet::from([definition.to_string()]);
+    let mut queue: VecDeque<(String, Vec<String>)> = VecDeque::from([(definition.to_string(), Vec::new())]);
+
+    while let Some((path, via)) = queue.pop_front() {
+        for item in uses {
+            let reexported = match path.strip_prefix(&item.target).and_then(|rest| rest.strip_prefix("::")) {
+                Some(rest) => format!("{}::{}", item.path, rest),
+                None if !item.is_glob && path == item.target => item.path.clone(),
+                None => continue,
+            };
+            if reexported.split("::").count() > MAX_SEGMENTS || !seen.insert(reexported.clone()) {
+                continue;
+            }
+            let via: Vec<String> = std::iter::once(path.clone()).chain(via.iter().cloned()).collect();
+            reexports.push(Reexport { path: reexported.clone(), via: via.clone() });
+            queue.push_back((reexported, via));
+        }
+    }
+
+    reexports.sort_by(|a, b| a.path.cmp(&b.path));
+    reexports
+}
+
+/// Whether no module along `path` is known to be private
+pub fn is_public_path(path: &str, modules: &HashMap<String, bool>) -> bool {
+    let segments: Vec<&str> = path.split("::").collect();
+    (2..segments.len()).all(|len| modules.get(&segments[..len].join("::")) != Some(&false))
+}
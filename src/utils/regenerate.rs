@@ -0,0 +1,74 @@
+// ============================================================================
+// Regenerate - Committed Diagrams from build.rs (`generate_to`)
+// ============================================================================
+//
+// Keeps a diagram in the repository in step with the code, from the build
+// script of the crate it documents (rust-grapher in [build-dependencies]):
+//
+//   fn main() -> Result<(), Box<dyn std::error::Error>> {
+//       let options = rust_grapher::GenerateOptions::new(["deps", "--workspace-only"])
+//           .check(std::env::var_os("CI").is_some());
+//       rust_grapher::generate_to("docs/deps.md", &options)
+//   }
+//
+// Arguments are those of the CLI, without the program name. Build scripts run
+// in the package directory, so relative paths (the diagram, --manifest-path,
+// --source-dir) start there. The file is only written when its content
+// changes, which keeps cargo from rebuilding for nothing; in check mode it is
+// never written and a stale or missing diagram fails the build.
+
+use std::{error::Error, fs, path::Path};
+
+use crate::{types::CheckFailed, utils::{cli::{parse_cli, run_cli}, helper::write_atomic}};
+
+/// What `generate_to` draws, and whether it only checks the file
+#[derive(Clone, Default)]
+pub struct GenerateOptions {
+    /// Command line without the program name, e.g. `["fn-graph", "--format", "dot"]`
+    pub args: Vec<String>,
+    /// Fail when the file differs from a fresh diagram instead of writing it
+    pub check: bool,
+}
+
+impl GenerateOptions {
+    pub fn new<I, S>(args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        GenerateOptions { args: args.into_iter().map(Into::into).collect(), check: false }
+    }
+
+    pub fn check(mut self, check: bool) -> Self {
+        self.check = check;
+        self
+    }
+}
+
+/// Draw the graph `options.args` describe into `path`, or with `options.check`, fail unless
+/// `path` already holds it
+pub fn generate_to(path: impl AsRef<Path>, options: &GenerateOptions) -> Result<(), Box<dyn Error>> {
+    let path = path.as_ref();
+    let argv = std::iter::once("rust-grapher".to_string()).chain(options.args.iter().cloned());
+    let cli = parse_cli(argv).map_err(|e| e.render().to_string())?;
+
+    let output = match run_cli(&cli) {
+        Ok((_, Some(_))) => return Err(format!("--output and --out-dir are not allowed, the graph goes to {}", path.display()).into()),
+        Ok((output, None)) => output,
+        Err(e) => match e.downcast::<CheckFailed>() {
+            Ok(failed) => return Err(format!("Check failed: {}", failed).into()),
+            Err(e) => return Err(e),
+        },
+    };
+
+    let current = fs::read_to_string(path).ok();
+    if current.as_deref() == Some(output.as_str()) {
+        return Ok(());
+    }
+    if options.check {
+        let problem = if current.is_some() { "is out of date" } else { "does not exist" };
+        return Err(format!("{} {}; build without the check to regenerate it", path.display(), problem).into());
+    }
+    write_atomic(path, output).map_err(|e| format!("Error writing to {}: {}", path.display(), e))?;
+    Ok(())
+}
@@ -0,0 +1,85 @@
+// ============================================================================
+// Registry Names (.cargo/config.toml)
+// ============================================================================
+//
+// Cargo source IDs only carry the index URL of a registry. The names users
+// know come from the `[registries]` tables of the cargo configuration, read
+// like cargo does: `.cargo/config.toml` (or `.cargo/config`) in the workspace
+// root and every directory above it, then $CARGO_HOME, with the nearest file
+// winning, plus CARGO_REGISTRIES_<NAME>_INDEX variables:
+//
+//   [registries.internal]
+//   index = "sparse+https://cargo.example.com/index/"
+//
+// crates.io is always `crates-io`; a registry no configuration names goes by
+// the host of its index.
+
+use std::{collections::HashMap, fs, path::{Path, PathBuf}};
+
+use crate::{types::{GraphData, WarningCode}, utils::{helper::is_crates_io, toml, warnings::warn}};
+
+/// Registry names by index URL, as configured for the workspace at `workspace_root`
+pub fn configured_registries(workspace_root: &Path) -> HashMap<String, String> {
+    let mut files: Vec<PathBuf> = workspace_root.ancestors()
+        .flat_map(|dir| [dir.join(".cargo/config.toml"), dir.join(".cargo/config")])
+        .collect();
+    let cargo_home = std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")).map(|home| PathBuf::from(home).join(".cargo")));
+    if let Some(cargo_home) = cargo_home {
+        files.extend([cargo_home.join("config.toml"), cargo_home.join("config")]);
+    }
+
+    // Farthest first, so nearer files overwrite
+    let mut registries = HashMap::new();
+    for file in files.iter().rev().filter(|file| file.is_file()) {
+        let config = match fs::read_to_string(file).map_err(|e| e.to_string()).and_then(|content| toml::parse(&content)) {
+            Ok(config) => config,
+            Err(e) => {
+                warn(WarningCode::MissingData, format!("no registry names from {}: {}", file.display(), e));
+                continue;
+            }
+        };
+        for (name, table) in config.get("registries").and_then(toml::TomlValue::as_table).unwrap_or_default() {
+            if let Some(index) = table.get("index").and_then(toml::TomlValue::as_str) {
+                registries.insert(index_key(index), name.clone());
+            }
+        }
+    }
+
+    for (key, value) in std::env::vars() {
+        if let Some(name) = key.strip_prefix("CARGO_REGISTRIES_").and_then(|rest| rest.strip_suffix("_INDEX")) {
+            registries.insert(index_key(&value), name.to_lowercase().replace('_', "-"));
+        }
+    }
+    registries
+}
+
+/// The registry a cargo source ID points at, by name; None for git and path sources
+pub fn registry_name(source: &str, registries: &HashMap<String, String>) -> Option<String> {
+    if is_crates_io(source) {
+        return Some("crates-io".to_string());
+    }
+    if !source.starts_with("registry+") && !source.starts_with("sparse+") {
+        return None;
+    }
+    let key = index_key(source);
+    if let Some(name) = registries.get(&key) {
+        return Some(name.clone());
+    }
+    let host = key.split_once("://").map_or(key.as_str(), |(_, rest)| rest);
+    // file:// indexes have no host
+    Some(host.split(['/', ':']).next().filter(|host| !host.is_empty()).unwrap_or(&key).to_string())
+}
+
+/// Set `registry` on every crate from a registry
+pub fn attach_registries(graph_data: &mut GraphData, registries: &HashMap<String, String>) {
+    for info in graph_data.graph.node_weights_mut() {
+        info.registry = info.source.as_deref().and_then(|source| registry_name(source, registries));
+    }
+}
+
+/// An index URL as configured or inside a source ID: without `registry+` and the trailing slash
+fn index_key(index: &str) -> String {
+    index.strip_prefix("registry+").unwrap_or(index).trim_end_matches('/').to_string()
+}
@@ -0,0 +1,100 @@
+// ============================================================================
+// Render - JSON Graphs from Anywhere (`render`)
+// ============================================================================
+//
+// Draws a graph in the schema of `-f json`, whether rust-grapher or another
+// tool wrote it, with the filters and styling of `deps` and `fn-graph`. The
+// least a file needs:
+//
+//   { "nodes": [{ "id": "a" }, { "id": "b" }], "edges": [{ "from": "a", "to": "b" }] }
+//
+// A node with a `qualified_name` makes it a function graph. Every other field
+// (`name`, `version`, `license`, `group`, `metrics`, `kinds`, ...) is read
+// when present, as for `combine`.
+
+use std::{error::Error, path::PathBuf};
+
+use crate::{types::{DepsArgs, FnGraphArgs, FnNodeInfo, NodeInfo, OutputFormat, RenderArgs, SourceLocations}, utils};
+
+use utils::combine::{is_fn_graph, load_json_graph, to_deps_graph_data, to_fn_graph_data};
+use utils::filter_expr::FilterExpr;
+use utils::graph::filter_attributes;
+use utils::grapher::{render_deps_graph, render_fn_graph};
+use utils::groups::load_groups;
+
+pub fn run_render(args: &RenderArgs) -> Result<(String, Option<PathBuf>), Box<dyn Error>> {
+    if matches!(args.format, OutputFormat::CallHierarchy | OutputFormat::Sarif) {
+        return Err(format!("--format {} is only available for fn-graph", args.format.name()).into());
+    }
+    let groups = args.groups.as_deref().map(load_groups).transpose()?;
+    let graph = load_json_graph(&args.input)?;
+
+    let output = if is_fn_graph(&graph) {
+        let fn_args = FnGraphArgs {
+            format: args.format.clone(),
+            no_fence: args.no_fence,
+            direction: args.direction.clone(),
+            focus: args.focus.clone(),
+            fade_by_hops: args.fade_by_hops,
+            depth: args.depth,
+            filter: args.filter.clone(),
+            sample: args.sample,
+            max_edges_per_node: args.max_edges_per_node,
+            prune_leaves: args.prune_leaves,
+            min_fanin: args.min_fanin,
+            page_size: args.page_size,
+            page: args.page,
+            show_signatures: args.show_signatures,
+            color_by: args.color_by,
+            color_metric: args.color_metric.clone(),
+            theme: args.theme.clone(),
+            mermaid_version: args.mermaid_version,
+            highlight: args.highlight.clone(),
+            ..Default::default()
+        };
+        let filter = args.filter.as_deref()
+            .map(|source| FilterExpr::parse(source, &filter_attributes::<FnNodeInfo>()))
+            .transpose()?;
+        let mut graph_data = to_fn_graph_data(&graph);
+        render_fn_graph(&mut graph_data, &fn_args, filter.as_ref(), groups.as_deref(), &[], &SourceLocations::default())?
+    } else {
+        let deps_args = DepsArgs {
+            format: args.format.clone(),
+            no_fence: args.no_fence,
+            direction: args.direction.clone(),
+            focus: args.focus.clone(),
+            fade_by_hops: args.fade_by_hops,
+            filter: args.filter.clone(),
+            sample: args.sample,
+            max_edges_per_node: args.max_edges_per_node,
+            prune_leaves: args.prune_leaves,
+            min_fanin: args.min_fanin,
+            page_size: args.page_size,
+            page: args.page,
+            show_versions: args.show_versions,
+            show_licenses: args.show_licenses,
+            highlight_copyleft: args.highlight_copyleft,
+            group_by_kind: args.group_by_kind,
+            separate_components: args.separate_components,
+            color_by: args.color_by,
+            color_metric: args.color_metric.clone(),
+            theme: args.theme.clone(),
+            mermaid_version: args.mermaid_version,
+            highlight: args.highlight.clone(),
+            ..Default::default()
+        };
+        let filter = args.filter.as_deref()
+            .map(|source| FilterExpr::parse(source, &filter_attributes::<NodeInfo>()))
+            .transpose()?;
+        let mut graph_data = to_deps_graph_data(&graph);
+
+        // Depth is measured from the workspace members, as for a cargo graph
+        let roots: Vec<(String, String)> = graph_data.graph.node_weights()
+            .filter(|info| info.is_workspace_member)
+            .map(|info| (info.name.clone(), info.version.clone()))
+            .collect();
+        render_deps_graph(&mut graph_data, &deps_args, filter.as_ref(), groups.as_deref(), &roots)?
+    };
+
+    Ok((output, args.output.clone()))
+}
@@ -0,0 +1,193 @@
+// ============================================================================
+// Self-Test (`selftest`)
+// ============================================================================
+//
+// Runs both analyses on rust-grapher's own sources, compiled into the binary
+// so the test works after `cargo install`, and checks what they must find:
+//
+//   ok    cargo: cargo 1.80.0 (...)
+//   ok    deps: rust-grapher in its own manifest
+//   ok    fn-graph: main calls run_cli
+//   skip  graphviz: `dot` not found (only needed to turn -f dot into images)
+//
+// `deps` runs `cargo metadata --no-deps --offline` on a copy of the manifest
+// in a temporary directory, so no network is needed. A missing Graphviz is
+// reported but fails nothing. Any failed check exits with status 2.
+
+use std::{error::Error, fs, io::Write, path::{Path, PathBuf}, process::{Command, Stdio}};
+
+use crate::{types::{CheckFailed, DepsArgs, FnGraphArgs, OutputFormat}, utils::{generator::generate_deps_mermaid, grapher::{fn_graph_from_sources, load_deps_graph}}};
+
+/// The sources analyzed, as `(path, content)`
+const SOURCES: &[(&str, &str)] = &[
+    ("src/main.rs", include_str!("../main.rs")),
+    ("src/utils/cli.rs", include_str!("cli.rs")),
+    ("src/utils/grapher.rs", include_str!("grapher.rs")),
+];
+const MANIFEST: &str = include_str!("../../Cargo.toml");
+
+/// Calls the call graph must contain, as `(caller, callee)` function names.
+/// Only entry points that stay put; how `run_cli` dispatches is its own business.
+const EXPECTED_CALLS: &[(&str, &str)] = &[
+    ("main", "run_cli"),
+    ("run_deps", "load_deps_graph"),
+];
+
+enum Outcome {
+    Ok(String),
+    Failed(String),
+    Skipped(String),
+}
+
+pub fn run_selftest() -> Result<(String, Option<PathBuf>), Box<dyn Error>> {
+    let mut results: Vec<(&str, Outcome)> = vec![("cargo", check_cargo()), ("deps", check_deps())];
+
+    let dot = match fn_graph_json() {
+        Ok(graph) => {
+            let (parsed, calls) = check_calls(&graph);
+            results.push(("fn-graph", parsed));
+            results.extend(calls.into_iter().map(|outcome| ("fn-graph", outcome)));
+            fn_graph_from_sources(SOURCES, &FnGraphArgs { format: OutputFormat::Dot, ..Default::default() }).ok()
+        }
+        Err(e) => {
+            results.push(("fn-graph", Outcome::Failed(e.to_string())));
+            None
+        }
+    };
+    results.push(("graphviz", check_graphviz(dot.as_deref())));
+
+    let mut output = String::new();
+    for (name, outcome) in &results {
+        let (status, message) = match outcome {
+            Outcome::Ok(message) => ("ok", message),
+            Outcome::Failed(message) => ("FAIL", message),
+            Outcome::Skipped(message) => ("skip", message),
+        };
+        output.push_str(&format!("{:<5} {}: {}\n", status, name, message));
+    }
+
+    let failed = results.iter().filter(|(_, outcome)| matches!(outcome, Outcome::Failed(_))).count();
+    if failed > 0 {
+        return Err(Box::new(CheckFailed { message: format!("{} of {} self-test checks failed", failed, results.len()), output, output_path: None }));
+    }
+    Ok((output, None))
+}
+
+fn check_cargo() -> Outcome {
+    match Command::new("cargo").arg("--version").output() {
+        Ok(output) if output.status.success() => Outcome::Ok(String::from_utf8_lossy(&output.stdout).trim().to_string()),
+        Ok(output) => Outcome::Failed(format!("`cargo --version` failed: {}", String::from_utf8_lossy(&output.stderr).trim())),
+        Err(e) => Outcome::Failed(format!("cannot run cargo ({}); `deps` needs it on PATH", e)),
+    }
+}
+
+/// `deps --workspace-only --offline` on a copy of the manifest, with empty targets
+fn check_deps() -> Outcome {
+    let dir = std::env::temp_dir().join(format!("rust-grapher-selftest-{}", std::process::id()));
+    let result = write_manifest_copy(&dir).and_then(|manifest_path| {
+        let args = DepsArgs { manifest_path, workspace_only: true, offline: true, ..Default::default() };
+        let (graph_data, _) = load_deps_graph(&args)?;
+        let found = graph_data.graph.node_weights().any(|info| info.name == "rust-grapher" && info.is_workspace_member);
+        if !found || generate_deps_mermaid(&graph_data, &args).is_empty() {
+            return Err("rust-grapher is missing from the graph of its own manifest".into());
+        }
+        Ok(())
+    });
+    let _ = fs::remove_dir_all(&dir);
+
+    match result {
+        Ok(()) => Outcome::Ok("rust-grapher in its own manifest".to_string()),
+        Err(e) => Outcome::Failed(e.to_string()),
+    }
+}
+
+fn write_manifest_copy(dir: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    fs::create_dir_all(dir.join("src"))?;
+    fs::write(dir.join("src/main.rs"), "")?;
+    fs::write(dir.join("src/lib.rs"), "")?;
+    let manifest_path = dir.join("Cargo.toml");
+    fs::write(&manifest_path, MANIFEST)?;
+    Ok(manifest_path)
+}
+
+fn fn_graph_json() -> Result<serde_json::Value, Box<dyn Error>> {
+    let output = fn_graph_from_sources(SOURCES, &FnGraphArgs { format: OutputFormat::Json, ..Default::default() })?;
+    Ok(serde_json::from_str(&output)?)
+}
+
+/// Whether the sources parsed into functions, and one outcome per expected call
+fn check_calls(graph: &serde_json::Value) -> (Outcome, Vec<Outcome>) {
+    let empty = Vec::new();
+    let nodes = graph["nodes"].as_array().unwrap_or(&empty);
+    let edges = graph["edges"].as_array().unwrap_or(&empty);
+    let name_of = |id: &serde_json::Value| nodes.iter()
+        .find(|node| node["id"] == *id)
+        .and_then(|node| node["name"].as_str());
+
+    let parsed = if nodes.is_empty() {
+        Outcome::Failed(format!("no functions in {} source files", SOURCES.len()))
+    } else {
+        Outcome::Ok(format!("{} source files parsed, {} functions, {} calls", SOURCES.len(), nodes.len(), edges.len()))
+    };
+
+    let calls = EXPECTED_CALLS.iter()
+        .map(|&(caller, callee)| {
+            let found = edges.iter().any(|edge| name_of(&edge["from"]) == Some(caller) && name_of(&edge["to"]) == Some(callee));
+            match found {
+                true => Outcome::Ok(format!("{} calls {}", caller, callee)),
+                false => Outcome::Failed(format!("no call from {} to {}", caller, callee)),
+            }
+        })
+        .collect();
+    (parsed, calls)
+}
+
+/// `dot -V`, then the call graph laid out as SVG
+fn check_graphviz(dot_source: Option<&str>) -> Outcome {
+    let version = match Command::new("dot").arg("-V").output() {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        _ => return Outcome::Skipped("`dot` not found (only needed to turn -f dot into images)".to_string()),
+    };
+    let Some(source) = dot_source else {
+        return Outcome::Skipped(format!("{}; no DOT output to lay out", version));
+    };
+
+    let child = Command::new("dot").arg("-Tsvg").stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::piped()).spawn();
+    let result = child.and_then(|mut child| {
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(source.as_bytes())?;
+        }
+        child.wait_with_output()
+    });
+    match result {
+        Ok(output) if output.status.success() => Outcome::Ok(format!("{} lays out the call graph", version)),
+        Ok(output) => Outcome::Failed(format!("dot rejected the call graph: {}", String::from_utf8_lossy(&output.stderr).trim())),
+        Err(e) => Outcome::Failed(format!("cannot run dot: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expected_calls_are_found() {
+        let graph = fn_graph_json().unwrap();
+        let (parsed, calls) = check_calls(&graph);
+        for outcome in std::iter::once(parsed).chain(calls) {
+            if let Outcome::Failed(message) = outcome {
+                panic!("{}", message);
+            }
+        }
+    }
+
+    #[test]
+    fn selftest_passes() {
+        if let Err(e) = run_selftest() {
+            match e.downcast::<CheckFailed>() {
+                Ok(check) => panic!("{}\n{}", check.message, check.output),
+                Err(e) => panic!("{}", e),
+            }
+        }
+    }
+}
@@ -0,0 +1,142 @@
+// ============================================================================
+// Binary Size Overlay (--size-data)
+// ============================================================================
+//
+// Reads compiled function sizes from `cargo bloat -n 0 --message-format json`
+// or a linker map (GNU ld / gold, LLD, or Apple ld64 `-map`) and sums them per
+// function node as `metric.size` in bytes. Generic instantiations and closures
+// add up on the function they were written in.
+
+use std::{error::Error, fs, path::Path};
+
+use serde_json::Value;
+
+use crate::{types::FnGraphData, utils::demangle::{demangle, SymbolIndex}};
+
+/// Metric name set on function nodes
+pub const SIZE: &str = "size";
+
+/// Symbol (mangled or demangled) and its size in bytes
+pub struct SymbolSize {
+    pub symbol: String,
+    pub size: u64,
+}
+
+pub fn load_sizes(path: &Path) -> Result<Vec<SymbolSize>, Box<dyn Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let sizes = if content.trim_start().starts_with('{') {
+        parse_bloat_json(&content).map_err(|e| format!("Invalid cargo bloat output {}: {}", path.display(), e))?
+    } else {
+        parse_linker_map(&content)
+    };
+
+    if sizes.is_empty() {
+        return Err(format!("No Rust function sizes found in {}", path.display()).into());
+    }
+    Ok(sizes)
+}
+
+fn parse_bloat_json(content: &str) -> Result<Vec<SymbolSize>, String> {
+    let value: Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    let functions = value["functions"].as_array().ok_or("missing \"functions\" list")?;
+
+    Ok(functions.iter()
+        .filter_map(|function| Some(SymbolSize {
+            symbol: function["name"].as_str()?.to_string(),
+            size: function["size"].as_u64()?,
+        }))
+        .collect())
+}
+
+/// Rust symbols from any of the supported map formats; everything else is skipped
+fn parse_linker_map(content: &str) -> Vec<SymbolSize> {
+    let mut sizes = Vec::new();
+    let mut pending_section: Option<&str> = None;
+    let mut in_ld64_symbols = false;
+
+    for line in content.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+
+        // ld64: `0x100001000  0x000001A4  [  1] _ZN...E` after `# Symbols:`
+        if line.starts_with("# Symbols:") {
+            in_ld64_symbols = true;
+            continue;
+        }
+        if in_ld64_symbols {
+            if let (Some(size), Some((_, symbol))) = (parts.get(1).and_then(|size| parse_hex(size)), line.split_once("] ")) {
+                push_rust_symbol(&mut sizes, symbol.trim(), size);
+            }
+            continue;
+        }
+
+        // GNU ld: ` .text.<symbol>  0xADDR  0xSIZE  file`, wrapped onto the next line for long names
+        if let Some(section) = pending_section.take() {
+            if let (Some(_), Some(size)) = (parts.first().and_then(|a| parse_hex(a)), parts.get(1).and_then(|s| parse_hex(s))) {
+                push_rust_symbol(&mut sizes, section, size);
+                continue;
+            }
+        }
+        if let Some(section) = parts.first().and_then(|first| text_section_symbol(first)) {
+            match (parts.get(1).and_then(|a| parse_hex(a)), parts.get(2).and_then(|s| parse_hex(s))) {
+                (Some(_), Some(size)) => push_rust_symbol(&mut sizes, section, size),
+                _ if parts.len() == 1 => pending_section = Some(section),
+                _ => {}
+            }
+            continue;
+        }
+
+        // LLD: `VMA LMA Size Align file.o:(.text.<symbol>)` input section rows; the symbol rows
+        // below them are printed half-demangled, so the section name is the reliable one
+        if let [vma, lma, size, align, input] = parts.as_slice() {
+            let numeric = [vma, lma, align].iter().all(|field| u64::from_str_radix(field, 16).is_ok());
+            let section = input.rsplit_once(":(").and_then(|(_, section)| section.strip_suffix(')'));
+            if let (true, Some(symbol), Ok(size)) = (numeric, section.and_then(text_section_symbol), u64::from_str_radix(size, 16)) {
+                push_rust_symbol(&mut sizes, symbol, size);
+            }
+        }
+    }
+
+    sizes
+}
+
+/// Symbol of a per-function text section such as `.text._ZN...E` or `.text.unlikely._ZN...E`
+fn text_section_symbol(section: &str) -> Option<&str> {
+    let symbol = section.strip_prefix(".text.")?;
+    Some(["unlikely.", "hot.", "startup.", "exit."].iter()
+        .find_map(|prefix| symbol.strip_prefix(prefix))
+        .unwrap_or(symbol))
+}
+
+fn parse_hex(text: &str) -> Option<u64> {
+    u64::from_str_radix(text.strip_prefix("0x").or_else(|| text.strip_prefix("0X"))?, 16).ok()
+}
+
+fn push_rust_symbol(sizes: &mut Vec<SymbolSize>, symbol: &str, size: u64) {
+    if size > 0 && demangle(symbol).is_some() {
+        sizes.push(SymbolSize { symbol: symbol.to_string(), size });
+    }
+}
+
+/// How many bytes landed on graph nodes
+pub struct SizeMatch {
+    pub total: u64,
+    pub matched: u64,
+}
+
+/// Add each symbol's size to the `size` metric of the node it belongs to; `crates` as for `SymbolIndex::new`
+pub fn apply_sizes(graph_data: &mut FnGraphData, sizes: &[SymbolSize], crates: &[String]) -> SizeMatch {
+    let index = SymbolIndex::new(graph_data, crates);
+    let matches: Vec<_> = sizes.iter().map(|entry| (index.lookup(&entry.symbol), entry.size)).collect();
+
+    let mut result = SizeMatch { total: 0, matched: 0 };
+    for (idx, size) in matches {
+        result.total += size;
+        if let Some(idx) = idx {
+            result.matched += size;
+            *graph_data.graph[idx].metrics.entry(SIZE.to_string()).or_default() += size as f64;
+        }
+    }
+    result
+}
@@ -0,0 +1,149 @@
+// ============================================================================
+// Dependency Statistics (deps --stats)
+// ============================================================================
+//
+// A summary of the graph instead of a drawing, as text or with `-f json` as
+// an object for scripts:
+//
+//   Crates:         45 (1 workspace member)
+//   Edges:          73 (normal 70, build 2, dev 1)
+//   Max depth:      6
+//   Longest chain:  7 crates: app -> reqwest -> hyper -> ...
+//   Duplicates:     1 crate in several versions
+//     windows-sys  0.48.0, 0.52.0
+//   Top fan-in:
+//     12  serde 1.0.228
+//
+// Depth counts the fewest edges from the root packages; the longest chain is
+// the longest path of dependencies, leaving out edges that close a cycle.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use petgraph::{graph::NodeIndex, Direction};
+
+use crate::{types::{DepKind, GraphData}, utils::graph::compute_depths};
+
+/// Crates listed under "Top fan-in"
+const TOP_FAN_IN: usize = 10;
+
+/// The summary of `graph_data`, as JSON or as text; depth is measured from `roots`
+pub fn generate_deps_stats(graph_data: &GraphData, roots: &[NodeIndex], json: bool) -> String {
+    let graph = &graph_data.graph;
+    let members = graph.node_weights().filter(|info| info.is_workspace_member && !info.is_build_script).count();
+    let kind_count = |kind: DepKind| graph.edge_weights().filter(|kinds| kinds.contains(kind)).count();
+    let max_depth = compute_depths(graph, roots).into_values().max();
+
+    let mut versions: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for info in graph.node_weights().filter(|info| !info.is_build_script) {
+        versions.entry(&info.name).or_default().insert(&info.version);
+    }
+    versions.retain(|_, versions| versions.len() > 1);
+
+    // Versions tell apart the copies of a duplicated crate
+    let chain: Vec<String> = longest_chain(graph_data).into_iter()
+        .map(|idx| match versions.contains_key(graph[idx].name.as_str()) {
+            true => format!("{}@{}", graph[idx].name, graph[idx].version),
+            false => graph[idx].name.clone(),
+        })
+        .collect();
+
+    let mut fan_in: Vec<(NodeIndex, usize)> = graph.node_indices()
+        .map(|idx| (idx, graph.neighbors_directed(idx, Direction::Incoming).count()))
+        .filter(|&(_, count)| count > 0)
+        .collect();
+    fan_in.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| graph[a.0].name.cmp(&graph[b.0].name)));
+    fan_in.truncate(TOP_FAN_IN);
+
+    if json {
+        let output = serde_json::json!({
+            "nodes": graph.node_count(),
+            "workspace_members": members,
+            "edges": graph.edge_count(),
+            "edges_by_kind": {
+                "normal": kind_count(DepKind::Normal),
+                "build": kind_count(DepKind::Build),
+                "dev": kind_count(DepKind::Dev),
+            },
+            "max_depth": max_depth,
+            "longest_chain": chain,
+            "duplicates": versions,
+            "top_fan_in": fan_in.iter().map(|&(idx, count)| serde_json::json!({
+                "name": graph[idx].name,
+                "version": graph[idx].version,
+                "fan_in": count,
+            })).collect::<Vec<_>>(),
+        });
+        return serde_json::to_string_pretty(&output).unwrap_or_default();
+    }
+
+    let plural = |count: usize, word: &str| format!("{} {}{}", count, word, if count == 1 { "" } else { "s" });
+    let mut output = String::new();
+    output.push_str(&format!("Crates:         {} ({})\n", graph.node_count(), plural(members, "workspace member")));
+    output.push_str(&format!(
+        "Edges:          {} (normal {}, build {}, dev {})\n",
+        graph.edge_count(),
+        kind_count(DepKind::Normal),
+        kind_count(DepKind::Build),
+        kind_count(DepKind::Dev),
+    ));
+    output.push_str(&format!("Max depth:      {}\n", max_depth.map_or("-".to_string(), |depth| depth.to_string())));
+    output.push_str(&format!("Longest chain:  {}: {}\n", plural(chain.len(), "crate"), chain.join(" -> ")));
+    output.push_str(&format!("Duplicates:     {} in several versions\n", plural(versions.len(), "crate")));
+    let width = versions.keys().map(|name| name.len()).max().unwrap_or(0);
+    for (name, versions) in &versions {
+        output.push_str(&format!("  {:<width$}  {}\n", name, versions.iter().copied().collect::<Vec<_>>().join(", "), width = width));
+    }
+    output.push_str("Top fan-in:\n");
+    for &(idx, count) in &fan_in {
+        output.push_str(&format!("  {:>4}  {} {}\n", count, graph[idx].name, graph[idx].version));
+    }
+    output
+}
+
+/// Nodes of the longest dependency path, skipping edges back into the path being explored
+fn longest_chain(graph_data: &GraphData) -> Vec<NodeIndex> {
+    let graph = &graph_data.graph;
+    // Longest path starting at each node, as (length in nodes, next node)
+    let mut longest: HashMap<NodeIndex, (usize, Option<NodeIndex>)> = HashMap::new();
+
+    for start in graph.node_indices() {
+        if longest.contains_key(&start) {
+            continue;
+        }
+        // Iterative DFS; a node is finished once all its successors are
+        let mut stack: Vec<(NodeIndex, bool)> = vec![(start, false)];
+        let mut on_path: BTreeSet<NodeIndex> = BTreeSet::new();
+        while let Some((idx, expanded)) = stack.pop() {
+            if expanded {
+                on_path.remove(&idx);
+                let best = graph.neighbors_directed(idx, Direction::Outgoing)
+                    .filter_map(|next| longest.get(&next).map(|&(length, _)| (length, next)))
+                    .max_by(|a, b| a.0.cmp(&b.0).then_with(|| b.1.cmp(&a.1)));
+                longest.insert(idx, best.map_or((1, None), |(length, next)| (length + 1, Some(next))));
+                continue;
+            }
+            if longest.contains_key(&idx) || !on_path.insert(idx) {
+                continue;
+            }
+            stack.push((idx, true));
+            for next in graph.neighbors_directed(idx, Direction::Outgoing) {
+                if !longest.contains_key(&next) && !on_path.contains(&next) {
+                    stack.push((next, false));
+                }
+            }
+        }
+    }
+
+    let Some((mut idx, _)) = graph.node_indices()
+        .filter_map(|idx| longest.get(&idx).map(|&(length, _)| (idx, length)))
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+    else {
+        return Vec::new();
+    };
+    let mut chain = vec![idx];
+    while let Some(&(_, Some(next))) = longest.get(&idx) {
+        chain.push(next);
+        idx = next;
+    }
+    chain
+}
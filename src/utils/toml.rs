@@ -0,0 +1,358 @@
+// ============================================================================
+// Minimal TOML Reader
+// ============================================================================
+//
+// Covers the subset used by rust-grapher's own files: `[table]` and
+// `[[array.of.tables]]` headers, bare/quoted/dotted keys, basic and literal
+// strings, integers, floats, booleans, (multi-line) arrays and inline tables.
+// Dates and multi-line strings are not supported. Tables keep key order.
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TomlValue {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Array(Vec<TomlValue>),
+    Table(Vec<(String, TomlValue)>),
+}
+
+impl TomlValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            TomlValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_table(&self) -> Option<&[(String, TomlValue)]> {
+        match self {
+            TomlValue::Table(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    /// Look up a key of a table
+    pub fn get(&self, key: &str) -> Option<&TomlValue> {
+        self.as_table()?.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Strings of an array, or a single string as a one-element list
+    pub fn as_string_list(&self) -> Option<Vec<String>> {
+        match self {
+            TomlValue::String(s) => Some(vec![s.clone()]),
+            TomlValue::Array(items) => items.iter().map(|v| v.as_str().map(str::to_string)).collect(),
+            _ => None,
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            TomlValue::String(_) => "string",
+            TomlValue::Integer(_) => "integer",
+            TomlValue::Float(_) => "float",
+            TomlValue::Boolean(_) => "boolean",
+            TomlValue::Array(_) => "array",
+            TomlValue::Table(_) => "table",
+        }
+    }
+}
+
+/// Parse a document into its root table
+pub fn parse(source: &str) -> Result<TomlValue, String> {
+    let mut parser = Parser { chars: source.chars().collect(), pos: 0, line: 1 };
+    let mut root: Vec<(String, TomlValue)> = Vec::new();
+    let mut current: Vec<String> = Vec::new();
+
+    loop {
+        parser.skip_trivia();
+        let Some(c) = parser.peek() else { break };
+
+        if c == '[' {
+            parser.pos += 1;
+            let array_of_tables = parser.peek() == Some('[');
+            if array_of_tables {
+                parser.pos += 1;
+            }
+            parser.skip_spaces();
+            let path = parser.parse_key()?;
+            parser.skip_spaces();
+            parser.expect(']')?;
+            if array_of_tables {
+                parser.expect(']')?;
+                push_array_table(&mut root, &path).map_err(|e| parser.error(&e))?;
+            } else {
+                table_at(&mut root, &path).map_err(|e| parser.error(&e))?;
+            }
+            current = path;
+        } else {
+            let key = parser.parse_key()?;
+            parser.skip_spaces();
+            parser.expect('=')?;
+            parser.skip_spaces();
+            let value = parser.parse_value()?;
+            let table = table_at(&mut root, &current).map_err(|e| parser.error(&e))?;
+            insert(table, &key, value).map_err(|e| parser.error(&e))?;
+        }
+
+        parser.skip_spaces();
+        parser.skip_comment();
+        match parser.peek() {
+            None | Some('\n') | Some('\r') => {}
+            Some(c) => return Err(parser.error(&format!("unexpected '{}' after value", c))),
+        }
+    }
+
+    Ok(TomlValue::Table(root))
+}
+
+/// Table at `path`, created on demand; the last element of an array of tables is used
+fn table_at<'a>(root: &'a mut Vec<(String, TomlValue)>, path: &[String]) -> Result<&'a mut Vec<(String, TomlValue)>, String> {
+    let mut table = root;
+    for part in path {
+        let pos = match table.iter().position(|(k, _)| k == part) {
+            Some(pos) => pos,
+            None => {
+                table.push((part.clone(), TomlValue::Table(Vec::new())));
+                table.len() - 1
+            }
+        };
+        table = match &mut table[pos].1 {
+            TomlValue::Table(entries) => entries,
+            TomlValue::Array(items) => match items.last_mut() {
+                Some(TomlValue::Table(entries)) => entries,
+                _ => return Err(format!("'{}' is not a table", part)),
+            },
+            _ => return Err(format!("'{}' is not a table", part)),
+        };
+    }
+    Ok(table)
+}
+
+fn push_array_table(root: &mut Vec<(String, TomlValue)>, path: &[String]) -> Result<(), String> {
+    let (last, parent) = path.split_last().ok_or("empty table header")?;
+    let table = table_at(root, parent)?;
+    match table.iter_mut().find(|(k, _)| k == last) {
+        Some((_, TomlValue::Array(items))) => items.push(TomlValue::Table(Vec::new())),
+        Some(_) => return Err(format!("'{}' is not an array of tables", last)),
+        None => table.push((last.clone(), TomlValue::Array(vec![TomlValue::Table(Vec::new())]))),
+    }
+    Ok(())
+}
+
+/// Insert a (possibly dotted) key, rejecting duplicates
+fn insert(table: &mut Vec<(String, TomlValue)>, key: &[String], value: TomlValue) -> Result<(), String> {
+    let (last, parents) = key.split_last().ok_or("empty key")?;
+    let table = table_at(table, parents)?;
+    if table.iter().any(|(k, _)| k == last) {
+        return Err(format!("duplicate key '{}'", last));
+    }
+    table.push((last.clone(), value));
+    Ok(())
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("line {}: {}", self.line, message)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) => Err(self.error(&format!("expected '{}', found '{}'", expected, c))),
+            None => Err(self.error(&format!("expected '{}', found end of file", expected))),
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ') | Some('\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// Whitespace, newlines and comments
+    fn skip_trivia(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n') => {
+                    self.line += 1;
+                    self.pos += 1;
+                }
+                Some('\r') => self.pos += 1,
+                _ => break,
+            }
+        }
+    }
+
+    fn parse_key(&mut self) -> Result<Vec<String>, String> {
+        let mut parts = Vec::new();
+        loop {
+            self.skip_spaces();
+            let part = match self.peek() {
+                Some('"') => self.parse_basic_string()?,
+                Some('\'') => self.parse_literal_string()?,
+                _ => {
+                    let start = self.pos;
+                    while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                        self.pos += 1;
+                    }
+                    if start == self.pos {
+                        return Err(self.error("expected a key"));
+                    }
+                    self.chars[start..self.pos].iter().collect()
+                }
+            };
+            parts.push(part);
+            self.skip_spaces();
+            if self.peek() == Some('.') {
+                self.pos += 1;
+            } else {
+                return Ok(parts);
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<TomlValue, String> {
+        match self.peek() {
+            Some('"') => Ok(TomlValue::String(self.parse_basic_string()?)),
+            Some('\'') => Ok(TomlValue::String(self.parse_literal_string()?)),
+            Some('[') => self.parse_array(),
+            Some('{') => self.parse_inline_table(),
+            Some(_) => self.parse_scalar(),
+            None => Err(self.error("expected a value")),
+        }
+    }
+
+    fn parse_basic_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(value);
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    let escaped = match self.peek() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some(c) => return Err(self.error(&format!("unsupported escape '\\{}'", c))),
+                        None => return Err(self.error("unterminated string")),
+                    };
+                    value.push(escaped);
+                    self.pos += 1;
+                }
+                Some(c) => {
+                    value.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    fn parse_literal_string(&mut self) -> Result<String, String> {
+        self.expect('\'')?;
+        let start = self.pos;
+        while !matches!(self.peek(), None | Some('\'') | Some('\n')) {
+            self.pos += 1;
+        }
+        let value = self.chars[start..self.pos].iter().collect();
+        self.expect('\'')?;
+        Ok(value)
+    }
+
+    fn parse_array(&mut self) -> Result<TomlValue, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_trivia();
+            if self.peek() == Some(']') {
+                self.pos += 1;
+                return Ok(TomlValue::Array(items));
+            }
+            items.push(self.parse_value()?);
+            self.skip_trivia();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {}
+                _ => return Err(self.error("expected ',' or ']' in array")),
+            }
+        }
+    }
+
+    fn parse_inline_table(&mut self) -> Result<TomlValue, String> {
+        self.expect('{')?;
+        let mut entries = Vec::new();
+        loop {
+            self.skip_spaces();
+            if self.peek() == Some('}') {
+                self.pos += 1;
+                return Ok(TomlValue::Table(entries));
+            }
+            let key = self.parse_key()?;
+            self.skip_spaces();
+            self.expect('=')?;
+            self.skip_spaces();
+            let value = self.parse_value()?;
+            insert(&mut entries, &key, value).map_err(|e| self.error(&e))?;
+            self.skip_spaces();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => {}
+                _ => return Err(self.error("expected ',' or '}' in inline table")),
+            }
+        }
+    }
+
+    fn parse_scalar(&mut self) -> Result<TomlValue, String> {
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.' | '_')) {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+
+        match text.as_str() {
+            "true" => return Ok(TomlValue::Boolean(true)),
+            "false" => return Ok(TomlValue::Boolean(false)),
+            "" => return Err(self.error("expected a value")),
+            _ => {}
+        }
+
+        let digits = text.replace('_', "");
+        if let Ok(value) = digits.parse::<i64>() {
+            return Ok(TomlValue::Integer(value));
+        }
+        if let Ok(value) = digits.parse::<f64>() {
+            return Ok(TomlValue::Float(value));
+        }
+        Err(self.error(&format!("invalid value '{}'", text)))
+    }
+}
@@ -0,0 +1,86 @@
+// ============================================================================
+// Warnings (--deny / --allow)
+// ============================================================================
+//
+// Every warning has a code, printed after the message:
+//
+//   Warning: skipping src/broken.rs:3:7: expected `;` [parse-error]
+//
+// A run collects its warnings in a frame on this thread: --allow drops them
+// before they are printed, --deny turns the run into a failed check once its
+// output is ready, and JSON output lists them under "warnings". Runs nested
+// inside a run (--inject-map blocks) get frames of their own, adding to the
+// codes of the enclosing run and passing their warnings up to it. Outside any
+// run, e.g. when the library is called directly, warnings are only printed.
+
+use std::cell::RefCell;
+
+use crate::types::{Warning, WarningCode};
+
+struct Frame {
+    allow: Vec<WarningCode>,
+    deny: Vec<WarningCode>,
+    warnings: Vec<Warning>,
+}
+
+thread_local! {
+    static FRAMES: RefCell<Vec<Frame>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Print a warning, unless the current run allows its code, and record it for the run
+pub fn warn(code: WarningCode, message: impl Into<String>) {
+    let message = message.into();
+    let allowed = FRAMES.with(|frames| {
+        frames.borrow().last().is_some_and(|frame| frame.allow.contains(&code) && !frame.deny.contains(&code))
+    });
+    if allowed {
+        return;
+    }
+
+    eprintln!("Warning: {} [{}]", message, code.name());
+    FRAMES.with(|frames| {
+        if let Some(frame) = frames.borrow_mut().last_mut() {
+            frame.warnings.push(Warning { code, message });
+        }
+    });
+}
+
+/// Run `f` with its own warning frame, returning its result and the warnings it printed
+pub fn collect<T>(allow: &[WarningCode], deny: &[WarningCode], f: impl FnOnce() -> T) -> (T, Vec<Warning>) {
+    FRAMES.with(|frames| {
+        let mut frames = frames.borrow_mut();
+        let mut frame = Frame { allow: allow.to_vec(), deny: deny.to_vec(), warnings: Vec::new() };
+        if let Some(parent) = frames.last() {
+            frame.allow.extend(&parent.allow);
+            frame.deny.extend(&parent.deny);
+        }
+        frames.push(frame);
+    });
+    let result = f();
+    let warnings = FRAMES.with(|frames| {
+        let mut frames = frames.borrow_mut();
+        let warnings = frames.pop().map(|frame| frame.warnings).unwrap_or_default();
+        if let Some(parent) = frames.last_mut() {
+            parent.warnings.extend(warnings.iter().cloned());
+        }
+        warnings
+    });
+    (result, warnings)
+}
+
+/// `output`, a JSON object, with a "warnings" array of `{code, message}`; unchanged when
+/// there are no warnings or it is not an object
+pub fn with_json_warnings(output: String, warnings: &[Warning]) -> String {
+    if warnings.is_empty() {
+        return output;
+    }
+    let Ok(serde_json::Value::Object(mut object)) = serde_json::from_str::<serde_json::Value>(&output) else {
+        return output;
+    };
+
+    let entries: Vec<serde_json::Value> = warnings.iter()
+        .map(|warning| serde_json::json!({ "code": warning.code.name(), "message": warning.message }))
+        .collect();
+    object.insert("warnings".to_string(), serde_json::Value::Array(entries));
+    serde_json::to_string_pretty(&object).unwrap_or(output)
+}
@@ -0,0 +1,142 @@
+// ============================================================================
+// Dependency Paths (`why`)
+// ============================================================================
+//
+// Every path through the resolved graph from a workspace root (a member no other
+// member depends on) to the crate asked about, shortest first:
+//
+//   app -> api -> engine -> serde
+//   app -> engine -> serde
+//
+// Only the crates that lead to it are walked, so every partial path can still be
+// completed; with --limit the search stops after the N shortest paths.
+
+use std::{collections::{HashMap, HashSet, VecDeque}, error::Error, path::PathBuf};
+
+use petgraph::{graph::{DiGraph, NodeIndex}, Direction};
+
+use crate::{types::{DepKinds, DepsArgs, NodeInfo, WhyArgs, WhyFormat}, utils::{generator::{generate_deps_dot, generate_deps_mermaid}, graph::{reachable_from, GraphNode}, grapher::load_deps_graph}};
+
+pub fn run_why(args: &WhyArgs) -> Result<(String, Option<PathBuf>), Box<dyn Error>> {
+    let (name, version) = match args.crate_name.split_once('@') {
+        Some((name, version)) => (name, Some(version)),
+        None => (args.crate_name.as_str(), None),
+    };
+
+    let mut deps_args = DepsArgs {
+        manifest_path: args.manifest_path.clone(),
+        no_dev: args.no_dev,
+        no_build: args.no_build,
+        no_fence: args.no_fence,
+        direction: args.direction.clone(),
+        theme: args.theme.clone(),
+        mermaid_version: args.mermaid_version,
+        ..Default::default()
+    };
+    let (mut graph_data, _) = load_deps_graph(&deps_args)?;
+    let graph = &graph_data.graph;
+
+    let targets: Vec<NodeIndex> = graph.node_indices()
+        .filter(|&idx| !graph[idx].is_build_script && graph[idx].matches_focus(name))
+        .filter(|&idx| version.is_none_or(|version| graph[idx].version == version))
+        .collect();
+    if targets.is_empty() {
+        return Err(format!("'{}' is not in the dependency graph", args.crate_name).into());
+    }
+
+    let paths = dependency_paths(graph, &workspace_roots(graph), &targets, args.limit);
+    if paths.is_empty() {
+        return Err(format!("No workspace member depends on '{}'", args.crate_name).into());
+    }
+
+    let output = match args.format {
+        WhyFormat::Text => paths_text(graph, &paths),
+        WhyFormat::Mermaid | WhyFormat::Dot => {
+            // Only the crates and edges on the paths
+            let on_path: HashSet<NodeIndex> = paths.iter().flatten().copied().collect();
+            let path_edges: HashSet<(NodeIndex, NodeIndex)> = paths.iter()
+                .flat_map(|path| path.windows(2).map(|pair| (pair[0], pair[1])))
+                .collect();
+            deps_args.highlight = vec![graph[targets[0]].name.clone()];
+            graph_data.graph.retain_edges(|graph, edge| graph.edge_endpoints(edge).is_some_and(|ends| path_edges.contains(&ends)));
+            graph_data.retain_nodes(|idx| on_path.contains(&idx));
+
+            match args.format {
+                WhyFormat::Dot => generate_deps_dot(&graph_data, &deps_args),
+                _ => generate_deps_mermaid(&graph_data, &deps_args),
+            }
+        }
+    };
+    Ok((output, args.output.clone()))
+}
+
+/// Workspace members no other member depends on, or every member when they depend on each other in a cycle
+fn workspace_roots(graph: &DiGraph<NodeInfo, DepKinds>) -> Vec<NodeIndex> {
+    let members: Vec<NodeIndex> = graph.node_indices()
+        .filter(|&idx| graph[idx].is_workspace_member && !graph[idx].is_build_script)
+        .collect();
+    let roots: Vec<NodeIndex> = members.iter()
+        .copied()
+        .filter(|&idx| !graph.neighbors_directed(idx, Direction::Incoming).any(|dependent| graph[dependent].is_workspace_member))
+        .collect();
+    if roots.is_empty() { members } else { roots }
+}
+
+/// Simple paths from `roots` to `targets`, breadth-first so the shortest come first;
+/// at most `limit` of them unless it is 0
+fn dependency_paths(graph: &DiGraph<NodeInfo, DepKinds>, roots: &[NodeIndex], targets: &[NodeIndex], limit: usize) -> Vec<Vec<NodeIndex>> {
+    let leads_to_target = reachable_from(graph, targets, Direction::Incoming);
+    let targets: HashSet<NodeIndex> = targets.iter().copied().collect();
+
+    let mut queue: VecDeque<Vec<NodeIndex>> = roots.iter()
+        .filter(|root| leads_to_target.contains(root))
+        .map(|&root| vec![root])
+        .collect();
+    let mut paths = Vec::new();
+    while let Some(path) = queue.pop_front() {
+        let last = path[path.len() - 1];
+        if targets.contains(&last) {
+            paths.push(path);
+            if paths.len() == limit {
+                break;
+            }
+            continue;
+        }
+        for next in graph.neighbors_directed(last, Direction::Outgoing) {
+            if leads_to_target.contains(&next) && !path.contains(&next) {
+                let mut longer = path.clone();
+                longer.push(next);
+                queue.push_back(longer);
+            }
+        }
+    }
+    paths
+}
+
+/// One `app -> tokio -> mio` chain per line, naming versions only for crates in several
+fn paths_text(graph: &DiGraph<NodeInfo, DepKinds>, paths: &[Vec<NodeIndex>]) -> String {
+    let mut versions: HashMap<&str, HashSet<&str>> = HashMap::new();
+    for info in graph.node_weights() {
+        versions.entry(info.name.as_str()).or_default().insert(info.version.as_str());
+    }
+    let label = |idx: NodeIndex| {
+        let info = &graph[idx];
+        if versions[info.name.as_str()].len() > 1 {
+            format!("{}@{}", info.name, info.version)
+        } else {
+            info.name.clone()
+        }
+    };
+
+    let mut lines: Vec<(usize, String)> = paths.iter()
+        .map(|path| (path.len(), path.iter().map(|&idx| label(idx)).collect::<Vec<_>>().join(" -> ")))
+        .collect();
+    lines.sort();
+
+    let mut output = String::new();
+    for (_, line) in lines {
+        output.push_str(&line);
+        output.push('\n');
+    }
+    output
+}
@@ -0,0 +1,80 @@
+// ============================================================================
+// WebAssembly Exports
+// ============================================================================
+//
+// Plain exports for wasm32-unknown-unknown, no wasm-bindgen glue needed. Build
+// with `--no-default-features`. JavaScript copies UTF-8 strings into memory
+// from `alloc`, calls `fn_graph` with the source and the fn-graph options (one
+// argument per line, e.g. "--format\njson\n--focus\nmain"), then reads the
+// result text, which is the error message if `fn_graph` returned 0:
+//
+//   const ok = exports.fn_graph(srcPtr, srcLen, argsPtr, argsLen);
+//   const out = new Uint8Array(exports.memory.buffer, exports.result_ptr(), exports.result_len());
+//   exports.dealloc(srcPtr, srcLen);
+//
+// The source is analyzed as a single file named `main.rs`.
+
+use std::{cell::RefCell, slice};
+
+use clap::{Args, Command, FromArgMatches};
+
+use crate::{types::FnGraphArgs, utils::grapher::fn_graph_from_sources};
+
+thread_local! {
+    /// Output (or error) of the last `fn_graph` call
+    static RESULT: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Reserve `len` bytes for the caller to fill
+#[no_mangle]
+pub extern "C" fn alloc(len: usize) -> *mut u8 {
+    let mut buffer = Vec::<u8>::with_capacity(len);
+    let ptr = buffer.as_mut_ptr();
+    std::mem::forget(buffer);
+    ptr
+}
+
+/// Release memory from `alloc`
+///
+/// # Safety
+/// `ptr` and `len` must come from one `alloc` call.
+#[no_mangle]
+pub unsafe extern "C" fn dealloc(ptr: *mut u8, len: usize) {
+    drop(Vec::from_raw_parts(ptr, 0, len));
+}
+
+/// Render the call graph of a source file; 1 on success, 0 with an error message as the result
+///
+/// # Safety
+/// Both pointers must point to that many initialized bytes.
+#[no_mangle]
+pub unsafe extern "C" fn fn_graph(source_ptr: *const u8, source_len: usize, args_ptr: *const u8, args_len: usize) -> u32 {
+    let source = String::from_utf8_lossy(slice::from_raw_parts(source_ptr, source_len));
+    let args = String::from_utf8_lossy(slice::from_raw_parts(args_ptr, args_len));
+
+    let result = parse_args(&args).and_then(|args| {
+        fn_graph_from_sources(&[("main.rs", &source)], &args).map_err(|e| e.to_string())
+    });
+    let ok = result.is_ok();
+    RESULT.with(|cell| *cell.borrow_mut() = result.unwrap_or_else(|e| e));
+    ok as u32
+}
+
+#[no_mangle]
+pub extern "C" fn result_ptr() -> *const u8 {
+    RESULT.with(|cell| cell.borrow().as_ptr())
+}
+
+#[no_mangle]
+pub extern "C" fn result_len() -> usize {
+    RESULT.with(|cell| cell.borrow().len())
+}
+
+/// fn-graph options with the CLI's defaults and validation
+fn parse_args(args: &str) -> Result<FnGraphArgs, String> {
+    let argv = std::iter::once("fn-graph").chain(args.lines().filter(|arg| !arg.is_empty()));
+    let matches = FnGraphArgs::augment_args(Command::new("fn-graph"))
+        .try_get_matches_from(argv)
+        .map_err(|e| e.to_string())?;
+    FnGraphArgs::from_arg_matches(&matches).map_err(|e| e.to_string())
+}